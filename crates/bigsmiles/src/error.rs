@@ -1,3 +1,4 @@
+use opensmiles::LimitKind;
 use thiserror::Error;
 
 /// Errors that can occur when parsing a BigSMILES string.
@@ -23,6 +24,11 @@ pub enum ParseError {
     #[error("empty SMILES in stochastic fragment")]
     EmptySmiles,
 
+    /// A limit of the parse options was exceeded. Atoms and bonds are
+    /// counted over the whole BigSMILES string.
+    #[error("{kind} limit of {limit} exceeded")]
+    LimitExceeded { kind: LimitKind, limit: usize },
+
     /// The SMILES fragment embedded in a BigSMILES string failed to parse.
     #[error("SMILES parse error: {0}")]
     SmilesError(String),
@@ -30,6 +36,11 @@ pub enum ParseError {
 
 impl From<opensmiles::ParserError> for ParseError {
     fn from(e: opensmiles::ParserError) -> Self {
-        ParseError::SmilesError(e.to_string())
+        match e {
+            opensmiles::ParserError::LimitExceeded { kind, limit, .. } => {
+                ParseError::LimitExceeded { kind, limit }
+            }
+            e => ParseError::SmilesError(e.to_string()),
+        }
     }
 }
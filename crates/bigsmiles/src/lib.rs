@@ -31,4 +31,4 @@ pub mod parser;
 // Re-export public API
 pub use ast::*;
 pub use error::*;
-pub use parser::parse;
+pub use parser::{parse, parse_with};
@@ -1,3 +1,5 @@
+use opensmiles::{LimitKind, Molecule, ParseOptions};
+
 use crate::{
     ast::{
         BigSmiles, BigSmilesSegment, BondDescriptor, BondDescriptorKind, StochasticFragment,
@@ -21,7 +23,34 @@ use crate::{
 /// let pe_end = parse("CC{[$]CC[$]}CC").unwrap();
 /// ```
 pub fn parse(input: &str) -> Result<BigSmiles, ParseError> {
-    Parser::new(input).parse_bigsmiles()
+    parse_with(input, &ParseOptions::default())
+}
+
+/// Parse a BigSMILES string, reading its SMILES parts with the given options.
+///
+/// The input length, atom and bond limits of `options` apply to the whole
+/// string; the other options apply to each SMILES part.
+///
+/// # Examples
+///
+/// ```rust
+/// use bigsmiles::{opensmiles::ParseOptions, parse, parse_with};
+///
+/// let options = ParseOptions {
+///     max_charge: 1,
+///     ..Default::default()
+/// };
+/// assert!(parse("{[]CC([O-2])[]}").is_ok());
+/// assert!(parse_with("{[]CC([O-2])[]}", &options).is_err());
+/// ```
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<BigSmiles, ParseError> {
+    if input.len() > options.max_input_length {
+        return Err(ParseError::LimitExceeded {
+            kind: LimitKind::InputLength,
+            limit: options.max_input_length,
+        });
+    }
+    Parser::new(input, options).parse_bigsmiles()
 }
 
 // ── Connection-atom helper ────────────────────────────────────────────────────
@@ -123,11 +152,46 @@ fn right_connection_atom(smiles: &str) -> usize {
 struct Parser<'a> {
     input: &'a str,
     pos: usize,
+    options: &'a ParseOptions,
+    atoms: usize, // atoms read so far, in all SMILES parts
+    bonds: usize, // bonds read so far, in all SMILES parts
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
-        Parser { input, pos: 0 }
+    fn new(input: &'a str, options: &'a ParseOptions) -> Self {
+        Parser {
+            input,
+            pos: 0,
+            options,
+            atoms: 0,
+            bonds: 0,
+        }
+    }
+
+    /// Parses a SMILES part with what is left of the atom and bond limits.
+    fn parse_smiles(&mut self, smiles: &str) -> Result<Molecule, ParseError> {
+        let options = ParseOptions {
+            max_input_length: usize::MAX,
+            max_atoms: self.options.max_atoms - self.atoms,
+            max_bonds: self.options.max_bonds - self.bonds,
+            ..self.options.clone()
+        };
+        let molecule = opensmiles::parse_with(smiles, &options).map_err(|e| match e {
+            opensmiles::ParserError::LimitExceeded {
+                kind: kind @ (LimitKind::Atoms | LimitKind::Bonds),
+                ..
+            } => ParseError::LimitExceeded {
+                kind,
+                limit: match kind {
+                    LimitKind::Atoms => self.options.max_atoms,
+                    _ => self.options.max_bonds,
+                },
+            },
+            e => e.into(),
+        })?;
+        self.atoms += molecule.nodes().len();
+        self.bonds += molecule.bonds().len();
+        Ok(molecule)
     }
 
     // ── Primitive helpers ────────────────────────────────────────────────────
@@ -326,7 +390,7 @@ impl<'a> Parser<'a> {
         }
         let smiles_raw = smiles_str.to_owned();
         let right_atom = right_connection_atom(smiles_str);
-        let molecule = opensmiles::parse(smiles_str)?;
+        let molecule = self.parse_smiles(smiles_str)?;
         let right = self.parse_bond_descriptor()?;
         Ok(StochasticFragment {
             left,
@@ -421,7 +485,7 @@ impl<'a> Parser<'a> {
                 Some(_) => {
                     let smiles_str = self.extract_outer_smiles();
                     if !smiles_str.is_empty() {
-                        let mol = opensmiles::parse(smiles_str)?;
+                        let mol = self.parse_smiles(smiles_str)?;
                         segments.push(BigSmilesSegment::Smiles(mol));
                     }
                 }
@@ -1,6 +1,6 @@
 //! Integration tests — error cases.
 
-use bigsmiles::{parse, ParseError};
+use bigsmiles::{opensmiles::ParseOptions, parse, parse_with, ParseError};
 
 // ── Structural errors ─────────────────────────────────────────────────────────
 
@@ -62,3 +62,63 @@ fn error_bond_descriptor_unclosed_bracket() {
         "got {err:?}"
     );
 }
+
+// ── Parse options ─────────────────────────────────────────────────────────────
+
+#[test]
+fn error_parse_options_apply_to_every_smiles() {
+    let options = ParseOptions {
+        max_isotope: 20,
+        ..Default::default()
+    };
+    assert!(parse_with("{[$]CC[$]}[13CH3]", &options).is_ok());
+    let err = parse_with("{[$]C[24CH2][$]}", &options).unwrap_err();
+    assert!(matches!(err, ParseError::SmilesError(_)), "got {err:?}");
+    let err = parse_with("[24CH3]{[$]CC[$]}", &options).unwrap_err();
+    assert!(matches!(err, ParseError::SmilesError(_)), "got {err:?}");
+}
+
+#[test]
+fn error_limits_cover_the_whole_string() {
+    use bigsmiles::opensmiles::LimitKind;
+
+    let limit = |kind, limit| Err(ParseError::LimitExceeded { kind, limit });
+    let atoms = ParseOptions {
+        max_atoms: 5,
+        ..Default::default()
+    };
+    assert!(parse_with("CC{[$]CC[$]}C", &atoms).is_ok());
+    // Each part is small enough; together they are not.
+    assert_eq!(
+        parse_with("CC{[$]CC[$]}CCC", &atoms),
+        limit(LimitKind::Atoms, 5)
+    );
+    let bonds = ParseOptions {
+        max_bonds: 3,
+        ..Default::default()
+    };
+    assert!(parse_with("CC{[$]CC[$],[$]CC[$]}", &bonds).is_ok());
+    assert_eq!(
+        parse_with("CC{[$]CC[$],[$]CC[$]}CC", &bonds),
+        limit(LimitKind::Bonds, 3)
+    );
+
+    let length = ParseOptions {
+        max_input_length: 12,
+        ..Default::default()
+    };
+    assert!(parse_with("{[$]CCCC[$]}", &length).is_ok());
+    assert_eq!(
+        parse_with("{[$]CCCC[$]}C", &length),
+        limit(LimitKind::InputLength, 12)
+    );
+    let depth = ParseOptions {
+        max_branch_depth: 1,
+        ..Default::default()
+    };
+    assert!(parse_with("{[$]C(C)(C)[$]}", &depth).is_ok());
+    assert_eq!(
+        parse_with("{[$]C(C(C))[$]}", &depth),
+        limit(LimitKind::BranchDepth, 1)
+    );
+}
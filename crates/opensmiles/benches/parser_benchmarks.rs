@@ -8,6 +8,7 @@
 //! - `reference`: 5 representative molecules for cross-language comparison
 //! - `seq_vs_parallel`: find the batch size where parallelism pays off (parallel feature only)
 //! - `scaling`: parser performance vs molecule size, tracks memory footprint
+//! - `large`: linear scaling up to 10^6 atoms and 10^5 nested branches
 //! - `huckel`: overhead of Hückel aromaticity validation on aromatic molecules
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
@@ -42,6 +43,16 @@ fn generate_teflon(n: usize) -> String {
     "C(F)(F)".repeat(n)
 }
 
+/// Cyclopropyl chain: C1CC1C1CC1... — one ring closure per 3 atoms, ring numbers reused
+fn generate_cyclopropyls(n: usize) -> String {
+    "C1CC1".repeat(n)
+}
+
+/// Nested branches: C(C(C(...)))  — n branches inside one another
+fn generate_nested(n: usize) -> String {
+    format!("{}C{}", "C(".repeat(n), ")".repeat(n))
+}
+
 #[cfg(feature = "parallel")]
 fn generate_batch_dataset(size: usize) -> Vec<&'static str> {
     (0..size)
@@ -139,12 +150,60 @@ fn bench_scaling(c: &mut Criterion) {
     group.finish();
 }
 
+/// Large inputs: the parser works in a single pass, so atoms per second
+/// should stay flat from 10^4 to 10^6 atoms, and deep nesting must neither
+/// slow it down nor overflow the stack.
+///
+/// Uses Throughput::Elements (atoms) so Criterion reports atoms/sec.
+fn bench_large(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large");
+    group.sample_size(10);
+
+    // PEG chains, 3 atoms per repeat unit
+    for atoms in [10_000, 100_000, 1_000_000] {
+        let smiles = generate_peg(atoms / 3);
+        group.throughput(Throughput::Elements(atoms as u64));
+        group.bench_with_input(BenchmarkId::new("peg", atoms), &smiles, |b, s| {
+            b.iter(|| parse(black_box(s)))
+        });
+    }
+
+    // Ring closures, 3 atoms per ring
+    for atoms in [10_000, 100_000, 1_000_000] {
+        let smiles = generate_cyclopropyls(atoms / 3);
+        group.throughput(Throughput::Elements(atoms as u64));
+        group.bench_with_input(BenchmarkId::new("rings", atoms), &smiles, |b, s| {
+            b.iter(|| parse(black_box(s)))
+        });
+    }
+
+    // Teflon, a branch on every other atom
+    for atoms in [10_000, 100_000, 1_000_000] {
+        let smiles = generate_teflon(atoms / 3);
+        group.throughput(Throughput::Elements(atoms as u64));
+        group.bench_with_input(BenchmarkId::new("teflon", atoms), &smiles, |b, s| {
+            b.iter(|| parse(black_box(s)))
+        });
+    }
+
+    // Nested branches, one atom per level
+    for depth in [1_000, 10_000, 100_000] {
+        let smiles = generate_nested(depth);
+        group.throughput(Throughput::Elements(depth as u64 + 1));
+        group.bench_with_input(BenchmarkId::new("nested", depth), &smiles, |b, s| {
+            b.iter(|| parse(black_box(s)))
+        });
+    }
+
+    group.finish();
+}
+
 /// Hückel validation overhead: measures the cost of aromaticity checking
 /// after parsing, for molecules with different aromatic complexity.
 ///
 /// Benchmarks `parse()` alone vs `parse() + validate_aromaticity()` to
-/// isolate the validation overhead. Useful for deciding whether to enable
-/// the `huckel-validation` feature flag.
+/// isolate the validation overhead. Useful for deciding whether to turn on
+/// `ParseOptions::huckel_validation`.
 fn bench_huckel(c: &mut Criterion) {
     let mut group = c.benchmark_group("huckel");
 
@@ -183,10 +242,17 @@ criterion_group!(
     bench_reference,
     bench_seq_vs_parallel,
     bench_scaling,
+    bench_large,
     bench_huckel,
 );
 
 #[cfg(not(feature = "parallel"))]
-criterion_group!(benches, bench_reference, bench_scaling, bench_huckel,);
+criterion_group!(
+    benches,
+    bench_reference,
+    bench_scaling,
+    bench_large,
+    bench_huckel,
+);
 
 criterion_main!(benches);
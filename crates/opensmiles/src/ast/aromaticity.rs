@@ -1,6 +1,10 @@
+use std::collections::{HashMap, HashSet};
+
+use super::bond::Bond;
 use super::graph::Ring;
 use super::molecule::Molecule;
-use crate::{MoleculeError, NodeIndex};
+use super::rings;
+use crate::{BondType, MoleculeError, NodeIndex};
 
 /// Result of aromaticity validation for a single ring.
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +49,22 @@ fn pi_electron_contribution(molecule: &Molecule, node_idx: NodeIndex) -> Option<
         return None;
     }
 
+    // An exocyclic double bond to a non-aromatic atom (e.g. the C=O of a
+    // pyridone) takes the p orbital out of the ring: no pi contribution.
+    let has_exocyclic_double = molecule.bonds().iter().any(|bond| {
+        let other = if bond.source() == node_idx {
+            bond.target()
+        } else if bond.target() == node_idx {
+            bond.source()
+        } else {
+            return false;
+        };
+        bond.kind() == BondType::Double && !molecule.nodes()[other as usize].aromatic()
+    });
+    if has_exocyclic_double {
+        return Some(0);
+    }
+
     let sigma_bonds = count_sigma_bonds(molecule, node_idx);
 
     // Calculate electrons remaining after forming sigma bonds, adjusted for charge
@@ -148,6 +168,359 @@ pub fn require_valid_aromaticity(molecule: &Molecule) -> Result<(), MoleculeErro
     Ok(())
 }
 
+/// Rules used to decide which rings are aromatic when perceiving
+/// aromaticity from a Kekulé structure.
+///
+/// All models require every ring atom to be sp2 and the ring to hold
+/// 4n+2 pi electrons; they differ in which atoms may contribute and which
+/// rings are considered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AromaticityModel {
+    /// Daylight-like model. Atoms with a ring double bond give one electron,
+    /// pyrrole-type N, O, S, Se, Te and carbanions give two, carbocations and
+    /// boron give none, and an exocyclic double bond to N, O or S gives none
+    /// (2-pyridone is aromatic). Rings of any size are considered, as well as
+    /// the envelopes of fused rings (azulene is aromatic only as a whole).
+    #[default]
+    Daylight,
+    /// Only 5- and 6-membered rings made of carbon atoms, each considered
+    /// on its own: benzene and the cyclopentadienyl anion, but no heterocycles.
+    SimpleCarbocycle,
+    /// MDL-like model. Only atoms with a double bond inside the ring system
+    /// contribute, so rings with lone-pair donors or exocyclic double bonds
+    /// (pyrrole, furan, 2-pyridone) stay non-aromatic.
+    Mdl,
+}
+
+/// Largest number of fused rings merged into one envelope.
+const MAX_ENVELOPE_RINGS: usize = 3;
+
+/// Aromatic atoms and bonds (as sorted index pairs) of a molecule.
+pub(crate) type AromaticSets = (Vec<bool>, HashSet<(NodeIndex, NodeIndex)>);
+
+/// Perceives aromaticity in the Kekulé parts of a molecule.
+///
+/// Atoms and bonds already marked aromatic are kept and count as aromatic
+/// neighbours for fused rings. Rings are only searched among the remaining
+/// single and double bonds.
+pub(crate) fn perceive(molecule: &Molecule, model: AromaticityModel) -> AromaticSets {
+    let n = molecule.nodes().len();
+    let mut aromatic: Vec<bool> = molecule
+        .nodes()
+        .iter()
+        .map(|node| node.aromatic())
+        .collect();
+    let mut aromatic_bonds: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+
+    let mut neighbours: Vec<Vec<(NodeIndex, BondType)>> = vec![Vec::new(); n];
+    let mut edges: Vec<Option<(NodeIndex, NodeIndex)>> = vec![None; molecule.bonds().len()];
+    for (id, bond) in molecule.bonds().iter().enumerate() {
+        let (s, t) = (bond.source(), bond.target());
+        if bond.kind() == BondType::Disconnected {
+            continue;
+        }
+        neighbours[s as usize].push((t, bond.kind()));
+        neighbours[t as usize].push((s, bond.kind()));
+        if bond.kind() == BondType::Aromatic {
+            aromatic_bonds.insert((s.min(t), s.max(t)));
+            continue;
+        }
+        let candidate = |a: NodeIndex| {
+            let node = &molecule.nodes()[a as usize];
+            !node.aromatic() && node.atom().element().can_be_aromatic()
+        };
+        if candidate(s) && candidate(t) {
+            edges[id] = Some((s, t));
+        }
+    }
+
+    // One relevant cycle per family: listing every relevant cycle can take
+    // exponential time on caged ring systems.
+    let mut rings: Vec<Vec<NodeIndex>> = rings::perceive(n, &edges)
+        .families
+        .into_iter()
+        .map(|cycle| cycle.nodes)
+        .collect();
+    if model == AromaticityModel::SimpleCarbocycle {
+        rings.retain(|ring| {
+            matches!(ring.len(), 5 | 6) && ring.iter().all(|&a| atomic_number(molecule, a) == 6)
+        });
+    }
+    // Each candidate cycle with the rings it is made of: an aromatic
+    // envelope makes the fusion bonds inside it aromatic too.
+    let mut cycles: Vec<(Vec<NodeIndex>, Vec<usize>)> = rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| (ring.clone(), vec![i]))
+        .collect();
+    if model != AromaticityModel::SimpleCarbocycle {
+        cycles.extend(fused_envelopes(&rings));
+    }
+
+    // A ring can become aromatic once a fused neighbour is (naphthalene,
+    // indole in Kekulé form), so repeat until nothing changes.
+    let mut done = vec![false; cycles.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for ((cycle, members), done) in cycles.iter().zip(done.iter_mut()) {
+            if *done || cycle.iter().all(|&a| aromatic[a as usize]) {
+                continue;
+            }
+            if !is_aromatic_cycle(molecule, &neighbours, cycle, &aromatic, model) {
+                continue;
+            }
+            *done = true;
+            changed = true;
+            for &a in cycle {
+                aromatic[a as usize] = true;
+            }
+            for ring in members.iter().map(|&m| &rings[m]) {
+                for i in 0..ring.len() {
+                    let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                    aromatic_bonds.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+    }
+
+    (aromatic, aromatic_bonds)
+}
+
+fn atomic_number(molecule: &Molecule, atom: NodeIndex) -> u8 {
+    molecule.nodes()[atom as usize]
+        .atom()
+        .element()
+        .element_data()
+        .atomic_number
+}
+
+/// Counts the pi electrons of `cycle` under `model` and applies Hückel's rule.
+fn is_aromatic_cycle(
+    molecule: &Molecule,
+    neighbours: &[Vec<(NodeIndex, BondType)>],
+    cycle: &[NodeIndex],
+    aromatic: &[bool],
+    model: AromaticityModel,
+) -> bool {
+    let mut total: u32 = 0;
+    for &atom in cycle {
+        match cycle_atom_electrons(molecule, neighbours, atom, cycle, aromatic, model) {
+            Some(electrons) => total += electrons as u32,
+            None => return false,
+        }
+    }
+    total <= u8::MAX as u32 && satisfies_huckel(total as u8)
+}
+
+/// Pi electrons an atom gives to `cycle`, or `None` if it cannot be part of
+/// an aromatic ring under `model`.
+fn cycle_atom_electrons(
+    molecule: &Molecule,
+    neighbours: &[Vec<(NodeIndex, BondType)>],
+    atom: NodeIndex,
+    cycle: &[NodeIndex],
+    aromatic: &[bool],
+    model: AromaticityModel,
+) -> Option<u8> {
+    let node = &molecule.nodes()[atom as usize];
+    let sigma = neighbours[atom as usize].len() + node.hydrogens() as usize;
+    if sigma > 3 {
+        return None;
+    }
+
+    let mut double = None;
+    for &(other, kind) in &neighbours[atom as usize] {
+        match kind {
+            BondType::Double if double.is_none() => double = Some(other),
+            BondType::Double | BondType::Triple | BondType::Quadruple => return None,
+            _ => {}
+        }
+    }
+
+    if let Some(other) = double {
+        if cycle.contains(&other) || aromatic[other as usize] {
+            return Some(1);
+        }
+        let electronegative = matches!(atomic_number(molecule, other), 7 | 8 | 16);
+        return (model == AromaticityModel::Daylight && electronegative).then_some(0);
+    }
+    if model == AromaticityModel::Mdl {
+        return None;
+    }
+
+    let charge = node.atom().charge();
+    match (atomic_number(molecule, atom), charge, sigma) {
+        (6, -1, 3) => Some(2),
+        (6, 1, 3) => Some(0),
+        _ if model == AromaticityModel::SimpleCarbocycle => None,
+        (7 | 15 | 33, 0, 3) | (7 | 15 | 33, -1, 2) => Some(2),
+        (8 | 16 | 34 | 52, 0, 2) => Some(2),
+        (5, 0, 3) => Some(0),
+        _ => None,
+    }
+}
+
+/// Outer cycles of up to [`MAX_ENVELOPE_RINGS`] rings fused through shared
+/// bonds, e.g. the 10-membered perimeter of azulene, with the indices of the
+/// rings they enclose.
+fn fused_envelopes(rings: &[Vec<NodeIndex>]) -> Vec<(Vec<NodeIndex>, Vec<usize>)> {
+    let edge_sets: Vec<HashSet<(NodeIndex, NodeIndex)>> = rings
+        .iter()
+        .map(|ring| {
+            (0..ring.len())
+                .map(|i| {
+                    let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                    (a.min(b), a.max(b))
+                })
+                .collect()
+        })
+        .collect();
+    let mut edge_rings: HashMap<(NodeIndex, NodeIndex), Vec<usize>> = HashMap::new();
+    for (i, edges) in edge_sets.iter().enumerate() {
+        for &edge in edges {
+            edge_rings.entry(edge).or_default().push(i);
+        }
+    }
+    let fused: Vec<Vec<usize>> = edge_sets
+        .iter()
+        .enumerate()
+        .map(|(i, edges)| {
+            let mut fused: Vec<usize> = edges
+                .iter()
+                .flat_map(|edge| &edge_rings[edge])
+                .copied()
+                .filter(|&j| j != i)
+                .collect();
+            fused.sort_unstable();
+            fused.dedup();
+            fused
+        })
+        .collect();
+
+    let mut seen: HashSet<Vec<usize>> = HashSet::new();
+    let mut frontier: Vec<Vec<usize>> = (0..rings.len()).map(|i| vec![i]).collect();
+    let mut envelopes = Vec::new();
+    for _ in 1..MAX_ENVELOPE_RINGS {
+        let mut next = Vec::new();
+        for subset in &frontier {
+            for &member in subset {
+                for &ring in &fused[member] {
+                    if subset.contains(&ring) {
+                        continue;
+                    }
+                    let mut grown = subset.clone();
+                    grown.push(ring);
+                    grown.sort_unstable();
+                    if !seen.insert(grown.clone()) {
+                        continue;
+                    }
+                    let mut edges: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+                    for &r in &grown {
+                        for &edge in &edge_sets[r] {
+                            if !edges.remove(&edge) {
+                                edges.insert(edge);
+                            }
+                        }
+                    }
+                    if let Some(cycle) = single_cycle(&edges) {
+                        envelopes.push((cycle, grown.clone()));
+                    }
+                    next.push(grown);
+                }
+            }
+        }
+        frontier = next;
+    }
+    envelopes
+}
+
+/// Orders the atoms of an edge set that forms exactly one simple cycle.
+fn single_cycle(edges: &HashSet<(NodeIndex, NodeIndex)>) -> Option<Vec<NodeIndex>> {
+    let mut adjacent: HashMap<NodeIndex, Vec<NodeIndex>> = HashMap::new();
+    for &(a, b) in edges {
+        adjacent.entry(a).or_default().push(b);
+        adjacent.entry(b).or_default().push(a);
+    }
+    if adjacent.values().any(|list| list.len() != 2) {
+        return None;
+    }
+    let start = *adjacent.keys().min()?;
+    let mut cycle = vec![start];
+    let (mut previous, mut current) = (start, adjacent[&start][0]);
+    while current != start {
+        cycle.push(current);
+        let list = &adjacent[&current];
+        let next = if list[0] == previous {
+            list[1]
+        } else {
+            list[0]
+        };
+        previous = current;
+        current = next;
+    }
+    (cycle.len() == adjacent.len()).then_some(cycle)
+}
+
+impl Molecule {
+    /// Returns a copy of the molecule with aromaticity perceived under
+    /// `model`: atoms of aromatic rings are flagged aromatic and their ring
+    /// bonds become [`BondType::Aromatic`].
+    ///
+    /// The molecule is first kekulized, so aromatic and Kekulé input give the
+    /// same result, and lowercase rings the model does not consider aromatic
+    /// are written out with single and double bonds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MoleculeError::KekulizationFailed`] if aromatic input cannot
+    /// be kekulized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, AromaticityModel};
+    ///
+    /// let benzene = parse("C1=CC=CC=C1").unwrap();
+    /// let aromatic = benzene.aromatize(AromaticityModel::Daylight).unwrap();
+    /// assert!(aromatic.nodes().iter().all(|n| n.aromatic()));
+    /// assert_eq!(aromatic.to_string(), "c1ccccc1");
+    /// ```
+    pub fn aromatize(&self, model: AromaticityModel) -> Result<Molecule, MoleculeError> {
+        let has_aromatic = self.nodes().iter().any(|node| node.aromatic())
+            || self
+                .bonds()
+                .iter()
+                .any(|bond| bond.kind() == BondType::Aromatic);
+        let kekule = if has_aromatic {
+            self.kekulize()?
+        } else {
+            self.clone()
+        };
+
+        let (aromatic, aromatic_bonds) = perceive(&kekule, model);
+        let nodes = kekule
+            .nodes()
+            .iter()
+            .zip(&aromatic)
+            .map(|(node, &aromatic)| node.with_aromatic(aromatic))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bonds = kekule
+            .bonds()
+            .iter()
+            .map(|bond| {
+                let (s, t) = (bond.source(), bond.target());
+                if aromatic_bonds.contains(&(s.min(t), s.max(t))) {
+                    Bond::new(BondType::Aromatic, s, t)
+                } else {
+                    bond.clone()
+                }
+            })
+            .collect();
+        Ok(Molecule::new(nodes, bonds))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,6 +589,15 @@ mod tests {
         assert!(checks[0].is_valid);
     }
 
+    #[test]
+    fn pyridone_carbonyl_contributes_no_pi_electrons() {
+        let mol = parse("O=c1cccc[nH]1").unwrap();
+        let checks = validate_aromaticity(&mol);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].pi_electrons, Some(6));
+        assert!(checks[0].is_valid);
+    }
+
     #[test]
     fn huckel_validation_passes_for_benzene() {
         let mol = parse("c1ccccc1").unwrap();
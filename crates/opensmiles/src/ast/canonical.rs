@@ -0,0 +1,1263 @@
+//! Canonical atom ranking and canonical SMILES output.
+//!
+//! The ranking follows the classic Morgan / CANON scheme (Weininger et al.,
+//! *J. Chem. Inf. Comput. Sci.* **1989**, 29, 97–101):
+//!
+//! 1. Atoms are partitioned by a graph invariant (degree, element, isotope,
+//!    charge, hydrogen count, aromaticity, atom class, smallest ring size
+//!    and size of the connected component).
+//! 2. The partition is refined: atoms of the same class are split by the
+//!    sorted list of `(neighbour class, bond order)` pairs until nothing
+//!    changes any more.
+//! 3. Each connected component is then searched on its own: while some
+//!    class still holds several atoms, each of its atoms is promoted in
+//!    turn and the refinement is run again (individualization-refinement).
+//!    Every way of breaking the ties ends in distinct ranks; the one whose
+//!    bonds and stereo, written in rank order, come first is kept.
+//! 4. The components are written in the order of those certificates.
+//!
+//! Atoms still tied after step 2 are often, but not always, related by a
+//! symmetry of the graph: in regular graphs such as
+//! `C12C3C4C(C43)C5C1C25`, refinement cannot tell apart atoms no symmetry
+//! relates, so promoting one or the other gives different strings. The
+//! search of step 3 is kept small by skipping the atoms that a symmetry
+//! found on the way maps to one already tried, and by giving atoms bonded
+//! to the same atoms, such as the methyls of a tert-butyl group, their
+//! ranks without trying each of them.
+
+use std::collections::HashSet;
+
+use super::bond::BondType;
+use super::chirality::Chirality;
+use super::molecule::{Molecule, WriteOptions, WriterGraph};
+use super::rings::RingInfo;
+use super::stereo;
+use crate::NodeIndex;
+
+impl Molecule {
+    /// Returns a canonical rank for every atom of the molecule.
+    ///
+    /// Ranks are distinct, start at `0` and do not depend on the order in
+    /// which atoms were written in the input. Explicit hydrogens that the
+    /// writer folds into their neighbour's hydrogen count are ranked last.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let a = parse("OCC").unwrap().canonical_ranks();
+    /// let b = parse("CCO").unwrap().canonical_ranks();
+    /// // The oxygen gets the same rank whatever its input position.
+    /// assert_eq!(a[0], b[2]);
+    /// ```
+    pub fn canonical_ranks(&self) -> Vec<u32> {
+        let graph = self.writer_graph();
+        canonical_ranks(self, &graph)
+    }
+
+    /// Serializes the molecule to canonical SMILES.
+    ///
+    /// Every input form of the same molecular graph — any atom order, ring
+    /// numbering, branch layout, Kekulé or aromatic form, explicit or
+    /// implicit hydrogens — produces byte-identical output. Disconnected
+    /// components are written in canonical order, separated by `.`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let a = parse("OCC").unwrap().to_canonical_smiles();
+    /// let b = parse("C(O)C").unwrap().to_canonical_smiles();
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn to_canonical_smiles(&self) -> String {
+        let graph = self.writer_graph();
+        let ranks = canonical_ranks(self, &graph);
+        self.write_smiles(&graph, Some(&ranks), &WriteOptions::default())
+            .expect("bond order sums are always known when writing")
+    }
+}
+
+/// Sortable invariant of a single atom, used for the initial partition.
+type AtomInvariant = (usize, u8, u32, i8, u8, bool, u32, usize, usize);
+
+/// Sorted `(neighbour rank, bond code)` pairs of an atom.
+type Signature = Vec<(u32, u8)>;
+
+fn atom_invariant(
+    molecule: &Molecule,
+    graph: &WriterGraph,
+    rings: &RingInfo,
+    components: &[usize],
+    atom: usize,
+) -> AtomInvariant {
+    let node = &molecule.nodes()[atom];
+    (
+        graph.neighbours[atom].len(),
+        node.atom().element().element_data().atomic_number,
+        node.atom().isotope().map_or(0, |i| i as u32 + 1),
+        node.atom().charge(),
+        node.hydrogens() + graph.virtual_h[atom],
+        graph.effective_aromatic[atom],
+        node.class().map_or(0, |c| c as u32 + 1),
+        rings.smallest_ring_size(atom as NodeIndex).unwrap_or(0),
+        components[atom],
+    )
+}
+
+/// Size of the connected component of every atom, in the writer graph.
+fn component_sizes(graph: &WriterGraph) -> Vec<usize> {
+    let n = graph.neighbours.len();
+    let mut sizes = vec![0; n];
+    let mut component = Vec::new();
+    for start in 0..n {
+        if sizes[start] != 0 {
+            continue;
+        }
+        sizes[start] = 1;
+        component.push(start);
+        let mut next = 0;
+        while let Some(&atom) = component.get(next) {
+            next += 1;
+            for &(v, _) in &graph.neighbours[atom] {
+                if sizes[v as usize] == 0 {
+                    sizes[v as usize] = 1;
+                    component.push(v as usize);
+                }
+            }
+        }
+        for atom in component.drain(..) {
+            sizes[atom] = next;
+        }
+    }
+    sizes
+}
+
+/// Numeric code for a bond as seen by the refinement.
+pub(crate) fn bond_code(kind: BondType) -> u8 {
+    match kind {
+        BondType::Simple | BondType::Up | BondType::Down => 1,
+        BondType::Double => 2,
+        BondType::Triple => 3,
+        BondType::Aromatic => 4,
+        BondType::Quadruple => 5,
+        BondType::Disconnected => 0,
+    }
+}
+
+/// An ordered partition of the atoms.
+///
+/// `order` lists the atoms class by class; the rank of an atom is the
+/// position where its class starts in `order`, so splitting a class never
+/// renumbers the other ones.
+#[derive(Clone)]
+struct Partition {
+    order: Vec<usize>,
+    /// Position of every atom in `order`.
+    position: Vec<usize>,
+    rank: Vec<u32>,
+    /// Class size, indexed by the class start position.
+    size: Vec<usize>,
+    /// Every class before this position holds a single atom.
+    settled: usize,
+    /// Scratch marks of [`refine`](Self::refine), all `false` in between.
+    touched: Vec<bool>,
+}
+
+impl Partition {
+    fn new<K: Ord>(n: usize, mut atoms: Vec<usize>, key: impl Fn(usize) -> K) -> Partition {
+        atoms.sort_by_key(|&a| key(a));
+        let mut rank = vec![u32::MAX; n];
+        let mut position = vec![usize::MAX; n];
+        let mut size = vec![0; atoms.len()];
+        let mut start = 0;
+        for pos in 0..atoms.len() {
+            if pos > 0 && key(atoms[pos]) != key(atoms[pos - 1]) {
+                start = pos;
+            }
+            rank[atoms[pos]] = start as u32;
+            position[atoms[pos]] = pos;
+            size[start] += 1;
+        }
+        Partition {
+            order: atoms,
+            position,
+            rank,
+            size,
+            settled: 0,
+            touched: vec![false; n],
+        }
+    }
+
+    /// Iteratively splits classes by neighbour signature until stable.
+    ///
+    /// `changed` holds the atoms whose rank changed since the partition was
+    /// last stable. Only their neighbours can get a new signature: the other
+    /// atoms of a class keep the one they share, stay at the start of the
+    /// class and keep their rank, so a round costs time in proportion to
+    /// the atoms it touches rather than to the classes.
+    fn refine(&mut self, graph: &WriterGraph, mut changed: Vec<usize>) {
+        while !changed.is_empty() {
+            let mut touched: Vec<usize> = changed
+                .iter()
+                .flat_map(|&a| graph.neighbours[a].iter())
+                .map(|&(v, _)| v as usize)
+                .filter(|&v| self.size[self.rank[v] as usize] > 1)
+                .collect();
+            touched.sort_unstable_by_key(|&v| (self.rank[v], v));
+            touched.dedup();
+            for &atom in &touched {
+                self.touched[atom] = true;
+            }
+
+            // Signatures are computed against the ranks of the previous round
+            // so that the result does not depend on the order cells are visited.
+            let mut splits = Vec::new();
+            let mut first = 0;
+            while first < touched.len() {
+                let start = self.rank[touched[first]] as usize;
+                let count = touched[first..]
+                    .iter()
+                    .take_while(|&&a| self.rank[a] as usize == start)
+                    .count();
+                let members = &touched[first..first + count];
+                first += count;
+                // Untouched atoms of the class all share the signature the
+                // class had when it was last stable; one of them is among
+                // the first `count + 1` positions.
+                let reference = (count < self.size[start]).then(|| {
+                    let atom = self.order[start..]
+                        .iter()
+                        .copied()
+                        .find(|&a| !self.touched[a])
+                        .expect("the class has an untouched atom");
+                    self.signature(graph, atom)
+                });
+                let mut moved: Vec<(Signature, usize)> = members
+                    .iter()
+                    .map(|&a| (self.signature(graph, a), a))
+                    .filter(|(signature, _)| Some(signature) != reference.as_ref())
+                    .collect();
+                if reference.is_none() {
+                    keep_largest(&mut moved);
+                }
+                if !moved.is_empty() {
+                    splits.push((start, moved));
+                }
+            }
+            for &atom in &touched {
+                self.touched[atom] = false;
+            }
+
+            changed = Vec::new();
+            for (start, moved) in splits {
+                changed.extend(self.split_off(start, moved));
+            }
+        }
+    }
+
+    /// Moves `moved` to the end of the class starting at `start`, in classes
+    /// of their own ordered by key, and returns the moved atoms.
+    fn split_off<K: Ord>(&mut self, start: usize, mut moved: Vec<(K, usize)>) -> Vec<usize> {
+        let len = self.size[start];
+        let tail = start + len - moved.len();
+        // Swap the moved atoms ahead of the tail with the others in it.
+        let mut ahead: Vec<usize> = moved
+            .iter()
+            .map(|&(_, a)| self.position[a])
+            .filter(|&pos| pos < tail)
+            .collect();
+        for &(_, atom) in &moved {
+            self.touched[atom] = true;
+        }
+        for pos in tail..start + len {
+            if !self.touched[self.order[pos]] {
+                let to = ahead
+                    .pop()
+                    .expect("as many moved atoms ahead as others behind");
+                self.order.swap(pos, to);
+                self.position[self.order[pos]] = pos;
+                self.position[self.order[to]] = to;
+            }
+        }
+        for &(_, atom) in &moved {
+            self.touched[atom] = false;
+        }
+
+        moved.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        self.size[start] = tail - start;
+        let mut sub_start = tail;
+        for offset in 0..moved.len() {
+            let pos = tail + offset;
+            if offset > 0 && moved[offset].0 != moved[offset - 1].0 {
+                sub_start = pos;
+            }
+            let atom = moved[offset].1;
+            self.order[pos] = atom;
+            self.position[atom] = pos;
+            self.size[pos] = 0;
+            self.rank[atom] = sub_start as u32;
+            self.size[sub_start] += 1;
+        }
+        moved.into_iter().map(|(_, atom)| atom).collect()
+    }
+
+    /// Splits the given classes by `key` and returns the atoms whose rank
+    /// changed.
+    fn split<K: Ord>(&mut self, cells: &[usize], key: impl Fn(usize) -> K) -> Vec<usize> {
+        let mut changed = Vec::new();
+        for &start in cells {
+            let len = self.size[start];
+            if len < 2 {
+                continue;
+            }
+            let mut keyed: Vec<(K, usize)> = self.order[start..start + len]
+                .iter()
+                .map(|&a| (key(a), a))
+                .collect();
+            keep_largest(&mut keyed);
+            if !keyed.is_empty() {
+                changed.extend(self.split_off(start, keyed));
+            }
+        }
+        changed
+    }
+
+    /// Starts of all classes that still hold several atoms.
+    fn tied_cells(&self) -> Vec<usize> {
+        let mut cells = Vec::new();
+        let mut start = 0;
+        while start < self.order.len() {
+            if self.size[start] > 1 {
+                cells.push(start);
+            }
+            start += self.size[start];
+        }
+        cells
+    }
+
+    fn signature(&self, graph: &WriterGraph, atom: usize) -> Signature {
+        let mut signature: Signature = graph.neighbours[atom]
+            .iter()
+            .map(|&(v, kind)| {
+                (
+                    self.rank[v as usize],
+                    bond_code(graph.effective_bond(atom as NodeIndex, v, kind)),
+                )
+            })
+            .collect();
+        signature.sort_unstable();
+        signature
+    }
+
+    /// Returns the start of the first class that still holds several atoms,
+    /// or `None` once every atom has a rank of its own.
+    fn first_tied_cell(&mut self) -> Option<usize> {
+        // Classes are only ever split, so the classes already passed over
+        // stay single.
+        while self.settled < self.order.len() {
+            if self.size[self.settled] > 1 {
+                return Some(self.settled);
+            }
+            self.settled += 1;
+        }
+        None
+    }
+
+    /// Members of the class starting at `start`, in atom order.
+    fn members(&self, start: usize) -> Vec<usize> {
+        let mut members = self.order[start..start + self.size[start]].to_vec();
+        members.sort_unstable();
+        members
+    }
+
+    /// Splits the class starting at `start` by promoting `atom` ahead of
+    /// the others, and returns the members of the class.
+    fn individualize(&mut self, start: usize, atom: usize) -> Vec<usize> {
+        let len = self.size[start];
+        let mut members = self.members(start);
+        let chosen = members.iter().position(|&a| a == atom).expect("a member");
+        members[..=chosen].rotate_right(1);
+        for (offset, &member) in members.iter().enumerate() {
+            self.order[start + offset] = member;
+            self.position[member] = start + offset;
+        }
+        self.size[start] = 1;
+        self.size[start + 1] = len - 1;
+        for &member in &members[1..] {
+            self.rank[member] = start as u32 + 1;
+        }
+        members
+    }
+
+    /// Gives every atom of the class starting at `start` a rank of its own,
+    /// in atom order.
+    fn discretize(&mut self, start: usize) {
+        for (offset, member) in self.members(start).into_iter().enumerate() {
+            self.order[start + offset] = member;
+            self.position[member] = start + offset;
+            self.rank[member] = (start + offset) as u32;
+            self.size[start + offset] = 1;
+        }
+    }
+
+    /// Tells whether the atoms of the class starting at `start` are twins:
+    /// their neighbours all have a rank of their own, so that, the partition
+    /// being stable, they are bonded to the same atoms and any order of them
+    /// gives the same ranking. Atoms `near` written stereo are never twins.
+    fn twins(&self, graph: &WriterGraph, start: usize, near: &[bool]) -> bool {
+        self.order[start..start + self.size[start]]
+            .iter()
+            .all(|&atom| {
+                !near[atom]
+                    && graph.neighbours[atom]
+                        .iter()
+                        .all(|&(v, _)| self.size[self.rank[v as usize] as usize] == 1)
+            })
+    }
+}
+
+/// Removes the largest group of equal keys, the one with the smallest key
+/// among equally large ones: it keeps the start of the class, so its atoms
+/// keep their rank and the others, which move, are at most half of them.
+fn keep_largest<K: Ord>(keyed: &mut Vec<(K, usize)>) {
+    keyed.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let (mut best, mut best_len) = (0, 0);
+    let mut first = 0;
+    while first < keyed.len() {
+        let len = keyed[first..]
+            .iter()
+            .take_while(|(key, _)| *key == keyed[first].0)
+            .count();
+        if len > best_len {
+            (best, best_len) = (first, len);
+        }
+        first += len;
+    }
+    keyed.drain(best..best + best_len);
+}
+
+/// Computes distinct canonical ranks for every atom of the molecule.
+pub(crate) fn canonical_ranks(molecule: &Molecule, graph: &WriterGraph) -> Vec<u32> {
+    canonical_ranking(molecule, graph).ranks
+}
+
+/// Canonical ranks of the atoms of a molecule, with what was known before
+/// ties were broken.
+pub(crate) struct Ranking {
+    /// Distinct ranks.
+    pub(crate) ranks: Vec<u32>,
+    /// Ranks reached before ties are broken: atoms sharing one are not told
+    /// apart by the refinement. Folded hydrogens have none (`u32::MAX`).
+    pub(crate) classes: Vec<u32>,
+    /// The stereo descriptors of [`StereoSites::labels`] against `classes`.
+    /// A descriptor is `(0, 0)` for an atom that is no stereocentre, or
+    /// whose neighbours are symmetric.
+    pub(crate) labels: Vec<(u8, u8)>,
+    /// Parities of the ring-relative centres against `ranks`, as `(rank,
+    /// parity)` pairs in rank order.
+    pub(crate) ring_parities: Vec<(u32, u8)>,
+}
+
+/// Computes the [`Ranking`] of every atom of the molecule.
+pub(crate) fn canonical_ranking(molecule: &Molecule, graph: &WriterGraph) -> Ranking {
+    let n = molecule.nodes().len();
+    let atoms: Vec<usize> = (0..n).filter(|&i| !graph.removable_h[i]).collect();
+
+    let rings = molecule.ring_info();
+    let components = component_sizes(graph);
+    let mut partition = Partition::new(n, atoms.clone(), |a| {
+        atom_invariant(molecule, graph, &rings, &components, a)
+    });
+    partition.refine(graph, atoms.clone());
+    // Stereo descriptors are only meaningful once the neighbours they refer
+    // to are told apart, so they are added after the graph refinement.
+    let sites = StereoSites::new(molecule, graph);
+    let labels = loop {
+        let labels = sites.labels(n, &partition.rank);
+        let changed = partition.split(&partition.tied_cells(), |a| labels[a]);
+        if changed.is_empty() {
+            break labels;
+        }
+        partition.refine(graph, changed);
+    };
+    let classes = partition.rank.clone();
+    let written = sites.written(molecule, graph);
+
+    // Components are searched on their own and written one after the other,
+    // in the order of their certificates.
+    let mut components: Vec<(ComponentKey, Vec<usize>)> = graph
+        .components()
+        .into_iter()
+        .map(|atoms| {
+            let atoms: Vec<usize> = atoms.into_iter().map(|a| a as usize).collect();
+            Component::new(graph, &written, &classes, atoms).canonical()
+        })
+        .collect();
+    components.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut ranks = vec![u32::MAX; n];
+    let mut next = 0;
+    for (_, by_rank) in components {
+        for atom in by_rank {
+            ranks[atom] = next;
+            next += 1;
+        }
+    }
+
+    // Folded hydrogens come last, in the order of the atom that carries them.
+    let mut hydrogens: Vec<(u32, usize)> = (0..n)
+        .filter(|&i| graph.removable_h[i])
+        .map(|i| (ranks[graph_neighbour_of_h(molecule, i) as usize], i))
+        .collect();
+    hydrogens.sort_unstable();
+    for (offset, (_, h)) in hydrogens.into_iter().enumerate() {
+        ranks[h] = (atoms.len() + offset) as u32;
+    }
+    let final_labels = written.labels(n, &ranks);
+    let mut ring_parities: Vec<(u32, u8)> = (0..n)
+        .filter(|&atom| written.ring_relative[atom].is_some())
+        .map(|atom| (ranks[atom], final_labels[atom].0))
+        .collect();
+    ring_parities.sort_unstable();
+    Ranking {
+        ranks,
+        classes,
+        labels,
+        ring_parities,
+    }
+}
+
+/// What a ranking says of the molecule: its bonds, as `(rank, rank, bond
+/// code)` triples, and its written stereo, as `(rank, parity, geometry)`
+/// triples. Rankings with the same certificate write the same SMILES.
+type Certificate = (Vec<(u32, u32, u8)>, Vec<(u32, u8, u8)>);
+
+/// The classes of the atoms of a component in rank order, and its
+/// [`Certificate`]: components are ordered by it.
+type ComponentKey = (Vec<u32>, Certificate);
+
+/// A connected component renumbered from zero, with its own graph, stereo
+/// sites and stable partition.
+struct Component {
+    /// Atom of the molecule of every atom of the component.
+    atoms: Vec<usize>,
+    graph: WriterGraph,
+    stereo: StereoSites,
+    partition: Partition,
+    /// Class of every atom of the component in the whole molecule.
+    classes: Vec<u32>,
+}
+
+impl Component {
+    /// Restricts the molecule to `atoms`, one of its components, and
+    /// `classes`, a stable partition of the molecule, to those atoms.
+    fn new(
+        graph: &WriterGraph,
+        stereo: &StereoSites,
+        classes: &[u32],
+        atoms: Vec<usize>,
+    ) -> Component {
+        let mut local = vec![NodeIndex::MAX; graph.neighbours.len()];
+        for (i, &atom) in atoms.iter().enumerate() {
+            local[atom] = i as NodeIndex;
+        }
+        let neighbours: Vec<Vec<(NodeIndex, BondType)>> = atoms
+            .iter()
+            .map(|&atom| {
+                graph.neighbours[atom]
+                    .iter()
+                    .map(|&(v, kind)| {
+                        let kind = graph.effective_bond(atom as NodeIndex, v, kind);
+                        (local[v as usize], kind)
+                    })
+                    .collect()
+            })
+            .collect();
+        let k = atoms.len();
+        let graph = WriterGraph {
+            neighbours,
+            removable_h: vec![false; k],
+            virtual_h: vec![0; k],
+            effective_aromatic: vec![false; k],
+            aromatic_bonds: HashSet::new(),
+        };
+        let stereo = stereo.restricted(&local, &graph);
+        let classes: Vec<u32> = atoms.iter().map(|&a| classes[a]).collect();
+        // The restriction of a stable partition to a component is stable.
+        let partition = Partition::new(k, (0..k).collect(), |a| classes[a]);
+        Component {
+            atoms,
+            graph,
+            stereo,
+            partition,
+            classes,
+        }
+    }
+
+    /// Searches the canonical ranking of the component and returns its key
+    /// with its atoms in rank order.
+    fn canonical(self) -> (ComponentKey, Vec<usize>) {
+        let mut search = Search {
+            graph: &self.graph,
+            stereo: &self.stereo,
+            first: None,
+            best: None,
+            automorphisms: Vec::new(),
+        };
+        search.explore(self.partition, &mut Vec::new());
+        let best = search.best.expect("the search reaches a leaf");
+        let mut by_rank = vec![0; self.atoms.len()];
+        for (atom, &rank) in best.rank.iter().enumerate() {
+            by_rank[rank as usize] = atom;
+        }
+        let classes = by_rank.iter().map(|&a| self.classes[a]).collect();
+        let atoms = by_rank.into_iter().map(|a| self.atoms[a]).collect();
+        ((classes, best.certificate), atoms)
+    }
+}
+
+/// Most automorphisms kept by [`Search`] to prune its tree.
+const MAX_AUTOMORPHISMS: usize = 256;
+
+/// A ranking reached by the [`Search`], with the atoms promoted to reach it.
+struct Leaf {
+    path: Vec<usize>,
+    rank: Vec<u32>,
+    certificate: Certificate,
+}
+
+/// Individualization-refinement search for the ranking with the smallest
+/// [`Certificate`].
+///
+/// Each node of the search tree is a stable partition; its children promote
+/// in turn every atom of its first tied class and refine again, down to
+/// partitions where every atom has a rank of its own. Two leaves with the
+/// same certificate reveal an automorphism of the molecule, which is used
+/// to skip the children known to lead to the same certificates as one
+/// already explored.
+struct Search<'a> {
+    graph: &'a WriterGraph,
+    stereo: &'a StereoSites,
+    first: Option<Leaf>,
+    best: Option<Leaf>,
+    /// Automorphisms found so far, as atom → atom maps.
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    /// Explores the subtree of `partition`, reached by promoting `path`.
+    ///
+    /// Returns the level to go back to when a leaf turned out to be the
+    /// image of the first or best one: the subtree of the node at that
+    /// level which holds it is then the image of one already explored.
+    fn explore(&mut self, mut partition: Partition, path: &mut Vec<usize>) -> Option<usize> {
+        let start = loop {
+            let Some(start) = partition.first_tied_cell() else {
+                return self.leaf(partition, path);
+            };
+            if !partition.twins(self.graph, start, &self.stereo.near) {
+                break start;
+            }
+            partition.discretize(start);
+        };
+
+        let level = path.len();
+        let mut explored: Vec<usize> = Vec::new();
+        let mut orbits: Option<(usize, Vec<usize>)> = None;
+        for atom in partition.members(start) {
+            if !explored.is_empty() {
+                if orbits.as_ref().map(|o| o.0) != Some(self.automorphisms.len()) {
+                    orbits = Some((self.automorphisms.len(), self.orbits(path)));
+                }
+                let (_, orbit) = orbits.as_ref().expect("just computed");
+                if explored.iter().any(|&e| orbit[e] == orbit[atom]) {
+                    continue;
+                }
+            }
+            explored.push(atom);
+            let mut child = partition.clone();
+            let members = child.individualize(start, atom);
+            child.refine(self.graph, members);
+            path.push(atom);
+            let back = self.explore(child, path);
+            path.pop();
+            if let Some(back) = back.filter(|&back| back < level) {
+                return Some(back);
+            }
+        }
+        None
+    }
+
+    /// Compares the ranking of a discrete partition with the first and best
+    /// ones.
+    fn leaf(&mut self, partition: Partition, path: &[usize]) -> Option<usize> {
+        let certificate = self.stereo.certificate(self.graph, &partition.rank);
+        let leaf = Leaf {
+            path: path.to_vec(),
+            rank: partition.rank,
+            certificate,
+        };
+        let (Some(first), Some(best)) = (&self.first, &self.best) else {
+            self.first = Some(Leaf {
+                path: leaf.path.clone(),
+                rank: leaf.rank.clone(),
+                certificate: leaf.certificate.clone(),
+            });
+            self.best = Some(leaf);
+            return None;
+        };
+        for known in [first, best] {
+            if known.certificate == leaf.certificate {
+                let common = known
+                    .path
+                    .iter()
+                    .zip(&leaf.path)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let automorphism = automorphism(&known.rank, &leaf.rank);
+                if self.automorphisms.len() < MAX_AUTOMORPHISMS {
+                    self.automorphisms.push(automorphism);
+                }
+                return Some(common);
+            }
+        }
+        if leaf.certificate < best.certificate {
+            self.best = Some(leaf);
+        }
+        None
+    }
+
+    /// Orbits of the automorphisms found so far that fix every atom of
+    /// `path`, as the smallest atom of the orbit of every atom.
+    fn orbits(&self, path: &[usize]) -> Vec<usize> {
+        let n = self.graph.neighbours.len();
+        let mut parent: Vec<usize> = (0..n).collect();
+        fn root(parent: &mut [usize], mut a: usize) -> usize {
+            while parent[a] != a {
+                parent[a] = parent[parent[a]];
+                a = parent[a];
+            }
+            a
+        }
+        for automorphism in &self.automorphisms {
+            if path.iter().any(|&p| automorphism[p] != p) {
+                continue;
+            }
+            for (a, &b) in automorphism.iter().enumerate() {
+                let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                if ra != rb {
+                    parent[ra.max(rb)] = ra.min(rb);
+                }
+            }
+        }
+        (0..n).map(|a| root(&mut parent, a)).collect()
+    }
+}
+
+/// The map that sends the atom of every rank in `from` to the atom of the
+/// same rank in `to`. Atoms without a rank are left in place.
+fn automorphism(from: &[u32], to: &[u32]) -> Vec<usize> {
+    let mut by_rank = vec![usize::MAX; to.len()];
+    for (atom, &rank) in to.iter().enumerate() {
+        if rank != u32::MAX {
+            by_rank[rank as usize] = atom;
+        }
+    }
+    from.iter()
+        .enumerate()
+        .map(|(atom, &rank)| {
+            if rank == u32::MAX {
+                atom
+            } else {
+                by_rank[rank as usize]
+            }
+        })
+        .collect()
+}
+
+/// One atom of a stereo double bond: its written substituents and the side
+/// of its first marked neighbour.
+#[derive(Clone)]
+struct DoubleBondEnd {
+    atom: NodeIndex,
+    substituents: Vec<NodeIndex>,
+    marked: NodeIndex,
+    side: bool,
+}
+
+/// The tetrahedral centres and stereo double bonds of a molecule, prepared
+/// once so that their descriptors can be read against any ranks.
+struct StereoSites {
+    /// Tetrahedral centres with their chirality and reference neighbour
+    /// order, the centre standing for its implicit hydrogen.
+    centres: Vec<(NodeIndex, Chirality, Vec<NodeIndex>)>,
+    double_bonds: Vec<[DoubleBondEnd; 2]>,
+    /// Ring system of the ring-relative centres (see
+    /// [`Molecule::written_stereo_centres`]), once only written sites are
+    /// kept.
+    ring_relative: Vec<Option<usize>>,
+    /// Atoms whose rank the descriptors depend on.
+    near: Vec<bool>,
+}
+
+impl StereoSites {
+    fn new(molecule: &Molecule, graph: &WriterGraph) -> StereoSites {
+        let n = molecule.nodes().len();
+        let incident = molecule.incident_bonds();
+        let mut centres = Vec::new();
+        for (atom, node) in molecule.nodes().iter().enumerate() {
+            let Some(chirality) = node
+                .chirality()
+                .filter(|&c| stereo::is_tetrahedral(Some(c)))
+            else {
+                continue;
+            };
+            if graph.removable_h[atom] {
+                continue;
+            }
+            let center = atom as NodeIndex;
+            let bonds = &incident[atom];
+            let degree = stereo::reference_order(center, bonds, false).len();
+            let implicit = node.hydrogens() > 0 || degree == 3;
+            let reference = stereo::reference_order(center, bonds, implicit)
+                .into_iter()
+                .map(|v| {
+                    if graph.removable_h[v as usize] {
+                        center
+                    } else {
+                        v
+                    }
+                })
+                .collect();
+            centres.push((center, chirality, reference));
+        }
+
+        // Side of the first marked substituent of `center`, as stored.
+        let end = |center: NodeIndex, other: NodeIndex| {
+            let (marked, side) = incident[center as usize].iter().find_map(|bond| {
+                let neighbour = if bond.source() == center {
+                    bond.target()
+                } else {
+                    bond.source()
+                };
+                if neighbour == other {
+                    return None;
+                }
+                stereo::side_of(bond, center).map(|side| (neighbour, side))
+            })?;
+            let substituents: Vec<NodeIndex> = graph.neighbours[center as usize]
+                .iter()
+                .map(|&(v, _)| v)
+                .filter(|&v| v != other)
+                .collect();
+            (!substituents.is_empty()).then_some(DoubleBondEnd {
+                atom: center,
+                substituents,
+                marked,
+                side,
+            })
+        };
+        let double_bonds = molecule
+            .bonds()
+            .iter()
+            .filter(|bond| {
+                bond.kind() == BondType::Double
+                    && graph.effective_bond(bond.source(), bond.target(), bond.kind())
+                        == BondType::Double
+            })
+            .filter_map(|bond| {
+                let (a, b) = (bond.source(), bond.target());
+                Some([end(a, b)?, end(b, a)?])
+            })
+            .collect();
+
+        let mut sites = StereoSites {
+            centres,
+            double_bonds,
+            ring_relative: vec![None; n],
+            near: vec![false; n],
+        };
+        sites.mark_near(graph);
+        sites
+    }
+
+    /// Keeps the sites the SMILES writer outputs.
+    fn written(&self, molecule: &Molecule, graph: &WriterGraph) -> StereoSites {
+        let (suppressed, ring_relative) = molecule.written_stereo_centres(graph);
+        let mut sites = StereoSites {
+            centres: self
+                .centres
+                .iter()
+                .filter(|(atom, _, _)| !suppressed[*atom as usize])
+                .cloned()
+                .collect(),
+            double_bonds: self
+                .double_bonds
+                .iter()
+                .filter(|[a, b]| !molecule.has_symmetric_side(graph, a.atom, b.atom))
+                .map(|[a, b]| [a.clone(), b.clone()])
+                .collect(),
+            ring_relative,
+            near: vec![false; molecule.nodes().len()],
+        };
+        sites.mark_near(graph);
+        sites
+    }
+
+    /// Renumbers the sites of a component with `local`, the number of every
+    /// atom in the component or `NodeIndex::MAX` for the other atoms, and
+    /// drops the sites of the other components.
+    fn restricted(&self, local: &[NodeIndex], graph: &WriterGraph) -> StereoSites {
+        let map = |v: NodeIndex| local[v as usize];
+        let end = |end: &DoubleBondEnd| DoubleBondEnd {
+            atom: map(end.atom),
+            substituents: end.substituents.iter().map(|&v| map(v)).collect(),
+            marked: map(end.marked),
+            side: end.side,
+        };
+        let k = graph.neighbours.len();
+        let mut ring_relative = vec![None; k];
+        for (atom, &system) in self.ring_relative.iter().enumerate() {
+            if map(atom as NodeIndex) != NodeIndex::MAX {
+                ring_relative[map(atom as NodeIndex) as usize] = system;
+            }
+        }
+        let mut sites = StereoSites {
+            centres: self
+                .centres
+                .iter()
+                .filter(|(atom, _, _)| map(*atom) != NodeIndex::MAX)
+                .map(|(atom, chirality, reference)| {
+                    (
+                        map(*atom),
+                        *chirality,
+                        reference.iter().map(|&v| map(v)).collect(),
+                    )
+                })
+                .collect(),
+            double_bonds: self
+                .double_bonds
+                .iter()
+                .filter(|[a, _]| map(a.atom) != NodeIndex::MAX)
+                .map(|[a, b]| [end(a), end(b)])
+                .collect(),
+            ring_relative,
+            near: vec![false; k],
+        };
+        sites.mark_near(graph);
+        sites
+    }
+
+    fn mark_near(&mut self, graph: &WriterGraph) {
+        let atoms = self.centres.iter().map(|&(atom, _, _)| atom).chain(
+            self.double_bonds
+                .iter()
+                .flat_map(|ends| ends.iter().map(|e| e.atom)),
+        );
+        let near = &mut self.near;
+        for atom in atoms {
+            near[atom as usize] = true;
+            for &(v, _) in &graph.neighbours[atom as usize] {
+                near[v as usize] = true;
+            }
+        }
+    }
+
+    /// Computes a stereo descriptor for every atom from `rank`.
+    ///
+    /// A tetrahedral centre gets the parity of its neighbours sorted by rank
+    /// (the implicit hydrogen first); a stereo double bond gets cis or trans
+    /// between the highest-ranked substituents of its two atoms. Descriptors
+    /// that depend on tied neighbours are left at zero.
+    fn labels(&self, n: usize, rank: &[u32]) -> Vec<(u8, u8)> {
+        let mut labels = vec![(0, 0); n];
+        for (center, chirality, reference) in &self.centres {
+            let key = |v: NodeIndex| {
+                if v == *center {
+                    0
+                } else {
+                    rank[v as usize] as u64 + 1
+                }
+            };
+            let mut sorted = reference.clone();
+            sorted.sort_by_key(|&v| key(v));
+            if sorted.windows(2).any(|w| key(w[0]) == key(w[1])) {
+                continue;
+            }
+            if let Some(odd) = stereo::is_odd_permutation(reference, &sorted) {
+                let parity = if odd {
+                    stereo::invert(*chirality)
+                } else {
+                    *chirality
+                };
+                labels[*center as usize].0 = if parity == Chirality::TH1 { 1 } else { 2 };
+            }
+        }
+
+        for [a, b] in &self.double_bonds {
+            let (Some(side_a), Some(side_b)) = (a.top_side(rank), b.top_side(rank)) else {
+                continue;
+            };
+            let label = if side_a == side_b { 1 } else { 2 };
+            labels[a.atom as usize].1 = label;
+            labels[b.atom as usize].1 = label;
+        }
+        labels
+    }
+
+    /// The [`Certificate`] of distinct ranks.
+    fn certificate(&self, graph: &WriterGraph, rank: &[u32]) -> Certificate {
+        let mut bonds: Vec<(u32, u32, u8)> = Vec::new();
+        for (atom, neighbours) in graph.neighbours.iter().enumerate() {
+            for &(v, kind) in neighbours {
+                if rank[atom] < rank[v as usize] {
+                    let code = bond_code(graph.effective_bond(atom as NodeIndex, v, kind));
+                    bonds.push((rank[atom], rank[v as usize], code));
+                }
+            }
+        }
+        bonds.sort_unstable();
+
+        let mut stereo: Vec<(u32, u8, u8)> = self
+            .labels(rank.len(), rank)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &label)| label != (0, 0))
+            .map(|(atom, &(parity, geometry))| (rank[atom], parity, geometry))
+            .collect();
+        stereo.sort_unstable();
+        (bonds, stereo)
+    }
+}
+
+impl DoubleBondEnd {
+    /// Side of the highest-ranked substituent, or `None` when it is tied.
+    fn top_side(&self, rank: &[u32]) -> Option<bool> {
+        let top = *self
+            .substituents
+            .iter()
+            .max_by_key(|&&v| rank[v as usize])?;
+        if self
+            .substituents
+            .iter()
+            .any(|&v| v != top && rank[v as usize] == rank[top as usize])
+        {
+            return None;
+        }
+        Some(if self.marked == top {
+            self.side
+        } else {
+            !self.side
+        })
+    }
+}
+
+/// Returns the heavy atom a removable hydrogen is bonded to.
+fn graph_neighbour_of_h(molecule: &Molecule, h: usize) -> NodeIndex {
+    molecule
+        .bonds()
+        .iter()
+        .find_map(|bond| {
+            if bond.source() as usize == h {
+                Some(bond.target())
+            } else if bond.target() as usize == h {
+                Some(bond.source())
+            } else {
+                None
+            }
+        })
+        .unwrap_or(h as NodeIndex)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::ast::stereo::{invert, is_odd_permutation, is_tetrahedral, reference_order};
+    use crate::{parse, Bond, Molecule};
+
+    /// Rebuilds `molecule` with its atoms renumbered by `perm` (old → new)
+    /// and its bond list reversed, keeping every tetrahedral centre.
+    pub(crate) fn permuted(molecule: &Molecule, perm: &[usize]) -> Molecule {
+        let bonds: Vec<Bond> = molecule
+            .bonds()
+            .iter()
+            .rev()
+            .map(|b| {
+                Bond::new(
+                    b.kind(),
+                    perm[b.source() as usize] as u32,
+                    perm[b.target() as usize] as u32,
+                )
+            })
+            .collect();
+        let mut nodes = vec![None; perm.len()];
+        for (old, node) in molecule.nodes().iter().enumerate() {
+            let mut chirality = node.chirality();
+            if is_tetrahedral(chirality) {
+                let new = perm[old] as u32;
+                let degree = reference_order(old as u32, molecule.bonds(), false).len();
+                let implicit = node.hydrogens() > 0 || degree == 3;
+                let before: Vec<u32> = reference_order(old as u32, molecule.bonds(), implicit)
+                    .into_iter()
+                    .map(|v| perm[v as usize] as u32)
+                    .collect();
+                let after = reference_order(new, &bonds, implicit);
+                if is_odd_permutation(&before, &after) == Some(true) {
+                    chirality = chirality.map(invert);
+                }
+            }
+            nodes[perm[old]] = Some(node.with_hydrogens(node.hydrogens(), chirality));
+        }
+        Molecule::new(nodes.into_iter().map(Option::unwrap).collect(), bonds)
+    }
+
+    /// Deterministic pseudo-random permutations (xorshift).
+    pub(crate) fn permutations(n: usize, count: usize) -> Vec<Vec<usize>> {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut out = Vec::new();
+        for _ in 0..count {
+            let mut perm: Vec<usize> = (0..n).collect();
+            for i in (1..n).rev() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                perm.swap(i, (state % (i as u64 + 1)) as usize);
+            }
+            out.push(perm);
+        }
+        out
+    }
+
+    #[test]
+    fn canonical_smiles_is_invariant_under_atom_renumbering() {
+        for smiles in [
+            "CC(=O)Oc1ccccc1C(=O)O",
+            "CN1C=NC2=C1C(=O)N(C(=O)N2C)C",
+            "c1ccc2ccccc2c1",
+            "C1CC2CCC1CC2",
+            "C12C3C4C1C5C2C3C45",
+            "OC(=O)CC(O)(CC(=O)O)C(=O)O",
+            "[NH4+].[Cl-].CCO",
+            "C1CCC2(CC1)CCCC2",
+            "c1ccc(cc1)-c1ccccc1",
+            "C1CC1.C1CCCCC1",
+            "C1CCC1.C1CCC1.C1CCCCCCC1",
+            "C12C3C4C1C5C2C3C45.C1CCC2CCCCC2C1",
+            "O.O.CC.C1CC1",
+            "C12C3C4C(C43)C5C1C25",
+            "C[C@H]1C[C@@H](C)C[C@H](C)C1",
+            "C[C@@H]1C[C@H](C)C[C@H](C)C1",
+            "C[C@H]1CC[C@@H](C)CC1",
+            "C1CC[C@H]2CCCC[C@@H]2C1",
+            "C1CC[C@H]2CCCC[C@H]2C1",
+            "C[C@H]1C[C@@H](C)C[C@H](C)C1.C[C@@H]1C[C@H](C)C[C@H](C)C1",
+            "C[C@H]1CC2(CC1)CC[C@H](C)CC2",
+            "C[C@H]1C[C@H]2C[C@@H](C)C[C@@H]2C1",
+            "F/C=C/C=C/C=C\\F",
+            "C[C@@H](O)[C@H](O)C",
+        ] {
+            let mol = parse(smiles).unwrap();
+            let expected = mol.to_canonical_smiles();
+            assert_eq!(
+                parse(&expected).unwrap().to_canonical_smiles(),
+                expected,
+                "{smiles}"
+            );
+            for perm in permutations(mol.nodes().len(), 20) {
+                assert_eq!(
+                    permuted(&mol, &perm).to_canonical_smiles(),
+                    expected,
+                    "{smiles} with permutation {perm:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_smiles_of_regular_graphs_is_invariant_under_atom_renumbering() {
+        // Cubic graphs that are not vertex-transitive: refinement leaves
+        // atoms tied that no symmetry relates.
+        for smiles in [
+            "C12C3C4C(C43)C5C1C25",
+            "C12C3C(C32)C4C5C4C15",
+            "C12C3C(C32)C4C5C1C45",
+            "C12C3C1C4C5C4C6C(C56)C32",
+            "C12C3C4C(C42)C5C6C(C13)C56",
+        ] {
+            let mol = parse(smiles).unwrap();
+            let expected = mol.to_canonical_smiles();
+            for perm in permutations(mol.nodes().len(), 50) {
+                assert_eq!(
+                    permuted(&mol, &perm).to_canonical_smiles(),
+                    expected,
+                    "{smiles} with permutation {perm:?}"
+                );
+            }
+        }
+        assert_eq!(
+            canonical("C12C3C4C(C43)C5C1C25"),
+            canonical("C12C3C4C5C4C5C1C23")
+        );
+    }
+
+    #[test]
+    fn canonical_smiles_tells_ring_stereoisomers_apart() {
+        // Every assignment of the `C{i}` centres, against the number of
+        // distinct stereoisomers.
+        for (scaffold, isomers) in [
+            // Inositols
+            ("OC{0}1C{1}(O)C{2}(O)C{3}(O)C{4}(O)C{5}1O", 9),
+            // 1,3,5-trimethylcyclohexane
+            ("CC{0}1CC{1}(C)CC{2}(C)C1", 2),
+            // 1,2,3,4-tetramethylcyclopentane
+            ("CC{0}1CC{1}(C)C{2}(C)C{3}1C", 10),
+            // 3,7-dimethylbicyclo[3.3.0]octane
+            ("CC{0}1CC{1}2CC{2}(C)CC{3}2C1", 5),
+        ] {
+            let k = scaffold.matches('{').count();
+            let mut written = std::collections::BTreeSet::new();
+            for mask in 0..1u32 << k {
+                let mut smiles = scaffold.to_string();
+                for i in 0..k {
+                    let centre = if mask >> i & 1 == 1 {
+                        "[C@@H]"
+                    } else {
+                        "[C@H]"
+                    };
+                    smiles = smiles.replace(&format!("C{{{i}}}"), centre);
+                }
+                let mol = parse(&smiles).unwrap();
+                let expected = mol.to_canonical_smiles();
+                assert_eq!(canonical(&expected), expected, "{smiles}");
+                for perm in permutations(mol.nodes().len(), 4) {
+                    assert_eq!(
+                        permuted(&mol, &perm).to_canonical_smiles(),
+                        expected,
+                        "{smiles} with permutation {perm:?}"
+                    );
+                }
+                written.insert(expected);
+            }
+            assert_eq!(written.len(), isomers, "{scaffold}");
+        }
+    }
+
+    fn canonical(smiles: &str) -> String {
+        parse(smiles).unwrap().to_canonical_smiles()
+    }
+
+    #[test]
+    fn ranks_are_distinct() {
+        let mol = parse("C1CCCCC1").unwrap();
+        let mut ranks = mol.canonical_ranks();
+        ranks.sort_unstable();
+        assert_eq!(ranks, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn folded_hydrogens_are_ranked_last() {
+        let mol = parse("[H]OC").unwrap();
+        assert_eq!(mol.canonical_ranks()[0], 2);
+    }
+}
@@ -637,28 +637,569 @@ impl OrganicAtom {
     }
 }
 
-/// Returns the mass of a specific isotope.
-/// For common isotopes, returns the known mass; otherwise, uses the mass number as approximation.
+/// A nuclide with its exact mass and natural abundance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Isotope {
+    pub atomic_number: u8,
+    pub mass_number: u16,
+    /// Exact mass in Daltons.
+    pub mass: f64,
+    /// Natural abundance as a mole fraction (0 for radioactive nuclides).
+    pub abundance: f64,
+}
+
+const fn iso(atomic_number: u8, mass_number: u16, mass: f64, abundance: f64) -> Isotope {
+    Isotope {
+        atomic_number,
+        mass_number,
+        mass,
+        abundance,
+    }
+}
+
+/// Isotopic compositions from the NIST "Atomic Weights and Isotopic
+/// Compositions" tables: every naturally occurring isotope, the long-lived
+/// nuclides of radioactive elements and common radiolabels. Sorted by
+/// atomic number, then mass number.
+#[rustfmt::skip]
+const ISOTOPES: &[Isotope] = &[
+    iso(1, 1, 1.00782503223, 0.999885),
+    iso(1, 2, 2.01410177812, 0.000115),
+    iso(1, 3, 3.0160492779, 0.0),
+    iso(2, 3, 3.0160293201, 0.00000134),
+    iso(2, 4, 4.00260325413, 0.99999866),
+    iso(3, 6, 6.0151228874, 0.0759),
+    iso(3, 7, 7.0160034366, 0.9241),
+    iso(4, 9, 9.012183065, 1.0),
+    iso(4, 10, 10.013534695, 0.0),
+    iso(5, 10, 10.01293695, 0.199),
+    iso(5, 11, 11.00930536, 0.801),
+    iso(6, 11, 11.0114336, 0.0),
+    iso(6, 12, 12.0, 0.9893),
+    iso(6, 13, 13.00335483507, 0.0107),
+    iso(6, 14, 14.0032419884, 0.0),
+    iso(7, 13, 13.00573861, 0.0),
+    iso(7, 14, 14.00307400443, 0.99636),
+    iso(7, 15, 15.00010889888, 0.00364),
+    iso(8, 15, 15.0030656, 0.0),
+    iso(8, 16, 15.99491461957, 0.99757),
+    iso(8, 17, 16.9991317565, 0.00038),
+    iso(8, 18, 17.99915961286, 0.00205),
+    iso(9, 18, 18.000938, 0.0),
+    iso(9, 19, 18.99840316273, 1.0),
+    iso(10, 20, 19.9924401762, 0.9048),
+    iso(10, 21, 20.993846685, 0.0027),
+    iso(10, 22, 21.991385114, 0.0925),
+    iso(11, 22, 21.99443742, 0.0),
+    iso(11, 23, 22.989769282, 1.0),
+    iso(12, 24, 23.985041697, 0.7899),
+    iso(12, 25, 24.985836976, 0.1),
+    iso(12, 26, 25.982592968, 0.1101),
+    iso(13, 27, 26.98153853, 1.0),
+    iso(14, 28, 27.97692653465, 0.92223),
+    iso(14, 29, 28.9764946649, 0.04685),
+    iso(14, 30, 29.973770136, 0.03092),
+    iso(15, 31, 30.97376199842, 1.0),
+    iso(15, 32, 31.97390764, 0.0),
+    iso(15, 33, 32.9717257, 0.0),
+    iso(16, 32, 31.9720711744, 0.9499),
+    iso(16, 33, 32.9714589098, 0.0075),
+    iso(16, 34, 33.967867004, 0.0425),
+    iso(16, 35, 34.96903231, 0.0),
+    iso(16, 36, 35.96708071, 0.0001),
+    iso(17, 35, 34.968852682, 0.7576),
+    iso(17, 36, 35.968306822, 0.0),
+    iso(17, 37, 36.965902602, 0.2424),
+    iso(18, 36, 35.967545105, 0.003336),
+    iso(18, 38, 37.96273211, 0.000629),
+    iso(18, 40, 39.9623831237, 0.996035),
+    iso(19, 39, 38.9637064864, 0.932581),
+    iso(19, 40, 39.963998166, 0.000117),
+    iso(19, 41, 40.9618252579, 0.067302),
+    iso(20, 40, 39.962590863, 0.96941),
+    iso(20, 42, 41.95861783, 0.00647),
+    iso(20, 43, 42.95876644, 0.00135),
+    iso(20, 44, 43.95548156, 0.02086),
+    iso(20, 45, 44.95618635, 0.0),
+    iso(20, 46, 45.953689, 0.00004),
+    iso(20, 48, 47.95252276, 0.00187),
+    iso(21, 45, 44.95590828, 1.0),
+    iso(22, 46, 45.95262772, 0.0825),
+    iso(22, 47, 46.95175879, 0.0744),
+    iso(22, 48, 47.94794198, 0.7372),
+    iso(22, 49, 48.94786568, 0.0541),
+    iso(22, 50, 49.94478689, 0.0518),
+    iso(23, 50, 49.94715601, 0.0025),
+    iso(23, 51, 50.94395704, 0.9975),
+    iso(24, 50, 49.94604183, 0.04345),
+    iso(24, 51, 50.94476502, 0.0),
+    iso(24, 52, 51.94050623, 0.83789),
+    iso(24, 53, 52.94064815, 0.09501),
+    iso(24, 54, 53.93887916, 0.02365),
+    iso(25, 54, 53.9403576, 0.0),
+    iso(25, 55, 54.93804391, 1.0),
+    iso(26, 54, 53.93960899, 0.05845),
+    iso(26, 56, 55.93493633, 0.91754),
+    iso(26, 57, 56.93539284, 0.02119),
+    iso(26, 58, 57.93327443, 0.00282),
+    iso(26, 59, 58.93487434, 0.0),
+    iso(27, 57, 56.93629057, 0.0),
+    iso(27, 59, 58.93319429, 1.0),
+    iso(27, 60, 59.9338163, 0.0),
+    iso(28, 58, 57.93534241, 0.68077),
+    iso(28, 60, 59.93078588, 0.26223),
+    iso(28, 61, 60.93105557, 0.011399),
+    iso(28, 62, 61.92834537, 0.036346),
+    iso(28, 63, 62.92966963, 0.0),
+    iso(28, 64, 63.92796682, 0.009255),
+    iso(29, 63, 62.92959772, 0.6915),
+    iso(29, 64, 63.92976434, 0.0),
+    iso(29, 65, 64.9277897, 0.3085),
+    iso(30, 64, 63.92914201, 0.4917),
+    iso(30, 65, 64.92924077, 0.0),
+    iso(30, 66, 65.92603381, 0.2773),
+    iso(30, 67, 66.92712775, 0.0404),
+    iso(30, 68, 67.92484455, 0.1845),
+    iso(30, 70, 69.9253192, 0.0061),
+    iso(31, 67, 66.9282025, 0.0),
+    iso(31, 68, 67.9279805, 0.0),
+    iso(31, 69, 68.9255735, 0.60108),
+    iso(31, 71, 70.92470258, 0.39892),
+    iso(32, 68, 67.9280953, 0.0),
+    iso(32, 70, 69.92424875, 0.2057),
+    iso(32, 72, 71.922075826, 0.2745),
+    iso(32, 73, 72.923458956, 0.0775),
+    iso(32, 74, 73.921177761, 0.365),
+    iso(32, 76, 75.921402726, 0.0773),
+    iso(33, 73, 72.9238291, 0.0),
+    iso(33, 74, 73.9239286, 0.0),
+    iso(33, 75, 74.92159457, 1.0),
+    iso(34, 74, 73.922475934, 0.0089),
+    iso(34, 75, 74.92252287, 0.0),
+    iso(34, 76, 75.919213704, 0.0937),
+    iso(34, 77, 76.919914154, 0.0763),
+    iso(34, 78, 77.91730928, 0.2377),
+    iso(34, 79, 78.91849929, 0.0),
+    iso(34, 80, 79.9165218, 0.4961),
+    iso(34, 82, 81.9166995, 0.0873),
+    iso(35, 76, 75.924542, 0.0),
+    iso(35, 77, 76.9213792, 0.0),
+    iso(35, 79, 78.9183376, 0.5069),
+    iso(35, 81, 80.9162897, 0.4931),
+    iso(35, 82, 81.9168032, 0.0),
+    iso(36, 78, 77.92036494, 0.00355),
+    iso(36, 80, 79.91637808, 0.02286),
+    iso(36, 81, 80.9165912, 0.0),
+    iso(36, 82, 81.91348273, 0.11593),
+    iso(36, 83, 82.91412716, 0.115),
+    iso(36, 84, 83.9114977282, 0.56987),
+    iso(36, 85, 84.9125273, 0.0),
+    iso(36, 86, 85.9106106269, 0.17279),
+    iso(37, 82, 81.918209, 0.0),
+    iso(37, 85, 84.9117897379, 0.7217),
+    iso(37, 86, 85.91116743, 0.0),
+    iso(37, 87, 86.909180531, 0.2783),
+    iso(38, 82, 81.9183999, 0.0),
+    iso(38, 84, 83.9134191, 0.0056),
+    iso(38, 85, 84.912932, 0.0),
+    iso(38, 86, 85.9092606, 0.0986),
+    iso(38, 87, 86.9088775, 0.07),
+    iso(38, 88, 87.9056125, 0.8258),
+    iso(38, 89, 88.9074511, 0.0),
+    iso(38, 90, 89.90773, 0.0),
+    iso(39, 86, 85.914886, 0.0),
+    iso(39, 88, 87.9095016, 0.0),
+    iso(39, 89, 88.9058403, 1.0),
+    iso(39, 90, 89.9071439, 0.0),
+    iso(40, 89, 88.9088814, 0.0),
+    iso(40, 90, 89.9046977, 0.5145),
+    iso(40, 91, 90.9056396, 0.1122),
+    iso(40, 92, 91.9050347, 0.1715),
+    iso(40, 94, 93.9063108, 0.1738),
+    iso(40, 96, 95.9082714, 0.028),
+    iso(41, 93, 92.906373, 1.0),
+    iso(41, 95, 94.9068324, 0.0),
+    iso(42, 92, 91.90680796, 0.1453),
+    iso(42, 94, 93.9050849, 0.0915),
+    iso(42, 95, 94.90583877, 0.1584),
+    iso(42, 96, 95.90467612, 0.1667),
+    iso(42, 97, 96.90601812, 0.096),
+    iso(42, 98, 97.90540482, 0.2439),
+    iso(42, 99, 98.90770851, 0.0),
+    iso(42, 100, 99.9074718, 0.0982),
+    iso(43, 97, 96.9063667, 0.0),
+    iso(43, 98, 97.9072124, 0.0),
+    iso(43, 99, 98.9062508, 0.0),
+    iso(44, 96, 95.90759025, 0.0554),
+    iso(44, 97, 96.9075471, 0.0),
+    iso(44, 98, 97.9052868, 0.0187),
+    iso(44, 99, 98.9059341, 0.1276),
+    iso(44, 100, 99.9042143, 0.126),
+    iso(44, 101, 100.9055769, 0.1706),
+    iso(44, 102, 101.9043441, 0.3155),
+    iso(44, 103, 102.9063186, 0.0),
+    iso(44, 104, 103.9054275, 0.1862),
+    iso(44, 106, 105.9073291, 0.0),
+    iso(45, 103, 102.905498, 1.0),
+    iso(45, 105, 104.9056885, 0.0),
+    iso(46, 102, 101.9056022, 0.0102),
+    iso(46, 103, 102.9060809, 0.0),
+    iso(46, 104, 103.9040305, 0.1114),
+    iso(46, 105, 104.9050796, 0.2233),
+    iso(46, 106, 105.9034804, 0.2733),
+    iso(46, 108, 107.9038916, 0.2646),
+    iso(46, 109, 108.9059504, 0.0),
+    iso(46, 110, 109.9051722, 0.1172),
+    iso(47, 107, 106.9050916, 0.51839),
+    iso(47, 109, 108.9047553, 0.48161),
+    iso(47, 110, 109.9061102, 0.0),
+    iso(47, 111, 110.9052959, 0.0),
+    iso(48, 106, 105.9064599, 0.0125),
+    iso(48, 108, 107.9041834, 0.0089),
+    iso(48, 109, 108.9049867, 0.0),
+    iso(48, 110, 109.90300661, 0.1249),
+    iso(48, 111, 110.90418287, 0.128),
+    iso(48, 112, 111.90276287, 0.2413),
+    iso(48, 113, 112.90440813, 0.1222),
+    iso(48, 114, 113.90336509, 0.2873),
+    iso(48, 116, 115.90476315, 0.0749),
+    iso(49, 111, 110.9051085, 0.0),
+    iso(49, 113, 112.90406184, 0.0429),
+    iso(49, 115, 114.903878776, 0.9571),
+    iso(50, 112, 111.90482387, 0.0097),
+    iso(50, 113, 112.9051757, 0.0),
+    iso(50, 114, 113.9027827, 0.0066),
+    iso(50, 115, 114.903344699, 0.0034),
+    iso(50, 116, 115.9017428, 0.1454),
+    iso(50, 117, 116.90295398, 0.0768),
+    iso(50, 118, 117.90160657, 0.2422),
+    iso(50, 119, 118.90331117, 0.0859),
+    iso(50, 120, 119.90220163, 0.3258),
+    iso(50, 122, 121.9034438, 0.0463),
+    iso(50, 124, 123.9052766, 0.0579),
+    iso(51, 121, 120.903812, 0.5721),
+    iso(51, 123, 122.9042132, 0.4279),
+    iso(51, 124, 123.9059350, 0.0),
+    iso(51, 125, 124.905253, 0.0),
+    iso(52, 120, 119.9040593, 0.0009),
+    iso(52, 122, 121.9030435, 0.0255),
+    iso(52, 123, 122.9042698, 0.0089),
+    iso(52, 124, 123.9028171, 0.0474),
+    iso(52, 125, 124.9044299, 0.0707),
+    iso(52, 126, 125.9033109, 0.1884),
+    iso(52, 128, 127.90446128, 0.3174),
+    iso(52, 130, 129.906222748, 0.3408),
+    iso(53, 123, 122.9055898, 0.0),
+    iso(53, 124, 123.9062091, 0.0),
+    iso(53, 125, 124.9046294, 0.0),
+    iso(53, 127, 126.9044719, 1.0),
+    iso(53, 129, 128.9049837, 0.0),
+    iso(53, 131, 130.9061263, 0.0),
+    iso(54, 124, 123.905892, 0.000952),
+    iso(54, 126, 125.9042983, 0.00089),
+    iso(54, 128, 127.903531, 0.019102),
+    iso(54, 129, 128.9047808611, 0.264006),
+    iso(54, 130, 129.903509349, 0.04071),
+    iso(54, 131, 130.90508406, 0.212324),
+    iso(54, 132, 131.9041550856, 0.269086),
+    iso(54, 133, 132.9059108, 0.0),
+    iso(54, 134, 133.90539466, 0.104357),
+    iso(54, 136, 135.907214484, 0.088573),
+    iso(55, 133, 132.905451961, 1.0),
+    iso(55, 134, 133.906718503, 0.0),
+    iso(55, 137, 136.9070895, 0.0),
+    iso(56, 130, 129.9063207, 0.00106),
+    iso(56, 132, 131.9050611, 0.00101),
+    iso(56, 133, 132.9060074, 0.0),
+    iso(56, 134, 133.90450818, 0.02417),
+    iso(56, 135, 134.90568838, 0.06592),
+    iso(56, 136, 135.90457573, 0.07854),
+    iso(56, 137, 136.90582714, 0.11232),
+    iso(56, 138, 137.905247, 0.71698),
+    iso(57, 138, 137.9071149, 0.0008881),
+    iso(57, 139, 138.9063563, 0.9991119),
+    iso(58, 136, 135.90712921, 0.00185),
+    iso(58, 138, 137.905991, 0.00251),
+    iso(58, 140, 139.9054431, 0.8845),
+    iso(58, 142, 141.9092504, 0.11114),
+    iso(59, 141, 140.9076576, 1.0),
+    iso(60, 142, 141.907729, 0.27152),
+    iso(60, 143, 142.90982, 0.12174),
+    iso(60, 144, 143.910093, 0.23798),
+    iso(60, 145, 144.9125793, 0.08293),
+    iso(60, 146, 145.9131226, 0.17189),
+    iso(60, 148, 147.9168993, 0.05756),
+    iso(60, 150, 149.9209022, 0.05638),
+    iso(61, 145, 144.9127559, 0.0),
+    iso(61, 147, 146.915145, 0.0),
+    iso(62, 144, 143.9120065, 0.0307),
+    iso(62, 147, 146.9149044, 0.1499),
+    iso(62, 148, 147.9148292, 0.1124),
+    iso(62, 149, 148.9171921, 0.1382),
+    iso(62, 150, 149.9172829, 0.0738),
+    iso(62, 152, 151.9197397, 0.2675),
+    iso(62, 153, 152.9221047, 0.0),
+    iso(62, 154, 153.9222169, 0.2275),
+    iso(63, 151, 150.9198578, 0.4781),
+    iso(63, 152, 151.9217522, 0.0),
+    iso(63, 153, 152.921238, 0.5219),
+    iso(64, 152, 151.9197995, 0.002),
+    iso(64, 153, 152.9217580, 0.0),
+    iso(64, 154, 153.9208741, 0.0218),
+    iso(64, 155, 154.9226305, 0.148),
+    iso(64, 156, 155.9221312, 0.2047),
+    iso(64, 157, 156.9239686, 0.1565),
+    iso(64, 158, 157.9241123, 0.2484),
+    iso(64, 160, 159.9270624, 0.2186),
+    iso(65, 159, 158.9253547, 1.0),
+    iso(65, 161, 160.9275778, 0.0),
+    iso(66, 156, 155.9242847, 0.00056),
+    iso(66, 158, 157.9244159, 0.00095),
+    iso(66, 160, 159.9252046, 0.02329),
+    iso(66, 161, 160.9269405, 0.18889),
+    iso(66, 162, 161.9268056, 0.25475),
+    iso(66, 163, 162.9287383, 0.24896),
+    iso(66, 164, 163.9291819, 0.2826),
+    iso(66, 165, 164.9317105, 0.0),
+    iso(67, 165, 164.9303288, 1.0),
+    iso(67, 166, 165.9322909, 0.0),
+    iso(68, 162, 161.9287884, 0.00139),
+    iso(68, 164, 163.9292088, 0.01601),
+    iso(68, 166, 165.9302995, 0.33503),
+    iso(68, 167, 166.9320546, 0.22869),
+    iso(68, 168, 167.9323767, 0.26978),
+    iso(68, 169, 168.9345968, 0.0),
+    iso(68, 170, 169.9354702, 0.1491),
+    iso(69, 169, 168.9342179, 1.0),
+    iso(69, 170, 169.9358060, 0.0),
+    iso(70, 168, 167.9338896, 0.00123),
+    iso(70, 169, 168.9351825, 0.0),
+    iso(70, 170, 169.9347664, 0.02982),
+    iso(70, 171, 170.9363302, 0.1409),
+    iso(70, 172, 171.9363859, 0.2168),
+    iso(70, 173, 172.9382151, 0.16103),
+    iso(70, 174, 173.9388664, 0.32026),
+    iso(70, 176, 175.9425764, 0.12996),
+    iso(71, 175, 174.9407752, 0.97401),
+    iso(71, 176, 175.9426897, 0.02599),
+    iso(71, 177, 176.9437615, 0.0),
+    iso(72, 174, 173.9400461, 0.0016),
+    iso(72, 176, 175.9414076, 0.0526),
+    iso(72, 177, 176.9432277, 0.186),
+    iso(72, 178, 177.9437058, 0.2728),
+    iso(72, 179, 178.9458232, 0.1362),
+    iso(72, 180, 179.946557, 0.3508),
+    iso(73, 180, 179.9474648, 0.0001201),
+    iso(73, 181, 180.9479958, 0.9998799),
+    iso(73, 182, 181.9501519, 0.0),
+    iso(74, 180, 179.9467108, 0.0012),
+    iso(74, 182, 181.94820394, 0.265),
+    iso(74, 183, 182.95022275, 0.1431),
+    iso(74, 184, 183.95093092, 0.3064),
+    iso(74, 186, 185.9543628, 0.2843),
+    iso(74, 188, 187.9584862, 0.0),
+    iso(75, 185, 184.9529545, 0.374),
+    iso(75, 186, 185.9549856, 0.0),
+    iso(75, 187, 186.9557501, 0.626),
+    iso(75, 188, 187.9581115, 0.0),
+    iso(76, 184, 183.9524885, 0.0002),
+    iso(76, 186, 185.953835, 0.0159),
+    iso(76, 187, 186.9557474, 0.0196),
+    iso(76, 188, 187.9558352, 0.1324),
+    iso(76, 189, 188.9581442, 0.1615),
+    iso(76, 190, 189.9584437, 0.2626),
+    iso(76, 192, 191.961477, 0.4078),
+    iso(77, 191, 190.9605893, 0.373),
+    iso(77, 192, 191.9626002, 0.0),
+    iso(77, 193, 192.9629216, 0.627),
+    iso(78, 190, 189.9599297, 0.00012),
+    iso(78, 192, 191.9610387, 0.00782),
+    iso(78, 194, 193.9626809, 0.3286),
+    iso(78, 195, 194.9647917, 0.3378),
+    iso(78, 196, 195.96495209, 0.2521),
+    iso(78, 198, 197.9678949, 0.07356),
+    iso(79, 195, 194.9650352, 0.0),
+    iso(79, 197, 196.96656879, 1.0),
+    iso(79, 198, 197.9682429, 0.0),
+    iso(79, 199, 198.9687652, 0.0),
+    iso(80, 196, 195.9658326, 0.0015),
+    iso(80, 197, 196.9672128, 0.0),
+    iso(80, 198, 197.9667686, 0.0997),
+    iso(80, 199, 198.96828064, 0.1687),
+    iso(80, 200, 199.96832659, 0.231),
+    iso(80, 201, 200.97030284, 0.1318),
+    iso(80, 202, 201.9706434, 0.2986),
+    iso(80, 203, 202.9728728, 0.0),
+    iso(80, 204, 203.97349398, 0.0687),
+    iso(81, 201, 200.9708190, 0.0),
+    iso(81, 203, 202.9723446, 0.2952),
+    iso(81, 204, 203.9738639, 0.0),
+    iso(81, 205, 204.9744278, 0.7048),
+    iso(82, 204, 203.973044, 0.014),
+    iso(82, 206, 205.9744657, 0.241),
+    iso(82, 207, 206.9758973, 0.221),
+    iso(82, 208, 207.9766525, 0.524),
+    iso(82, 210, 209.9841889, 0.0),
+    iso(82, 212, 211.9918977, 0.0),
+    iso(83, 207, 206.978471, 0.0),
+    iso(83, 209, 208.9803991, 1.0),
+    iso(83, 210, 209.9841207, 0.0),
+    iso(83, 212, 211.9912860, 0.0),
+    iso(83, 213, 212.9943851, 0.0),
+    iso(84, 208, 207.9812461, 0.0),
+    iso(84, 209, 208.9824308, 0.0),
+    iso(84, 210, 209.9828741, 0.0),
+    iso(85, 209, 208.9861702, 0.0),
+    iso(85, 210, 209.9871479, 0.0),
+    iso(85, 211, 210.9874966, 0.0),
+    iso(86, 211, 210.9906011, 0.0),
+    iso(86, 220, 220.0113941, 0.0),
+    iso(86, 222, 222.0175782, 0.0),
+    iso(87, 212, 211.9962250, 0.0),
+    iso(87, 221, 221.0142552, 0.0),
+    iso(87, 223, 223.019736, 0.0),
+    iso(88, 223, 223.0185023, 0.0),
+    iso(88, 224, 224.020212, 0.0),
+    iso(88, 225, 225.0236119, 0.0),
+    iso(88, 226, 226.0254103, 0.0),
+    iso(88, 228, 228.0310707, 0.0),
+    iso(89, 225, 225.0232300, 0.0),
+    iso(89, 227, 227.0277523, 0.0),
+    iso(90, 227, 227.0277042, 0.0),
+    iso(90, 228, 228.0287413, 0.0),
+    iso(90, 229, 229.0317627, 0.0),
+    iso(90, 230, 230.0331341, 0.0),
+    iso(90, 232, 232.0380558, 1.0),
+    iso(91, 231, 231.0358842, 1.0),
+    iso(91, 233, 233.0402473, 0.0),
+    iso(92, 232, 232.0371563, 0.0),
+    iso(92, 233, 233.0396355, 0.0),
+    iso(92, 234, 234.0409523, 0.000054),
+    iso(92, 235, 235.0439301, 0.007204),
+    iso(92, 236, 236.0455682, 0.0),
+    iso(92, 238, 238.0507884, 0.992742),
+    iso(93, 236, 236.04657, 0.0),
+    iso(93, 237, 237.0481736, 0.0),
+    iso(93, 239, 239.0529392, 0.0),
+    iso(94, 238, 238.0495601, 0.0),
+    iso(94, 239, 239.0521636, 0.0),
+    iso(94, 240, 240.0538138, 0.0),
+    iso(94, 241, 241.0568517, 0.0),
+    iso(94, 242, 242.0587428, 0.0),
+    iso(94, 244, 244.0642053, 0.0),
+    iso(95, 241, 241.0568293, 0.0),
+    iso(95, 243, 243.0613813, 0.0),
+    iso(96, 243, 243.0613893, 0.0),
+    iso(96, 244, 244.0627528, 0.0),
+    iso(96, 245, 245.0654915, 0.0),
+    iso(96, 246, 246.0672238, 0.0),
+    iso(96, 247, 247.0703541, 0.0),
+    iso(96, 248, 248.0723499, 0.0),
+    iso(97, 247, 247.0703073, 0.0),
+    iso(97, 249, 249.0749877, 0.0),
+    iso(98, 249, 249.0748539, 0.0),
+    iso(98, 250, 250.0764062, 0.0),
+    iso(98, 251, 251.0795886, 0.0),
+    iso(98, 252, 252.0816272, 0.0),
+    iso(99, 252, 252.08298, 0.0),
+    iso(99, 254, 254.0880222, 0.0),
+    iso(100, 253, 253.0851852, 0.0),
+    iso(100, 257, 257.0951061, 0.0),
+    iso(101, 258, 258.0984315, 0.0),
+    iso(101, 260, 260.10365, 0.0),
+    iso(102, 255, 255.093191, 0.0),
+    iso(102, 259, 259.10103, 0.0),
+    iso(103, 262, 262.10961, 0.0),
+    iso(103, 266, 266.11983, 0.0),
+    iso(104, 265, 265.11668, 0.0),
+    iso(104, 267, 267.12179, 0.0),
+    iso(105, 268, 268.12567, 0.0),
+    iso(106, 269, 269.12863, 0.0),
+    iso(106, 271, 271.13393, 0.0),
+    iso(107, 270, 270.13336, 0.0),
+    iso(107, 272, 272.13826, 0.0),
+    iso(108, 270, 270.13429, 0.0),
+    iso(108, 277, 277.15190, 0.0),
+    iso(109, 276, 276.15159, 0.0),
+    iso(109, 278, 278.15631, 0.0),
+    iso(110, 281, 281.16451, 0.0),
+    iso(111, 280, 280.16514, 0.0),
+    iso(111, 282, 282.16912, 0.0),
+    iso(112, 285, 285.17712, 0.0),
+    iso(113, 284, 284.17873, 0.0),
+    iso(113, 286, 286.18221, 0.0),
+    iso(114, 289, 289.19042, 0.0),
+    iso(115, 288, 288.19274, 0.0),
+    iso(115, 290, 290.19598, 0.0),
+    iso(116, 293, 293.20449, 0.0),
+    iso(117, 292, 292.20746, 0.0),
+    iso(117, 294, 294.21046, 0.0),
+    iso(118, 294, 294.21392, 0.0),
+];
+
+impl AtomSymbol {
+    /// Returns the tabulated isotopes of this element, by increasing mass
+    /// number. Empty for the wildcard.
+    pub fn isotopes(&self) -> &'static [Isotope] {
+        let z = self.atomic_number();
+        let start = ISOTOPES.partition_point(|i| i.atomic_number < z);
+        let end = ISOTOPES.partition_point(|i| i.atomic_number <= z);
+        &ISOTOPES[start..end]
+    }
+
+    /// Returns the mass of the most abundant isotope in Daltons.
+    ///
+    /// Elements without a natural isotopic composition (Tc, Pm, Po and
+    /// beyond Bi except Th, Pa and U) use the isotope nearest their
+    /// standard mass.
+    pub fn monoisotopic_mass(&self) -> f64 {
+        let isotopes = self.isotopes();
+        let natural = isotopes
+            .iter()
+            .filter(|i| i.abundance > 0.0)
+            .max_by(|a, b| a.abundance.total_cmp(&b.abundance));
+        let standard = self.standard_mass();
+        natural
+            .or_else(|| {
+                isotopes.iter().min_by(|a, b| {
+                    (a.mass - standard)
+                        .abs()
+                        .total_cmp(&(b.mass - standard).abs())
+                })
+            })
+            .map_or(0.0, |i| i.mass)
+    }
+
+    /// Returns the average mass in Daltons, weighted by natural abundance.
+    ///
+    /// Falls back to [`standard_mass`](Self::standard_mass) for elements
+    /// without a natural isotopic composition.
+    pub fn average_mass(&self) -> f64 {
+        let (mut weighted, mut total) = (0.0, 0.0);
+        for isotope in self.isotopes() {
+            weighted += isotope.mass * isotope.abundance;
+            total += isotope.abundance;
+        }
+        if total > 0.0 {
+            weighted / total
+        } else {
+            self.standard_mass()
+        }
+    }
+}
+
+/// Returns the exact mass of a specific isotope.
+/// For nuclides missing from the table, uses the mass number as approximation.
 pub const fn isotope_mass(element: &AtomSymbol, mass_number: u16) -> f64 {
-    match (element.atomic_number(), mass_number) {
-        // Hydrogen isotopes
-        (1, 1) => 1.00783, // protium
-        (1, 2) => 2.01410, // deuterium
-        (1, 3) => 3.01605, // tritium
-        // Carbon isotopes
-        (6, 12) => 12.0,     // carbon-12 (exact by definition)
-        (6, 13) => 13.00335, // carbon-13
-        (6, 14) => 14.00324, // carbon-14
-        // Nitrogen isotopes
-        (7, 14) => 14.00307,
-        (7, 15) => 15.00011,
-        // Oxygen isotopes
-        (8, 16) => 15.99491,
-        (8, 17) => 16.99913,
-        (8, 18) => 17.99916,
-        // Fallback: mass number as approximation
-        (_, m) => m as f64,
+    let z = element.atomic_number();
+    let mut i = 0;
+    while i < ISOTOPES.len() {
+        if ISOTOPES[i].atomic_number == z && ISOTOPES[i].mass_number == mass_number {
+            return ISOTOPES[i].mass;
+        }
+        i += 1;
     }
+    mass_number as f64
 }
 
 impl super::atom::Atom {
@@ -793,6 +1334,44 @@ mod tests {
         assert!((isotope_mass(&h, 2) - 2.01410).abs() < 1e-4);
     }
 
+    #[test]
+    fn isotope_mass_beyond_light_elements() {
+        assert!((isotope_mass(&AtomSymbol::Organic(OrganicAtom::Cl), 37) - 36.96590).abs() < 1e-5);
+        assert!((isotope_mass(&AtomSymbol::Organic(OrganicAtom::Br), 81) - 80.91629).abs() < 1e-5);
+        assert!((isotope_mass(&AtomSymbol::Organic(OrganicAtom::S), 34) - 33.96787).abs() < 1e-5);
+        assert!((isotope_mass(&AtomSymbol::U, 235) - 235.04393).abs() < 1e-5);
+    }
+
+    #[test]
+    fn isotope_table_is_sorted_and_complete() {
+        assert!(ISOTOPES.windows(2).all(
+            |w| (w[0].atomic_number, w[0].mass_number) < (w[1].atomic_number, w[1].mass_number)
+        ));
+        for z in 1..=118u8 {
+            let isotopes: Vec<_> = ISOTOPES.iter().filter(|i| i.atomic_number == z).collect();
+            assert!(!isotopes.is_empty(), "no isotope for Z = {z}");
+            let total: f64 = isotopes.iter().map(|i| i.abundance).sum();
+            assert!(
+                total == 0.0 || (total - 1.0).abs() < 1e-3,
+                "Z = {z}: {total}"
+            );
+        }
+    }
+
+    #[test]
+    fn monoisotopic_and_average_masses() {
+        let c = AtomSymbol::Organic(OrganicAtom::C);
+        assert_eq!(c.monoisotopic_mass(), 12.0);
+        assert!((c.average_mass() - 12.0107).abs() < 1e-4);
+        let br = AtomSymbol::Organic(OrganicAtom::Br);
+        assert!((br.monoisotopic_mass() - 78.91834).abs() < 1e-5);
+        assert!((br.average_mass() - 79.904).abs() < 1e-3);
+        assert!((AtomSymbol::Tc.monoisotopic_mass() - 96.90637).abs() < 1e-5);
+        assert_eq!(AtomSymbol::Tc.average_mass(), 97.0);
+        assert_eq!(AtomSymbol::Wildcard.monoisotopic_mass(), 0.0);
+        assert!(AtomSymbol::Wildcard.isotopes().is_empty());
+    }
+
     #[test]
     fn isotope_mass_unknown_fallback() {
         let c = AtomSymbol::Organic(OrganicAtom::C);
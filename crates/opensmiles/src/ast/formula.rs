@@ -0,0 +1,345 @@
+//! Molecular formulas in Hill notation.
+//!
+//! A [`Formula`] counts the atoms of a molecule, implicit hydrogens included,
+//! keeping isotopically labelled atoms apart, and records the net charge.
+//! Formulas are written in Hill order: carbon, then hydrogen, then the other
+//! elements alphabetically; without carbon every element is alphabetical.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::atom::{AtomSymbol, OrganicAtom};
+use super::molecule::Molecule;
+use crate::FormulaError;
+
+/// The elemental composition and net charge of a molecule.
+///
+/// Two formulas compare equal when they hold the same atoms and charge,
+/// whatever order they were written in.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::{parse, Formula};
+///
+/// let formula = parse("CCO").unwrap().formula();
+/// assert_eq!(formula.to_string(), "C2H6O");
+/// assert_eq!(formula, "HOC2H5".parse::<Formula>().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Formula {
+    /// `(element, isotope, count)`, kept in Hill order.
+    entries: Vec<(AtomSymbol, Option<u16>, u32)>,
+    charge: i32,
+}
+
+impl Formula {
+    /// Returns the number of atoms of `element`, all isotopes included.
+    pub fn count(&self, element: AtomSymbol) -> u32 {
+        self.entries
+            .iter()
+            .filter(|(e, _, _)| *e == element)
+            .map(|&(_, _, n)| n)
+            .sum()
+    }
+
+    /// Returns the total number of atoms.
+    pub fn atom_count(&self) -> u32 {
+        self.entries.iter().map(|&(_, _, n)| n).sum()
+    }
+
+    /// Returns the net charge.
+    pub fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    /// Returns the `(element, isotope, count)` entries in Hill order.
+    ///
+    /// Unlabelled atoms have no isotope and come before the labelled atoms
+    /// of the same element.
+    pub fn iter(&self) -> impl Iterator<Item = (AtomSymbol, Option<u16>, u32)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// Returns the double-bond equivalents (degree of unsaturation).
+    ///
+    /// Every element takes its lowest common valence (C 4, N 3, O 2, H and
+    /// halogens 1, ...) and the charge counts as that many extra hydrogens
+    /// removed or added, so `C6H6` and `C5H6N+` both give 4. Returns `None`
+    /// when the formula holds a wildcard or a transition metal, lanthanide
+    /// or actinide, whose valence is not fixed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// assert_eq!(parse("c1ccccc1").unwrap().formula().double_bond_equivalents(), Some(4.0));
+    /// assert_eq!(parse("CC=O").unwrap().formula().double_bond_equivalents(), Some(1.0));
+    /// ```
+    pub fn double_bond_equivalents(&self) -> Option<f64> {
+        let mut sum = self.charge as f64;
+        for &(element, _, n) in &self.entries {
+            sum += (lowest_valence(element)? as f64 - 2.0) * n as f64;
+        }
+        Some(1.0 + sum / 2.0)
+    }
+
+    fn add(&mut self, element: AtomSymbol, isotope: Option<u16>, n: u32) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(e, i, _)| *e == element && *i == isotope)
+        {
+            Some(entry) => entry.2 += n,
+            None => self.entries.push((element, isotope, n)),
+        }
+    }
+
+    /// Sorts the entries in Hill order.
+    fn sort(&mut self) {
+        let carbon = AtomSymbol::Organic(OrganicAtom::C);
+        let has_carbon = self.entries.iter().any(|&(e, _, _)| e == carbon);
+        self.entries.sort_by_cached_key(|&(element, isotope, _)| {
+            let rank = match element {
+                AtomSymbol::Wildcard => 3,
+                _ if has_carbon && element == carbon => 0,
+                AtomSymbol::H if has_carbon => 1,
+                _ => 2,
+            };
+            (rank, element.to_string(), isotope)
+        });
+    }
+}
+
+/// Lowest common valence of a main-group element.
+fn lowest_valence(element: AtomSymbol) -> Option<u32> {
+    let z = element.atomic_number();
+    match z {
+        0 | 21..=30 | 39..=48 | 57..=80 | 89..=112 => None,
+        2 | 10 | 18 | 36 | 54 | 86 | 118 => Some(0),
+        _ => {
+            let electrons = element.valence_electrons() as u32;
+            Some(electrons.min(8 - electrons))
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &(element, isotope, n) in &self.entries {
+            match isotope {
+                Some(mass) => write!(f, "[{mass}{element}]")?,
+                None => write!(f, "{element}")?,
+            }
+            if n > 1 {
+                write!(f, "{n}")?;
+            }
+        }
+        match self.charge {
+            0 => Ok(()),
+            1 => write!(f, "+"),
+            -1 => write!(f, "-"),
+            c if c > 0 => write!(f, "+{c}"),
+            c => write!(f, "-{}", -c),
+        }
+    }
+}
+
+impl FromStr for Formula {
+    type Err = FormulaError;
+
+    /// Parses a formula such as `C2H6O`, `C6H5NO2-`, `[13C]H4`, `Ca(OH)2`
+    /// or `SO4-2`. Elements may appear in any order and more than once; a
+    /// charge is a trailing sign followed by an optional magnitude.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        let mut groups = vec![Formula::default()];
+        let mut charge = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '(' => {
+                    groups.push(Formula::default());
+                    i += 1;
+                }
+                ')' => {
+                    if groups.len() == 1 {
+                        return Err(FormulaError::UnexpectedCharacter(')', i));
+                    }
+                    i += 1;
+                    let multiplier = read_count(&chars, &mut i)?.unwrap_or(1);
+                    let group = groups.pop().unwrap_or_default();
+                    let top = groups.last_mut().expect("outer group");
+                    for (element, isotope, n) in group.entries {
+                        let n = n
+                            .checked_mul(multiplier)
+                            .ok_or(FormulaError::CountTooLarge(i))?;
+                        top.add(element, isotope, n);
+                    }
+                }
+                '+' | '-' => {
+                    let sign = if chars[i] == '+' { 1 } else { -1 };
+                    i += 1;
+                    let magnitude = read_count(&chars, &mut i)?.unwrap_or(1);
+                    if i < chars.len() {
+                        return Err(FormulaError::UnexpectedCharacter(chars[i], i));
+                    }
+                    charge = i32::try_from(magnitude)
+                        .map_err(|_| FormulaError::CountTooLarge(i))?
+                        * sign;
+                }
+                _ => {
+                    let (element, isotope) = read_atom(&chars, &mut i)?;
+                    let n = read_count(&chars, &mut i)?.unwrap_or(1);
+                    groups
+                        .last_mut()
+                        .expect("outer group")
+                        .add(element, isotope, n);
+                }
+            }
+        }
+
+        if groups.len() > 1 {
+            return Err(FormulaError::UnclosedParenthesis);
+        }
+        let mut formula = groups.pop().unwrap_or_default();
+        formula.entries.retain(|&(_, _, n)| n > 0);
+        if formula.entries.is_empty() {
+            return Err(FormulaError::Empty);
+        }
+        formula.charge = charge;
+        formula.sort();
+        Ok(formula)
+    }
+}
+
+/// Reads an optional `[mass]` prefix and an element symbol (or `*`).
+fn read_atom(chars: &[char], i: &mut usize) -> Result<(AtomSymbol, Option<u16>), FormulaError> {
+    let mut isotope = None;
+    if chars[*i] == '[' {
+        *i += 1;
+        let start = *i;
+        let mass = read_count(chars, i)?
+            .ok_or_else(|| unexpected(chars, *i))
+            .and_then(|m| u16::try_from(m).map_err(|_| FormulaError::CountTooLarge(start)))?;
+        isotope = Some(mass);
+        let element = read_atom(chars, i)?.0;
+        if chars.get(*i) != Some(&']') {
+            return Err(unexpected(chars, *i));
+        }
+        *i += 1;
+        return Ok((element, isotope));
+    }
+
+    let start = *i;
+    match chars[*i] {
+        '*' => {
+            *i += 1;
+            return Ok((AtomSymbol::Wildcard, isotope));
+        }
+        c if c.is_ascii_uppercase() => *i += 1,
+        _ => return Err(unexpected(chars, *i)),
+    }
+    while *i < chars.len() && chars[*i].is_ascii_lowercase() {
+        *i += 1;
+    }
+    let symbol: String = chars[start..*i].iter().collect();
+    symbol
+        .parse()
+        .map(|element| (element, isotope))
+        .map_err(|_| FormulaError::UnknownElement(symbol))
+}
+
+/// Reads an optional decimal count.
+fn read_count(chars: &[char], i: &mut usize) -> Result<Option<u32>, FormulaError> {
+    let start = *i;
+    let mut value: Option<u32> = None;
+    while let Some(digit) = chars.get(*i).and_then(|c| c.to_digit(10)) {
+        value = Some(
+            value
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(digit))
+                .ok_or(FormulaError::CountTooLarge(start))?,
+        );
+        *i += 1;
+    }
+    Ok(value)
+}
+
+fn unexpected(chars: &[char], i: usize) -> FormulaError {
+    match chars.get(i) {
+        Some(&c) => FormulaError::UnexpectedCharacter(c, i),
+        None => FormulaError::UnclosedParenthesis,
+    }
+}
+
+impl Molecule {
+    /// Returns the molecular formula, counting implicit and bracket
+    /// hydrogens as well as explicit hydrogen atoms.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// assert_eq!(parse("[O-][N+](=O)c1ccccc1").unwrap().formula().to_string(), "C6H5NO2");
+    /// assert_eq!(parse("CC(=O)[O-]").unwrap().formula().to_string(), "C2H3O2-");
+    /// assert_eq!(parse("[13CH4]").unwrap().formula().to_string(), "[13C]H4");
+    /// ```
+    pub fn formula(&self) -> Formula {
+        let mut formula = Formula::default();
+        for node in self.nodes() {
+            let atom = node.atom();
+            formula.add(*atom.element(), atom.isotope(), 1);
+            if node.hydrogens() > 0 {
+                formula.add(AtomSymbol::H, None, node.hydrogens() as u32);
+            }
+            formula.charge += atom.charge() as i32;
+        }
+        formula.sort();
+        formula
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hill_order_puts_carbon_and_hydrogen_first() {
+        let formula: Formula = "ONH3C".parse().unwrap();
+        assert_eq!(formula.to_string(), "CH3NO");
+        let inorganic: Formula = "SO4H2".parse().unwrap();
+        assert_eq!(inorganic.to_string(), "H2O4S");
+    }
+
+    #[test]
+    fn isotopes_follow_their_element() {
+        let formula: Formula = "[13C]C[2H]H5O".parse().unwrap();
+        assert_eq!(formula.to_string(), "C[13C]H5[2H]O");
+        assert_eq!(formula.count(AtomSymbol::H), 6);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<Formula>(), Err(FormulaError::Empty));
+        assert_eq!(
+            "C2?".parse::<Formula>(),
+            Err(FormulaError::UnexpectedCharacter('?', 2))
+        );
+        assert_eq!(
+            "Xy".parse::<Formula>(),
+            Err(FormulaError::UnknownElement("Xy".to_string()))
+        );
+        assert_eq!(
+            "Ca(OH".parse::<Formula>(),
+            Err(FormulaError::UnclosedParenthesis)
+        );
+        assert_eq!(
+            "C-2H".parse::<Formula>(),
+            Err(FormulaError::UnexpectedCharacter('H', 3))
+        );
+    }
+}
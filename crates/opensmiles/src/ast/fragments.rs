@@ -0,0 +1,159 @@
+//! Connected components of a molecule.
+//!
+//! Dot-separated SMILES (`[Na+].[Cl-]`) parse into a single [`Molecule`]
+//! whose atoms fall into several connected components. Each component can
+//! be extracted as its own molecule, with the original atom indices kept
+//! alongside so results can be mapped back.
+
+use super::atom::AtomSymbol;
+use super::bond::{Bond, BondType};
+use super::molecule::Molecule;
+use crate::NodeIndex;
+
+/// A connected component extracted from a molecule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    molecule: Molecule,
+    atoms: Vec<NodeIndex>,
+}
+
+impl Fragment {
+    /// Returns the component as a standalone molecule.
+    pub fn molecule(&self) -> &Molecule {
+        &self.molecule
+    }
+
+    /// Returns the original index of every atom of the fragment: atom `i`
+    /// of [`molecule`](Self::molecule) is atom `atoms()[i]` of the source.
+    pub fn atoms(&self) -> &[NodeIndex] {
+        &self.atoms
+    }
+
+    /// Consumes the fragment and returns its molecule.
+    pub fn into_molecule(self) -> Molecule {
+        self.molecule
+    }
+}
+
+impl Molecule {
+    /// Returns the component index of every atom. Components are numbered
+    /// in order of their lowest atom index.
+    fn component_labels(&self) -> (Vec<usize>, usize) {
+        let n = self.nodes().len();
+        let mut adjacency: Vec<Vec<NodeIndex>> = vec![Vec::new(); n];
+        for bond in self.bonds() {
+            if bond.kind() != BondType::Disconnected {
+                adjacency[bond.source() as usize].push(bond.target());
+                adjacency[bond.target() as usize].push(bond.source());
+            }
+        }
+
+        let mut labels = vec![usize::MAX; n];
+        let mut count = 0;
+        for start in 0..n {
+            if labels[start] != usize::MAX {
+                continue;
+            }
+            labels[start] = count;
+            let mut stack = vec![start as NodeIndex];
+            while let Some(u) = stack.pop() {
+                for &v in &adjacency[u as usize] {
+                    if labels[v as usize] == usize::MAX {
+                        labels[v as usize] = count;
+                        stack.push(v);
+                    }
+                }
+            }
+            count += 1;
+        }
+        (labels, count)
+    }
+
+    /// Splits the molecule into its connected components, ordered by their
+    /// lowest atom index.
+    ///
+    /// Atoms and bonds keep their relative order, so chirality, hydrogen
+    /// counts and the written form of each component are preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let salt = parse("C[NH3+].[Cl-]").unwrap();
+    /// let fragments = salt.fragments();
+    /// assert_eq!(fragments.len(), 2);
+    /// assert_eq!(fragments[0].molecule().to_string(), "[NH3+]C");
+    /// assert_eq!(fragments[1].atoms(), &[2]);
+    /// ```
+    pub fn fragments(&self) -> Vec<Fragment> {
+        let (labels, count) = self.component_labels();
+
+        // Index of every atom within its fragment.
+        let mut atoms: Vec<Vec<NodeIndex>> = vec![Vec::new(); count];
+        let mut local = vec![0 as NodeIndex; labels.len()];
+        for (atom, &label) in labels.iter().enumerate() {
+            local[atom] = atoms[label].len() as NodeIndex;
+            atoms[label].push(atom as NodeIndex);
+        }
+
+        let mut bonds: Vec<Vec<Bond>> = vec![Vec::new(); count];
+        for bond in self.bonds() {
+            if bond.kind() == BondType::Disconnected {
+                continue;
+            }
+            let (a, b) = (bond.source() as usize, bond.target() as usize);
+            bonds[labels[a]].push(Bond::new(bond.kind(), local[a], local[b]));
+        }
+
+        atoms
+            .into_iter()
+            .zip(bonds)
+            .map(|(atoms, bonds)| {
+                let nodes = atoms
+                    .iter()
+                    .map(|&atom| self.nodes()[atom as usize].clone())
+                    .collect();
+                Fragment {
+                    molecule: Molecule::new(nodes, bonds),
+                    atoms,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the fragment with the most heavy (non-hydrogen) atoms; ties
+    /// go to the fragment written first. Returns `None` for an empty
+    /// molecule.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let salt = parse("[Na+].[O-]C(=O)c1ccccc1").unwrap();
+    /// let parent = salt.largest_fragment().unwrap();
+    /// assert_eq!(parent.molecule().to_string(), "[O-]C(=O)c1ccccc1");
+    /// ```
+    pub fn largest_fragment(&self) -> Option<Fragment> {
+        let heavy_atoms = |fragment: &Fragment| {
+            fragment
+                .molecule()
+                .nodes()
+                .iter()
+                .filter(|node| *node.atom().element() != AtomSymbol::H)
+                .count()
+        };
+        self.fragments()
+            .into_iter()
+            .rev()
+            .max_by_key(|fragment| heavy_atoms(fragment))
+    }
+
+    /// Returns the index in [`fragments`](Self::fragments) of the component
+    /// holding `atom`, or `None` if the atom does not exist.
+    pub fn component_of(&self, atom: NodeIndex) -> Option<usize> {
+        let (labels, _) = self.component_labels();
+        labels.get(atom as usize).copied()
+    }
+}
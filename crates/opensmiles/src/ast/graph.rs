@@ -1,7 +1,7 @@
 use super::bond::BondType;
 use super::molecule::Molecule;
+use super::rings;
 use crate::NodeIndex;
-use std::collections::{HashSet, VecDeque};
 
 /// A ring represented by the ordered sequence of node indices.
 #[derive(Debug, Clone, PartialEq)]
@@ -17,128 +17,28 @@ impl Ring {
 
 /// Finds all aromatic rings in a molecule.
 ///
-/// Uses the shortest-cycle-per-edge approach:
-/// 1. Build a subgraph of only aromatic nodes connected by aromatic bonds
-/// 2. For each edge, find the shortest cycle containing it (by removing the edge
-///    and finding the shortest path between its endpoints)
-/// 3. Deduplicate rings by their node sets
+/// Returns one relevant cycle per family (see [`RingInfo`](crate::RingInfo))
+/// of the subgraph made of aromatic atoms joined by aromatic bonds, smallest
+/// first.
 pub fn find_aromatic_rings(molecule: &Molecule) -> Vec<Ring> {
-    let n = molecule.nodes().len();
-    if n == 0 {
-        return Vec::new();
-    }
-
-    // Build aromatic subgraph adjacency list
-    let mut adj: Vec<Vec<NodeIndex>> = vec![Vec::new(); n];
-    let mut is_aromatic = vec![false; n];
-    let mut aromatic_edges: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
-
-    for (i, node) in molecule.nodes().iter().enumerate() {
-        if node.aromatic() {
-            is_aromatic[i] = true;
-        }
-    }
-
-    for bond in molecule.bonds() {
-        let s = bond.source() as usize;
-        let t = bond.target() as usize;
-        if is_aromatic[s] && is_aromatic[t] && bond.kind() == BondType::Aromatic {
-            adj[s].push(bond.target());
-            adj[t].push(bond.source());
-            let edge = if bond.source() < bond.target() {
-                (bond.source(), bond.target())
-            } else {
-                (bond.target(), bond.source())
-            };
-            aromatic_edges.insert(edge);
-        }
-    }
-
-    find_rings_in_subgraph(&adj, &aromatic_edges, n)
-}
-
-/// Finds all minimal rings in a subgraph defined by an adjacency list and edge set.
-/// For each edge, finds the shortest cycle containing it (BFS), then deduplicates.
-/// Shared by `find_aromatic_rings` and Kekulé ring detection in display.
-pub(crate) fn find_rings_in_subgraph(
-    adj: &[Vec<NodeIndex>],
-    edges: &HashSet<(NodeIndex, NodeIndex)>,
-    n: usize,
-) -> Vec<Ring> {
-    let mut rings: Vec<Vec<NodeIndex>> = Vec::new();
-
-    for &(u, v) in edges {
-        if let Some(path) = shortest_path_excluding_edge(u, v, adj, n) {
-            let mut sorted = path.clone();
-            sorted.sort();
-            let is_duplicate = rings.iter().any(|existing| {
-                let mut existing_sorted = existing.clone();
-                existing_sorted.sort();
-                existing_sorted == sorted
-            });
-            if !is_duplicate {
-                rings.push(path);
-            }
-        }
-    }
-
-    rings.sort_by_key(|r| r.len());
-    rings.into_iter().map(|nodes| Ring { nodes }).collect()
-}
-
-/// BFS from `u` to `v` without using the direct edge (u, v).
-/// Returns the ring as the path from u to v (which, combined with the
-/// excluded edge, forms a cycle).
-pub(crate) fn shortest_path_excluding_edge(
-    u: NodeIndex,
-    v: NodeIndex,
-    adj: &[Vec<NodeIndex>],
-    n: usize,
-) -> Option<Vec<NodeIndex>> {
-    let mut visited = vec![false; n];
-    let mut parent: Vec<Option<NodeIndex>> = vec![None; n];
-    let mut queue = VecDeque::new();
-
-    visited[u as usize] = true;
-    queue.push_back(u);
-
-    let mut found = false;
-
-    'outer: while let Some(curr) = queue.pop_front() {
-        for &next in &adj[curr as usize] {
-            // Skip the direct edge u→v (only from u's side)
-            if curr == u && next == v {
-                continue;
-            }
-
-            if !visited[next as usize] {
-                visited[next as usize] = true;
-                parent[next as usize] = Some(curr);
-                if next == v {
-                    found = true;
-                    break 'outer;
-                }
-                queue.push_back(next);
-            }
-        }
-    }
-
-    if !found {
-        return None;
-    }
-
-    // Reconstruct path from u to v
-    let mut path = Vec::new();
-    let mut node = v;
-    loop {
-        path.push(node);
-        if node == u {
-            break;
-        }
-        node = parent[node as usize]?;
-    }
-    path.reverse();
-    Some(path)
+    let nodes = molecule.nodes();
+    let edges: Vec<Option<(NodeIndex, NodeIndex)>> = molecule
+        .bonds()
+        .iter()
+        .map(|bond| {
+            let (s, t) = (bond.source(), bond.target());
+            (bond.kind() == BondType::Aromatic
+                && nodes[s as usize].aromatic()
+                && nodes[t as usize].aromatic())
+            .then_some((s, t))
+        })
+        .collect();
+
+    rings::perceive(nodes.len(), &edges)
+        .families
+        .into_iter()
+        .map(|cycle| Ring { nodes: cycle.nodes })
+        .collect()
 }
 
 impl Molecule {
@@ -0,0 +1,274 @@
+//! Implicit and explicit hydrogens.
+//!
+//! Hydrogens are either counted on their heavy atom ([`Node::hydrogens`]) or
+//! present as `[H]` atoms of their own. Moving a hydrogen between the two
+//! forms changes the reference neighbour order of a chiral centre (see
+//! [`stereo`](super::stereo)), so the `@`/`@@` parity is recomputed.
+
+use super::atom::{Atom, AtomSymbol};
+use super::bond::{Bond, BondType};
+use super::molecule::Molecule;
+use super::node::Node;
+use super::stereo::{invert, is_odd_permutation, is_tetrahedral, reference_order, side_of};
+use crate::NodeIndex;
+
+/// Largest hydrogen count a [`Node`] can hold.
+const MAX_HYDROGENS: u8 = 9;
+
+impl Molecule {
+    /// Returns a copy of the molecule where every counted hydrogen is an
+    /// explicit `[H]` atom bonded to its heavy atom.
+    ///
+    /// The new hydrogens are appended after the existing atoms, in the order
+    /// of their heavy atoms, and existing atom indices are unchanged.
+    /// Tetrahedral centres keep their configuration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let methanol = parse("CO").unwrap().add_explicit_hydrogens();
+    /// assert_eq!(methanol.nodes().len(), 6);
+    /// assert!(methanol.nodes().iter().all(|n| n.hydrogens() == 0));
+    /// assert_eq!(methanol.to_string(), "OC");
+    /// ```
+    pub fn add_explicit_hydrogens(&self) -> Molecule {
+        let n = self.nodes().len();
+        let incident = self.incident_bonds();
+        let mut bonds = self.bonds().to_vec();
+        let mut nodes = Vec::with_capacity(n);
+        let mut added = 0;
+        for (atom, node) in self.nodes().iter().enumerate() {
+            let center = atom as NodeIndex;
+            let h = node.hydrogens() as usize;
+            if h == 0 {
+                nodes.push(node.clone());
+                continue;
+            }
+
+            let first_h = (n + added) as NodeIndex;
+            for k in 0..h {
+                bonds.push(Bond::new(
+                    BondType::Simple,
+                    center,
+                    (n + added + k) as NodeIndex,
+                ));
+            }
+            added += h;
+
+            let mut chirality = node.chirality();
+            if h == 1 && is_tetrahedral(chirality) {
+                // The counted hydrogen becomes the new atom, bonded last.
+                let before: Vec<NodeIndex> = reference_order(center, &incident[atom], true)
+                    .into_iter()
+                    .map(|v| if v == center { first_h } else { v })
+                    .collect();
+                let mut after = reference_order(center, &incident[atom], false);
+                after.push(first_h);
+                if is_odd_permutation(&before, &after) == Some(true) {
+                    chirality = chirality.map(invert);
+                }
+            }
+            nodes.push(node.with_hydrogens(0, chirality));
+        }
+
+        let hydrogen = Node::new(
+            Atom::new(AtomSymbol::H, 0, None).expect("neutral hydrogen is valid"),
+            false,
+            0,
+            None,
+            None,
+        )
+        .expect("plain hydrogen node is valid");
+        nodes.extend(std::iter::repeat(hydrogen).take(added));
+        Molecule::new(nodes, bonds)
+    }
+
+    /// Returns a copy of the molecule where explicit `[H]` atoms are folded
+    /// into the hydrogen count of their heavy atom.
+    ///
+    /// Only plain hydrogens with a single heavy neighbour are removed — the
+    /// ones the SMILES writer would fold anyway. Charged, isotopic, mapped or
+    /// chiral hydrogens, `[H][H]` and bridging hydrogens stay. Tetrahedral
+    /// centres keep their configuration, and a `/` or `\` mark carried by a
+    /// removed hydrogen moves to the other substituent of its double-bond
+    /// atom. The remaining atoms keep their relative order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let mol = parse("[H]C([H])([H])O[H]").unwrap().remove_explicit_hydrogens();
+    /// assert_eq!(mol.nodes().len(), 2);
+    /// assert_eq!(mol.nodes()[0].hydrogens(), 3);
+    /// assert_eq!(mol.nodes()[1].hydrogens(), 1);
+    /// ```
+    pub fn remove_explicit_hydrogens(&self) -> Molecule {
+        let n = self.nodes().len();
+        let mut neighbour_list: Vec<Vec<(NodeIndex, BondType)>> = vec![Vec::new(); n];
+        for bond in self.bonds() {
+            neighbour_list[bond.source() as usize].push((bond.target(), bond.kind()));
+            neighbour_list[bond.target() as usize].push((bond.source(), bond.kind()));
+        }
+
+        // Hydrogens folded into each heavy atom, within the node's limit.
+        let mut removed = self.removable_hydrogens(&neighbour_list);
+        let mut folded = vec![0u8; n];
+        for h in 0..n {
+            if removed[h] {
+                let heavy = neighbour_list[h][0].0 as usize;
+                if self.nodes()[heavy].hydrogens() + folded[heavy] < MAX_HYDROGENS {
+                    folded[heavy] += 1;
+                } else {
+                    removed[h] = false;
+                }
+            }
+        }
+        if !removed.contains(&true) {
+            return self.clone();
+        }
+        let incident = self.incident_bonds();
+
+        let mut bonds: Vec<Bond> = self
+            .bonds()
+            .iter()
+            .filter(|b| !removed[b.source() as usize] && !removed[b.target() as usize])
+            .cloned()
+            .collect();
+        self.move_directional_marks(&removed, &mut bonds);
+
+        let mut index = vec![0 as NodeIndex; n];
+        let mut nodes = Vec::with_capacity(n);
+        for (atom, node) in self.nodes().iter().enumerate() {
+            if removed[atom] {
+                continue;
+            }
+            let center = atom as NodeIndex;
+            let mut chirality = node.chirality();
+            if folded[atom] > 0 && is_tetrahedral(chirality) {
+                let own = &incident[atom];
+                let degree = own.len();
+                let implicit = node.hydrogens() > 0 || degree == 3;
+                let before: Vec<NodeIndex> = reference_order(center, own, implicit)
+                    .into_iter()
+                    .map(|v| if removed[v as usize] { center } else { v })
+                    .collect();
+                let kept: Vec<Bond> = own
+                    .iter()
+                    .filter(|b| !removed[b.source() as usize] && !removed[b.target() as usize])
+                    .cloned()
+                    .collect();
+                let after = reference_order(center, &kept, true);
+                if is_odd_permutation(&before, &after) == Some(true) {
+                    chirality = chirality.map(invert);
+                }
+            }
+            index[atom] = nodes.len() as NodeIndex;
+            nodes.push(node.with_hydrogens(node.hydrogens() + folded[atom], chirality));
+        }
+
+        let bonds = bonds
+            .into_iter()
+            .map(|b| {
+                Bond::new(
+                    b.kind(),
+                    index[b.source() as usize],
+                    index[b.target() as usize],
+                )
+            })
+            .collect();
+        Molecule::new(nodes, bonds)
+    }
+
+    /// Returns the number of hydrogen atoms bonded to `atom` as graph nodes
+    /// (`[H]`, `[2H]`, …), as opposed to counted on the atom itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let mol = parse("[H]C([2H])O").unwrap();
+    /// assert_eq!(mol.explicit_h_neighbours(1), 2);
+    /// assert_eq!(mol.nodes()[1].implicit_hydrogens(), 1);
+    /// assert_eq!(mol.total_hydrogens(1), 3);
+    /// ```
+    pub fn explicit_h_neighbours(&self, atom: NodeIndex) -> usize {
+        assert!((atom as usize) < self.nodes().len(), "atom out of bounds");
+        self.bonds()
+            .iter()
+            .filter(|bond| bond.kind() != BondType::Disconnected)
+            .filter_map(|bond| {
+                if bond.source() == atom {
+                    Some(bond.target())
+                } else if bond.target() == atom {
+                    Some(bond.source())
+                } else {
+                    None
+                }
+            })
+            .filter(|&v| *self.nodes()[v as usize].atom().element() == AtomSymbol::H)
+            .count()
+    }
+
+    /// Returns every hydrogen on `atom`: the implicit or bracket count of
+    /// its node plus its [explicit hydrogen neighbours](Self::explicit_h_neighbours).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom` is out of bounds.
+    pub fn total_hydrogens(&self, atom: NodeIndex) -> usize {
+        self.nodes()[atom as usize].hydrogens() as usize + self.explicit_h_neighbours(atom)
+    }
+
+    /// Bonds of every atom, in the order of the bond list.
+    pub(crate) fn incident_bonds(&self) -> Vec<Vec<Bond>> {
+        let mut incident: Vec<Vec<Bond>> = vec![Vec::new(); self.nodes().len()];
+        for bond in self.bonds() {
+            incident[bond.source() as usize].push(bond.clone());
+            incident[bond.target() as usize].push(bond.clone());
+        }
+        incident
+    }
+
+    /// Moves the `/` or `\` mark of a removed hydrogen to the other single
+    /// bond of its double-bond atom, with the opposite side, unless that
+    /// bond is already marked.
+    fn move_directional_marks(&self, removed: &[bool], kept: &mut [Bond]) {
+        for bond in self.bonds() {
+            let atom = if removed[bond.target() as usize] {
+                bond.source()
+            } else if removed[bond.source() as usize] {
+                bond.target()
+            } else {
+                continue;
+            };
+            let Some(h_side) = side_of(bond, atom) else {
+                continue;
+            };
+            let other = kept.iter_mut().find(|b| {
+                (b.source() == atom || b.target() == atom)
+                    && matches!(b.kind(), BondType::Simple | BondType::Up | BondType::Down)
+            });
+            if let Some(other) = other {
+                if other.kind() != BondType::Simple {
+                    continue;
+                }
+                // `(atom, x, Up)` puts `x` above `atom`; reversed bonds flip.
+                let up = if other.source() == atom {
+                    !h_side
+                } else {
+                    h_side
+                };
+                let kind = if up { BondType::Up } else { BondType::Down };
+                *other = Bond::new(kind, other.source(), other.target());
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Hashed, fixed-length molecule identifiers.
+//!
+//! An identity key has the form `AAAAAAAAAAAAAA-BBBBBBBBBB-V`: a 14-letter
+//! constitution block, a 10-letter block for isotopes and stereo, and a
+//! version letter. It is computed as follows; any change to these steps
+//! gets a new version letter.
+//!
+//! 1. The molecule is normalized: atom classes are dropped, removable `[H]`
+//!    atoms are folded into hydrogen counts and aromaticity is perceived
+//!    with the Daylight model, as for
+//!    [`Molecule::is_isomorphic`](crate::Molecule::is_isomorphic).
+//! 2. The atoms are numbered by their canonical rank (see
+//!    [`Molecule::canonical_ranks`](crate::Molecule::canonical_ranks)),
+//!    and the layers below are written in that numbering:
+//!    - `/c`: the element of every atom, lowercase when aromatic, separated
+//!      by commas (`/cC,C,O`);
+//!    - `/b`: the bonds as `i<bond>j` with `i < j`, sorted, where `<bond>` is
+//!      `-`, `=`, `#`, `$` or `:` (`/b0-1,1-2`);
+//!    - `/h`: the hydrogen count of every atom (`/h3,2,1`);
+//!    - `/q`: `atom:charge` for every charged atom (`/q2:-1`);
+//!    - `/i`: `atom:mass` for every atom with an isotope (`/i0:13`);
+//!    - `/t`: `atom:parity` for every tetrahedral stereocentre, `1` when its
+//!      neighbours sorted by rank (implicit hydrogen first) turn
+//!      anticlockwise (`@`), `2` otherwise;
+//!    - `/e`: `atom:geometry` for both atoms of every stereo double bond,
+//!      `1` when their highest-ranked substituents are cis, `2` when trans.
+//!
+//!    Stereocentres whose neighbours are symmetric are left out, except
+//!    those only stereogenic through their ring, such as the two centres of
+//!    1,4-dimethylcyclohexane: their parities are written against the
+//!    final ranks, which the canonical search chooses so that the key
+//!    tells the cis and trans isomers apart.
+//! 3. The first block hashes `OSK1` followed by the `/c`, `/b`, `/h` and
+//!    `/q` layers of the molecule with its isotopes, stereo and bond
+//!    directions removed and its hydrogens folded again, so that
+//!    stereoisomers and isotopologues share it. The second block hashes
+//!    `OSK1` followed by all seven layers of the molecule, or `OSK1` alone
+//!    when its `/i`, `/t` and `/e` layers are empty.
+//! 4. A block is the SHA-256 digest of its text, read as a big-endian
+//!    integer from its first 16 bytes and written in base 26 with the
+//!    letters `A` to `Z`, least significant digit first.
+
+use std::fmt::Write;
+
+use super::atom::Atom;
+use super::bond::{Bond, BondType};
+use super::canonical::canonical_ranking;
+use super::molecule::Molecule;
+use super::node::Node;
+use crate::sha256::sha256;
+
+/// Version letter of the identity key algorithm.
+pub const IDENTITY_KEY_VERSION: char = 'B';
+
+/// Prefix of every hashed text.
+const PREFIX: &str = "OSK1";
+
+impl Molecule {
+    /// Returns a fixed-length key identifying the molecule, suitable for
+    /// database joins.
+    ///
+    /// Molecules with the same constitution, isotopes, tetrahedral centres
+    /// and double-bond geometry get the same key, whatever the way they are
+    /// written. Other stereo classes (allenes, square planar centres, ...)
+    /// are not encoded, and distinct molecules may share a key through a
+    /// hash collision.
+    ///
+    /// The key reads `AAAAAAAAAAAAAA-BBBBBBBBBB-B`. The first block only
+    /// depends on the constitution (atoms, bonds, hydrogens and charges),
+    /// so stereoisomers and isotopically labelled forms share it; the
+    /// second covers isotopes and stereo; the last letter is the
+    /// [algorithm version](IDENTITY_KEY_VERSION). The algorithm is described
+    /// in the [module documentation](crate::ast::identity_key), and keys of
+    /// a given version do not change between releases.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let l = parse("N[C@@H](C)C(=O)O").unwrap().identity_key();
+    /// let d = parse("N[C@H](C)C(=O)O").unwrap().identity_key();
+    /// assert_eq!(l.len(), 27);
+    /// assert_eq!(l[..14], d[..14]);
+    /// assert_ne!(l, d);
+    /// assert_eq!(l, parse("C[C@H](N)C(O)=O").unwrap().identity_key());
+    /// ```
+    pub fn identity_key(&self) -> String {
+        let molecule = self.stripped(true).identity_form();
+        let skeleton = molecule.stripped(false).remove_explicit_hydrogens();
+
+        let (constitution, _) = layers(&skeleton, false);
+        let (full, labelled) = layers(&molecule, true);
+
+        let mut key = block(&constitution, 14);
+        key.push('-');
+        key.push_str(&block(if labelled { &full } else { PREFIX }, 10));
+        key.push('-');
+        key.push(IDENTITY_KEY_VERSION);
+        key
+    }
+
+    /// Returns a copy without atom classes and, unless `labels` is set,
+    /// without isotopes, chirality or bond directions.
+    fn stripped(&self, labels: bool) -> Molecule {
+        let nodes = self
+            .nodes()
+            .iter()
+            .map(|node| {
+                let atom = node.atom();
+                let isotope = atom.isotope().filter(|_| labels);
+                let atom = Atom::new(*atom.element(), atom.charge(), isotope)
+                    .expect("an atom without isotope is valid");
+                let chirality = node.chirality().filter(|_| labels);
+                Node::new(atom, node.aromatic(), node.hydrogens(), None, chirality)
+                    .expect("the node was valid")
+            })
+            .collect();
+        let bonds = self
+            .bonds()
+            .iter()
+            .map(|bond| match bond.kind() {
+                BondType::Up | BondType::Down if !labels => {
+                    Bond::new(BondType::Simple, bond.source(), bond.target())
+                }
+                _ => bond.clone(),
+            })
+            .collect();
+        Molecule::new(nodes, bonds)
+    }
+}
+
+/// Writes the layers of the module documentation, the `/i`, `/t` and `/e`
+/// ones only when `labels` is set, and tells whether those hold any entry.
+fn layers(molecule: &Molecule, labels: bool) -> (String, bool) {
+    let graph = molecule.writer_graph();
+    let ranking = canonical_ranking(molecule, &graph);
+    let (ranks, mut stereo) = (ranking.ranks, ranking.labels);
+    let n = molecule.nodes().len();
+    let mut by_rank = vec![0; n];
+    for (atom, &rank) in ranks.iter().enumerate() {
+        by_rank[rank as usize] = atom;
+    }
+    for (rank, parity) in ranking.ring_parities {
+        stereo[by_rank[rank as usize]].0 = parity;
+    }
+    let nodes = molecule.nodes();
+
+    let mut text = String::from(PREFIX);
+    text.push_str("/c");
+    for (i, &atom) in by_rank.iter().enumerate() {
+        if i > 0 {
+            text.push(',');
+        }
+        let symbol = nodes[atom].atom().element().to_string();
+        if nodes[atom].aromatic() {
+            text.push_str(&symbol.to_ascii_lowercase());
+        } else {
+            text.push_str(&symbol);
+        }
+    }
+
+    let mut bonds: Vec<(u32, u32, char)> = molecule
+        .bonds()
+        .iter()
+        .filter_map(|bond| {
+            let symbol = match bond.kind() {
+                BondType::Simple | BondType::Up | BondType::Down => '-',
+                BondType::Double => '=',
+                BondType::Triple => '#',
+                BondType::Quadruple => '$',
+                BondType::Aromatic => ':',
+                BondType::Disconnected => return None,
+            };
+            let (a, b) = (ranks[bond.source() as usize], ranks[bond.target() as usize]);
+            Some((a.min(b), a.max(b), symbol))
+        })
+        .collect();
+    bonds.sort_unstable();
+    text.push_str("/b");
+    for (i, (a, b, symbol)) in bonds.iter().enumerate() {
+        if i > 0 {
+            text.push(',');
+        }
+        let _ = write!(text, "{}{}{}", a, symbol, b);
+    }
+
+    text.push_str("/h");
+    for (i, &atom) in by_rank.iter().enumerate() {
+        if i > 0 {
+            text.push(',');
+        }
+        let _ = write!(text, "{}", nodes[atom].hydrogens());
+    }
+
+    let mut labelled = false;
+    let list = |text: &mut String, layer: &str, entries: Vec<(usize, String)>| {
+        text.push_str(layer);
+        for (i, (rank, value)) in entries.iter().enumerate() {
+            if i > 0 {
+                text.push(',');
+            }
+            let _ = write!(text, "{}:{}", rank, value);
+        }
+    };
+    let entries = |value: &dyn Fn(usize) -> Option<String>| -> Vec<(usize, String)> {
+        by_rank
+            .iter()
+            .enumerate()
+            .filter_map(|(rank, &atom)| value(atom).map(|v| (rank, v)))
+            .collect()
+    };
+
+    list(
+        &mut text,
+        "/q",
+        entries(&|atom| {
+            let charge = nodes[atom].atom().charge();
+            (charge != 0).then(|| charge.to_string())
+        }),
+    );
+    if labels {
+        let isotopes = entries(&|atom| nodes[atom].atom().isotope().map(|i| i.to_string()));
+        let centres = entries(&|atom| (stereo[atom].0 != 0).then(|| stereo[atom].0.to_string()));
+        let double_bonds =
+            entries(&|atom| (stereo[atom].1 != 0).then(|| stereo[atom].1.to_string()));
+        labelled = !(isotopes.is_empty() && centres.is_empty() && double_bonds.is_empty());
+        list(&mut text, "/i", isotopes);
+        list(&mut text, "/t", centres);
+        list(&mut text, "/e", double_bonds);
+    }
+    (text, labelled)
+}
+
+/// Hashes `text` into `len` letters.
+fn block(text: &str, len: usize) -> String {
+    let digest = sha256(text.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    let mut value = u128::from_be_bytes(bytes);
+    (0..len)
+        .map(|_| {
+            let letter = (b'A' + (value % 26) as u8) as char;
+            value /= 26;
+            letter
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::canonical::tests::{permutations, permuted};
+    use crate::parse;
+
+    #[test]
+    fn layers_of_a_small_molecule() {
+        let acetate = parse("CC(=O)[O-]").unwrap();
+        let ranks = acetate.canonical_ranks();
+        let (text, labelled) = layers(&acetate, true);
+        assert!(!labelled);
+        assert!(text.starts_with("OSK1/c"), "{text}");
+        assert!(text.ends_with("/i/t/e"), "{text}");
+        assert!(text.contains(&format!("/q{}:-1/", ranks[3])), "{text}");
+        assert_eq!(layers(&acetate, false).0, text.trim_end_matches("/i/t/e"));
+        assert!(layers(&parse("[13CH4]").unwrap(), true).1);
+    }
+
+    #[test]
+    fn blocks_are_letters() {
+        let block = block("OSK1", 14);
+        assert_eq!(block.len(), 14);
+        assert!(block.bytes().all(|b| b.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn stripping_drops_labels() {
+        let molecule = parse("[13CH3:1]/C=C/[C@H](F)Cl").unwrap();
+        let stripped = molecule.stripped(false);
+        assert!(stripped.nodes().iter().all(|n| n.class().is_none()
+            && n.chirality().is_none()
+            && n.atom().isotope().is_none()));
+        assert!(stripped
+            .bonds()
+            .iter()
+            .all(|b| !matches!(b.kind(), BondType::Up | BondType::Down)));
+        let kept = molecule.stripped(true);
+        assert_eq!(kept.nodes()[0].class(), None);
+        assert_eq!(kept.nodes()[0].atom().isotope(), Some(13));
+    }
+
+    #[test]
+    fn keys_are_invariant_under_atom_renumbering() {
+        for smiles in [
+            "C[C@H]1CC[C@@H](C)CC1",
+            "C[C@H]1CC[C@H](C)CC1",
+            "C1CC[C@H]2CCCC[C@@H]2C1",
+            "C1CC[C@H]2CCCC[C@H]2C1",
+            "C[C@@H]1C[C@H](C)C[C@H](C)C1",
+            "C[C@H]1C[C@@H](C)C[C@H](C)C1",
+            "C[C@H]1C[C@H]2C[C@@H](C)C[C@@H]2C1",
+            "C12C3C4C(C43)C5C1C25",
+            "C12C3C4C5C4C5C1C23",
+            "C12C3C(C32)C4C5C4C15",
+            "C12C3C4C(C42)C5C6C(C13)C56",
+        ] {
+            let molecule = parse(smiles).unwrap();
+            let expected = molecule.identity_key();
+            for perm in permutations(molecule.nodes().len(), 20) {
+                assert_eq!(
+                    permuted(&molecule, &perm).identity_key(),
+                    expected,
+                    "{smiles} with permutation {perm:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Graph isomorphism and identity-based equality of molecules.
+//!
+//! Both molecules are first brought to the same form: removable `[H]` atoms
+//! are folded into hydrogen counts and aromaticity is perceived with the
+//! Daylight model, so `C1=CC=CC=C1` and `c1ccccc1`, or `[H]OC` and `CO`,
+//! describe the same graph. A sorted list of atom signatures (element,
+//! isotope, charge, hydrogens, aromaticity and neighbours) rejects most
+//! pairs at once; the others are settled by an exact search for a
+//! bijection with the substructure matcher.
+
+use std::hash::{Hash, Hasher};
+
+use super::aromaticity::AromaticityModel;
+use super::bond::{Bond, BondType};
+use super::canonical::bond_code;
+use super::molecule::Molecule;
+use super::stereo::{is_tetrahedral, side_of};
+use crate::smarts::QueryMolecule;
+use crate::NodeIndex;
+
+/// Element, isotope, charge, hydrogens, aromaticity, degree and, when
+/// stereo is compared, tetrahedral chirality of an atom.
+type AtomInvariant = (u8, u16, i8, u8, bool, usize, bool);
+
+/// An atom invariant with the sorted invariants and bond codes of the
+/// neighbours.
+type Signature = (AtomInvariant, Vec<(AtomInvariant, u8)>);
+
+impl Molecule {
+    /// Returns `true` if the two molecules are the same compound, stereo
+    /// aside: some mapping of the atoms preserves element, isotope, charge,
+    /// hydrogen count, aromaticity and bond orders.
+    ///
+    /// Explicit hydrogens, Kekulé or aromatic input and atom order do not
+    /// matter; atom classes are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let a = parse("CCO").unwrap();
+    /// let b = parse("OCC").unwrap();
+    /// assert_ne!(a, b);
+    /// assert!(a.is_isomorphic(&b));
+    /// assert!(parse("c1ccccc1").unwrap().is_isomorphic(&parse("C1=CC=CC=C1").unwrap()));
+    /// ```
+    pub fn is_isomorphic(&self, other: &Molecule) -> bool {
+        isomorphic(&self.identity_form(), &other.identity_form(), false)
+    }
+
+    /// Returns `true` if the two molecules are the same stereoisomer: as
+    /// [`is_isomorphic`](Self::is_isomorphic), with tetrahedral centres
+    /// (`@`, `@@`) and double-bond geometry (`/`, `\`) compared as well. A
+    /// centre or double bond specified in one molecule must be specified
+    /// alike in the other.
+    pub fn is_isomorphic_with_stereo(&self, other: &Molecule) -> bool {
+        isomorphic(&self.identity_form(), &other.identity_form(), true)
+    }
+
+    /// Folds removable hydrogens and perceives aromaticity. A molecule whose
+    /// aromatic part cannot be kekulized keeps its aromaticity as written.
+    pub(crate) fn identity_form(&self) -> Molecule {
+        self.remove_explicit_hydrogens().perceived_aromaticity()
+    }
+
+    /// Perceives aromaticity as [`identity_form`](Self::identity_form) does,
+    /// keeping every atom and bond at its index.
+    pub(crate) fn perceived_aromaticity(&self) -> Molecule {
+        self.aromatize(AromaticityModel::Daylight)
+            .unwrap_or_else(|_| self.clone())
+    }
+}
+
+fn signatures(molecule: &Molecule, stereo: bool) -> Vec<Signature> {
+    let invariants: Vec<AtomInvariant> = molecule
+        .nodes()
+        .iter()
+        .map(|node| {
+            let atom = node.atom();
+            (
+                atom.element().element_data().atomic_number,
+                atom.isotope().unwrap_or(0),
+                atom.charge(),
+                node.hydrogens(),
+                node.aromatic(),
+                0,
+                stereo && is_tetrahedral(node.chirality()),
+            )
+        })
+        .collect();
+    let mut neighbours: Vec<Vec<(AtomInvariant, u8)>> = vec![Vec::new(); invariants.len()];
+    for bond in molecule.bonds() {
+        if bond.kind() == BondType::Disconnected {
+            continue;
+        }
+        let (s, t) = (bond.source() as usize, bond.target() as usize);
+        let code = bond_code(bond.kind());
+        neighbours[s].push((invariants[t], code));
+        neighbours[t].push((invariants[s], code));
+    }
+    let mut signatures: Vec<Signature> = invariants
+        .into_iter()
+        .zip(neighbours)
+        .map(|(mut invariant, mut neighbours)| {
+            invariant.5 = neighbours.len();
+            neighbours.sort_unstable();
+            (invariant, neighbours)
+        })
+        .collect();
+    signatures.sort_unstable();
+    signatures
+}
+
+/// Compares two molecules already in identity form.
+fn isomorphic(a: &Molecule, b: &Molecule, stereo: bool) -> bool {
+    isomorphic_given(a, b, stereo, &signatures(a, stereo), &signatures(b, stereo))
+}
+
+fn isomorphic_given(
+    a: &Molecule,
+    b: &Molecule,
+    stereo: bool,
+    signatures_a: &[Signature],
+    signatures_b: &[Signature],
+) -> bool {
+    if signatures_a != signatures_b {
+        return false;
+    }
+    if a.nodes().is_empty() {
+        return true;
+    }
+    // Same atom and bond counts: every match of `a` in `b` is a bijection.
+    let query = QueryMolecule::from_molecule(a, true);
+    let mut found = false;
+    b.for_each_match(&query, stereo, &mut |image| {
+        found = !stereo || double_bonds_agree(a, b, image);
+        !found
+    });
+    found
+}
+
+/// Indices of the bonds of every atom.
+fn incident_bonds(molecule: &Molecule) -> Vec<Vec<usize>> {
+    let mut incident = vec![Vec::new(); molecule.nodes().len()];
+    for (i, bond) in molecule.bonds().iter().enumerate() {
+        if bond.kind() != BondType::Disconnected {
+            incident[bond.source() as usize].push(i);
+            incident[bond.target() as usize].push(i);
+        }
+    }
+    incident
+}
+
+fn other_end(bond: &Bond, atom: NodeIndex) -> NodeIndex {
+    if bond.source() == atom {
+        bond.target()
+    } else {
+        bond.source()
+    }
+}
+
+/// Returns whether `x`, a substituent of `u` in the double bond `u=v`, lies
+/// above `u`: read from the bond to `x` when it is directional, otherwise
+/// opposite to another marked substituent.
+fn side(
+    molecule: &Molecule,
+    incident: &[Vec<usize>],
+    u: NodeIndex,
+    v: NodeIndex,
+    x: NodeIndex,
+) -> Option<bool> {
+    let bonds = incident[u as usize].iter().map(|&i| &molecule.bonds()[i]);
+    let mut others = None;
+    for bond in bonds {
+        let w = other_end(bond, u);
+        if w == x {
+            if let Some(side) = side_of(bond, u) {
+                return Some(side);
+            }
+        } else if w != v && others.is_none() {
+            others = side_of(bond, u).map(|side| !side);
+        }
+    }
+    others
+}
+
+/// Returns a substituent of each end of the double bond `u=v` and whether
+/// they are cis, or `None` if the geometry is not specified.
+fn geometry(
+    molecule: &Molecule,
+    incident: &[Vec<usize>],
+    u: NodeIndex,
+    v: NodeIndex,
+) -> Option<(NodeIndex, NodeIndex, bool)> {
+    let substituent = |center: NodeIndex, partner: NodeIndex| {
+        incident[center as usize]
+            .iter()
+            .map(|&i| &molecule.bonds()[i])
+            .find(|bond| other_end(bond, center) != partner && side_of(bond, center).is_some())
+            .map(|bond| other_end(bond, center))
+    };
+    let x = substituent(u, v)?;
+    let y = substituent(v, u)?;
+    let cis = side(molecule, incident, u, v, x)? == side(molecule, incident, v, u, y)?;
+    Some((x, y, cis))
+}
+
+/// Checks that a bijection from `a` to `b` keeps the double-bond geometry.
+fn double_bonds_agree(a: &Molecule, b: &Molecule, image: &[NodeIndex]) -> bool {
+    let (incident_a, incident_b) = (incident_bonds(a), incident_bonds(b));
+    let map = |atom: NodeIndex| image[atom as usize];
+    a.bonds()
+        .iter()
+        .filter(|bond| bond.kind() == BondType::Double)
+        .all(|bond| {
+            let (u, v) = (bond.source(), bond.target());
+            match (
+                geometry(a, &incident_a, u, v),
+                geometry(b, &incident_b, map(u), map(v)),
+            ) {
+                (None, None) => true,
+                (Some((x, y, cis)), Some(_)) => {
+                    let side_x = side(b, &incident_b, map(u), map(v), map(x));
+                    let side_y = side(b, &incident_b, map(v), map(u), map(y));
+                    matches!((side_x, side_y), (Some(sx), Some(sy)) if (sx == sy) == cis)
+                }
+                _ => false,
+            }
+        })
+}
+
+/// A molecule compared and hashed by chemical identity, for use as a
+/// `HashMap` or `HashSet` key.
+///
+/// Two keys are equal when their molecules are the same stereoisomer (see
+/// [`Molecule::is_isomorphic_with_stereo`]), whatever their atom order,
+/// hydrogen form or aromatic form.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashSet;
+/// use opensmiles::{parse, MoleculeKey};
+///
+/// let compounds: HashSet<MoleculeKey> = ["CCO", "OCC", "C(O)C", "COC"]
+///     .iter()
+///     .map(|s| MoleculeKey::new(parse(s).unwrap()))
+///     .collect();
+/// assert_eq!(compounds.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MoleculeKey {
+    molecule: Molecule,
+    normalized: Molecule,
+    signatures: Vec<Signature>,
+}
+
+impl MoleculeKey {
+    /// Wraps a molecule.
+    pub fn new(molecule: Molecule) -> MoleculeKey {
+        let normalized = molecule.identity_form();
+        let signatures = signatures(&normalized, true);
+        MoleculeKey {
+            molecule,
+            normalized,
+            signatures,
+        }
+    }
+
+    /// Returns the molecule as it was given.
+    pub fn molecule(&self) -> &Molecule {
+        &self.molecule
+    }
+
+    /// Consumes the key and returns its molecule.
+    pub fn into_molecule(self) -> Molecule {
+        self.molecule
+    }
+}
+
+impl From<Molecule> for MoleculeKey {
+    fn from(molecule: Molecule) -> Self {
+        MoleculeKey::new(molecule)
+    }
+}
+
+impl PartialEq for MoleculeKey {
+    fn eq(&self, other: &Self) -> bool {
+        isomorphic_given(
+            &self.normalized,
+            &other.normalized,
+            true,
+            &self.signatures,
+            &other.signatures,
+        )
+    }
+}
+
+impl Eq for MoleculeKey {}
+
+/// Hashes the atom signatures, which isomorphic molecules share.
+impl Hash for MoleculeKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.signatures.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn signatures_ignore_atom_order() {
+        let a = parse("CC(=O)N").unwrap();
+        let b = parse("NC(C)=O").unwrap();
+        assert_eq!(signatures(&a, false), signatures(&b, false));
+        assert_ne!(
+            signatures(&a, false),
+            signatures(&parse("CC(=O)O").unwrap(), false)
+        );
+    }
+
+    #[test]
+    fn identity_form_folds_hydrogens_and_perceives_aromaticity() {
+        let form = parse("[H]C1=CC=CC=C1").unwrap().identity_form();
+        assert_eq!(form.nodes().len(), 6);
+        assert!(form.nodes().iter().all(|n| n.aromatic()));
+    }
+
+    #[test]
+    fn double_bond_geometry() {
+        let molecule = parse("F/C=C/F").unwrap();
+        let incident = incident_bonds(&molecule);
+        assert_eq!(geometry(&molecule, &incident, 1, 2), Some((0, 3, false)));
+        let molecule = parse("F/C(Cl)=C/F").unwrap();
+        let incident = incident_bonds(&molecule);
+        assert_eq!(side(&molecule, &incident, 1, 3, 2), Some(true));
+        let molecule = parse("FC=CF").unwrap();
+        let incident = incident_bonds(&molecule);
+        assert_eq!(geometry(&molecule, &incident, 1, 2), None);
+    }
+}
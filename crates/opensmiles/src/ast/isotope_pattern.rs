@@ -0,0 +1,216 @@
+//! Isotope patterns for mass spectrometry.
+//!
+//! The isotopic envelope is the convolution of the isotope distributions of
+//! every atom. Each element is raised to its atom count by repeated
+//! squaring, then the elements are combined; after every convolution peaks
+//! of identical mass are merged and negligible ones pruned, which keeps the
+//! fine structure tractable for large molecules. Labelled atoms contribute
+//! their isotope only.
+
+use super::atom::AtomSymbol;
+use super::element_data::isotope_mass;
+use super::formula::Formula;
+use super::mass::ELECTRON_MASS;
+use super::molecule::Molecule;
+
+/// Mass of the proton in Daltons.
+const PROTON_MASS: f64 = 1.007_276_466_621;
+
+/// Peaks closer than this (in Daltons) come from the same composition.
+const SAME_MASS: f64 = 1e-6;
+
+/// Intermediate distributions keep peaks this much weaker than the final
+/// threshold, since they may still combine with intense peaks.
+const PRUNE_MARGIN: f64 = 1e-3;
+
+/// Options for [`Molecule::isotope_distribution`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsotopeDistributionOptions {
+    /// Number of protons added to the molecule as written (negative to
+    /// remove them). The ion charge is the molecule's net charge plus this
+    /// value; `0` keeps the molecule as written.
+    pub protons: i32,
+    /// Peaks weaker than this fraction of the most intense peak are dropped.
+    pub min_relative_intensity: f64,
+    /// When set, peaks closer than this m/z width are merged into their
+    /// intensity-weighted centroid, as an instrument of that resolution
+    /// would show them. `None` keeps the isotopic fine structure.
+    pub merge_width: Option<f64>,
+}
+
+impl Default for IsotopeDistributionOptions {
+    fn default() -> Self {
+        IsotopeDistributionOptions {
+            protons: 0,
+            min_relative_intensity: 1e-4,
+            merge_width: None,
+        }
+    }
+}
+
+/// One peak of an isotopic envelope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsotopePeak {
+    /// Mass-to-charge ratio; the mass itself for a neutral species.
+    pub mz: f64,
+    /// Fraction of the molecules in this peak.
+    pub probability: f64,
+    /// Intensity relative to the most intense peak (which has `1.0`).
+    pub intensity: f64,
+}
+
+impl Formula {
+    /// Returns the isotopic envelope of the formula, sorted by m/z.
+    ///
+    /// See [`Molecule::isotope_distribution`].
+    pub fn isotope_distribution(&self, options: &IsotopeDistributionOptions) -> Vec<IsotopePeak> {
+        let threshold = (options.min_relative_intensity * PRUNE_MARGIN).max(1e-15);
+
+        let mut peaks = vec![(0.0, 1.0)];
+        for (element, isotope, n) in self.iter() {
+            let atom = match isotope {
+                Some(mass_number) => vec![(isotope_mass(&element, mass_number), 1.0)],
+                None => natural_isotopes(element),
+            };
+            let element_peaks = power(&atom, n, threshold);
+            peaks = convolve(&peaks, &element_peaks, threshold);
+        }
+
+        let charge = self.charge() + options.protons;
+        let mass_shift =
+            options.protons as f64 * PROTON_MASS - self.charge() as f64 * ELECTRON_MASS;
+        let divisor = charge.unsigned_abs().max(1) as f64;
+        let mut peaks: Vec<(f64, f64)> = peaks
+            .into_iter()
+            .map(|(mass, p)| ((mass + mass_shift) / divisor, p))
+            .collect();
+
+        if let Some(width) = options.merge_width {
+            peaks = merge(peaks, width);
+        }
+        let max = peaks.iter().map(|&(_, p)| p).fold(0.0, f64::max);
+        peaks
+            .into_iter()
+            .filter(|&(_, p)| p >= max * options.min_relative_intensity)
+            .map(|(mz, probability)| IsotopePeak {
+                mz,
+                probability,
+                intensity: probability / max,
+            })
+            .collect()
+    }
+}
+
+/// Isotopes of an unlabelled atom as `(mass, probability)`.
+fn natural_isotopes(element: AtomSymbol) -> Vec<(f64, f64)> {
+    let natural: Vec<(f64, f64)> = element
+        .isotopes()
+        .iter()
+        .filter(|i| i.abundance > 0.0)
+        .map(|i| (i.mass, i.abundance))
+        .collect();
+    let total: f64 = natural.iter().map(|&(_, p)| p).sum();
+    if total > 0.0 {
+        natural.into_iter().map(|(m, p)| (m, p / total)).collect()
+    } else {
+        // No natural composition: a single peak at the reference isotope.
+        vec![(element.monoisotopic_mass(), 1.0)]
+    }
+}
+
+/// Distribution of `n` atoms, by repeated squaring.
+fn power(atom: &[(f64, f64)], mut n: u32, threshold: f64) -> Vec<(f64, f64)> {
+    let mut result = vec![(0.0, 1.0)];
+    let mut base = atom.to_vec();
+    while n > 0 {
+        if n & 1 == 1 {
+            result = convolve(&result, &base, threshold);
+        }
+        n >>= 1;
+        if n > 0 {
+            base = convolve(&base, &base, threshold);
+        }
+    }
+    result
+}
+
+/// Convolves two distributions, merging identical masses and dropping peaks
+/// below `threshold` times the most intense one.
+fn convolve(a: &[(f64, f64)], b: &[(f64, f64)], threshold: f64) -> Vec<(f64, f64)> {
+    let mut peaks: Vec<(f64, f64)> = a
+        .iter()
+        .flat_map(|&(ma, pa)| b.iter().map(move |&(mb, pb)| (ma + mb, pa * pb)))
+        .collect();
+    peaks = merge(peaks, SAME_MASS);
+    let max = peaks.iter().map(|&(_, p)| p).fold(0.0, f64::max);
+    peaks.retain(|&(_, p)| p >= max * threshold);
+    peaks
+}
+
+/// Merges runs of peaks whose consecutive gaps are below `width` into their
+/// probability-weighted centroid. Returns peaks sorted by mass.
+fn merge(mut peaks: Vec<(f64, f64)>, width: f64) -> Vec<(f64, f64)> {
+    peaks.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(peaks.len());
+    let mut last_mass = f64::NEG_INFINITY;
+    for (mass, p) in peaks {
+        match merged.last_mut() {
+            Some((centroid, total)) if mass - last_mass < width => {
+                *centroid = (*centroid * *total + mass * p) / (*total + p);
+                *total += p;
+            }
+            _ => merged.push((mass, p)),
+        }
+        last_mass = mass;
+    }
+    merged
+}
+
+impl Molecule {
+    /// Returns the isotopic envelope of the molecule, sorted by m/z.
+    ///
+    /// Implicit hydrogens are included, atoms with an explicit isotope
+    /// (`[13CH3]`) keep it, and electron masses are accounted for. The ion
+    /// charge is the molecule's net charge plus `options.protons`; m/z is
+    /// the mass divided by its magnitude (a neutral species reports masses).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, IsotopeDistributionOptions};
+    ///
+    /// let options = IsotopeDistributionOptions {
+    ///     protons: 1,
+    ///     merge_width: Some(0.1),
+    ///     ..Default::default()
+    /// };
+    /// let peaks = parse("Clc1ccccc1").unwrap().isotope_distribution(&options);
+    /// assert_eq!(format!("{:.4}", peaks[0].mz), "113.0153"); // [M+H]+
+    /// assert!((peaks[2].intensity - 0.32).abs() < 0.01); // 37Cl
+    /// ```
+    pub fn isotope_distribution(&self, options: &IsotopeDistributionOptions) -> Vec<IsotopePeak> {
+        self.formula().isotope_distribution(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_distant_peaks_apart() {
+        let merged = merge(vec![(2.0, 0.5), (1.0, 0.25), (1.05, 0.25)], 0.1);
+        assert_eq!(merged.len(), 2);
+        assert!((merged[0].0 - 1.025).abs() < 1e-12);
+        assert_eq!(merged[0].1, 0.5);
+        assert_eq!(merged[1], (2.0, 0.5));
+    }
+
+    #[test]
+    fn power_matches_binomial() {
+        // Two isotopes with equal abundance: binomial 1:3:3:1 for n = 3.
+        let peaks = power(&[(1.0, 0.5), (2.0, 0.5)], 3, 0.0);
+        let probabilities: Vec<f64> = peaks.iter().map(|&(_, p)| p).collect();
+        assert_eq!(probabilities, vec![0.125, 0.375, 0.375, 0.125]);
+    }
+}
@@ -0,0 +1,341 @@
+//! Kekulization: assigning alternating single and double bonds to aromatic
+//! systems.
+//!
+//! Every aromatic atom that still has an unpaired p electron after its sigma
+//! bonds (neutral `c`, pyridine-like `n`, `[n+]`, `[o+]`, ...) must receive
+//! exactly one double bond. Pyrrole-like `[nH]`, `o`, `s`, `[c-]` and atoms
+//! that already carry an exocyclic double bond keep only single bonds.
+//! Choosing the double bonds is a perfect matching on the aromatic bonds
+//! between the atoms that need one, found with Edmonds' blossom algorithm so
+//! that odd rings (furan, azulene, ...) are handled.
+
+use std::collections::VecDeque;
+
+use super::atom::AtomSymbol;
+use super::bond::{Bond, BondType};
+use super::molecule::Molecule;
+use crate::{MoleculeError, NodeIndex};
+
+const NONE: usize = usize::MAX;
+
+/// Whether an aromatic atom needs a double bond in the Kekulé form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PiDemand {
+    /// The atom keeps only single bonds.
+    None,
+    /// The atom must receive one double bond.
+    Required,
+    /// The atom may receive a double bond (wildcard `*`).
+    Optional,
+}
+
+/// Tells whether `atom`, whose bonds are of the kinds `bonds`, needs a
+/// double bond.
+fn pi_demand(molecule: &Molecule, atom: usize, bonds: &[BondType]) -> PiDemand {
+    let node = &molecule.nodes()[atom];
+    if !node.aromatic() {
+        return PiDemand::None;
+    }
+    let element = *node.atom().element();
+    if element == AtomSymbol::Wildcard {
+        return PiDemand::Optional;
+    }
+
+    let mut sigma = node.hydrogens() as i16;
+    for kind in bonds {
+        match kind {
+            // An exocyclic multiple bond already uses the p orbital.
+            BondType::Double | BondType::Triple | BondType::Quadruple => return PiDemand::None,
+            BondType::Disconnected => {}
+            _ => sigma += 1,
+        }
+    }
+
+    // One unpaired electron left after the sigma bonds and lone pairs.
+    let remaining =
+        element.element_data().valence_electrons as i16 - sigma - node.atom().charge() as i16;
+    if remaining > 0 && remaining % 2 == 1 {
+        PiDemand::Required
+    } else {
+        PiDemand::None
+    }
+}
+
+impl Molecule {
+    /// Returns a copy of the molecule with every aromatic bond replaced by
+    /// an explicit single or double bond and every atom marked non-aromatic.
+    ///
+    /// Atoms that need a double bond (`c`, `n`, `[n+]`, ...) each receive
+    /// exactly one; `[nH]`, `o`, `s`, `[c-]` and atoms with an exocyclic
+    /// double bond keep single bonds. Fused and odd-membered systems are
+    /// handled. Hydrogen counts, charges, chirality and directional bonds are
+    /// preserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MoleculeError::KekulizationFailed`] with the atoms left
+    /// without a double bond when no assignment exists (e.g. pyrrole written
+    /// as `c1ccnc1` instead of `c1cc[nH]c1`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, BondType};
+    ///
+    /// let benzene = parse("c1ccccc1").unwrap().kekulize().unwrap();
+    /// let doubles = benzene
+    ///     .bonds()
+    ///     .iter()
+    ///     .filter(|b| b.kind() == BondType::Double)
+    ///     .count();
+    /// assert_eq!(doubles, 3);
+    /// assert!(benzene.nodes().iter().all(|n| !n.aromatic()));
+    /// ```
+    pub fn kekulize(&self) -> Result<Molecule, MoleculeError> {
+        let n = self.nodes().len();
+        let mut incident: Vec<Vec<BondType>> = vec![Vec::new(); n];
+        for bond in self.bonds() {
+            incident[bond.source() as usize].push(bond.kind());
+            incident[bond.target() as usize].push(bond.kind());
+        }
+        let demand: Vec<PiDemand> = (0..n).map(|a| pi_demand(self, a, &incident[a])).collect();
+
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for bond in self.bonds() {
+            let (a, b) = (bond.source() as usize, bond.target() as usize);
+            if bond.kind() == BondType::Aromatic
+                && demand[a] != PiDemand::None
+                && demand[b] != PiDemand::None
+            {
+                adjacency[a].push(b);
+                adjacency[b].push(a);
+            }
+        }
+
+        let required: Vec<bool> = demand.iter().map(|&d| d == PiDemand::Required).collect();
+        let mate = maximum_matching(&adjacency, &required);
+
+        let mut unmatched: Vec<NodeIndex> = (0..n)
+            .filter(|&a| required[a] && mate[a] == NONE)
+            .map(|a| a as NodeIndex)
+            .collect();
+        if !unmatched.is_empty() {
+            unmatched.sort_unstable();
+            return Err(MoleculeError::KekulizationFailed { atoms: unmatched });
+        }
+
+        let bonds = self
+            .bonds()
+            .iter()
+            .map(|bond| {
+                let (a, b) = (bond.source(), bond.target());
+                let kind = match bond.kind() {
+                    BondType::Aromatic if mate[a as usize] == b as usize => BondType::Double,
+                    BondType::Aromatic => BondType::Simple,
+                    other => other,
+                };
+                Bond::new(kind, a, b)
+            })
+            .collect();
+        let nodes = self
+            .nodes()
+            .iter()
+            .map(|node| node.with_aromatic(false))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Molecule::new(nodes, bonds))
+    }
+}
+
+/// Maximum matching of a general graph, grown from the `required` vertices.
+///
+/// A greedy pass matches most vertices; Edmonds' blossom search then looks
+/// for an augmenting path from every required vertex left exposed. Augmenting
+/// never unmatches a vertex, so optional vertices are only used when they
+/// help a required one. Returns the mate of every vertex (`NONE` if exposed).
+fn maximum_matching(adjacency: &[Vec<usize>], required: &[bool]) -> Vec<usize> {
+    let n = adjacency.len();
+    let mut mate = vec![NONE; n];
+
+    // Vertices with fewer choices first: chain ends and ring fusions settle
+    // early, which leaves few exposed vertices for the blossom search.
+    let mut order: Vec<usize> = (0..n).filter(|&v| required[v]).collect();
+    order.sort_by_key(|&v| adjacency[v].len());
+    for &v in &order {
+        if mate[v] != NONE {
+            continue;
+        }
+        let partner = adjacency[v]
+            .iter()
+            .copied()
+            .filter(|&u| mate[u] == NONE)
+            .min_by_key(|&u| (!required[u], adjacency[u].len()));
+        if let Some(u) = partner {
+            mate[v] = u;
+            mate[u] = v;
+        }
+    }
+
+    let mut search = BlossomSearch::new(n);
+    for v in order {
+        if mate[v] == NONE {
+            if let Some(end) = search.augmenting_path(adjacency, &mate, v) {
+                search.augment(&mut mate, end);
+            }
+        }
+    }
+    mate
+}
+
+/// Scratch state of one blossom search (Edmonds' algorithm).
+struct BlossomSearch {
+    parent: Vec<usize>,
+    base: Vec<usize>,
+    used: Vec<bool>,
+    blossom: Vec<bool>,
+}
+
+impl BlossomSearch {
+    fn new(n: usize) -> Self {
+        BlossomSearch {
+            parent: vec![NONE; n],
+            base: (0..n).collect(),
+            used: vec![false; n],
+            blossom: vec![false; n],
+        }
+    }
+
+    /// Breadth-first search for an augmenting path from `root`; returns the
+    /// exposed vertex at its other end.
+    fn augmenting_path(
+        &mut self,
+        adjacency: &[Vec<usize>],
+        mate: &[usize],
+        root: usize,
+    ) -> Option<usize> {
+        let n = adjacency.len();
+        self.parent.fill(NONE);
+        self.used.fill(false);
+        for (v, base) in self.base.iter_mut().enumerate() {
+            *base = v;
+        }
+
+        self.used[root] = true;
+        let mut queue = VecDeque::from([root]);
+        while let Some(v) = queue.pop_front() {
+            for &to in &adjacency[v] {
+                if self.base[v] == self.base[to] || mate[v] == to {
+                    continue;
+                }
+                if to == root || (mate[to] != NONE && self.parent[mate[to]] != NONE) {
+                    // Odd cycle: contract it into a blossom around its base.
+                    let current_base = self.lowest_common_ancestor(mate, v, to);
+                    self.blossom.fill(false);
+                    self.mark_path(mate, v, current_base, to);
+                    self.mark_path(mate, to, current_base, v);
+                    for i in 0..n {
+                        if self.blossom[self.base[i]] {
+                            self.base[i] = current_base;
+                            if !self.used[i] {
+                                self.used[i] = true;
+                                queue.push_back(i);
+                            }
+                        }
+                    }
+                } else if self.parent[to] == NONE {
+                    self.parent[to] = v;
+                    if mate[to] == NONE {
+                        return Some(to);
+                    }
+                    self.used[mate[to]] = true;
+                    queue.push_back(mate[to]);
+                }
+            }
+        }
+        None
+    }
+
+    fn lowest_common_ancestor(&self, mate: &[usize], mut a: usize, mut b: usize) -> usize {
+        let mut seen = vec![false; mate.len()];
+        loop {
+            a = self.base[a];
+            seen[a] = true;
+            if mate[a] == NONE {
+                break;
+            }
+            a = self.parent[mate[a]];
+        }
+        loop {
+            b = self.base[b];
+            if seen[b] {
+                return b;
+            }
+            b = self.parent[mate[b]];
+        }
+    }
+
+    fn mark_path(&mut self, mate: &[usize], mut v: usize, base: usize, mut child: usize) {
+        while self.base[v] != base {
+            self.blossom[self.base[v]] = true;
+            self.blossom[self.base[mate[v]]] = true;
+            self.parent[v] = child;
+            child = mate[v];
+            v = self.parent[mate[v]];
+        }
+    }
+
+    /// Flips the matched and unmatched edges along the path ending at `end`.
+    fn augment(&self, mate: &mut [usize], mut end: usize) {
+        while end != NONE {
+            let previous = self.parent[end];
+            let next = mate[previous];
+            mate[end] = previous;
+            mate[previous] = end;
+            end = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(n: usize) -> Vec<Vec<usize>> {
+        (0..n).map(|i| vec![(i + n - 1) % n, (i + 1) % n]).collect()
+    }
+
+    #[test]
+    fn even_cycle_has_a_perfect_matching() {
+        let mate = maximum_matching(&cycle(6), &[true; 6]);
+        assert!(mate
+            .iter()
+            .enumerate()
+            .all(|(v, &m)| m != NONE && mate[m] == v));
+    }
+
+    #[test]
+    fn odd_cycle_leaves_one_vertex_exposed() {
+        let mate = maximum_matching(&cycle(5), &[true; 5]);
+        assert_eq!(mate.iter().filter(|&&m| m == NONE).count(), 1);
+    }
+
+    #[test]
+    fn augmenting_path_through_a_blossom() {
+        // Five-membered ring 0..4 matched as 1-2 and 3-4, with a pendant 5 on
+        // atom 3: the only augmenting path from 0 runs through the ring.
+        let mut adjacency = cycle(5);
+        adjacency[3].push(5);
+        adjacency.push(vec![3]);
+        let mut mate = vec![NONE, 2, 1, 4, 3, NONE];
+        let mut search = BlossomSearch::new(6);
+        let end = search.augmenting_path(&adjacency, &mate, 0).unwrap();
+        search.augment(&mut mate, end);
+        assert_eq!(mate, vec![4, 2, 1, 5, 0, 3]);
+    }
+
+    #[test]
+    fn optional_vertices_complete_a_matching() {
+        let mate = maximum_matching(&cycle(5), &[true, true, true, true, false]);
+        assert!((0..4).all(|v| mate[v] != NONE));
+    }
+}
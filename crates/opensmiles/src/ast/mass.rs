@@ -0,0 +1,73 @@
+//! Molecular masses.
+//!
+//! Unlabelled atoms take the average or monoisotopic mass of their element;
+//! isotopically labelled atoms (`[13C]`, `[2H]`) always take the exact mass
+//! of their isotope. A net charge removes or adds electron masses.
+
+use super::atom::AtomSymbol;
+use super::element_data::isotope_mass;
+use super::formula::Formula;
+use super::molecule::Molecule;
+
+/// Mass of the electron in Daltons (CODATA 2018).
+pub const ELECTRON_MASS: f64 = 0.000_548_579_909_065;
+
+impl Formula {
+    /// Returns the average mass in Daltons, from natural isotopic abundances.
+    pub fn average_mass(&self) -> f64 {
+        self.mass(|element| element.average_mass())
+    }
+
+    /// Returns the monoisotopic mass in Daltons, from the most abundant
+    /// isotope of each element.
+    pub fn monoisotopic_mass(&self) -> f64 {
+        self.mass(|element| element.monoisotopic_mass())
+    }
+
+    fn mass(&self, element_mass: impl Fn(&AtomSymbol) -> f64) -> f64 {
+        let atoms: f64 = self
+            .iter()
+            .map(|(element, isotope, n)| {
+                let mass = match isotope {
+                    Some(mass_number) => isotope_mass(&element, mass_number),
+                    None => element_mass(&element),
+                };
+                mass * n as f64
+            })
+            .sum();
+        atoms - self.charge() as f64 * ELECTRON_MASS
+    }
+}
+
+impl Molecule {
+    /// Returns the average molecular mass in Daltons, implicit hydrogens
+    /// included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let ethanol = parse("CCO").unwrap();
+    /// assert!((ethanol.average_mass() - 46.06844).abs() < 1e-4);
+    /// ```
+    pub fn average_mass(&self) -> f64 {
+        self.formula().average_mass()
+    }
+
+    /// Returns the monoisotopic molecular mass in Daltons, implicit
+    /// hydrogens included and corrected for the electrons lost or gained by
+    /// a charged species.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let ethanol = parse("CCO").unwrap();
+    /// assert_eq!(format!("{:.5}", ethanol.monoisotopic_mass()), "46.04186");
+    /// ```
+    pub fn monoisotopic_mass(&self) -> f64 {
+        self.formula().monoisotopic_mass()
+    }
+}
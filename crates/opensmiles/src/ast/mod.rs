@@ -1,16 +1,38 @@
 pub mod aromaticity;
 mod atom;
 mod bond;
+pub(crate) mod canonical;
 mod chirality;
 mod element_data;
+mod formula;
+mod fragments;
 pub mod graph;
+mod hydrogens;
+pub mod identity_key;
+mod isomorphism;
+mod isotope_pattern;
+mod kekule;
+mod mass;
 mod molecule;
 mod node;
+mod ring_systems;
+pub mod rings;
+pub(crate) mod stereo;
 
+pub use self::aromaticity::AromaticityModel;
 pub use self::atom::*;
 pub use self::bond::*;
 pub use self::chirality::*;
 pub use self::element_data::*;
+pub use self::formula::Formula;
+pub use self::fragments::Fragment;
 pub use self::graph::*;
+pub use self::identity_key::IDENTITY_KEY_VERSION;
+pub use self::isomorphism::MoleculeKey;
+pub use self::isotope_pattern::{IsotopeDistributionOptions, IsotopePeak};
+pub use self::mass::ELECTRON_MASS;
 pub use self::molecule::*;
 pub use self::node::*;
+pub use self::ring_systems::{RingAssembly, RingSystem, RingSystemKind};
+pub use self::rings::{RingInfo, MAX_RELEVANT_CYCLES};
+pub use self::stereo::StereoOutput;
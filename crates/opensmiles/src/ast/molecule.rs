@@ -3,15 +3,18 @@ use std::fmt;
 
 use crate::{
     ast::{
+        aromaticity::{self, AromaticityModel},
         atom::{AtomSymbol, OrganicAtom},
         bond::{Bond, BondType},
         chirality::Chirality,
         node::{Node, NodeBuilder},
+        stereo::{self, Emission, StereoOutput},
     },
     AtomError, MoleculeError, NodeError, NodeIndex,
 };
 
-type SpanningTreeResult = (Vec<Vec<(NodeIndex, BondType)>>, Vec<Vec<u8>>);
+/// Highest ring-closure number expressible in SMILES (`%99`).
+const MAX_RING_NUMBER: u8 = 99;
 
 /// Calcule la signature canonique du sous-graphe enraciné à `node`, sans
 /// repasser par les nœuds déjà visités. Utilisé pour détecter les voisins
@@ -57,41 +60,132 @@ fn canonical_subtree_string(
 /// Pour chaque atome portant une annotation chirale, renvoie `true` si deux
 /// voisins (y compris les H virtuels) ont la même signature canonique, auquel
 /// cas la chiralité n'est pas réelle et ne doit pas être affichée.
+///
+/// The second vector holds the ring system of the tetrahedral centres that
+/// are kept anyway because their only identical neighbours are the two ring
+/// branches: such a centre, as in 1,4-dimethylcyclohexane, is stereogenic
+/// when another such centre shares its ring system, and its parity then
+/// carries the relative configuration (cis or trans) of the ring.
 fn compute_suppress_chirality(
     nodes: &[Node],
     neighbour_list: &[Vec<(NodeIndex, BondType)>],
     virtual_h: &[u8],
-) -> Vec<bool> {
+    bridges: &[(NodeIndex, NodeIndex)],
+) -> (Vec<bool>, Vec<Option<usize>>) {
     let n = nodes.len();
+    let bridges: HashSet<(NodeIndex, NodeIndex)> = bridges.iter().copied().collect();
+    let ring_bond = |u: usize, v: NodeIndex| {
+        let u = u as NodeIndex;
+        !bridges.contains(&(u.min(v), u.max(v)))
+    };
     let mut suppress = vec![false; n];
+    let mut candidates = Vec::new();
     for i in 0..n {
         if nodes[i].chirality().is_none() {
             continue;
         }
-        let mut neighbor_strings: Vec<String> = Vec::new();
+        // (signature, through a ring bond)
+        let mut neighbor_strings: Vec<(String, bool)> = Vec::new();
         for &(w, _) in neighbour_list[i].iter() {
             let mut visited = vec![false; n];
             visited[i] = true;
-            neighbor_strings.push(canonical_subtree_string(
-                w as usize,
-                &mut visited,
-                nodes,
-                neighbour_list,
-                virtual_h,
+            neighbor_strings.push((
+                canonical_subtree_string(
+                    w as usize,
+                    &mut visited,
+                    nodes,
+                    neighbour_list,
+                    virtual_h,
+                ),
+                ring_bond(i, w),
             ));
         }
         let total_h = nodes[i].hydrogens() + virtual_h[i];
         for _ in 0..total_h {
-            neighbor_strings.push("H".to_string());
+            neighbor_strings.push(("H".to_string(), false));
         }
         let mut sorted = neighbor_strings.clone();
         sorted.sort();
-        sorted.dedup();
-        if sorted.len() < neighbor_strings.len() {
-            suppress[i] = true;
+        let duplicated: Vec<&(String, bool)> = sorted
+            .windows(2)
+            .filter(|w| w[0].0 == w[1].0)
+            .flat_map(|w| [&w[0], &w[1]])
+            .collect();
+        if duplicated.is_empty() {
+            continue;
+        }
+        suppress[i] = true;
+        if stereo::is_tetrahedral(nodes[i].chirality()) && duplicated.iter().all(|d| d.1) {
+            candidates.push(i);
         }
     }
-    suppress
+
+    // Ring systems: atoms joined by ring bonds.
+    let mut system = vec![None; n];
+    let mut count = Vec::new();
+    for &start in &candidates {
+        if system[start].is_none() {
+            let id = count.len();
+            count.push(0);
+            system[start] = Some(id);
+            let mut stack = vec![start];
+            while let Some(u) = stack.pop() {
+                for &(v, _) in &neighbour_list[u] {
+                    if system[v as usize].is_none() && ring_bond(u, v) {
+                        system[v as usize] = Some(id);
+                        stack.push(v as usize);
+                    }
+                }
+            }
+        }
+        if let Some(id) = system[start] {
+            count[id] += 1;
+        }
+    }
+    let mut ring_relative = vec![None; n];
+    for &i in &candidates {
+        if let Some(id) = system[i].filter(|&id| count[id] > 1) {
+            suppress[i] = false;
+            ring_relative[i] = Some(id);
+        }
+    }
+    (suppress, ring_relative)
+}
+
+/// Indique si l'un des atomes de la double liaison `u=v` porte deux
+/// substituants identiques, auquel cas l'isomérisme E/Z n'existe pas.
+fn double_bond_has_identical_side(
+    nodes: &[Node],
+    neighbour_list: &[Vec<(NodeIndex, BondType)>],
+    virtual_h: &[u8],
+    u: usize,
+    v: usize,
+) -> bool {
+    let n = nodes.len();
+    let side_has_identical = |center: usize, other: usize| -> bool {
+        let subs: Vec<usize> = neighbour_list[center]
+            .iter()
+            .filter(|&&(w, _)| w as usize != other)
+            .map(|&(w, _)| w as usize)
+            .collect();
+        if subs.len() < 2 {
+            return false;
+        }
+        let strings: Vec<String> = subs
+            .iter()
+            .map(|&w| {
+                let mut visited = vec![false; n];
+                visited[center] = true;
+                canonical_subtree_string(w, &mut visited, nodes, neighbour_list, virtual_h)
+            })
+            .collect();
+        let mut sorted = strings.clone();
+        sorted.sort();
+        sorted.dedup();
+        sorted.len() < strings.len()
+    };
+
+    side_has_identical(u, v) || side_has_identical(v, u)
 }
 
 /// Identifie les liaisons Up/Down qui décrivent un isomérisme E/Z inexistant
@@ -102,7 +196,6 @@ fn compute_suppress_stereo_bonds(
     bonds: &[Bond],
     virtual_h: &[u8],
 ) -> HashSet<(NodeIndex, NodeIndex)> {
-    let n = nodes.len();
     let mut suppress: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
 
     for bond in bonds {
@@ -112,30 +205,7 @@ fn compute_suppress_stereo_bonds(
         let u = bond.source() as usize;
         let v = bond.target() as usize;
 
-        let side_has_identical = |center: usize, other: usize| -> bool {
-            let subs: Vec<usize> = neighbour_list[center]
-                .iter()
-                .filter(|&&(w, _)| w as usize != other)
-                .map(|&(w, _)| w as usize)
-                .collect();
-            if subs.len() < 2 {
-                return false;
-            }
-            let strings: Vec<String> = subs
-                .iter()
-                .map(|&w| {
-                    let mut visited = vec![false; n];
-                    visited[center] = true;
-                    canonical_subtree_string(w, &mut visited, nodes, neighbour_list, virtual_h)
-                })
-                .collect();
-            let mut sorted = strings.clone();
-            sorted.sort();
-            sorted.dedup();
-            sorted.len() < strings.len()
-        };
-
-        if side_has_identical(u, v) || side_has_identical(v, u) {
+        if double_bond_has_identical_side(nodes, neighbour_list, virtual_h, u, v) {
             for bond2 in bonds {
                 if matches!(bond2.kind(), BondType::Up | BondType::Down) {
                     let s = bond2.source() as usize;
@@ -160,7 +230,9 @@ fn compute_suppress_stereo_bonds(
 /// these indices via [`Bond::source()`] and [`Bond::target()`].
 ///
 /// `Molecule` implements [`Display`](std::fmt::Display) which serializes it
-/// back to a canonical SMILES string (round-trip).
+/// back to a SMILES string (round-trip). Use
+/// [`Molecule::to_canonical_smiles()`] for output that does not depend on the
+/// input atom order.
 ///
 /// # Example
 ///
@@ -170,7 +242,7 @@ fn compute_suppress_stereo_bonds(
 /// let mol = parse("CCO").unwrap();
 /// assert_eq!(mol.nodes().len(), 3); // C, C, O
 /// assert_eq!(mol.bonds().len(), 2);
-/// println!("{}", mol); // canonical SMILES
+/// println!("{}", mol); // SMILES
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub struct Molecule {
@@ -181,18 +253,49 @@ pub struct Molecule {
 struct DfsState<'a> {
     neighbour_list: &'a [Vec<(NodeIndex, BondType)>],
     tree_children: &'a mut Vec<Vec<(NodeIndex, BondType)>>,
-    ring_pair_ids: &'a [Vec<u8>],
+    ring_pair_ids: &'a [Vec<u32>],
+    ring_pair_bonds: &'a HashMap<u32, (NodeIndex, NodeIndex, BondType)>,
     visited: &'a mut Vec<bool>,
     output: &'a mut Vec<String>,
     nodes_to_output_positions: &'a mut Vec<usize>,
-    pair_to_rnum: &'a mut HashMap<u8, u8>,
+    pair_to_rnum: &'a mut HashMap<u32, u8>,
     next_rnum: &'a mut u8,
     bridges: &'a [(NodeIndex, NodeIndex)],
     virtual_h: &'a [u8],
     effective_aromatic: &'a [bool],
     aromatic_bonds: &'a HashSet<(NodeIndex, NodeIndex)>,
-    suppress_chirality: &'a [bool],
+    chirality: &'a [Option<Chirality>],
+    stereo: StereoOutput,
+    keep_bracket_hydrogens: bool,
     suppress_stereo_bonds: &'a HashSet<(NodeIndex, NodeIndex)>,
+    marks: &'a HashMap<(NodeIndex, NodeIndex), BondType>,
+}
+
+impl DfsState<'_> {
+    /// Bond type written between `u` and `v`, once the aromatic overlay and
+    /// the stereo output mode are applied.
+    fn output_bond(&self, u: NodeIndex, v: NodeIndex, kind: BondType) -> BondType {
+        let edge = (u.min(v), u.max(v));
+        if self.aromatic_bonds.contains(&edge) {
+            return BondType::Aromatic;
+        }
+        match self.stereo {
+            StereoOutput::Recomputed => match self.marks.get(&edge) {
+                Some(&mark) => mark,
+                None if matches!(kind, BondType::Up | BondType::Down) => BondType::Simple,
+                None => kind,
+            },
+            StereoOutput::Omitted if matches!(kind, BondType::Up | BondType::Down) => {
+                BondType::Simple
+            }
+            _ if matches!(kind, BondType::Up | BondType::Down)
+                && self.suppress_stereo_bonds.contains(&edge) =>
+            {
+                BondType::Simple
+            }
+            _ => kind,
+        }
+    }
 }
 
 struct BridgeDfsState<'a> {
@@ -207,10 +310,69 @@ struct BridgeDfsState<'a> {
 struct SpanningTreeState<'a> {
     visited: &'a mut Vec<bool>,
     on_stack: &'a mut Vec<bool>,
-    ring_counter: &'a mut u8,
+    ring_counter: &'a mut u32,
     neighbour_list: &'a [Vec<(NodeIndex, BondType)>],
     tree_children: &'a mut Vec<Vec<(NodeIndex, BondType)>>,
-    ring_digits: &'a mut Vec<Vec<u8>>,
+    ring_digits: &'a mut Vec<Vec<u32>>,
+    ring_bonds: &'a mut HashMap<u32, (NodeIndex, NodeIndex, BondType)>,
+    ranks: Option<&'a [u32]>,
+}
+
+/// Heavy-atom view of a molecule shared by the SMILES writer and the
+/// canonical ranking: removable `[H]` atoms are folded into their neighbour's
+/// hydrogen count and the Kekulé aromaticity overlay is applied.
+pub(crate) struct WriterGraph {
+    /// Adjacency list without the removable hydrogens.
+    pub(crate) neighbours: Vec<Vec<(NodeIndex, BondType)>>,
+    /// `true` for explicit `[H]` atoms that are written as part of a hcount.
+    pub(crate) removable_h: Vec<bool>,
+    /// Number of folded explicit hydrogens per atom.
+    pub(crate) virtual_h: Vec<u8>,
+    /// Aromaticity of each atom once the Kekulé overlay is applied.
+    pub(crate) effective_aromatic: Vec<bool>,
+    /// Bonds written as aromatic, as `(min, max)` pairs.
+    pub(crate) aromatic_bonds: HashSet<(NodeIndex, NodeIndex)>,
+}
+
+impl WriterGraph {
+    /// Groups the written atoms into connected components.
+    ///
+    /// Components are returned in order of their lowest atom index, each one
+    /// sorted by atom index.
+    pub(crate) fn components(&self) -> Vec<Vec<NodeIndex>> {
+        let n = self.neighbours.len();
+        let mut seen = vec![false; n];
+        let mut components = Vec::new();
+        for start in 0..n {
+            if seen[start] || self.removable_h[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = vec![start as NodeIndex];
+            let mut stack = vec![start as NodeIndex];
+            while let Some(u) = stack.pop() {
+                for &(v, _) in &self.neighbours[u as usize] {
+                    if !seen[v as usize] {
+                        seen[v as usize] = true;
+                        component.push(v);
+                        stack.push(v);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Returns the bond type used for output, taking the aromatic overlay into account.
+    pub(crate) fn effective_bond(&self, u: NodeIndex, v: NodeIndex, kind: BondType) -> BondType {
+        if self.aromatic_bonds.contains(&(u.min(v), u.max(v))) {
+            BondType::Aromatic
+        } else {
+            kind
+        }
+    }
 }
 
 impl Molecule {
@@ -251,15 +413,32 @@ impl Molecule {
             bond_order_sum,
             state.virtual_h[current as usize],
             state.effective_aromatic[current as usize],
-            state.suppress_chirality[current as usize],
+            state.chirality[current as usize],
+            state.keep_bracket_hydrogens,
         )?);
 
         for &pair_id in &state.ring_pair_ids[current as usize] {
-            let rnum = *state.pair_to_rnum.entry(pair_id).or_insert_with(|| {
-                let n = *state.next_rnum;
-                *state.next_rnum += 1;
-                n
-            });
+            let rnum = match state.pair_to_rnum.remove(&pair_id) {
+                // Second occurrence: the ring closes here and its number becomes free.
+                Some(rnum) => rnum,
+                None => {
+                    let rnum = Self::allocate_ring_number(state);
+                    state.pair_to_rnum.insert(pair_id, rnum);
+                    // The ring-closure bond symbol is written on the opening digit.
+                    if let Some(&(a, b, kind)) = state.ring_pair_bonds.get(&pair_id) {
+                        let partner = if a == current { b } else { a };
+                        if let Some(s) = Self::bond_symbol(
+                            state.output_bond(current, partner, kind),
+                            state.effective_aromatic[current as usize],
+                            state.effective_aromatic[partner as usize],
+                            false,
+                        ) {
+                            state.output.push(s.to_string());
+                        }
+                    }
+                    rnum
+                }
+            };
             if rnum >= 10 {
                 state.output.push(format!("%{:02}", rnum));
             } else {
@@ -269,64 +448,82 @@ impl Molecule {
 
         state.visited[current as usize] = true;
 
-        let mut children = state.tree_children[current as usize].clone();
-        children.sort_by_key(|(n, _)| Self::subtree_size(*n, state.tree_children));
-        if let Some((main, branches)) = children.split_last() {
-            for branch in branches {
+        // Les enfants sont déjà triés dans l'ordre d'écriture (voir `sort_tree_children`).
+        let children = state.tree_children[current as usize].clone();
+        for (i, &(child, kind)) in children.iter().enumerate() {
+            let is_branch = i + 1 < children.len();
+            if is_branch {
                 state.output.push("(".to_string());
-                let edge = (current.min(branch.0), current.max(branch.0));
-                let effective_bond = if state.aromatic_bonds.contains(&edge) {
-                    BondType::Aromatic
-                } else if matches!(branch.1, BondType::Up | BondType::Down)
-                    && state.suppress_stereo_bonds.contains(&edge)
-                {
-                    BondType::Simple
-                } else {
-                    branch.1
-                };
-                if let Some(s) = Self::bond_symbol(
-                    effective_bond,
-                    state.effective_aromatic[current as usize],
-                    state.effective_aromatic[branch.0 as usize],
-                    state.bridges.contains(&edge),
-                ) {
-                    state.output.push(s.to_string());
-                }
-                self.dfs(branch.0, state)?;
-                state.output.push(")".to_string());
             }
-            let edge = (current.min(main.0), current.max(main.0));
-            let effective_bond = if state.aromatic_bonds.contains(&edge) {
-                BondType::Aromatic
-            } else if matches!(main.1, BondType::Up | BondType::Down)
-                && state.suppress_stereo_bonds.contains(&edge)
-            {
-                BondType::Simple
-            } else {
-                main.1
-            };
+            let edge = (current.min(child), current.max(child));
             if let Some(s) = Self::bond_symbol(
-                effective_bond,
+                state.output_bond(current, child, kind),
                 state.effective_aromatic[current as usize],
-                state.effective_aromatic[main.0 as usize],
+                state.effective_aromatic[child as usize],
                 state.bridges.contains(&edge),
             ) {
                 state.output.push(s.to_string());
             }
-            self.dfs(main.0, state)?;
+            self.dfs(child, state)?;
+            if is_branch {
+                state.output.push(")".to_string());
+            }
         }
 
         Ok(())
     }
 
-    fn subtree_size(start: NodeIndex, tree_children: &[Vec<(NodeIndex, BondType)>]) -> usize {
-        1 + tree_children[start as usize]
-            .iter()
-            .map(|(child, _)| Self::subtree_size(*child, tree_children))
-            .sum::<usize>()
+    /// Picks the number for a newly opened ring closure.
+    ///
+    /// Numbers are not reused while fresh ones are available; past `%99` the
+    /// lowest number not currently open is recycled.
+    fn allocate_ring_number(state: &mut DfsState) -> u8 {
+        if *state.next_rnum <= MAX_RING_NUMBER {
+            let n = *state.next_rnum;
+            *state.next_rnum += 1;
+            return n;
+        }
+        (1..=MAX_RING_NUMBER)
+            .find(|n| !state.pair_to_rnum.values().any(|open| open == n))
+            .unwrap_or(MAX_RING_NUMBER)
     }
 
-    fn removable_hydrogens(&self, neighbour_list: &[Vec<(NodeIndex, BondType)>]) -> Vec<bool> {
+    /// Sorts the children of every atom in writing order: the largest subtree
+    /// is written last (main chain), the others as branches, smallest first.
+    /// With `ranks`, ties are broken by canonical rank.
+    fn sort_tree_children(
+        starts: &[NodeIndex],
+        tree_children: &mut [Vec<(NodeIndex, BondType)>],
+        ranks: Option<&[u32]>,
+    ) {
+        // Tailles des sous-arbres, calculées en post-ordre sans récursion.
+        let mut size = vec![1usize; tree_children.len()];
+        let mut order: Vec<NodeIndex> = Vec::new();
+        let mut stack: Vec<NodeIndex> = starts.to_vec();
+        while let Some(u) = stack.pop() {
+            order.push(u);
+            stack.extend(tree_children[u as usize].iter().map(|&(v, _)| v));
+        }
+        for &u in order.iter().rev() {
+            size[u as usize] += tree_children[u as usize]
+                .iter()
+                .map(|&(v, _)| size[v as usize])
+                .sum::<usize>();
+        }
+        for children in tree_children.iter_mut() {
+            match ranks {
+                Some(ranks) => {
+                    children.sort_by_key(|&(v, _)| (size[v as usize], ranks[v as usize]))
+                }
+                None => children.sort_by_key(|&(v, _)| size[v as usize]),
+            }
+        }
+    }
+
+    pub(crate) fn removable_hydrogens(
+        &self,
+        neighbour_list: &[Vec<(NodeIndex, BondType)>],
+    ) -> Vec<bool> {
         let mut removable = vec![false; self.nodes.len()];
         for (i, node) in self.nodes.iter().enumerate() {
             if *node.atom().element() != AtomSymbol::H {
@@ -355,25 +552,45 @@ impl Molecule {
     fn best_starting_atom(
         &self,
         neighbour_list: &[Vec<(NodeIndex, BondType)>],
-        removable_h: &[bool],
+        component: &[NodeIndex],
+        ranks: Option<&[u32]>,
     ) -> NodeIndex {
-        let terminals: Vec<NodeIndex> = (0..self.nodes.len() as NodeIndex)
-            .filter(|&i| !removable_h[i as usize] && neighbour_list[i as usize].len() == 1)
+        if let Some(ranks) = ranks {
+            // Same preferences as below (terminal, then lowest degree, then
+            // heteroatom), with the canonical rank as the final tie-breaker.
+            return component
+                .iter()
+                .copied()
+                .min_by_key(|&i| {
+                    (
+                        neighbour_list[i as usize].len().max(1),
+                        *self.nodes[i as usize].atom().element()
+                            == AtomSymbol::Organic(OrganicAtom::C),
+                        ranks[i as usize],
+                    )
+                })
+                .unwrap_or(component[0]);
+        }
+
+        let terminals: Vec<NodeIndex> = component
+            .iter()
+            .copied()
+            .filter(|&i| neighbour_list[i as usize].len() == 1)
             .collect();
 
         if terminals.is_empty() {
             // Pas de terminaux : préférer les atomes de degré minimal pour éviter de
             // commencer sur un atome de jonction (spiro, pont) qui accumulerait
             // plusieurs ring closures sur le même atome dans la sortie canonique.
-            let min_degree = (0..self.nodes.len() as NodeIndex)
-                .filter(|&i| !removable_h[i as usize])
-                .map(|i| neighbour_list[i as usize].len())
+            let min_degree = component
+                .iter()
+                .map(|&i| neighbour_list[i as usize].len())
                 .min()
                 .unwrap_or(0);
-            let candidates: Vec<NodeIndex> = (0..self.nodes.len() as NodeIndex)
-                .filter(|&i| {
-                    !removable_h[i as usize] && neighbour_list[i as usize].len() == min_degree
-                })
+            let candidates: Vec<NodeIndex> = component
+                .iter()
+                .copied()
+                .filter(|&i| neighbour_list[i as usize].len() == min_degree)
                 .collect();
             for &c in &candidates {
                 if *self.nodes[c as usize].atom().element() != AtomSymbol::Organic(OrganicAtom::C) {
@@ -392,6 +609,40 @@ impl Molecule {
         terminals[0]
     }
 
+    /// Tells which chirality marks the writer drops, as its neighbours are
+    /// symmetric, and returns the ring system of every tetrahedral centre
+    /// that is only stereogenic through its ring, as in
+    /// 1,4-dimethylcyclohexane, and `None` for the other atoms (see
+    /// [`compute_suppress_chirality`]).
+    pub(crate) fn written_stereo_centres(
+        &self,
+        graph: &WriterGraph,
+    ) -> (Vec<bool>, Vec<Option<usize>>) {
+        if self.nodes.iter().all(|node| node.chirality().is_none()) {
+            let n = self.nodes.len();
+            return (vec![false; n], vec![None; n]);
+        }
+        let bridges = Self::find_bridges(self.nodes.len(), &graph.neighbours);
+        compute_suppress_chirality(&self.nodes, &graph.neighbours, &graph.virtual_h, &bridges)
+    }
+
+    /// Tells whether an atom of the double bond `a=b` carries two identical
+    /// substituents, so that the writer drops its geometry.
+    pub(crate) fn has_symmetric_side(
+        &self,
+        graph: &WriterGraph,
+        a: NodeIndex,
+        b: NodeIndex,
+    ) -> bool {
+        double_bond_has_identical_side(
+            &self.nodes,
+            &graph.neighbours,
+            &graph.virtual_h,
+            a as usize,
+            b as usize,
+        )
+    }
+
     fn find_bridges(
         n: usize,
         neighbour_list: &[Vec<(NodeIndex, BondType)>],
@@ -460,7 +711,16 @@ impl Molecule {
             .copied()
             .filter(|&(v, _)| Some(v) != parent)
             .collect();
-        sorted_neighbors.sort_by(|a, b| b.1.bond_order_priority().cmp(&a.1.bond_order_priority()));
+        match state.ranks {
+            Some(ranks) => sorted_neighbors.sort_by_key(|&(v, bond)| {
+                (
+                    std::cmp::Reverse(bond.bond_order_priority()),
+                    ranks[v as usize],
+                )
+            }),
+            None => sorted_neighbors
+                .sort_by_key(|&(_, bond)| std::cmp::Reverse(bond.bond_order_priority())),
+        }
 
         for (voisin, bond_type) in sorted_neighbors {
             if state.visited[voisin as usize] {
@@ -471,6 +731,9 @@ impl Molecule {
                     *state.ring_counter += 1;
                     state.ring_digits[current as usize].push(*state.ring_counter);
                     state.ring_digits[voisin as usize].push(*state.ring_counter);
+                    state
+                        .ring_bonds
+                        .insert(*state.ring_counter, (voisin, current, bond_type));
                 }
             } else {
                 state.tree_children[current as usize].push((voisin, bond_type));
@@ -481,30 +744,6 @@ impl Molecule {
         state.on_stack[current as usize] = false;
     }
 
-    fn build_spanning_tree_from(
-        &self,
-        start: NodeIndex,
-        neighbour_list: &[Vec<(NodeIndex, BondType)>],
-    ) -> SpanningTreeResult {
-        let mut tree_children: Vec<Vec<(NodeIndex, BondType)>> = vec![Vec::new(); self.nodes.len()];
-        let mut ring_pair_ids: Vec<Vec<u8>> = vec![Vec::new(); self.nodes.len()];
-        let mut visited = vec![false; self.nodes.len()];
-        let mut on_stack = vec![false; self.nodes.len()];
-        let mut ring_counter = 0;
-
-        let mut state = SpanningTreeState {
-            visited: &mut visited,
-            on_stack: &mut on_stack,
-            ring_counter: &mut ring_counter,
-            neighbour_list,
-            tree_children: &mut tree_children,
-            ring_digits: &mut ring_pair_ids,
-        };
-        self.build_spanning_tree_inner(start, None, &mut state);
-
-        (tree_children, ring_pair_ids)
-    }
-
     fn bond_symbol(
         kind: BondType,
         source_aromatic: bool,
@@ -541,15 +780,17 @@ impl Molecule {
         bond_order_sum: u8,
         extra_h: u8,
         effective_aromatic: bool,
-        suppress_chirality: bool,
+        chirality: Option<Chirality>,
+        keep_bracket_hydrogens: bool,
     ) -> Result<String, AtomError> {
         let node = &self.nodes[node_idx];
         let total_h = node.hydrogens() + extra_h;
 
         if node.atom().is_organic()
+            && !(keep_bracket_hydrogens && node.bracket_hydrogens().is_some())
             && node.atom().charge() == 0
             && node.atom().isotope().is_none()
-            && (node.chirality().is_none() || suppress_chirality)
+            && chirality.is_none()
             && node.class().is_none()
             && total_h
                 == node
@@ -573,10 +814,8 @@ impl Molecule {
             } else {
                 output.push_str(&element_str);
             }
-            if let Some(c) = node.chirality() {
-                if !suppress_chirality {
-                    output.push_str(&c.to_string());
-                }
+            if let Some(c) = chirality {
+                output.push_str(&c.to_string());
             }
 
             match total_h {
@@ -610,173 +849,11 @@ impl Molecule {
             Ok(output)
         }
     }
-
-    /// Trouve les cycles minimaux pour chaque arête du sous-graphe Kekulé
-    /// (liaisons Simple et Double uniquement).
-    fn find_kekule_rings(
-        n: usize,
-        neighbour_list: &[Vec<(NodeIndex, BondType)>],
-    ) -> Vec<super::graph::Ring> {
-        let mut adj: Vec<Vec<NodeIndex>> = vec![Vec::new(); n];
-        let mut edges: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
-
-        for u in 0..n as NodeIndex {
-            for &(v, bond) in &neighbour_list[u as usize] {
-                if matches!(bond, BondType::Simple | BondType::Double) {
-                    adj[u as usize].push(v);
-                    edges.insert((u.min(v), u.max(v)));
-                }
-            }
-        }
-
-        super::graph::find_rings_in_subgraph(&adj, &edges, n)
-    }
-
-    /// Vérifie si un cycle (avec liaisons Kekulé) est aromatique selon Hückel.
-    /// Retourne Some(pi_electrons) si aromatique, None sinon.
-    fn kekule_pi_electrons(
-        &self,
-        cycle: &[NodeIndex],
-        neighbour_list: &[Vec<(NodeIndex, BondType)>],
-    ) -> Option<u8> {
-        let n = cycle.len();
-        if n < 3 {
-            return None;
-        }
-
-        // Tous les atomes doivent pouvoir être aromatiques et ne pas l'être déjà
-        for &node_idx in cycle {
-            let node = &self.nodes[node_idx as usize];
-            if !node.atom().element().can_be_aromatic() {
-                return None;
-            }
-            if node.aromatic() {
-                return None;
-            }
-        }
-
-        let mut pi_electrons: i32 = 0;
-        let mut has_double_bond = vec![false; n];
-
-        for i in 0..n {
-            let a = cycle[i];
-            let b = cycle[(i + 1) % n];
-
-            let bond_type = neighbour_list[a as usize]
-                .iter()
-                .find(|&&(v, _)| v == b)
-                .map(|&(_, t)| t)?;
-
-            match bond_type {
-                BondType::Double => {
-                    pi_electrons += 2;
-                    has_double_bond[i] = true;
-                    has_double_bond[(i + 1) % n] = true;
-                }
-                BondType::Simple => {}
-                _ => return None,
-            }
-        }
-
-        // Pour les atomes sans double liaison dans le cycle, vérifier la paire libre
-        for i in 0..n {
-            if !has_double_bond[i] {
-                let node = &self.nodes[cycle[i] as usize];
-                let element = *node.atom().element();
-                let charge = node.atom().charge();
-                let hydrogens = node.hydrogens();
-
-                let contribution: i32 = match element {
-                    AtomSymbol::Organic(OrganicAtom::C) => {
-                        if charge < 0 {
-                            2
-                        } else {
-                            return None;
-                        }
-                    }
-                    AtomSymbol::Organic(OrganicAtom::N) => {
-                        if hydrogens > 0 || charge < 0 {
-                            2
-                        } else {
-                            return None;
-                        }
-                    }
-                    AtomSymbol::Organic(OrganicAtom::O) => 2,
-                    AtomSymbol::Organic(OrganicAtom::S) => 2,
-                    AtomSymbol::Organic(OrganicAtom::P) => {
-                        if hydrogens > 0 || charge < 0 {
-                            2
-                        } else {
-                            return None;
-                        }
-                    }
-                    AtomSymbol::Organic(OrganicAtom::B) => 0,
-                    AtomSymbol::Se | AtomSymbol::As | AtomSymbol::Te => 2,
-                    _ => return None,
-                };
-
-                pi_electrons += contribution;
-            }
-        }
-
-        if pi_electrons < 0 {
-            return None;
-        }
-        let pi = pi_electrons as u8;
-        if super::aromaticity::satisfies_huckel(pi) {
-            Some(pi)
-        } else {
-            None
-        }
-    }
-
-    /// Calcule l'overlay d'aromaticité pour l'affichage.
-    /// Détecte les cycles Kekulé aromatiques et les combine avec l'aromaticité existante.
-    /// Retourne (effective_aromatic_par_atome, ensemble_des_liaisons_aromatiques).
-    fn compute_kekule_aromatic_overlay(
-        &self,
-        neighbour_list: &[Vec<(NodeIndex, BondType)>],
-    ) -> (Vec<bool>, HashSet<(NodeIndex, NodeIndex)>) {
-        let n = self.nodes.len();
-
-        // Initialiser depuis l'aromaticité existante des nœuds
-        let mut effective_aromatic: Vec<bool> = self.nodes.iter().map(|nd| nd.aromatic()).collect();
-        let mut aromatic_bonds: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
-
-        for bond in &self.bonds {
-            if bond.kind() == BondType::Aromatic {
-                let edge = (
-                    bond.source().min(bond.target()),
-                    bond.source().max(bond.target()),
-                );
-                aromatic_bonds.insert(edge);
-            }
-        }
-
-        // Détecter les cycles Kekulé aromatiques et les ajouter à l'overlay
-        let cycles = Self::find_kekule_rings(n, neighbour_list);
-
-        for ring in &cycles {
-            if self
-                .kekule_pi_electrons(&ring.nodes, neighbour_list)
-                .is_some()
-            {
-                let len = ring.nodes.len();
-                for i in 0..len {
-                    let a = ring.nodes[i];
-                    let b = ring.nodes[(i + 1) % len];
-                    effective_aromatic[a as usize] = true;
-                    aromatic_bonds.insert((a.min(b), a.max(b)));
-                }
-            }
-        }
-
-        (effective_aromatic, aromatic_bonds)
-    }
 }
 
-impl fmt::Display for Molecule {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Molecule {
+    /// Builds the heavy-atom view used to write this molecule as SMILES.
+    pub(crate) fn writer_graph(&self) -> WriterGraph {
         let mut neighbour_list: Vec<Vec<(NodeIndex, BondType)>> =
             vec![Vec::new(); self.nodes.len()];
         for bond in &self.bonds {
@@ -806,53 +883,269 @@ impl fmt::Display for Molecule {
             }
         }
 
-        // Calculer l'overlay d'aromaticité Kekulé avant la construction de l'arbre couvrant
+        // Percevoir l'aromaticité des cycles Kekulé avant la construction de l'arbre couvrant
         let (effective_aromatic, aromatic_bonds) =
-            self.compute_kekule_aromatic_overlay(&neighbour_list_heavy);
+            aromaticity::perceive(self, AromaticityModel::Daylight);
+
+        WriterGraph {
+            neighbours: neighbour_list_heavy,
+            removable_h,
+            virtual_h,
+            effective_aromatic,
+            aromatic_bonds,
+        }
+    }
+
+    /// Writes the molecule as SMILES, one dot-separated component at a time.
+    ///
+    /// Without `ranks` the output follows the writer heuristics and the input
+    /// atom order. With `ranks` (one distinct value per atom, lower first)
+    /// every choice — start atom, branch order, ring-closure order and the
+    /// order of the components — is driven by the ranks instead.
+    pub(crate) fn write_smiles(
+        &self,
+        graph: &WriterGraph,
+        ranks: Option<&[u32]>,
+        options: &WriteOptions,
+    ) -> Result<String, AtomError> {
+        let n = self.nodes.len();
+        let stereo = options.stereo;
 
         // Déterminer les atomes chiraux fictifs et les liaisons stéréo fictives
-        let suppress_chirality =
-            compute_suppress_chirality(&self.nodes, &neighbour_list_heavy, &virtual_h);
+        let bridges = Self::find_bridges(n, &graph.neighbours);
+        let (suppress_chirality, _) =
+            compute_suppress_chirality(&self.nodes, &graph.neighbours, &graph.virtual_h, &bridges);
         let suppress_stereo_bonds = compute_suppress_stereo_bonds(
             &self.nodes,
-            &neighbour_list_heavy,
+            &graph.neighbours,
             &self.bonds,
-            &virtual_h,
+            &graph.virtual_h,
         );
 
-        let start = self.best_starting_atom(&neighbour_list_heavy, &removable_h);
-        let bridges = Self::find_bridges(self.nodes.len(), &neighbour_list_heavy);
-        let (mut tree_children, ring_pair_ids) =
-            self.build_spanning_tree_from(start, &neighbour_list_heavy);
+        let mut starts: Vec<NodeIndex> = graph
+            .components()
+            .iter()
+            .map(|component| self.best_starting_atom(&graph.neighbours, component, ranks))
+            .collect();
+        if let Some(ranks) = ranks {
+            starts.sort_by_key(|&start| ranks[start as usize]);
+        }
 
-        let mut output: Vec<String> = Vec::new();
-        let mut visited: Vec<bool> = vec![false; self.nodes.len()];
-        let mut nodes_to_output_positions: Vec<usize> = vec![0; self.nodes.len()];
-        let mut pair_to_rnum: HashMap<u8, u8> = HashMap::new();
-        let mut next_rnum: u8 = 1;
+        // En mode canonique, l'arbre couvrant ne doit pas dépendre de la forme
+        // Kekulé d'entrée : on le construit sur les liaisons effectives.
+        let effective_neighbours: Vec<Vec<(NodeIndex, BondType)>>;
+        let tree_neighbours = if ranks.is_some() {
+            effective_neighbours = graph
+                .neighbours
+                .iter()
+                .enumerate()
+                .map(|(u, list)| {
+                    list.iter()
+                        .map(|&(v, kind)| (v, graph.effective_bond(u as NodeIndex, v, kind)))
+                        .collect()
+                })
+                .collect();
+            &effective_neighbours
+        } else {
+            &graph.neighbours
+        };
 
+        let mut tree_children: Vec<Vec<(NodeIndex, BondType)>> = vec![Vec::new(); n];
+        let mut ring_pair_ids: Vec<Vec<u32>> = vec![Vec::new(); n];
+        let mut ring_pair_bonds: HashMap<u32, (NodeIndex, NodeIndex, BondType)> = HashMap::new();
         {
-            let mut state = DfsState {
-                neighbour_list: &neighbour_list_heavy,
-                tree_children: &mut tree_children,
-                ring_pair_ids: &ring_pair_ids,
+            let mut visited = vec![false; n];
+            let mut on_stack = vec![false; n];
+            let mut ring_counter = 0;
+            let mut state = SpanningTreeState {
                 visited: &mut visited,
-                output: &mut output,
-                nodes_to_output_positions: &mut nodes_to_output_positions,
-                pair_to_rnum: &mut pair_to_rnum,
-                next_rnum: &mut next_rnum,
-                bridges: &bridges,
-                virtual_h: &virtual_h,
-                effective_aromatic: &effective_aromatic,
-                aromatic_bonds: &aromatic_bonds,
-                suppress_chirality: &suppress_chirality,
-                suppress_stereo_bonds: &suppress_stereo_bonds,
+                on_stack: &mut on_stack,
+                ring_counter: &mut ring_counter,
+                neighbour_list: tree_neighbours,
+                tree_children: &mut tree_children,
+                ring_digits: &mut ring_pair_ids,
+                ring_bonds: &mut ring_pair_bonds,
+                ranks,
             };
-            self.dfs(start, &mut state).map_err(|_| fmt::Error)?;
+            for &start in &starts {
+                self.build_spanning_tree_inner(start, None, &mut state);
+            }
+        }
+        Self::sort_tree_children(&starts, &mut tree_children, ranks);
+
+        let (chirality, marks) = match stereo {
+            StereoOutput::Verbatim => (
+                self.nodes
+                    .iter()
+                    .zip(&suppress_chirality)
+                    .map(|(node, &suppress)| node.chirality().filter(|_| !suppress))
+                    .collect(),
+                HashMap::new(),
+            ),
+            StereoOutput::Omitted => (vec![None; n], HashMap::new()),
+            StereoOutput::Recomputed => {
+                let emission =
+                    Self::emission_order(&starts, &tree_children, &ring_pair_ids, &ring_pair_bonds);
+                let canonical = ranks.is_some();
+                let plan =
+                    self.plan_stereo(graph, &emission, canonical, &suppress_chirality, |a, b| {
+                        double_bond_has_identical_side(
+                            &self.nodes,
+                            &graph.neighbours,
+                            &graph.virtual_h,
+                            a as usize,
+                            b as usize,
+                        )
+                    });
+                (plan.chirality, plan.marks)
+            }
+        };
+
+        let mut output: Vec<String> = Vec::new();
+        let mut visited: Vec<bool> = vec![false; n];
+        let mut nodes_to_output_positions: Vec<usize> = vec![0; n];
+        let mut pair_to_rnum: HashMap<u32, u8> = HashMap::new();
+        let mut next_rnum: u8 = 1;
+
+        let mut state = DfsState {
+            neighbour_list: &graph.neighbours,
+            tree_children: &mut tree_children,
+            ring_pair_ids: &ring_pair_ids,
+            ring_pair_bonds: &ring_pair_bonds,
+            visited: &mut visited,
+            output: &mut output,
+            nodes_to_output_positions: &mut nodes_to_output_positions,
+            pair_to_rnum: &mut pair_to_rnum,
+            next_rnum: &mut next_rnum,
+            bridges: &bridges,
+            virtual_h: &graph.virtual_h,
+            effective_aromatic: &graph.effective_aromatic,
+            aromatic_bonds: &graph.aromatic_bonds,
+            chirality: &chirality,
+            stereo,
+            keep_bracket_hydrogens: options.keep_bracket_hydrogens,
+            suppress_stereo_bonds: &suppress_stereo_bonds,
+            marks: &marks,
+        };
+        for (i, &start) in starts.iter().enumerate() {
+            if i > 0 {
+                state.output.push(".".to_string());
+            }
+            self.dfs(start, &mut state)?;
+        }
+
+        Ok(output.join(""))
+    }
+
+    /// Computes the order in which `dfs` will emit the atoms.
+    fn emission_order<'a>(
+        starts: &[NodeIndex],
+        tree_children: &'a [Vec<(NodeIndex, BondType)>],
+        ring_pair_ids: &[Vec<u32>],
+        ring_pair_bonds: &HashMap<u32, (NodeIndex, NodeIndex, BondType)>,
+    ) -> Emission<'a> {
+        let n = tree_children.len();
+        let mut position = vec![usize::MAX; n];
+        let mut parent = vec![None; n];
+        let mut next = 0;
+        for &start in starts {
+            let mut stack = vec![start];
+            while let Some(u) = stack.pop() {
+                position[u as usize] = next;
+                next += 1;
+                for &(v, _) in tree_children[u as usize].iter().rev() {
+                    parent[v as usize] = Some(u);
+                    stack.push(v);
+                }
+            }
+        }
+        let ring_partners = ring_pair_ids
+            .iter()
+            .enumerate()
+            .map(|(u, ids)| {
+                ids.iter()
+                    .filter_map(|id| ring_pair_bonds.get(id))
+                    .map(|&(a, b, _)| if a as usize == u { b } else { a })
+                    .collect()
+            })
+            .collect();
+        Emission {
+            position,
+            parent,
+            children: tree_children,
+            ring_partners,
         }
+    }
+
+    /// Serializes the molecule to SMILES with the given stereo handling.
+    ///
+    /// The atom order and layout are the same as with
+    /// [`Display`](std::fmt::Display), which uses
+    /// [`StereoOutput::Recomputed`]; only the `@`/`@@` and `/`/`\`
+    /// annotations depend on `stereo`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, StereoOutput};
+    ///
+    /// let mol = parse("[C@@H](F)(Cl)Br").unwrap();
+    /// // The writer starts on F, which moves the hydrogen: the parity is recomputed.
+    /// assert_eq!(mol.to_smiles(StereoOutput::Recomputed), "F[C@H](Cl)Br");
+    /// assert_eq!(mol.to_smiles(StereoOutput::Verbatim), "F[C@@H](Cl)Br");
+    /// assert_eq!(mol.to_smiles(StereoOutput::Omitted), "FC(Cl)Br");
+    /// ```
+    pub fn to_smiles(&self, stereo: StereoOutput) -> String {
+        self.to_smiles_with(&WriteOptions {
+            stereo,
+            ..WriteOptions::default()
+        })
+    }
+
+    /// Serializes the molecule to SMILES with the given options.
+    ///
+    /// With the default options, this is the output of
+    /// [`Display`](std::fmt::Display).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, WriteOptions};
+    ///
+    /// let mol = parse("[CH3]C").unwrap();
+    /// assert_eq!(mol.to_string(), "CC");
+    /// let options = WriteOptions {
+    ///     keep_bracket_hydrogens: true,
+    ///     ..WriteOptions::default()
+    /// };
+    /// assert_eq!(mol.to_smiles_with(&options), "[CH3]C");
+    /// ```
+    pub fn to_smiles_with(&self, options: &WriteOptions) -> String {
+        let graph = self.writer_graph();
+        self.write_smiles(&graph, None, options)
+            .expect("bond order sums are always known when writing")
+    }
+}
 
-        let final_output = output.join("");
-        write!(f, "{final_output}")
+/// Options for [`Molecule::to_smiles_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    /// How `@`/`@@` and `/`/`\` are written.
+    pub stereo: StereoOutput,
+    /// Keep in brackets the atoms whose hydrogen count was written in
+    /// brackets (see [`Node::bracket_hydrogens`]), so that `[CH3]C` is
+    /// written back as such. When off, an organic-subset atom is only
+    /// bracketed when the valence model does not imply its hydrogens.
+    pub keep_bracket_hydrogens: bool,
+}
+
+impl fmt::Display for Molecule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let graph = self.writer_graph();
+        let smiles = self
+            .write_smiles(&graph, None, &WriteOptions::default())
+            .map_err(|_| fmt::Error)?;
+        write!(f, "{smiles}")
     }
 }
 
@@ -875,6 +1168,14 @@ impl MoleculeBuilder {
         &self.bonds
     }
 
+    pub(crate) fn nodes_mut(&mut self) -> &mut [NodeBuilder] {
+        &mut self.nodes
+    }
+
+    pub(crate) fn bonds_mut(&mut self) -> &mut [Bond] {
+        &mut self.bonds
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn add_atom(
         &mut self,
@@ -892,34 +1193,52 @@ impl MoleculeBuilder {
         Ok(self.nodes.len() - 1)
     }
 
-    pub(crate) fn add_branch(
+    pub(crate) fn add_bond(&mut self, source: NodeIndex, target: NodeIndex, kind: BondType) {
+        self.bonds.push(Bond::new(kind, source, target));
+    }
+
+    /// Re-expresses the parity of every `@`/`@@` atom relative to the
+    /// reference neighbour order (see the `stereo` module), given the order in
+    /// which the SMILES string listed its neighbours. The atom's own index in
+    /// `written_order` stands for its implicit hydrogen or lone pair.
+    pub(crate) fn normalize_chirality(
         &mut self,
-        m: MoleculeBuilder,
-        bond_type: BondType,
-        source: Option<NodeIndex>,
+        written_order: HashMap<NodeIndex, Vec<NodeIndex>>,
     ) {
-        let node_count = self.nodes.len() as NodeIndex;
-        if let Some(src) = source {
-            self.add_bond(src, node_count, bond_type);
+        // The bonds of each stereocentre, in one pass over the bonds.
+        let mut incident: HashMap<NodeIndex, Vec<Bond>> = HashMap::new();
+        for bond in &self.bonds {
+            for atom in [bond.source(), bond.target()] {
+                if written_order.contains_key(&atom) {
+                    incident.entry(atom).or_default().push(bond.clone());
+                }
+            }
         }
-        self.nodes.extend(m.nodes);
-        for bond in m.bonds {
-            // Translate branch-local indices to main molecule indices
-            // bond.source() and bond.target() are relative to the branch (0, 1, 2...)
-            // After extend, they start at node_count in the main molecule
-            self.add_bond(
-                node_count + bond.source(),
-                node_count + bond.target(),
-                bond.kind(),
-            );
+        for (atom, mut written) in written_order {
+            let node = &self.nodes[atom as usize];
+            let Some(chirality) = node.chirality() else {
+                continue;
+            };
+            let degree = written.iter().filter(|&&v| v != atom).count();
+            let implicit = node.hydrogens().unwrap_or(0) > 0 || degree == 3;
+            if !implicit {
+                written.retain(|&v| v != atom);
+            }
+            let bonds = incident.get(&atom).map_or(&[][..], Vec::as_slice);
+            let reference = stereo::reference_order(atom, bonds, implicit);
+            if stereo::is_odd_permutation(&written, &reference) == Some(true) {
+                self.nodes[atom as usize].set_chirality(Some(stereo::invert(chirality)));
+            }
         }
     }
 
-    pub(crate) fn add_bond(&mut self, source: NodeIndex, target: NodeIndex, kind: BondType) {
-        self.bonds.push(Bond::new(kind, source, target));
+    pub(crate) fn build(self) -> Result<Molecule, MoleculeError> {
+        self.build_locating().map_err(|(error, _)| error)
     }
 
-    pub(crate) fn build(self) -> Result<Molecule, MoleculeError> {
+    /// Builds the molecule; an error comes with the index of the atom it
+    /// concerns.
+    pub(crate) fn build_locating(self) -> Result<Molecule, (MoleculeError, NodeIndex)> {
         let mut nodes: Vec<Node> = Vec::new();
         let mut bond_orders_x2 = vec![0u8; self.nodes.len()];
 
@@ -931,7 +1250,10 @@ impl MoleculeBuilder {
         }
 
         for (index, node) in self.nodes.into_iter().enumerate() {
-            nodes.push(node.build(Some(bond_orders_x2[index] / 2))?);
+            let node = node
+                .build(Some(bond_orders_x2[index] / 2))
+                .map_err(|e| (e.into(), index as NodeIndex))?;
+            nodes.push(node);
         }
 
         Ok(Molecule {
@@ -7,21 +7,36 @@ use crate::{
 /// A fully resolved atom node in the molecule graph.
 ///
 /// Each `Node` holds an [`Atom`] (element, charge, isotope) along with
-/// aromaticity, explicit hydrogen count, atom class, and chirality — all
-/// resolved at parse time.
+/// aromaticity, hydrogen count, atom class, and chirality — all resolved at
+/// parse time. The node also remembers whether its hydrogen count was
+/// written in brackets (`[CH2]`) or derived from the valence model (`C`);
+/// equality ignores that origin, so `[CH3]` and the `C` of `CC` are equal.
 ///
 /// Nodes are connected to each other via [`Bond`](crate::Bond)s in a
 /// [`Molecule`](crate::Molecule).
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Node {
     atom: Atom,
     aromatic: bool,
     hydrogens: u8,
+    implicit: bool,
     class: Option<u16>,
     chirality: Option<Chirality>,
 }
 
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        self.atom == other.atom
+            && self.aromatic == other.aromatic
+            && self.hydrogens == other.hydrogens
+            && self.class == other.class
+            && self.chirality == other.chirality
+    }
+}
+
 impl Node {
+    /// Creates a node whose hydrogen count is taken as written, like the
+    /// hcount of a bracket atom.
     pub fn new(
         atom: Atom,
         aromatic: bool,
@@ -33,12 +48,9 @@ impl Node {
             return Err(NodeError::InvalidHydrogen(hydrogens));
         }
 
-        match class {
-            None => (),
-            Some(value) => {
-                if value > 999 {
-                    return Err(NodeError::InvalidClass(value));
-                }
+        if let Some(value) = class {
+            if value > 999 {
+                return Err(NodeError::InvalidClass(value));
             }
         }
 
@@ -50,6 +62,7 @@ impl Node {
             atom,
             aromatic,
             hydrogens,
+            implicit: false,
             class,
             chirality,
         })
@@ -70,10 +83,39 @@ impl Node {
     /// For bracket atoms (e.g. `[CH3]`) this is the explicit hcount.
     /// For organic-subset atoms (e.g. `C`) this is the implicit hydrogen
     /// count calculated from valence and bond order sum.
+    /// [`bracket_hydrogens`](Self::bracket_hydrogens) and
+    /// [`implicit_hydrogens`](Self::implicit_hydrogens) tell the two apart.
     pub fn hydrogens(&self) -> u8 {
         self.hydrogens
     }
 
+    /// Returns the hydrogen count derived from the valence model, or `0` if
+    /// the count was written in brackets.
+    ///
+    /// `C` in `CC` has 3 implicit hydrogens; `[CH3]` has none.
+    pub fn implicit_hydrogens(&self) -> u8 {
+        if self.implicit {
+            self.hydrogens
+        } else {
+            0
+        }
+    }
+
+    /// Returns the hydrogen count written in brackets, or `None` for an
+    /// organic-subset atom written without brackets.
+    ///
+    /// `[CH2]` gives `Some(2)` and `[C]` gives `Some(0)`, whatever the
+    /// valence model would imply. Compare with
+    /// [`Molecule::total_hydrogens`](crate::Molecule::total_hydrogens),
+    /// which also counts `[H]` neighbours.
+    pub fn bracket_hydrogens(&self) -> Option<u8> {
+        if self.implicit {
+            None
+        } else {
+            Some(self.hydrogens)
+        }
+    }
+
     /// Returns the atom class, if specified (e.g. `[C:1]` → `Some(1)`).
     pub fn class(&self) -> Option<u16> {
         self.class
@@ -83,6 +125,32 @@ impl Node {
     pub fn chirality(&self) -> Option<Chirality> {
         self.chirality
     }
+
+    /// Returns a copy with another hydrogen count and chirality. The count
+    /// keeps its origin (bracket or implicit).
+    pub(crate) fn with_hydrogens(&self, hydrogens: u8, chirality: Option<Chirality>) -> Node {
+        Node {
+            hydrogens,
+            chirality,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy with another aromaticity. The hydrogen count keeps its
+    /// origin.
+    pub(crate) fn with_aromatic(&self, aromatic: bool) -> Result<Node, NodeError> {
+        let node = Node::new(
+            self.atom.clone(),
+            aromatic,
+            self.hydrogens,
+            self.class,
+            self.chirality,
+        )?;
+        Ok(Node {
+            implicit: self.implicit,
+            ..node
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -115,27 +183,51 @@ impl NodeBuilder {
         })
     }
 
+    pub(crate) fn atom(&self) -> &Atom {
+        &self.atom
+    }
+
     pub(crate) fn aromatic(&self) -> Option<bool> {
         self.aromatic
     }
 
+    pub(crate) fn set_aromatic(&mut self, aromatic: bool) -> &mut Self {
+        self.aromatic = Some(aromatic);
+        self
+    }
+
+    pub(crate) fn chirality(&self) -> Option<Chirality> {
+        self.chirality
+    }
+
+    pub(crate) fn set_chirality(&mut self, chirality: Option<Chirality>) -> &mut Self {
+        self.chirality = chirality;
+        self
+    }
+
+    pub(crate) fn hydrogens(&self) -> Option<u8> {
+        self.hydrogens
+    }
+
     pub(crate) fn set_hydrogens(&mut self, h: u8) -> &mut Self {
         self.hydrogens = Some(h);
         self
     }
 
     pub(crate) fn build(mut self, bond_order_sum: Option<u8>) -> Result<Node, NodeError> {
-        if self.hydrogens.is_none() {
+        let implicit = self.hydrogens.is_none();
+        if implicit {
             let aromatic = self.aromatic.unwrap_or(false);
             self.set_hydrogens(self.atom.implicit_hydrogens(bond_order_sum, aromatic)?);
         }
 
-        Node::new(
+        let node = Node::new(
             self.atom,
             self.aromatic.ok_or(NodeError::UndefinedAromatic)?,
             self.hydrogens.ok_or(NodeError::UndefinedHydrogen)?,
             self.class,
             self.chirality,
-        )
+        )?;
+        Ok(Node { implicit, ..node })
     }
 }
@@ -0,0 +1,338 @@
+//! Ring systems and ring assemblies.
+//!
+//! Rings that share at least one atom belong to the same ring system. How
+//! two rings of a system meet decides its kind: a single shared atom is a
+//! spiro junction, a single shared bond an ortho-fusion, and two or more
+//! shared bonds a bridge whose end atoms are the bridgeheads. Cages such as
+//! cubane, whose rings pairwise share at most one bond, are bridged too:
+//! they have more relevant cycles than the cyclomatic number a fused system
+//! of the same atoms and bonds would have. Ring systems bonded directly to
+//! each other (biphenyl) form a ring assembly.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::bond::BondType;
+use super::graph::Ring;
+use super::molecule::Molecule;
+use super::rings::RingInfo;
+use crate::NodeIndex;
+
+/// How the rings of a [`RingSystem`] are joined.
+///
+/// A system mixing several kinds of junction gets the most complex one:
+/// bridged over fused over spiro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RingSystemKind {
+    /// A single ring.
+    Isolated,
+    /// Rings sharing bonds, each pair at most one (naphthalene, pyrene).
+    Fused,
+    /// Rings sharing single atoms only (spiro[4.4]nonane).
+    Spiro,
+    /// At least two rings sharing two or more bonds (norbornane, adamantane),
+    /// or a cage with more rings than its bonds close (cubane).
+    Bridged,
+}
+
+/// A group of rings connected through shared atoms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingSystem {
+    kind: RingSystemKind,
+    rings: Vec<Ring>,
+    atoms: Vec<NodeIndex>,
+    spiro_atoms: Vec<NodeIndex>,
+    bridgehead_atoms: Vec<NodeIndex>,
+}
+
+impl RingSystem {
+    /// Returns how the rings are joined.
+    pub fn kind(&self) -> RingSystemKind {
+        self.kind
+    }
+
+    /// Returns the relevant cycles of the system (see
+    /// [`RingInfo::ring_systems`]).
+    pub fn rings(&self) -> &[Ring] {
+        &self.rings
+    }
+
+    /// Returns the atoms of the system, in increasing order.
+    pub fn atoms(&self) -> &[NodeIndex] {
+        &self.atoms
+    }
+
+    /// Returns the atoms shared by two rings that have no other atom in
+    /// common, in increasing order.
+    pub fn spiro_atoms(&self) -> &[NodeIndex] {
+        &self.spiro_atoms
+    }
+
+    /// Returns the atoms at the ends of a path of two or more bonds shared
+    /// by two rings, in increasing order. In a cage where no two rings
+    /// share such a path, these are the atoms in three or more rings.
+    pub fn bridgehead_atoms(&self) -> &[NodeIndex] {
+        &self.bridgehead_atoms
+    }
+}
+
+/// Ring systems bonded directly to each other, as in biphenyl.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingAssembly {
+    systems: Vec<usize>,
+    atoms: Vec<NodeIndex>,
+}
+
+impl RingAssembly {
+    /// Returns the indices of the systems in [`Molecule::ring_systems`].
+    pub fn systems(&self) -> &[usize] {
+        &self.systems
+    }
+
+    /// Returns the ring atoms of the assembly, in increasing order.
+    pub fn atoms(&self) -> &[NodeIndex] {
+        &self.atoms
+    }
+}
+
+impl RingInfo {
+    /// Groups the relevant cycles into ring systems, ordered by their
+    /// lowest atom. The SSSR is grouped instead when there are more than
+    /// [`MAX_RELEVANT_CYCLES`](super::rings::MAX_RELEVANT_CYCLES) relevant
+    /// cycles.
+    pub fn ring_systems(&self) -> Vec<RingSystem> {
+        let rings = self.relevant_cycles().unwrap_or(self.sssr());
+        let mut parent: Vec<usize> = (0..rings.len()).collect();
+        let mut rings_of_atom: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+        for (index, ring) in rings.iter().enumerate() {
+            for &atom in &ring.nodes {
+                let list = rings_of_atom.entry(atom).or_default();
+                if let Some(&first) = list.first() {
+                    union(&mut parent, first, index);
+                }
+                list.push(index);
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+        for index in 0..rings.len() {
+            let root = find(&mut parent, index);
+            let group = *group_of_root.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(index);
+        }
+
+        let mut systems: Vec<RingSystem> = groups
+            .into_iter()
+            .map(|members| ring_system(rings, &members, &rings_of_atom))
+            .collect();
+        systems.sort_by_key(|system| system.atoms[0]);
+        systems
+    }
+}
+
+/// Classifies the junctions between the rings `members` of one system.
+fn ring_system(
+    rings: &[Ring],
+    members: &[usize],
+    rings_of_atom: &HashMap<NodeIndex, Vec<usize>>,
+) -> RingSystem {
+    let atoms: BTreeSet<NodeIndex> = members
+        .iter()
+        .flat_map(|&r| rings[r].nodes.iter().copied())
+        .collect();
+
+    // Only rings with an atom in common can form a junction.
+    let mut pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for atom in &atoms {
+        let list = &rings_of_atom[atom];
+        for (i, &a) in list.iter().enumerate() {
+            for &b in &list[i + 1..] {
+                pairs.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    let mut kind = if members.len() == 1 {
+        RingSystemKind::Isolated
+    } else {
+        RingSystemKind::Spiro
+    };
+    let mut spiro_atoms = BTreeSet::new();
+    let mut bridgehead_atoms = BTreeSet::new();
+    for (a, b) in pairs {
+        let bonds_b = ring_bonds(&rings[b]);
+        let shared: Vec<(NodeIndex, NodeIndex)> = ring_bonds(&rings[a])
+            .into_iter()
+            .filter(|bond| bonds_b.contains(bond))
+            .collect();
+        match shared.len() {
+            0 => {
+                let nodes_b: HashSet<NodeIndex> = rings[b].nodes.iter().copied().collect();
+                let common: Vec<NodeIndex> = rings[a]
+                    .nodes
+                    .iter()
+                    .copied()
+                    .filter(|atom| nodes_b.contains(atom))
+                    .collect();
+                if let [atom] = common[..] {
+                    spiro_atoms.insert(atom);
+                }
+            }
+            1 => {
+                if kind != RingSystemKind::Bridged {
+                    kind = RingSystemKind::Fused;
+                }
+            }
+            _ => {
+                kind = RingSystemKind::Bridged;
+                // The ends of the shared path touch a single shared bond.
+                let mut degree: HashMap<NodeIndex, usize> = HashMap::new();
+                for (u, v) in shared {
+                    *degree.entry(u).or_default() += 1;
+                    *degree.entry(v).or_default() += 1;
+                }
+                bridgehead_atoms.extend(
+                    degree
+                        .into_iter()
+                        .filter(|&(_, count)| count == 1)
+                        .map(|(atom, _)| atom),
+                );
+            }
+        }
+    }
+
+    // A fused system closes one ring per bond beyond a spanning tree; a
+    // cage has more relevant cycles than that.
+    let bonds: HashSet<(NodeIndex, NodeIndex)> = members
+        .iter()
+        .flat_map(|&r| ring_bonds(&rings[r]))
+        .collect();
+    if members.len() + atoms.len() > bonds.len() + 1 {
+        kind = RingSystemKind::Bridged;
+        if bridgehead_atoms.is_empty() {
+            bridgehead_atoms.extend(atoms.iter().copied().filter(|atom| {
+                rings_of_atom[atom]
+                    .iter()
+                    .filter(|r| members.contains(r))
+                    .count()
+                    >= 3
+            }));
+        }
+    }
+
+    RingSystem {
+        kind,
+        rings: members.iter().map(|&r| rings[r].clone()).collect(),
+        atoms: atoms.into_iter().collect(),
+        spiro_atoms: spiro_atoms.into_iter().collect(),
+        bridgehead_atoms: bridgehead_atoms.into_iter().collect(),
+    }
+}
+
+/// Bonds of a ring as sorted atom pairs.
+fn ring_bonds(ring: &Ring) -> HashSet<(NodeIndex, NodeIndex)> {
+    let n = ring.nodes.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring.nodes[i], ring.nodes[(i + 1) % n]);
+            (a.min(b), a.max(b))
+        })
+        .collect()
+}
+
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (ra, rb) = (find(parent, a), find(parent, b));
+    if ra != rb {
+        parent[ra.max(rb)] = ra.min(rb);
+    }
+}
+
+impl Molecule {
+    /// Returns the ring systems of the molecule, ordered by their lowest atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::{parse, RingSystemKind};
+    ///
+    /// let systems = parse("C1CC2CCC1C2").unwrap().ring_systems(); // norbornane
+    /// assert_eq!(systems.len(), 1);
+    /// assert_eq!(systems[0].kind(), RingSystemKind::Bridged);
+    /// assert_eq!(systems[0].bridgehead_atoms(), &[2, 5]);
+    /// ```
+    pub fn ring_systems(&self) -> Vec<RingSystem> {
+        self.ring_info().ring_systems()
+    }
+
+    /// Returns the ring assemblies: groups of two or more ring systems
+    /// joined directly by a bond, ordered by their lowest atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let biphenyl = parse("c1ccccc1-c1ccccc1").unwrap();
+    /// let assemblies = biphenyl.ring_assemblies();
+    /// assert_eq!(assemblies.len(), 1);
+    /// assert_eq!(assemblies[0].systems(), &[0, 1]);
+    /// ```
+    pub fn ring_assemblies(&self) -> Vec<RingAssembly> {
+        let systems = self.ring_systems();
+        let mut system_of_atom: HashMap<NodeIndex, usize> = HashMap::new();
+        for (index, system) in systems.iter().enumerate() {
+            for &atom in system.atoms() {
+                system_of_atom.insert(atom, index);
+            }
+        }
+
+        let mut parent: Vec<usize> = (0..systems.len()).collect();
+        for bond in self.bonds() {
+            if bond.kind() == BondType::Disconnected {
+                continue;
+            }
+            if let (Some(&a), Some(&b)) = (
+                system_of_atom.get(&bond.source()),
+                system_of_atom.get(&bond.target()),
+            ) {
+                if a != b {
+                    union(&mut parent, a, b);
+                }
+            }
+        }
+
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for index in 0..systems.len() {
+            let root = find(&mut parent, index);
+            members.entry(root).or_default().push(index);
+        }
+        let mut assemblies: Vec<RingAssembly> = members
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|group| {
+                let mut atoms: Vec<NodeIndex> = group
+                    .iter()
+                    .flat_map(|&s| systems[s].atoms().iter().copied())
+                    .collect();
+                atoms.sort_unstable();
+                RingAssembly {
+                    systems: group,
+                    atoms,
+                }
+            })
+            .collect();
+        assemblies.sort_by_key(|assembly| assembly.atoms[0]);
+        assemblies
+    }
+}
@@ -0,0 +1,916 @@
+//! Ring perception: ring membership, smallest set of smallest rings (SSSR)
+//! and relevant cycles.
+//!
+//! The graph is first split into biconnected components; bonds outside them
+//! are bridges and belong to no ring. Inside each component, candidate
+//! cycles are generated with Vismara's method: a breadth-first search from
+//! every atom `r`, restricted to the atoms ordered before `r`, yields the
+//! shortest cycles whose highest atom is `r`. Gaussian elimination over
+//! GF(2), by increasing size, then keeps the cycles that are independent of
+//! all strictly shorter ones (the relevant cycles) and a minimum basis among
+//! them (the SSSR).
+//!
+//! The searches are limited to a radius that only grows when the cycles found
+//! so far do not span the cycle space, and the elimination works on sparse
+//! edge lists, so the work stays close to linear in the molecule size even
+//! for ring systems with thousands of rings.
+//!
+//! Each relevant cycle kept by the elimination stands for a family of cycles
+//! of the same size built from other shortest paths. Families are only
+//! expanded on request: caged ring systems can have exponentially many
+//! relevant cycles, so their number is capped by [`MAX_RELEVANT_CYCLES`].
+
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::bond::BondType;
+use super::graph::Ring;
+use super::molecule::Molecule;
+use crate::NodeIndex;
+
+const NONE: usize = usize::MAX;
+
+/// Radius of the first search pass: cycles of up to `2 * radius + 1` atoms.
+const INITIAL_RADIUS: usize = 12;
+
+/// Largest number of relevant cycles [`RingInfo::relevant_cycles`] lists.
+pub const MAX_RELEVANT_CYCLES: usize = 10_000;
+
+/// Ring information of a molecule, computed by [`Molecule::ring_info`].
+///
+/// Bonds are referred to by their index in [`Molecule::bonds`].
+#[derive(Debug, Clone)]
+pub struct RingInfo {
+    sssr: Vec<Ring>,
+    atom_ring_size: Vec<Option<usize>>,
+    bond_ring_size: Vec<Option<usize>>,
+    /// The graph, kept to list the relevant cycles on request.
+    edges: Vec<Option<(NodeIndex, NodeIndex)>>,
+    relevant: OnceCell<Option<Vec<Ring>>>,
+}
+
+impl PartialEq for RingInfo {
+    fn eq(&self, other: &Self) -> bool {
+        // The relevant cycles follow from the graph, listed or not.
+        self.sssr == other.sssr
+            && self.atom_ring_size == other.atom_ring_size
+            && self.bond_ring_size == other.bond_ring_size
+            && self.edges == other.edges
+    }
+}
+
+impl RingInfo {
+    /// Returns the smallest set of smallest rings.
+    ///
+    /// The SSSR is a minimum cycle basis and is not unique for some ring
+    /// systems (e.g. bicyclo\[2.2.2\]octane); [`relevant_cycles`](Self::relevant_cycles)
+    /// is the unique superset of all such choices.
+    pub fn sssr(&self) -> &[Ring] {
+        &self.sssr
+    }
+
+    /// Returns the relevant cycles: every cycle that is not the sum of
+    /// strictly smaller cycles. This is the union of all minimum cycle bases.
+    ///
+    /// They are listed on the first call. Caged ring systems can have
+    /// exponentially many of them: `None` is returned when there would be
+    /// more than [`MAX_RELEVANT_CYCLES`].
+    pub fn relevant_cycles(&self) -> Option<&[Ring]> {
+        self.relevant
+            .get_or_init(|| {
+                let cycles =
+                    relevant_cycles(self.atom_ring_size.len(), &self.edges, MAX_RELEVANT_CYCLES)?;
+                Some(into_rings(cycles))
+            })
+            .as_deref()
+    }
+
+    /// Returns the number of rings (the cyclomatic number, i.e. the size of the
+    /// SSSR).
+    pub fn ring_count(&self) -> usize {
+        self.sssr.len()
+    }
+
+    /// Returns `true` if the atom belongs to at least one ring.
+    pub fn is_in_ring(&self, atom: NodeIndex) -> bool {
+        self.smallest_ring_size(atom).is_some()
+    }
+
+    /// Returns `true` if the bond at index `bond` belongs to at least one ring.
+    pub fn ring_bond(&self, bond: usize) -> bool {
+        self.smallest_bond_ring_size(bond).is_some()
+    }
+
+    /// Returns the size of the smallest ring containing the atom.
+    pub fn smallest_ring_size(&self, atom: NodeIndex) -> Option<usize> {
+        self.atom_ring_size.get(atom as usize).copied().flatten()
+    }
+
+    /// Returns the size of the smallest ring containing the bond at index `bond`.
+    pub fn smallest_bond_ring_size(&self, bond: usize) -> Option<usize> {
+        self.bond_ring_size.get(bond).copied().flatten()
+    }
+}
+
+impl Molecule {
+    /// Perceives the rings of the molecule.
+    ///
+    /// All bonds are considered, whatever their type; aromaticity plays no
+    /// role.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// // Norbornane: two five-membered rings sharing a bridge.
+    /// let rings = parse("C1CC2CCC1C2").unwrap().ring_info();
+    /// assert_eq!(rings.ring_count(), 2);
+    /// assert!(rings.sssr().iter().all(|ring| ring.size() == 5));
+    /// assert_eq!(rings.smallest_ring_size(0), Some(5));
+    /// ```
+    pub fn ring_info(&self) -> RingInfo {
+        let n = self.nodes().len();
+        let edges: Vec<Option<(NodeIndex, NodeIndex)>> = self
+            .bonds()
+            .iter()
+            .map(|bond| {
+                (bond.kind() != BondType::Disconnected).then_some((bond.source(), bond.target()))
+            })
+            .collect();
+        let cycles = perceive(n, &edges);
+
+        // The smallest ring through an atom is the smallest one through one
+        // of its bonds.
+        let mut atom_ring_size = vec![None; n];
+        for (edge, size) in edges.iter().zip(&cycles.edge_ring_size) {
+            let (Some((a, b)), Some(size)) = (*edge, *size) else {
+                continue;
+            };
+            for atom in [a, b] {
+                let slot: &mut Option<usize> = &mut atom_ring_size[atom as usize];
+                *slot = Some(slot.map_or(size, |s| s.min(size)));
+            }
+        }
+
+        RingInfo {
+            sssr: into_rings(cycles.sssr),
+            atom_ring_size,
+            bond_ring_size: cycles.edge_ring_size,
+            edges,
+            relevant: OnceCell::new(),
+        }
+    }
+}
+
+fn into_rings(cycles: Vec<Cycle>) -> Vec<Ring> {
+    cycles
+        .into_iter()
+        .map(|cycle| Ring { nodes: cycle.nodes })
+        .collect()
+}
+
+/// A cycle as an ordered atom sequence and the sorted ids of its edges.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cycle {
+    pub(crate) nodes: Vec<NodeIndex>,
+    pub(crate) edges: Vec<usize>,
+}
+
+/// SSSR, relevant families and smallest ring sizes of a graph.
+#[derive(Debug, Default)]
+pub(crate) struct Cycles {
+    pub(crate) sssr: Vec<Cycle>,
+    /// One relevant cycle of every family: at most polynomially many
+    /// cycles, among which the SSSR.
+    pub(crate) families: Vec<Cycle>,
+    /// Size of the smallest cycle through each edge, by edge id.
+    pub(crate) edge_ring_size: Vec<Option<usize>>,
+}
+
+/// Perceives the rings of the graph on `n` atoms whose edge `i` is
+/// `edges[i]` (`None` for edges to ignore). Parallel edges and self-loops are
+/// ignored.
+pub(crate) fn perceive(n: usize, edges: &[Option<(NodeIndex, NodeIndex)>]) -> Cycles {
+    let mut cycles = Cycles {
+        edge_ring_size: vec![None; edges.len()],
+        ..Cycles::default()
+    };
+    let mut local = vec![NONE; n];
+    for component in biconnected_components(&adjacency(n, edges)) {
+        let graph = ComponentGraph::new(&component, &mut local);
+        let found = graph.cycles();
+        for (edge, size) in graph.edge_ring_sizes(&found).into_iter().enumerate() {
+            cycles.edge_ring_size[graph.edge_ids[edge]] = Some(size);
+        }
+        cycles.families.extend(
+            found
+                .families
+                .iter()
+                .map(|family| graph.to_global(&family.nodes, &family.edges)),
+        );
+        cycles.sssr.extend(found.sssr);
+        for &(a, b, _) in &component {
+            local[a] = NONE;
+            local[b] = NONE;
+        }
+    }
+
+    sort_cycles(&mut cycles.sssr);
+    sort_cycles(&mut cycles.families);
+    cycles
+}
+
+/// Lists the relevant cycles of the graph of [`perceive`], or returns `None`
+/// if there could be more than `limit` of them.
+pub(crate) fn relevant_cycles(
+    n: usize,
+    edges: &[Option<(NodeIndex, NodeIndex)>],
+    limit: usize,
+) -> Option<Vec<Cycle>> {
+    let mut relevant = Vec::new();
+    let mut local = vec![NONE; n];
+    for component in biconnected_components(&adjacency(n, edges)) {
+        let graph = ComponentGraph::new(&component, &mut local);
+        let found = graph.cycles();
+        relevant.extend(graph.expand_families(&found, limit - relevant.len())?);
+        for &(a, b, _) in &component {
+            local[a] = NONE;
+            local[b] = NONE;
+        }
+    }
+    sort_cycles(&mut relevant);
+    Some(relevant)
+}
+
+fn sort_cycles(cycles: &mut [Cycle]) {
+    cycles.sort_by(|a, b| (a.nodes.len(), &a.edges).cmp(&(b.nodes.len(), &b.edges)));
+}
+
+/// Tells which atoms of the graph of [`perceive`] belong to a ring, without
+/// perceiving the rings themselves.
+pub(crate) fn ring_atoms(n: usize, edges: &[Option<(NodeIndex, NodeIndex)>]) -> Vec<bool> {
+    let mut in_ring = vec![false; n];
+    for component in biconnected_components(&adjacency(n, edges)) {
+        for (a, b, _) in component {
+            in_ring[a] = true;
+            in_ring[b] = true;
+        }
+    }
+    in_ring
+}
+
+/// Returns the `(neighbour, edge id)` pairs of every atom, without parallel
+/// edges and self-loops.
+fn adjacency(n: usize, edges: &[Option<(NodeIndex, NodeIndex)>]) -> Vec<Vec<(usize, usize)>> {
+    let mut adjacency: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+    let mut seen: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+    for (id, edge) in edges.iter().enumerate() {
+        let Some((a, b)) = *edge else {
+            continue;
+        };
+        if a == b || !seen.insert((a.min(b), a.max(b))) {
+            continue;
+        }
+        adjacency[a as usize].push((b as usize, id));
+        adjacency[b as usize].push((a as usize, id));
+    }
+    adjacency
+}
+
+/// Splits the graph into biconnected components (Tarjan, iterative) and
+/// returns those that contain a cycle, each as a list of
+/// `(atom, atom, edge id)` in depth-first order.
+fn biconnected_components(adjacency: &[Vec<(usize, usize)>]) -> Vec<Vec<(usize, usize, usize)>> {
+    let n = adjacency.len();
+    let mut discovery = vec![NONE; n];
+    let mut low = vec![0; n];
+    let mut time = 0;
+    let mut edge_stack: Vec<(usize, usize, usize)> = Vec::new();
+    let mut components = Vec::new();
+
+    for start in 0..n {
+        if discovery[start] != NONE {
+            continue;
+        }
+        discovery[start] = time;
+        low[start] = time;
+        time += 1;
+        // (atom, edge id it was reached through, next adjacency position)
+        let mut frames: Vec<(usize, usize, usize)> = vec![(start, NONE, 0)];
+        while let Some(frame) = frames.last_mut() {
+            let (v, parent_edge, position) = *frame;
+            if let Some(&(w, edge)) = adjacency[v].get(position) {
+                frame.2 += 1;
+                if edge == parent_edge {
+                    continue;
+                }
+                if discovery[w] == NONE {
+                    edge_stack.push((v, w, edge));
+                    discovery[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    frames.push((w, edge, 0));
+                } else if discovery[w] < discovery[v] {
+                    edge_stack.push((v, w, edge));
+                    low[v] = low[v].min(discovery[w]);
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some(&(u, _, _)) = frames.last() {
+                let tree_edge = (u, v, parent_edge);
+                low[u] = low[u].min(low[v]);
+                if low[v] >= discovery[u] {
+                    let mut component = Vec::new();
+                    while let Some(edge) = edge_stack.pop() {
+                        component.push(edge);
+                        if edge == tree_edge {
+                            break;
+                        }
+                    }
+                    // A single edge is a bridge.
+                    if component.len() > 1 {
+                        component.reverse();
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
+
+/// A biconnected component with local atom and edge numbering.
+struct ComponentGraph {
+    /// Global atom index of each local atom.
+    atoms: Vec<NodeIndex>,
+    /// Global edge id of each local edge.
+    edge_ids: Vec<usize>,
+    /// Local atoms of each local edge.
+    ends: Vec<(usize, usize)>,
+    /// `(neighbour, local edge)` pairs of each local atom.
+    adjacency: Vec<Vec<(usize, usize)>>,
+}
+
+impl ComponentGraph {
+    fn new(component: &[(usize, usize, usize)], local: &mut [usize]) -> Self {
+        let mut atoms = Vec::new();
+        let mut adjacency: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut edge_ids = Vec::with_capacity(component.len());
+        let mut ends = Vec::with_capacity(component.len());
+        let mut index = |atom: usize, atoms: &mut Vec<NodeIndex>, adjacency: &mut Vec<_>| {
+            if local[atom] == NONE {
+                local[atom] = atoms.len();
+                atoms.push(atom as NodeIndex);
+                adjacency.push(Vec::new());
+            }
+            local[atom]
+        };
+        for &(a, b, id) in component {
+            let (la, lb) = (
+                index(a, &mut atoms, &mut adjacency),
+                index(b, &mut atoms, &mut adjacency),
+            );
+            let local_edge = edge_ids.len();
+            edge_ids.push(id);
+            ends.push((la, lb));
+            adjacency[la].push((lb, local_edge));
+            adjacency[lb].push((la, local_edge));
+        }
+        ComponentGraph {
+            atoms,
+            edge_ids,
+            ends,
+            adjacency,
+        }
+    }
+
+    /// Returns the SSSR and the relevant families of the component.
+    fn cycles(&self) -> ComponentCycles {
+        let n = self.atoms.len();
+        let rank = self.edge_ids.len() + 1 - n;
+        if rank == 1 {
+            let (nodes, edges) = self.single_cycle();
+            return ComponentCycles {
+                sssr: vec![self.to_global(&nodes, &edges)],
+                families: vec![Prototype::new(0, nodes, edges, Closure::Single)],
+                radius: n,
+            };
+        }
+
+        let mut radius = INITIAL_RADIUS;
+        loop {
+            let mut prototypes = self.prototypes(radius);
+            prototypes.sort_by_key(|p| p.edges.len());
+
+            let mut basis = SparseBasis::default();
+            let mut sssr = Vec::new();
+            let mut relevant = vec![false; prototypes.len()];
+            let mut start = 0;
+            while start < prototypes.len() {
+                let size = prototypes[start].edges.len();
+                let end = start
+                    + prototypes[start..]
+                        .iter()
+                        .take_while(|p| p.edges.len() == size)
+                        .count();
+                // Relevance is decided against strictly shorter cycles only.
+                let group: Vec<usize> = (start..end)
+                    .filter(|&i| basis.is_independent(&prototypes[i].edges))
+                    .collect();
+                start = end;
+                for i in group {
+                    let prototype = &prototypes[i];
+                    if basis.insert(&prototype.edges) {
+                        sssr.push(self.to_global(&prototype.nodes, &prototype.edges));
+                    }
+                    relevant[i] = true;
+                }
+            }
+
+            // Relevant cycles are never longer than the longest basis cycle,
+            // so a full basis within the radius means none was missed.
+            if sssr.len() == rank || radius >= n {
+                let mut relevant = relevant.into_iter();
+                prototypes.retain(|_| relevant.next().unwrap_or(false));
+                return ComponentCycles {
+                    sssr,
+                    families: prototypes,
+                    radius,
+                };
+            }
+            radius *= 2;
+        }
+    }
+
+    /// Size of the smallest cycle through each local edge: a breadth-first
+    /// search between its atoms, bounded by the smallest family cycle
+    /// through it.
+    fn edge_ring_sizes(&self, found: &ComponentCycles) -> Vec<usize> {
+        let mut sizes = vec![NONE; self.edge_ids.len()];
+        for family in &found.families {
+            for &edge in &family.edges {
+                sizes[edge] = sizes[edge].min(family.edges.len());
+            }
+        }
+        if found.sssr.len() == 1 {
+            return sizes;
+        }
+
+        let mut distance = vec![NONE; self.atoms.len()];
+        let mut visited = Vec::new();
+        let mut queue = VecDeque::new();
+        for (edge, size) in sizes.iter_mut().enumerate() {
+            let (a, b) = self.ends[edge];
+            distance[a] = 0;
+            visited.push(a);
+            queue.push_back(a);
+            while let Some(v) = queue.pop_front() {
+                if v == b {
+                    *size = distance[b] + 1;
+                    break;
+                }
+                if distance[v] + 2 >= *size {
+                    continue;
+                }
+                for &(w, other) in &self.adjacency[v] {
+                    if other != edge && distance[w] == NONE {
+                        distance[w] = distance[v] + 1;
+                        visited.push(w);
+                        queue.push_back(w);
+                    }
+                }
+            }
+            queue.clear();
+            for v in visited.drain(..) {
+                distance[v] = NONE;
+            }
+        }
+        sizes
+    }
+
+    /// The component is a single cycle: walk around it.
+    fn single_cycle(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut nodes = vec![0];
+        let mut edges = Vec::with_capacity(self.edge_ids.len());
+        let (mut previous, mut current) = (NONE, 0);
+        loop {
+            let &(next, edge) = self.adjacency[current]
+                .iter()
+                .find(|&&(w, _)| w != previous)
+                .expect("every atom of a cycle has two neighbours");
+            edges.push(edge);
+            if next == 0 {
+                break;
+            }
+            nodes.push(next);
+            previous = current;
+            current = next;
+        }
+        (nodes, edges)
+    }
+
+    /// Breadth-first search from `root` over the atoms ordered before it, up
+    /// to `radius` bonds away.
+    fn search(&self, root: usize, radius: usize) -> Search {
+        let n = self.atoms.len();
+        let mut search = Search {
+            distance: vec![NONE; n],
+            parent: vec![(NONE, NONE); n],
+            visited: vec![root],
+        };
+        search.distance[root] = 0;
+        let mut queue = VecDeque::from([root]);
+        while let Some(v) = queue.pop_front() {
+            if search.distance[v] == radius {
+                continue;
+            }
+            for &(w, edge) in &self.adjacency[v] {
+                if w < root && search.distance[w] == NONE {
+                    search.distance[w] = search.distance[v] + 1;
+                    search.parent[w] = (v, edge);
+                    search.visited.push(w);
+                    queue.push_back(w);
+                }
+            }
+        }
+        search
+    }
+
+    /// Vismara's cycle prototypes: for every root, the odd cycles closed by
+    /// an edge between two atoms at the same distance and the even cycles
+    /// closed at an atom with two predecessors, when the two shortest paths
+    /// only meet at the root.
+    fn prototypes(&self, radius: usize) -> Vec<Prototype> {
+        let mut prototypes = Vec::new();
+        for root in 0..self.atoms.len() {
+            let search = self.search(root, radius);
+            for &y in &search.visited[1..] {
+                let mut predecessors = Vec::new();
+                for &(z, edge) in &self.adjacency[y] {
+                    let distance = search.distance[z];
+                    if distance == NONE {
+                        continue;
+                    }
+                    if distance + 1 == search.distance[y] {
+                        predecessors.push((z, edge));
+                    } else if distance == search.distance[y] && z < y && search.disjoint(y, z, root)
+                    {
+                        let mut nodes = search.path(y);
+                        let mut edges = search.path_edges(y);
+                        let mut back = search.path(z);
+                        back.reverse();
+                        nodes.extend(&back[..back.len() - 1]);
+                        edges.extend(search.path_edges(z));
+                        edges.push(edge);
+                        prototypes.push(Prototype::new(root, nodes, edges, Closure::Odd(y, z)));
+                    }
+                }
+                for (i, &(p, edge_p)) in predecessors.iter().enumerate() {
+                    for &(q, edge_q) in &predecessors[i + 1..] {
+                        if !search.disjoint(p, q, root) {
+                            continue;
+                        }
+                        let mut nodes = search.path(p);
+                        nodes.push(y);
+                        let mut back = search.path(q);
+                        back.reverse();
+                        nodes.extend(&back[..back.len() - 1]);
+                        let mut edges = search.path_edges(p);
+                        edges.extend(search.path_edges(q));
+                        edges.extend([edge_p, edge_q]);
+                        prototypes.push(Prototype::new(root, nodes, edges, Closure::Even(p, y, q)));
+                    }
+                }
+            }
+        }
+        prototypes
+    }
+
+    /// Expands every relevant family into its cycles: all cycles built from
+    /// the other shortest paths between the same atoms. Returns `None` if
+    /// there could be more than `limit` of them.
+    fn expand_families(&self, found: &ComponentCycles, limit: usize) -> Option<Vec<Cycle>> {
+        let mut by_root: HashMap<usize, Vec<&Prototype>> = HashMap::new();
+        for prototype in &found.families {
+            if let Closure::Single = prototype.closure {
+                return Some(vec![self.to_global(&prototype.nodes, &prototype.edges)]);
+            }
+            by_root.entry(prototype.root).or_default().push(prototype);
+        }
+
+        let searches: Vec<(Search, Vec<&Prototype>)> = by_root
+            .into_iter()
+            .map(|(root, prototypes)| (self.search(root, found.radius), prototypes))
+            .collect();
+        // Every pair of shortest paths is a candidate: count them before
+        // listing any.
+        let mut candidates: usize = 0;
+        for (search, prototypes) in &searches {
+            let counts = self.path_counts(search);
+            for prototype in prototypes {
+                let (a, b) = prototype.closure.ends();
+                candidates = candidates.saturating_add(counts[a].saturating_mul(counts[b]));
+            }
+        }
+        if candidates > limit {
+            return None;
+        }
+
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        let mut cycles = Vec::new();
+        for (search, prototypes) in &searches {
+            for prototype in prototypes {
+                let (a, b) = prototype.closure.ends();
+                let middle = match prototype.closure {
+                    Closure::Even(_, y, _) => Some(y),
+                    _ => None,
+                };
+                let paths_a = self.shortest_paths(search, a);
+                let paths_b = self.shortest_paths(search, b);
+                for path_a in &paths_a {
+                    for path_b in &paths_b {
+                        if path_a[1..].iter().any(|v| path_b[1..].contains(v)) {
+                            continue;
+                        }
+                        let mut nodes = path_a.clone();
+                        nodes.extend(middle);
+                        nodes.extend(path_b[1..].iter().rev());
+                        let edges: Vec<usize> = (0..nodes.len())
+                            .map(|i| self.edge_between(nodes[i], nodes[(i + 1) % nodes.len()]))
+                            .collect();
+                        let mut key = edges.clone();
+                        key.sort_unstable();
+                        if seen.insert(key) {
+                            cycles.push(self.to_global(&nodes, &edges));
+                        }
+                    }
+                }
+            }
+        }
+        Some(cycles)
+    }
+
+    /// Number of shortest paths from the search root to every atom.
+    fn path_counts(&self, search: &Search) -> Vec<usize> {
+        let mut counts = vec![0usize; self.atoms.len()];
+        for &v in &search.visited {
+            if search.distance[v] == 0 {
+                counts[v] = 1;
+                continue;
+            }
+            counts[v] = self.adjacency[v]
+                .iter()
+                .filter(|&&(w, _)| {
+                    search.distance[w] != NONE && search.distance[w] + 1 == search.distance[v]
+                })
+                .fold(0usize, |sum, &(w, _)| sum.saturating_add(counts[w]));
+        }
+        counts
+    }
+
+    /// All shortest paths from the search root to `target`, root first.
+    fn shortest_paths(&self, search: &Search, target: usize) -> Vec<Vec<usize>> {
+        if search.distance[target] == 0 {
+            return vec![vec![target]];
+        }
+        let mut paths = Vec::new();
+        for &(previous, _) in &self.adjacency[target] {
+            if search.distance[previous] != NONE
+                && search.distance[previous] + 1 == search.distance[target]
+            {
+                for mut path in self.shortest_paths(search, previous) {
+                    path.push(target);
+                    paths.push(path);
+                }
+            }
+        }
+        paths
+    }
+
+    fn edge_between(&self, a: usize, b: usize) -> usize {
+        self.adjacency[a]
+            .iter()
+            .find(|&&(w, _)| w == b)
+            .map(|&(_, edge)| edge)
+            .expect("consecutive cycle atoms are bonded")
+    }
+
+    /// Converts a local cycle to global indices, starting from its lowest atom.
+    fn to_global(&self, nodes: &[usize], edges: &[usize]) -> Cycle {
+        let mut nodes: Vec<NodeIndex> = nodes.iter().map(|&v| self.atoms[v]).collect();
+        let start = (0..nodes.len()).min_by_key(|&i| nodes[i]).unwrap_or(0);
+        nodes.rotate_left(start);
+        if nodes.len() > 2 && nodes[nodes.len() - 1] < nodes[1] {
+            nodes[1..].reverse();
+        }
+        let mut edges: Vec<usize> = edges.iter().map(|&e| self.edge_ids[e]).collect();
+        edges.sort_unstable();
+        Cycle { nodes, edges }
+    }
+}
+
+/// Result of [`ComponentGraph::search`].
+struct Search {
+    distance: Vec<usize>,
+    /// `(parent atom, edge to parent)` in the search tree.
+    parent: Vec<(usize, usize)>,
+    visited: Vec<usize>,
+}
+
+impl Search {
+    /// Tree path from the root to `v`, root first.
+    fn path(&self, mut v: usize) -> Vec<usize> {
+        let mut path = vec![v];
+        while self.distance[v] > 0 {
+            v = self.parent[v].0;
+            path.push(v);
+        }
+        path.reverse();
+        path
+    }
+
+    fn path_edges(&self, mut v: usize) -> Vec<usize> {
+        let mut edges = Vec::with_capacity(self.distance[v]);
+        while self.distance[v] > 0 {
+            edges.push(self.parent[v].1);
+            v = self.parent[v].0;
+        }
+        edges
+    }
+
+    /// Whether the tree paths to `a` and `b` (at the same distance) only
+    /// share the root.
+    fn disjoint(&self, mut a: usize, mut b: usize, root: usize) -> bool {
+        while a != b {
+            a = self.parent[a].0;
+            b = self.parent[b].0;
+        }
+        a == root
+    }
+}
+
+/// How a prototype cycle is closed opposite its root.
+#[derive(Debug, Clone, Copy)]
+enum Closure {
+    /// Odd cycle closed by the edge between two atoms at the same distance.
+    Odd(usize, usize),
+    /// Even cycle closed at the middle atom, reached from two predecessors.
+    Even(usize, usize, usize),
+    /// The component is a single cycle.
+    Single,
+}
+
+impl Closure {
+    /// The atoms the two shortest paths of the cycle lead to.
+    fn ends(self) -> (usize, usize) {
+        match self {
+            Closure::Odd(y, z) => (y, z),
+            Closure::Even(p, _, q) => (p, q),
+            Closure::Single => (0, 0),
+        }
+    }
+}
+
+/// Rings of one biconnected component.
+struct ComponentCycles {
+    sssr: Vec<Cycle>,
+    /// The relevant prototypes, one per family.
+    families: Vec<Prototype>,
+    /// Search radius the prototypes were found with.
+    radius: usize,
+}
+
+struct Prototype {
+    root: usize,
+    nodes: Vec<usize>,
+    /// Sorted local edge ids.
+    edges: Vec<usize>,
+    closure: Closure,
+}
+
+impl Prototype {
+    fn new(root: usize, nodes: Vec<usize>, mut edges: Vec<usize>, closure: Closure) -> Self {
+        edges.sort_unstable();
+        Prototype {
+            root,
+            nodes,
+            edges,
+            closure,
+        }
+    }
+}
+
+/// Incremental GF(2) elimination over sorted edge lists. Each row is keyed
+/// by its smallest edge, so any non-zero combination of rows has a row key
+/// as its smallest edge.
+#[derive(Default)]
+struct SparseBasis {
+    rows: HashMap<usize, Vec<usize>>,
+}
+
+impl SparseBasis {
+    fn reduce(&self, edges: &[usize]) -> Vec<usize> {
+        let mut vector = edges.to_vec();
+        while let Some(row) = vector.first().and_then(|pivot| self.rows.get(pivot)) {
+            vector = symmetric_difference(&vector, row);
+        }
+        vector
+    }
+
+    fn is_independent(&self, edges: &[usize]) -> bool {
+        !self.reduce(edges).is_empty()
+    }
+
+    /// Adds the vector if it is independent; returns whether it was added.
+    fn insert(&mut self, edges: &[usize]) -> bool {
+        let reduced = self.reduce(edges);
+        match reduced.first() {
+            Some(&pivot) => {
+                self.rows.insert(pivot, reduced);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn symmetric_difference(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut result = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => {
+                result.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                result.push(b[j]);
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    result.extend_from_slice(&a[i..]);
+    result.extend_from_slice(&b[j..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_basis_detects_dependence() {
+        let mut basis = SparseBasis::default();
+        assert!(basis.insert(&[0, 1, 2]));
+        assert!(basis.insert(&[2, 3, 4]));
+        assert!(!basis.is_independent(&[0, 1, 3, 4]));
+        assert!(basis.is_independent(&[1, 3]));
+        assert!(!basis.insert(&[0, 1, 2]));
+    }
+
+    #[test]
+    fn bridges_are_not_in_components() {
+        // Two triangles joined by a bridge 2-3.
+        let edges: Vec<Option<(NodeIndex, NodeIndex)>> =
+            [(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 5), (5, 3)]
+                .into_iter()
+                .map(Some)
+                .collect();
+        let cycles = perceive(6, &edges);
+        assert_eq!(cycles.sssr.len(), 2);
+        assert!(cycles.families.iter().all(|c| !c.edges.contains(&3)));
+    }
+
+    #[test]
+    fn ring_atoms_skip_chains() {
+        // A triangle with a tail 2-3-4, and a parallel edge 3-4.
+        let edges: Vec<Option<(NodeIndex, NodeIndex)>> =
+            [(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3)]
+                .into_iter()
+                .map(Some)
+                .collect();
+        assert_eq!(ring_atoms(5, &edges), [true, true, true, false, false]);
+    }
+
+    #[test]
+    fn radius_grows_for_large_rings() {
+        // A 40-membered ring fused to a 41-membered one needs a larger radius
+        // than the first pass.
+        let mut edges: Vec<Option<(NodeIndex, NodeIndex)>> =
+            (0..40).map(|i| Some((i, (i + 1) % 40))).collect();
+        edges.push(Some((0, 40)));
+        edges.extend((40..79).map(|i| Some((i, i + 1))));
+        edges.push(Some((79, 20)));
+        let cycles = perceive(80, &edges);
+        assert_eq!(cycles.sssr.len(), 2);
+        let mut sizes: Vec<usize> = cycles.sssr.iter().map(|c| c.nodes.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![40, 61]);
+    }
+}
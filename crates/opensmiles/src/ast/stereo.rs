@@ -0,0 +1,420 @@
+//! Stereo bookkeeping shared by the parser and the SMILES writer.
+//!
+//! # Tetrahedral centres
+//!
+//! The `@`/`@@` parity of a [`Node`](super::node::Node) is stored relative to
+//! a *reference order* of its neighbours: the order in which its bonds appear
+//! in [`Molecule::bonds()`], with the implicit hydrogen (or lone pair) placed
+//! right after the first neighbour when that neighbour has a lower index than
+//! the centre, and first otherwise. For a SMILES string without ring closures
+//! on the centre this is exactly the order the neighbours are written in; the
+//! parser adjusts the parity in the other cases.
+//!
+//! # Double bonds
+//!
+//! A directional bond stored as `(source, target, Up)` means "`target` lies
+//! above `source`", exactly as `source/target` reads in SMILES; `Down` means
+//! below. Two substituents of a double bond are *cis* when they lie on the
+//! same side of their respective double-bond atom.
+
+use std::collections::HashMap;
+
+use super::bond::{Bond, BondType};
+use super::chirality::Chirality;
+use super::molecule::{Molecule, WriterGraph};
+use crate::NodeIndex;
+
+/// How the SMILES writer handles stereo annotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoOutput {
+    /// Write `@`/`@@` and `/`/`\` exactly as stored, whatever order the
+    /// writer emits the neighbours in. The output may describe another
+    /// stereoisomer.
+    Verbatim,
+    /// Recompute `@`/`@@` and the `/`/`\` marks from the order in which the
+    /// writer emits the neighbours, so that the output describes the same
+    /// stereoisomer as the molecule. This is what
+    /// [`Display`](std::fmt::Display) does.
+    ///
+    /// Only tetrahedral centres and double bonds are recomputed; the other
+    /// chirality classes (`@AL`, `@SP`, `@TB`, `@OH`) are written as stored.
+    #[default]
+    Recomputed,
+    /// Drop every stereo annotation.
+    Omitted,
+}
+
+/// Returns `true` for the tetrahedral chirality classes (`@`, `@@`).
+pub(crate) fn is_tetrahedral(chirality: Option<Chirality>) -> bool {
+    matches!(chirality, Some(Chirality::TH1 | Chirality::TH2))
+}
+
+/// Swaps `@` and `@@`; other chirality classes are returned unchanged.
+pub(crate) fn invert(chirality: Chirality) -> Chirality {
+    match chirality {
+        Chirality::TH1 => Chirality::TH2,
+        Chirality::TH2 => Chirality::TH1,
+        other => other,
+    }
+}
+
+/// Returns the reference neighbour order of `center` (see the module
+/// documentation). The implicit hydrogen or lone pair, when `implicit` is
+/// set, is represented by `center` itself.
+pub(crate) fn reference_order(center: NodeIndex, bonds: &[Bond], implicit: bool) -> Vec<NodeIndex> {
+    let mut order: Vec<NodeIndex> = bonds
+        .iter()
+        .filter_map(|bond| {
+            if bond.source() == center {
+                Some(bond.target())
+            } else if bond.target() == center {
+                Some(bond.source())
+            } else {
+                None
+            }
+        })
+        .collect();
+    if implicit {
+        let position = match order.first() {
+            Some(&first) if first < center => 1,
+            _ => 0,
+        };
+        order.insert(position, center);
+    }
+    order
+}
+
+/// Returns whether `to` is an odd permutation of `from`, or `None` when the
+/// two lists do not hold the same distinct elements.
+pub(crate) fn is_odd_permutation(from: &[NodeIndex], to: &[NodeIndex]) -> Option<bool> {
+    if from.len() != to.len() {
+        return None;
+    }
+    let mut positions = Vec::with_capacity(to.len());
+    for item in to {
+        let position = from.iter().position(|x| x == item)?;
+        if positions.contains(&position) {
+            return None;
+        }
+        positions.push(position);
+    }
+    let mut inversions = 0;
+    for i in 0..positions.len() {
+        for j in i + 1..positions.len() {
+            if positions[i] > positions[j] {
+                inversions += 1;
+            }
+        }
+    }
+    Some(inversions % 2 == 1)
+}
+
+/// Returns `true` when `neighbour` lies above `center` according to a
+/// directional bond, `false` when below, `None` for other bonds.
+pub(crate) fn side_of(bond: &Bond, center: NodeIndex) -> Option<bool> {
+    let up = match bond.kind() {
+        BondType::Up => true,
+        BondType::Down => false,
+        _ => return None,
+    };
+    if bond.source() == center {
+        Some(up)
+    } else {
+        Some(!up)
+    }
+}
+
+/// Order in which the writer emits the atoms and their neighbours.
+pub(crate) struct Emission<'a> {
+    /// Preorder position of each written atom (`usize::MAX` if not written).
+    pub(crate) position: Vec<usize>,
+    /// Spanning-tree parent of each atom.
+    pub(crate) parent: Vec<Option<NodeIndex>>,
+    /// Spanning-tree children, in the order they are written.
+    pub(crate) children: &'a [Vec<(NodeIndex, BondType)>],
+    /// Ring-closure partners, in the order the digits are written.
+    pub(crate) ring_partners: Vec<Vec<NodeIndex>>,
+}
+
+/// Stereo annotations computed for one particular emission order.
+pub(crate) struct StereoPlan {
+    /// Parity to write for each atom.
+    pub(crate) chirality: Vec<Option<Chirality>>,
+    /// Directional marks to write, keyed by `(min, max)` atom pair and
+    /// oriented from the atom written first to the atom written second.
+    pub(crate) marks: HashMap<(NodeIndex, NodeIndex), BondType>,
+}
+
+impl Molecule {
+    /// Recomputes the stereo annotations for the given emission order.
+    ///
+    /// Double-bond marks keep the orientation of the input where possible;
+    /// with `canonical` they are chosen independently of it.
+    pub(crate) fn plan_stereo(
+        &self,
+        graph: &WriterGraph,
+        emission: &Emission,
+        canonical: bool,
+        suppress_chirality: &[bool],
+        suppress_double_bond: impl Fn(NodeIndex, NodeIndex) -> bool,
+    ) -> StereoPlan {
+        let n = self.nodes().len();
+        let mut chirality = vec![None; n];
+        for (atom, node) in self.nodes().iter().enumerate() {
+            if suppress_chirality[atom] || graph.removable_h[atom] {
+                continue;
+            }
+            chirality[atom] = match node.chirality() {
+                Some(c) if is_tetrahedral(Some(c)) => {
+                    self.emitted_parity(graph, emission, atom as NodeIndex, c)
+                }
+                other => other,
+            };
+        }
+
+        StereoPlan {
+            chirality,
+            marks: self.plan_double_bonds(graph, emission, canonical, suppress_double_bond),
+        }
+    }
+
+    /// Parity of a tetrahedral centre relative to the emitted neighbour order.
+    fn emitted_parity(
+        &self,
+        graph: &WriterGraph,
+        emission: &Emission,
+        center: NodeIndex,
+        stored: Chirality,
+    ) -> Option<Chirality> {
+        let node = &self.nodes()[center as usize];
+        let bonds = self.bonds();
+        let degree = reference_order(center, bonds, false).len();
+        let implicit = node.hydrogens() > 0 || degree == 3;
+        // Folded `[H]` atoms are written as part of the hydrogen count.
+        let reference: Vec<NodeIndex> = reference_order(center, bonds, implicit)
+            .into_iter()
+            .map(|v| {
+                if graph.removable_h[v as usize] {
+                    center
+                } else {
+                    v
+                }
+            })
+            .collect();
+
+        let c = center as usize;
+        let mut emitted = Vec::with_capacity(4);
+        if let Some(parent) = emission.parent[c] {
+            emitted.push(parent);
+        }
+        if node.hydrogens() + graph.virtual_h[c] > 0 || graph.neighbours[c].len() == 3 {
+            emitted.push(center);
+        }
+        emitted.extend(emission.ring_partners[c].iter().copied());
+        emitted.extend(emission.children[c].iter().map(|&(v, _)| v));
+
+        match is_odd_permutation(&reference, &emitted) {
+            Some(true) => Some(invert(stored)),
+            Some(false) => Some(stored),
+            None => Some(stored),
+        }
+    }
+
+    /// Chooses the `/`/`\` marks for every stereo double bond.
+    ///
+    /// One mark is written on each side of a double bond, on the bond to the
+    /// neighbour emitted first; marks already placed for a conjugated double
+    /// bond are reused.
+    fn plan_double_bonds(
+        &self,
+        graph: &WriterGraph,
+        emission: &Emission,
+        canonical: bool,
+        suppress: impl Fn(NodeIndex, NodeIndex) -> bool,
+    ) -> HashMap<(NodeIndex, NodeIndex), BondType> {
+        let n = self.nodes().len();
+        let mut incident: Vec<Vec<&Bond>> = vec![Vec::new(); n];
+        for bond in self.bonds() {
+            incident[bond.source() as usize].push(bond);
+            incident[bond.target() as usize].push(bond);
+        }
+
+        // Side of the first marked substituent of `center`, as stored.
+        let stored_side = |center: NodeIndex, other: NodeIndex| -> Option<(NodeIndex, bool)> {
+            incident[center as usize].iter().find_map(|bond| {
+                let neighbour = if bond.source() == center {
+                    bond.target()
+                } else {
+                    bond.source()
+                };
+                if neighbour == other {
+                    return None;
+                }
+                side_of(bond, center).map(|side| (neighbour, side))
+            })
+        };
+
+        // (first atom, second atom, input side of the first reference, cis)
+        let mut stereo_bonds: Vec<(NodeIndex, NodeIndex, bool, bool)> = Vec::new();
+        for bond in self.bonds() {
+            let (a, b) = (bond.source(), bond.target());
+            if bond.kind() != BondType::Double
+                || graph.effective_bond(a, b, bond.kind()) != BondType::Double
+                || suppress(a, b)
+            {
+                continue;
+            }
+            let (Some((u, side_u)), Some((v, side_v))) = (stored_side(a, b), stored_side(b, a))
+            else {
+                continue;
+            };
+            // Express the configuration between the first emitted substituents.
+            let (a, b, u, side_u, v, side_v) =
+                if emission.position[a as usize] <= emission.position[b as usize] {
+                    (a, b, u, side_u, v, side_v)
+                } else {
+                    (b, a, v, side_v, u, side_u)
+                };
+            let (Some(ref_a), Some(ref_b)) = (
+                self.first_emitted_substituent(graph, emission, a, b),
+                self.first_emitted_substituent(graph, emission, b, a),
+            ) else {
+                continue;
+            };
+            let side_ref_a = side_u == (ref_a == u);
+            let side_ref_b = side_v == (ref_b == v);
+            stereo_bonds.push((a, b, side_ref_a, side_ref_a == side_ref_b));
+        }
+        stereo_bonds.sort_by_key(|&(a, _, _, _)| emission.position[a as usize]);
+
+        let mut marks: HashMap<(NodeIndex, NodeIndex), BondType> = HashMap::new();
+        for (a, b, input_side, cis) in stereo_bonds {
+            let ref_a = self
+                .first_emitted_substituent(graph, emission, a, b)
+                .expect("checked above");
+            let ref_b = self
+                .first_emitted_substituent(graph, emission, b, a)
+                .expect("checked above");
+
+            let side_a = self
+                .marked_side(graph, emission, &marks, a, b, ref_a)
+                .unwrap_or_else(|| {
+                    let side = canonical || input_side;
+                    set_mark(&mut marks, emission, a, ref_a, side);
+                    side
+                });
+            let wanted = if cis { side_a } else { !side_a };
+            if self
+                .marked_side(graph, emission, &marks, b, a, ref_b)
+                .is_none()
+            {
+                set_mark(&mut marks, emission, b, ref_b, wanted);
+            }
+        }
+        marks
+    }
+
+    /// Written substituent of `center` (other than `other`) emitted first.
+    fn first_emitted_substituent(
+        &self,
+        graph: &WriterGraph,
+        emission: &Emission,
+        center: NodeIndex,
+        other: NodeIndex,
+    ) -> Option<NodeIndex> {
+        graph.neighbours[center as usize]
+            .iter()
+            .map(|&(v, _)| v)
+            .filter(|&v| v != other)
+            .min_by_key(|&v| emission.position[v as usize])
+    }
+
+    /// Side of `reference` around `center` implied by marks already planned
+    /// on the bonds of `center`, if any.
+    fn marked_side(
+        &self,
+        graph: &WriterGraph,
+        emission: &Emission,
+        marks: &HashMap<(NodeIndex, NodeIndex), BondType>,
+        center: NodeIndex,
+        other: NodeIndex,
+        reference: NodeIndex,
+    ) -> Option<bool> {
+        graph.neighbours[center as usize]
+            .iter()
+            .map(|&(v, _)| v)
+            .filter(|&v| v != other)
+            .find_map(|v| {
+                let kind = *marks.get(&(center.min(v), center.max(v)))?;
+                let (first, _) = emitted_direction(emission, center, v);
+                let bond = Bond::new(kind, first, if first == center { v } else { center });
+                let side = side_of(&bond, center)?;
+                Some(if v == reference { side } else { !side })
+            })
+    }
+}
+
+/// Returns the bond `(u, v)` oriented the way the writer emits it.
+fn emitted_direction(emission: &Emission, u: NodeIndex, v: NodeIndex) -> (NodeIndex, NodeIndex) {
+    if emission.position[u as usize] <= emission.position[v as usize] {
+        (u, v)
+    } else {
+        (v, u)
+    }
+}
+
+/// Records the mark that puts `neighbour` on the requested side of `center`.
+fn set_mark(
+    marks: &mut HashMap<(NodeIndex, NodeIndex), BondType>,
+    emission: &Emission,
+    center: NodeIndex,
+    neighbour: NodeIndex,
+    up: bool,
+) {
+    let (first, _) = emitted_direction(emission, center, neighbour);
+    let kind = if (first == center) == up {
+        BondType::Up
+    } else {
+        BondType::Down
+    };
+    marks.insert((center.min(neighbour), center.max(neighbour)), kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutation_parity() {
+        assert_eq!(
+            is_odd_permutation(&[1, 2, 3, 4], &[1, 2, 3, 4]),
+            Some(false)
+        );
+        assert_eq!(is_odd_permutation(&[1, 2, 3, 4], &[2, 1, 3, 4]), Some(true));
+        assert_eq!(
+            is_odd_permutation(&[1, 2, 3, 4], &[2, 3, 1, 4]),
+            Some(false)
+        );
+        assert_eq!(is_odd_permutation(&[1, 2, 3], &[1, 2, 5]), None);
+        assert_eq!(is_odd_permutation(&[1, 2, 3], &[1, 1, 2]), None);
+    }
+
+    #[test]
+    fn implicit_hydrogen_follows_preceding_atom() {
+        let bonds = vec![
+            Bond::new(BondType::Simple, 0, 1),
+            Bond::new(BondType::Simple, 1, 2),
+            Bond::new(BondType::Simple, 1, 3),
+        ];
+        assert_eq!(reference_order(1, &bonds, true), vec![0, 1, 2, 3]);
+        assert_eq!(reference_order(0, &bonds, true), vec![0, 1]);
+    }
+
+    #[test]
+    fn directional_bond_sides() {
+        let bond = Bond::new(BondType::Up, 0, 1);
+        assert_eq!(side_of(&bond, 0), Some(true));
+        assert_eq!(side_of(&bond, 1), Some(false));
+        assert_eq!(side_of(&Bond::new(BondType::Simple, 0, 1), 0), None);
+    }
+}
@@ -0,0 +1,45 @@
+//! Molecular formula parsing errors.
+
+use thiserror::Error;
+
+/// Errors that can occur when parsing a molecular formula string.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FormulaError {
+    /// The formula contains no element.
+    #[error("empty formula")]
+    Empty,
+
+    /// Unexpected character in the formula string.
+    #[error("unexpected character '{0}' at position {1}")]
+    UnexpectedCharacter(char, usize),
+
+    /// The element symbol is not recognized.
+    #[error("unknown element: '{0}'")]
+    UnknownElement(String),
+
+    /// Missing closing parenthesis.
+    #[error("missing closing parenthesis ')'")]
+    UnclosedParenthesis,
+
+    /// An element count or charge does not fit in the formula.
+    #[error("count too large at position {0}")]
+    CountTooLarge(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages_are_descriptive() {
+        assert_eq!(FormulaError::Empty.to_string(), "empty formula");
+        assert_eq!(
+            FormulaError::UnexpectedCharacter('?', 3).to_string(),
+            "unexpected character '?' at position 3"
+        );
+        assert_eq!(
+            FormulaError::UnknownElement("Xy".to_string()).to_string(),
+            "unknown element: 'Xy'"
+        );
+    }
+}
@@ -7,6 +7,11 @@
 //! - [`NodeError`] - Node-related errors (hydrogens, class, aromaticity)
 //! - [`MoleculeError`] - Molecule construction errors
 //! - [`ParserError`] - SMILES string parsing errors
+//! - [`LimitKind`] - Resource limits a [`ParserError::LimitExceeded`] names
+//! - [`FormulaError`] - Molecular formula parsing errors
+//! - [`SmartsError`] - SMARTS pattern parsing errors
+//! - [`MolfileError`] - Molfile and SDF reading errors
+//! - [`Span`] - Byte range of the input a [`ParserError`] points at
 //!
 //! # Error hierarchy
 //!
@@ -21,16 +26,25 @@
 //! ```
 //!
 //! `From` conversions are implemented to allow using the `?` operator
-//! throughout the hierarchy.
+//! throughout the hierarchy, except into [`ParserError`], whose variants
+//! also carry the [`Span`] of the input they come from.
 
 mod atom;
 mod bond;
+mod formula;
 mod molecule;
+mod molfile;
 mod node;
 mod parser;
+mod smarts;
+mod span;
 
 pub use atom::AtomError;
 pub use bond::BondError;
+pub use formula::FormulaError;
 pub use molecule::MoleculeError;
+pub use molfile::MolfileError;
 pub use node::NodeError;
-pub use parser::ParserError;
+pub use parser::{LimitKind, ParserError};
+pub use smarts::SmartsError;
+pub use span::Span;
@@ -22,6 +22,11 @@ pub enum MoleculeError {
         ring: Vec<NodeIndex>,
         pi_electrons: u8,
     },
+
+    /// No alternating single/double bond assignment exists for these
+    /// aromatic atoms.
+    #[error("cannot kekulize aromatic atoms {atoms:?}")]
+    KekulizationFailed { atoms: Vec<NodeIndex> },
 }
 
 #[cfg(test)]
@@ -0,0 +1,111 @@
+//! Molfile and SDF reading errors.
+
+use thiserror::Error;
+
+use super::MoleculeError;
+
+/// Errors that can occur when reading a Molfile or an SDF record.
+///
+/// Line numbers start at 1; in an SDF file they count from the start of the
+/// file.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MolfileError {
+    /// The input ends before the connection table does.
+    #[error("unexpected end of input at line {0}")]
+    UnexpectedEnd(usize),
+
+    /// The counts line (or V3000 `COUNTS` line) cannot be read.
+    #[error("invalid counts line at line {0}")]
+    InvalidCounts(usize),
+
+    /// An atom line cannot be read.
+    #[error("invalid atom at line {0}")]
+    InvalidAtom(usize),
+
+    /// A bond line cannot be read.
+    #[error("invalid bond at line {0}")]
+    InvalidBond(usize),
+
+    /// A property line (`M  CHG`, `M  ISO`, ...) cannot be read.
+    #[error("invalid property at line {0}")]
+    InvalidProperty(usize),
+
+    /// The atom symbol is not an element.
+    #[error("unknown element '{1}' at line {0}")]
+    UnknownElement(usize, String),
+
+    /// A bond or property refers to an atom that does not exist.
+    #[error("no atom {1} for the reference at line {0}")]
+    UnknownAtom(usize, usize),
+
+    /// The atom cannot be represented (charge, isotope, hydrogen count or
+    /// atom map out of range).
+    #[error("unsupported atom at line {0}: {1}")]
+    UnsupportedAtom(usize, MoleculeError),
+
+    /// An SDF record starting at this line holds more bytes than allowed.
+    #[error("record at line {0} exceeds {1} bytes")]
+    RecordTooLarge(usize, usize),
+
+    /// Reading the input failed.
+    #[error("I/O error: {0}")]
+    Io(String),
+}
+
+impl MolfileError {
+    /// Returns the line the error was found on, if any.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            MolfileError::UnexpectedEnd(line)
+            | MolfileError::InvalidCounts(line)
+            | MolfileError::InvalidAtom(line)
+            | MolfileError::InvalidBond(line)
+            | MolfileError::InvalidProperty(line)
+            | MolfileError::UnknownElement(line, _)
+            | MolfileError::UnknownAtom(line, _)
+            | MolfileError::UnsupportedAtom(line, _)
+            | MolfileError::RecordTooLarge(line, _) => Some(*line),
+            MolfileError::Io(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AtomError;
+
+    #[test]
+    fn error_messages_are_descriptive() {
+        assert_eq!(
+            MolfileError::UnexpectedEnd(7).to_string(),
+            "unexpected end of input at line 7"
+        );
+        assert_eq!(
+            MolfileError::UnknownElement(5, "Xx".to_string()).to_string(),
+            "unknown element 'Xx' at line 5"
+        );
+        assert_eq!(
+            MolfileError::UnknownAtom(9, 12).to_string(),
+            "no atom 12 for the reference at line 9"
+        );
+        assert_eq!(
+            MolfileError::UnsupportedAtom(6, AtomError::InvalidCharge(20).into()).to_string(),
+            format!(
+                "unsupported atom at line 6: {}",
+                AtomError::InvalidCharge(20)
+            )
+        );
+        assert_eq!(
+            MolfileError::RecordTooLarge(4, 1024).to_string(),
+            "record at line 4 exceeds 1024 bytes"
+        );
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(MolfileError::InvalidBond(3).line(), Some(3));
+        assert_eq!(MolfileError::RecordTooLarge(4, 1024).line(), Some(4));
+        assert_eq!(MolfileError::Io("broken pipe".to_string()).line(), None);
+    }
+}
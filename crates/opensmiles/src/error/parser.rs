@@ -1,11 +1,17 @@
 //! SMILES parsing errors.
 
+use std::fmt;
+
 use thiserror::Error;
 
-use super::{BondError, MoleculeError, NodeError};
+use super::{BondError, MoleculeError, NodeError, Span};
 use crate::NodeIndex;
 
 /// Errors that can occur when parsing a SMILES string.
+///
+/// Every error raised by the parser carries the [`Span`] of the input it
+/// points at (see [`span`](Self::span)); ring errors carry the spans of both
+/// ring-bond digits. [`render`](Self::render) shows them under the input.
 #[derive(Debug, Clone, PartialEq, Error)]
 pub enum ParserError {
     /// Feature not yet implemented.
@@ -14,91 +20,315 @@ pub enum ParserError {
 
     /// The molecule contains too many nodes (maximum 4294967295).
     #[error("too many nodes in molecule (maximum 4294967295)")]
-    TooManyNodes,
+    TooManyNodes(Span),
 
     #[error("At least one node is necessary before creating a bond")]
-    NoAtomToBond,
+    NoAtomToBond(Span),
 
     /// Unexpected character in SMILES string.
-    #[error("unexpected character '{0}' at position {1}")]
-    UnexpectedCharacter(char, usize),
+    #[error("unexpected character '{0}' at position {}", .1.start)]
+    UnexpectedCharacter(char, Span),
 
     /// Unexpected end of input.
     #[error("unexpected end of input, expected: {0}")]
-    UnexpectedEndOfInput(String),
+    UnexpectedEndOfInput(String, Span),
 
     /// Bracket Atom must have an element
     #[error("brackets atom must have an element")]
-    MissingElementInBracketAtom,
+    MissingElementInBracketAtom(Span),
 
     #[error("charge in bracket atom must have a sign")]
-    ChargeWithoutSign,
+    ChargeWithoutSign(Span),
 
-    /// Missing closing parenthesis.
+    /// Missing closing parenthesis, at the opening one.
     #[error("missing closing parenthesis ')'")]
-    UnclosedParenthesis,
+    UnclosedParenthesis(Span),
 
     /// Missing opening parenthesis.
     #[error("missing opening parenthesis '('")]
-    UnopenedParenthesis,
+    UnopenedParenthesis(Span),
 
     /// Empty branch.
     #[error("empty branch detected")]
-    EmptyBranch,
+    EmptyBranch(Span),
 
-    /// Unclosed ring.
+    /// Unclosed rings, in the order they were opened, with the span of each
+    /// opening digit.
     #[error("unclosed ring(s): {0:?}")]
-    UnclosedRing(Vec<u8>),
+    UnclosedRing(Vec<u8>, Vec<Span>),
 
-    /// Mismatched bond types for ring closure.
+    /// Mismatched bond types for ring closure, at the opening and closing
+    /// bonds.
     #[error("mismatched bond types for ring {0}")]
-    MismatchedRingBond(u8),
+    MismatchedRingBond(u8, Span, Span),
 
     /// Bond without preceding atom.
     #[error("bond without preceding atom")]
-    BondWithoutPrecedingAtom,
+    BondWithoutPrecedingAtom(Span),
 
     /// Bond without following atom.
     #[error("bond without following atom")]
-    BondWithoutFollowingAtom,
+    BondWithoutFollowingAtom(Span),
+
+    /// Text after the SMILES while [titles are not
+    /// allowed](crate::ParseOptions::allow_title).
+    #[error("unexpected text after the SMILES")]
+    TitleNotAllowed(Span),
 
     /// Hydrogens having hydrogens count is illegal
     #[error("hydrogens cannot have hydrogens count")]
-    HydrogenWithHydrogenCount,
+    HydrogenWithHydrogenCount(Span),
 
     #[error("charge should be between -15 and +15 {0}")]
-    ChargeOutOfRange(String),
+    ChargeOutOfRange(String, Span),
 
     #[error("hydrogen cannot be greater than 9 {0}")]
-    HydrogenOutOfRange(String),
+    HydrogenOutOfRange(String, Span),
 
-    #[error("invalid chirality class: {0} at position {1}")]
-    InvalidChiralityClass(String, usize),
+    #[error("invalid chirality class: {0} at position {}", .1.start)]
+    InvalidChiralityClass(String, Span),
 
-    #[error("invalid chirality specification: {0} at position {1}")]
-    InvalidChiralitySpec(String, usize),
+    #[error("invalid chirality specification: {0} at position {}", .1.start)]
+    InvalidChiralitySpec(String, Span),
 
-    /// Atom bonded to itself (e.g., C11).
+    /// Atom bonded to itself (e.g., C11), at the opening and closing digits.
     #[error("atom cannot be bonded to itself (ring {0})")]
-    SelfBond(u8),
+    SelfBond(u8, Span, Span),
 
-    /// Duplicate bond between the same pair of atoms (e.g., C12CCCCC12).
+    /// Duplicate bond between the same pair of atoms (e.g., C12CCCCC12), at
+    /// the opening and closing digits of the ring bond.
     #[error("duplicate bond between atoms {0} and {1}")]
-    DuplicateBond(NodeIndex, NodeIndex),
+    DuplicateBond(NodeIndex, NodeIndex, Span, Span),
 
-    /// Error from molecule construction.
-    #[error(transparent)]
-    MoleculeError(#[from] MoleculeError),
+    /// A limit of the [parse options](crate::ParseOptions) was exceeded, at
+    /// the text that went over it.
+    #[error("{kind} limit of {limit} exceeded")]
+    LimitExceeded {
+        kind: LimitKind,
+        limit: usize,
+        span: Span,
+    },
+
+    /// Error from molecule construction, at the atoms involved.
+    #[error("{0}")]
+    MoleculeError(MoleculeError, Span),
 
     /// Error from a node.
-    #[error(transparent)]
-    NodeError(#[from] NodeError),
+    #[error("{0}")]
+    NodeError(NodeError, Span),
 
     /// Error from a bond.
-    #[error(transparent)]
-    BondError(#[from] BondError),
+    #[error("{0}")]
+    BondError(BondError, Span),
+}
+
+impl ParserError {
+    /// Returns the name of the variant, e.g. `"UnclosedRing"`, to group
+    /// errors by kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParserError::NotYetImplemented => "NotYetImplemented",
+            ParserError::TooManyNodes(_) => "TooManyNodes",
+            ParserError::NoAtomToBond(_) => "NoAtomToBond",
+            ParserError::UnexpectedCharacter(..) => "UnexpectedCharacter",
+            ParserError::UnexpectedEndOfInput(..) => "UnexpectedEndOfInput",
+            ParserError::MissingElementInBracketAtom(_) => "MissingElementInBracketAtom",
+            ParserError::ChargeWithoutSign(_) => "ChargeWithoutSign",
+            ParserError::UnclosedParenthesis(_) => "UnclosedParenthesis",
+            ParserError::UnopenedParenthesis(_) => "UnopenedParenthesis",
+            ParserError::EmptyBranch(_) => "EmptyBranch",
+            ParserError::UnclosedRing(..) => "UnclosedRing",
+            ParserError::MismatchedRingBond(..) => "MismatchedRingBond",
+            ParserError::BondWithoutPrecedingAtom(_) => "BondWithoutPrecedingAtom",
+            ParserError::BondWithoutFollowingAtom(_) => "BondWithoutFollowingAtom",
+            ParserError::TitleNotAllowed(_) => "TitleNotAllowed",
+            ParserError::HydrogenWithHydrogenCount(_) => "HydrogenWithHydrogenCount",
+            ParserError::ChargeOutOfRange(..) => "ChargeOutOfRange",
+            ParserError::HydrogenOutOfRange(..) => "HydrogenOutOfRange",
+            ParserError::InvalidChiralityClass(..) => "InvalidChiralityClass",
+            ParserError::InvalidChiralitySpec(..) => "InvalidChiralitySpec",
+            ParserError::SelfBond(..) => "SelfBond",
+            ParserError::DuplicateBond(..) => "DuplicateBond",
+            ParserError::LimitExceeded { .. } => "LimitExceeded",
+            ParserError::MoleculeError(..) => "MoleculeError",
+            ParserError::NodeError(..) => "NodeError",
+            ParserError::BondError(..) => "BondError",
+        }
+    }
+
+    /// Returns the span of the input the error points at: for ring errors,
+    /// the first ring-bond digit involved. Only
+    /// [`NotYetImplemented`](Self::NotYetImplemented) has none.
+    pub fn span(&self) -> Option<Span> {
+        self.labels().first().map(|(span, _)| *span)
+    }
+
+    /// Returns every span the error points at, with a hint for each.
+    fn labels(&self) -> Vec<(Span, String)> {
+        let label = |span: &Span, hint: &str| vec![(*span, hint.to_string())];
+        match self {
+            ParserError::NotYetImplemented => Vec::new(),
+            ParserError::UnexpectedCharacter(_, span) => label(span, "not valid here"),
+            ParserError::UnexpectedEndOfInput(expected, span) => {
+                label(span, &format!("expected {} here", expected))
+            }
+            ParserError::MissingElementInBracketAtom(span) => {
+                label(span, "no element in this bracket atom")
+            }
+            ParserError::ChargeWithoutSign(span) => label(span, "charge without sign"),
+            ParserError::UnclosedParenthesis(span) => {
+                label(span, "branch opened here, never closed")
+            }
+            ParserError::UnopenedParenthesis(span) => label(span, "no branch to close"),
+            ParserError::EmptyBranch(span) => label(span, "branch without atom"),
+            ParserError::UnclosedRing(rings, spans) => rings
+                .iter()
+                .zip(spans)
+                .map(|(ring, span)| (*span, format!("ring {} opened here, never closed", ring)))
+                .collect(),
+            ParserError::MismatchedRingBond(ring, open, close) => vec![
+                (*open, format!("ring {} opened with this bond", ring)),
+                (*close, "and closed with another one".to_string()),
+            ],
+            ParserError::SelfBond(ring, open, close) => vec![
+                (*open, format!("ring {} opened here", ring)),
+                (*close, "and closed on the same atom".to_string()),
+            ],
+            ParserError::DuplicateBond(_, _, open, close) => vec![
+                (*open, "ring opened here".to_string()),
+                (
+                    *close,
+                    "and closed between atoms already bonded".to_string(),
+                ),
+            ],
+            ParserError::BondWithoutPrecedingAtom(span) => label(span, "no atom before this bond"),
+            ParserError::BondWithoutFollowingAtom(span) => label(span, "no atom after this bond"),
+            ParserError::TitleNotAllowed(span) => label(span, "titles are not allowed"),
+            ParserError::HydrogenWithHydrogenCount(span) => {
+                label(span, "hydrogen with a hydrogen count")
+            }
+            ParserError::ChargeOutOfRange(_, span) => label(span, "charge out of range"),
+            ParserError::HydrogenOutOfRange(_, span) => label(span, "too many hydrogens"),
+            ParserError::InvalidChiralityClass(_, span)
+            | ParserError::InvalidChiralitySpec(_, span) => label(span, "unknown chirality"),
+            ParserError::LimitExceeded { kind, limit, span } => {
+                label(span, &format!("more than {} {}", limit, kind.plural()))
+            }
+            ParserError::TooManyNodes(span)
+            | ParserError::NoAtomToBond(span)
+            | ParserError::MoleculeError(_, span)
+            | ParserError::NodeError(_, span)
+            | ParserError::BondError(_, span) => label(span, ""),
+        }
+    }
+
+    /// Renders the error for display: the message, then the input with each
+    /// span underlined by carets and followed by a hint. Long inputs are
+    /// cut to the part around the spans.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    ///
+    /// let input = "C1CC(C2)C";
+    /// let error = parse(input).unwrap_err();
+    /// assert_eq!(
+    ///     error.render(input),
+    ///     "error: unclosed ring(s): [1, 2]\n\
+    ///      \x20 | C1CC(C2)C\n\
+    ///      \x20 |  ^ ring 1 opened here, never closed\n\
+    ///      \x20 |       ^ ring 2 opened here, never closed\n"
+    /// );
+    /// ```
+    pub fn render(&self, input: &str) -> String {
+        let mut text = format!("error: {}\n", self);
+        let labels = self.labels();
+        let (Some(first), Some(last)) = (
+            labels.iter().map(|(span, _)| span.start).min(),
+            labels.iter().map(|(span, _)| span.end).max(),
+        ) else {
+            return text;
+        };
+
+        let mut from = first.min(input.len()).saturating_sub(CONTEXT);
+        while !input.is_char_boundary(from) {
+            from -= 1;
+        }
+        let mut to = (last + CONTEXT).min(input.len());
+        while !input.is_char_boundary(to) {
+            to += 1;
+        }
+        let shown = &input[from..to];
+        let shown = match shown.find(['\n', '\r']) {
+            Some(line_end) if from + line_end >= last => &shown[..line_end],
+            _ => shown,
+        };
+        let prefix = if from > 0 { "..." } else { "" };
+        let suffix = if from + shown.len() < input.len() {
+            "..."
+        } else {
+            ""
+        };
+        text.push_str(&format!("  | {}{}{}\n", prefix, shown, suffix));
+
+        let column = |offset: usize| {
+            let offset = offset.clamp(from, input.len());
+            prefix.len() + input.get(from..offset).map_or(0, |s| s.chars().count())
+        };
+        for (span, hint) in labels {
+            let start = column(span.start);
+            let width = (column(span.end) - start).max(1);
+            let line = format!("{}{} {}", " ".repeat(start), "^".repeat(width), hint);
+            text.push_str(&format!("  | {}\n", line.trim_end()));
+        }
+        text
+    }
+}
+
+/// A resource limit of the [parse options](crate::ParseOptions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// [`max_input_length`](crate::ParseOptions::max_input_length), in bytes.
+    InputLength,
+    /// [`max_atoms`](crate::ParseOptions::max_atoms).
+    Atoms,
+    /// [`max_bonds`](crate::ParseOptions::max_bonds).
+    Bonds,
+    /// [`max_branch_depth`](crate::ParseOptions::max_branch_depth).
+    BranchDepth,
+    /// [`max_open_rings`](crate::ParseOptions::max_open_rings).
+    OpenRings,
 }
 
+impl LimitKind {
+    /// Returns what the limit counts, e.g. `"atoms"`.
+    fn plural(self) -> &'static str {
+        match self {
+            LimitKind::InputLength => "bytes",
+            LimitKind::Atoms => "atoms",
+            LimitKind::Bonds => "bonds",
+            LimitKind::BranchDepth => "nested branches",
+            LimitKind::OpenRings => "open rings",
+        }
+    }
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LimitKind::InputLength => "input length",
+            LimitKind::Atoms => "atom",
+            LimitKind::Bonds => "bond",
+            LimitKind::BranchDepth => "branch depth",
+            LimitKind::OpenRings => "open ring",
+        })
+    }
+}
+
+/// Bytes of input shown around the spans of a rendered error.
+const CONTEXT: usize = 40;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,69 +341,140 @@ mod tests {
         );
 
         assert_eq!(
-            ParserError::TooManyNodes.to_string(),
+            ParserError::TooManyNodes(Span::at(0)).to_string(),
             "too many nodes in molecule (maximum 4294967295)"
         );
 
         assert_eq!(
-            ParserError::UnexpectedCharacter('$', 5).to_string(),
+            ParserError::UnexpectedCharacter('$', Span::new(5, 6)).to_string(),
             "unexpected character '$' at position 5"
         );
 
         assert_eq!(
-            ParserError::UnexpectedEndOfInput("element".to_string()).to_string(),
+            ParserError::UnexpectedEndOfInput("element".to_string(), Span::at(3)).to_string(),
             "unexpected end of input, expected: element"
         );
 
         assert_eq!(
-            ParserError::UnclosedParenthesis.to_string(),
+            ParserError::UnclosedParenthesis(Span::new(1, 2)).to_string(),
             "missing closing parenthesis ')'"
         );
 
         assert_eq!(
-            ParserError::UnclosedRing(vec!(1, 2, 5)).to_string(),
+            ParserError::UnclosedRing(vec![1, 2, 5], vec![Span::at(0); 3]).to_string(),
             "unclosed ring(s): [1, 2, 5]"
         );
 
         assert_eq!(
-            ParserError::MismatchedRingBond(2).to_string(),
+            ParserError::MismatchedRingBond(2, Span::new(1, 3), Span::new(7, 9)).to_string(),
             "mismatched bond types for ring 2"
         );
 
         assert_eq!(
-            ParserError::BondWithoutPrecedingAtom.to_string(),
+            ParserError::BondWithoutPrecedingAtom(Span::new(0, 1)).to_string(),
             "bond without preceding atom"
         );
 
         assert_eq!(
-            ParserError::BondWithoutFollowingAtom.to_string(),
+            ParserError::BondWithoutFollowingAtom(Span::new(1, 2)).to_string(),
             "bond without following atom"
         );
+
+        assert_eq!(
+            ParserError::TitleNotAllowed(Span::new(4, 11)).to_string(),
+            "unexpected text after the SMILES"
+        );
+
+        assert_eq!(
+            ParserError::LimitExceeded {
+                kind: LimitKind::BranchDepth,
+                limit: 64,
+                span: Span::new(70, 71),
+            }
+            .to_string(),
+            "branch depth limit of 64 exceeded"
+        );
     }
 
     #[test]
-    fn molecule_error_conversion() {
-        let mol_err = MoleculeError::NodeError(super::super::NodeError::UndefinedHydrogen);
-        let parser_err: ParserError = mol_err.into();
-
-        assert!(matches!(parser_err, ParserError::MoleculeError(_)));
+    fn kinds_name_the_variant() {
+        assert_eq!(
+            ParserError::UnclosedRing(vec![1], vec![Span::new(1, 2)]).kind(),
+            "UnclosedRing"
+        );
+        assert_eq!(
+            ParserError::UnexpectedCharacter('$', Span::new(5, 6)).kind(),
+            "UnexpectedCharacter"
+        );
+        assert_eq!(
+            ParserError::NodeError(NodeError::InvalidHydrogen(99), Span::at(0)).kind(),
+            "NodeError"
+        );
     }
 
     #[test]
-    fn node_error_conversion() {
-        let node_err = super::super::NodeError::InvalidHydrogen(99);
-        let parser_err: ParserError = node_err.into();
+    fn wrapped_errors_keep_their_message() {
+        let mol_err = MoleculeError::NodeError(NodeError::UndefinedHydrogen);
+        let parser_err = ParserError::MoleculeError(mol_err, Span::new(0, 1));
+        assert_eq!(parser_err.to_string(), "undefined hydrogen count");
 
-        assert!(matches!(parser_err, ParserError::NodeError(_)));
+        let parser_err = ParserError::NodeError(NodeError::InvalidHydrogen(99), Span::new(0, 7));
         assert_eq!(parser_err.to_string(), "invalid hydrogen count: 99");
+        assert_eq!(parser_err.span(), Some(Span::new(0, 7)));
+
+        let parser_err = ParserError::BondError(BondError::UnknownBond('c'), Span::new(1, 2));
+        assert_eq!(parser_err.to_string(), "unknown bond: 'c'");
     }
 
     #[test]
-    fn bond_error_conversion() {
-        let bond_err = super::super::BondError::UnknownBond('c');
-        let parser_err: ParserError = bond_err.into();
+    fn spans_of_ring_errors() {
+        let error = ParserError::MismatchedRingBond(1, Span::new(1, 3), Span::new(7, 9));
+        assert_eq!(error.span(), Some(Span::new(1, 3)));
+        assert_eq!(ParserError::NotYetImplemented.span(), None);
+    }
 
-        assert!(matches!(parser_err, ParserError::BondError(_)));
-        assert_eq!(parser_err.to_string(), "unknown bond: 'c'");
+    #[test]
+    fn rendering() {
+        let error = ParserError::MismatchedRingBond(1, Span::new(1, 3), Span::new(7, 9));
+        assert_eq!(
+            error.render("C=1CCCC-1"),
+            "error: mismatched bond types for ring 1\n  | C=1CCCC-1\n  |  ^^ ring 1 opened with this bond\n  |        ^^ and closed with another one\n"
+        );
+        let error = ParserError::BondWithoutFollowingAtom(Span::new(2, 3));
+        assert_eq!(
+            error.render("CC= ethene"),
+            "error: bond without following atom\n  | CC= ethene\n  |   ^ no atom after this bond\n"
+        );
+        let error = ParserError::UnexpectedEndOfInput("]".to_string(), Span::at(4));
+        assert!(error
+            .render("[NH4")
+            .ends_with("  | [NH4\n  |     ^ expected ] here\n"));
+        assert_eq!(
+            ParserError::NotYetImplemented.render("C"),
+            "error: feature not yet implemented\n"
+        );
+    }
+
+    #[test]
+    fn rendering_cuts_long_inputs() {
+        let input = format!("{}(C{}", "C".repeat(100), "C".repeat(100));
+        let error = ParserError::UnclosedParenthesis(Span::new(100, 101));
+        let rendered = error.render(&input);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(
+            lines[1],
+            format!("  | ...{}(C{}...", "C".repeat(40), "C".repeat(39))
+        );
+        assert_eq!(
+            lines[2],
+            format!("  | {}^ branch opened here, never closed", " ".repeat(43))
+        );
+
+        // Columns count characters, not bytes.
+        let error = ParserError::UnexpectedCharacter('x', Span::new(3, 4));
+        assert_eq!(
+            error.render("é?x").lines().nth(2),
+            Some("  |   ^ not valid here")
+        );
     }
 }
@@ -0,0 +1,95 @@
+//! SMARTS parsing errors.
+
+use thiserror::Error;
+
+use super::ParserError;
+
+/// Errors that can occur when parsing a SMARTS pattern.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SmartsError {
+    /// The pattern contains no atom.
+    #[error("empty SMARTS pattern")]
+    Empty,
+
+    /// Unexpected character in the pattern.
+    #[error("unexpected character '{0}' at position {1}")]
+    UnexpectedCharacter(char, usize),
+
+    /// Unexpected end of input.
+    #[error("unexpected end of input, expected: {0}")]
+    UnexpectedEndOfInput(String),
+
+    /// An operator is missing an operand (e.g. `[C,]` or `[!]`).
+    #[error("missing primitive at position {0}")]
+    MissingPrimitive(usize),
+
+    /// A primitive count does not fit (e.g. `[X999]`).
+    #[error("number out of range: {0}")]
+    NumberOutOfRange(String),
+
+    /// Missing closing parenthesis.
+    #[error("missing closing parenthesis ')'")]
+    UnclosedParenthesis,
+
+    /// Missing opening parenthesis.
+    #[error("missing opening parenthesis '('")]
+    UnopenedParenthesis,
+
+    /// Empty branch.
+    #[error("empty branch detected")]
+    EmptyBranch,
+
+    /// Unclosed ring.
+    #[error("unclosed ring(s): {0:?}")]
+    UnclosedRing(Vec<u8>),
+
+    /// Both ends of a ring closure carry a different bond expression.
+    #[error("mismatched bond expressions for ring {0}")]
+    MismatchedRingBond(u8),
+
+    /// Atom bonded to itself (e.g., `C11`).
+    #[error("atom cannot be bonded to itself (ring {0})")]
+    SelfBond(u8),
+
+    /// Bond without preceding atom.
+    #[error("bond without preceding atom")]
+    BondWithoutPrecedingAtom,
+
+    /// Bond without following atom.
+    #[error("bond without following atom")]
+    BondWithoutFollowingAtom,
+
+    /// Error from the parts shared with the SMILES lexer (element symbols,
+    /// charges, chirality).
+    #[error(transparent)]
+    ParserError(#[from] ParserError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages_are_descriptive() {
+        assert_eq!(SmartsError::Empty.to_string(), "empty SMARTS pattern");
+        assert_eq!(
+            SmartsError::UnexpectedCharacter('?', 3).to_string(),
+            "unexpected character '?' at position 3"
+        );
+        assert_eq!(
+            SmartsError::MissingPrimitive(4).to_string(),
+            "missing primitive at position 4"
+        );
+        assert_eq!(
+            SmartsError::MismatchedRingBond(1).to_string(),
+            "mismatched bond expressions for ring 1"
+        );
+    }
+
+    #[test]
+    fn parser_error_conversion() {
+        let err: SmartsError = ParserError::ChargeWithoutSign(crate::Span::new(2, 3)).into();
+        assert!(matches!(err, SmartsError::ParserError(_)));
+        assert_eq!(err.to_string(), "charge in bracket atom must have a sign");
+    }
+}
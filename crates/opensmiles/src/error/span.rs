@@ -0,0 +1,77 @@
+//! Locations in parsed text.
+
+use std::fmt;
+use std::ops::Range;
+
+/// A byte range of the parsed text, `start` included and `end` excluded.
+///
+/// Spans count bytes, so `&input[span.start..span.end]` is the text an
+/// error points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    /// Offset of the first byte.
+    pub start: usize,
+    /// Offset just past the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a span from `start` to `end`.
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Creates an empty span at `offset`, such as the end of the input.
+    pub fn at(offset: usize) -> Span {
+        Span::new(offset, offset)
+    }
+
+    /// Returns the number of bytes covered.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the span covers no byte.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the smallest span covering both spans.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns the span moved `offset` bytes further.
+    pub fn shifted(self, offset: usize) -> Span {
+        Span::new(self.start + offset, self.end + offset)
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Range<usize> {
+        span.start..span.end
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans() {
+        let span = Span::new(2, 4);
+        assert_eq!(span.len(), 2);
+        assert!(!span.is_empty());
+        assert!(Span::at(3).is_empty());
+        assert_eq!(span.to(Span::new(7, 8)), Span::new(2, 8));
+        assert_eq!(span.shifted(10), Span::new(12, 14));
+        assert_eq!(&"C[NH4+]"[Range::from(Span::new(1, 7))], "[NH4+]");
+        assert_eq!(span.to_string(), "2..4");
+    }
+}
@@ -0,0 +1,306 @@
+//! Character-level lexing shared by the SMILES and SMARTS parsers.
+//!
+//! Both languages write elements, isotopes, charges, hydrogen counts,
+//! chirality and atom classes the same way inside brackets. The [`Lexer`]
+//! trait provides those pieces on top of a character cursor.
+
+use std::str::FromStr;
+
+use crate::error::{ParserError, Span};
+use crate::{AtomSymbol, Chirality, NodeError, OrganicAtom};
+
+/// A character cursor with the bracket-atom lexing rules of OpenSMILES.
+pub(crate) trait Lexer {
+    /// Consumes and returns the next character.
+    fn next(&mut self) -> Option<char>;
+
+    /// Returns the next character without consuming it.
+    fn peek(&mut self) -> Option<&char>;
+
+    /// Returns the number of bytes consumed so far.
+    fn position(&self) -> usize;
+
+    /// Returns the span from `start` to the current position.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.position())
+    }
+
+    /// Parse element symbol. `in_bracket` controls whether all two-letter elements
+    /// are allowed (true) or only organic subset Cl/Br (false).
+    ///
+    /// Returns the parsed `AtomSymbol` directly, avoiding intermediate String allocations.
+    fn parse_element_symbol(
+        &mut self,
+        c: char,
+        in_bracket: bool,
+    ) -> Result<AtomSymbol, ParserError> {
+        if c == '*' {
+            return Ok(AtomSymbol::Wildcard);
+        }
+
+        if c.is_ascii_uppercase() {
+            if let Some(&next_c) = self.peek() {
+                if next_c.is_ascii_lowercase() {
+                    if in_bracket {
+                        // In brackets, all valid two-letter elements are allowed
+                        let buf = [c as u8, next_c as u8];
+                        let two_letter = std::str::from_utf8(&buf).unwrap();
+                        if let Ok(sym) = AtomSymbol::from_str(two_letter) {
+                            self.next();
+                            return Ok(sym);
+                        }
+                    } else {
+                        // Outside brackets, only Cl and Br are valid two-letter elements
+                        match (c, next_c) {
+                            ('C', 'l') => {
+                                self.next();
+                                return Ok(AtomSymbol::Organic(OrganicAtom::Cl));
+                            }
+                            ('B', 'r') => {
+                                self.next();
+                                return Ok(AtomSymbol::Organic(OrganicAtom::Br));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        } else if in_bracket && c.is_ascii_lowercase() {
+            // Aromatic two-letter symbols: se, as, te (OpenSMILES spec)
+            if let Some(&next_c) = self.peek() {
+                if next_c.is_ascii_lowercase() {
+                    let buf = [c as u8, next_c as u8];
+                    let two_letter = std::str::from_utf8(&buf).unwrap();
+                    if let Ok(sym) = AtomSymbol::from_str(two_letter) {
+                        self.next();
+                        return Ok(sym);
+                    }
+                }
+            }
+        }
+
+        // Single character element
+        let buf = [c.to_ascii_uppercase() as u8];
+        let s = std::str::from_utf8(&buf).unwrap();
+        AtomSymbol::from_str(s).map_err(|e| {
+            let span = self.span_from(self.position() - c.len_utf8());
+            ParserError::NodeError(NodeError::AtomError(e), span)
+        })
+    }
+
+    fn parse_isotope(&mut self) -> Option<u16> {
+        let mut builder = String::new();
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            builder.push(self.next().unwrap());
+        }
+        builder.parse::<u16>().ok()
+    }
+
+    fn parse_class(&mut self) -> Option<u16> {
+        if self.peek().is_some_and(|c| *c == ':') {
+            self.next();
+            let mut builder = String::new();
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                builder.push(self.next().unwrap());
+            }
+            builder.parse::<u16>().ok()
+        } else {
+            None
+        }
+    }
+
+    fn parse_chirality(&mut self) -> Result<Option<Chirality>, ParserError> {
+        if self.peek() != Some(&'@') {
+            return Ok(None);
+        }
+        let start = self.position();
+        self.next(); // consume first '@'
+
+        match self.peek() {
+            Some(&'@') => {
+                self.next();
+                Ok(Some(Chirality::TH2))
+            }
+            Some(&'T') => {
+                self.next();
+                match self.next() {
+                    Some('H') => self.parse_chirality_index(start, 1, 2, |n| match n {
+                        1 => Some(Chirality::TH1),
+                        2 => Some(Chirality::TH2),
+                        _ => None,
+                    }),
+                    Some('B') => {
+                        self.parse_chirality_index(start, 1, 20, |n| Chirality::tb(n as u8))
+                    }
+                    Some(c) => Err(ParserError::InvalidChiralitySpec(
+                        format!("@T{}", c),
+                        self.span_from(start),
+                    )),
+                    None => Err(ParserError::UnexpectedEndOfInput(
+                        "chirality class".to_string(),
+                        self.span_from(self.position()),
+                    )),
+                }
+            }
+            Some(&'A') => {
+                self.next();
+                match self.next() {
+                    Some('L') => self.parse_chirality_index(start, 1, 2, |n| match n {
+                        1 => Some(Chirality::AL1),
+                        2 => Some(Chirality::AL2),
+                        _ => None,
+                    }),
+                    Some(c) => Err(ParserError::InvalidChiralitySpec(
+                        format!("@A{}", c),
+                        self.span_from(start),
+                    )),
+                    None => Err(ParserError::UnexpectedEndOfInput(
+                        "chirality class".to_string(),
+                        self.span_from(self.position()),
+                    )),
+                }
+            }
+            Some(&'S') => {
+                self.next();
+                match self.next() {
+                    Some('P') => self.parse_chirality_index(start, 1, 3, |n| match n {
+                        1 => Some(Chirality::SP1),
+                        2 => Some(Chirality::SP2),
+                        3 => Some(Chirality::SP3),
+                        _ => None,
+                    }),
+                    Some(c) => Err(ParserError::InvalidChiralitySpec(
+                        format!("@S{}", c),
+                        self.span_from(start),
+                    )),
+                    None => Err(ParserError::UnexpectedEndOfInput(
+                        "chirality class".to_string(),
+                        self.span_from(self.position()),
+                    )),
+                }
+            }
+            Some(&'O') => {
+                self.next();
+                match self.next() {
+                    Some('H') => {
+                        self.parse_chirality_index(start, 1, 30, |n| Chirality::oh(n as u8))
+                    }
+                    Some(c) => Err(ParserError::InvalidChiralitySpec(
+                        format!("@O{}", c),
+                        self.span_from(start),
+                    )),
+                    None => Err(ParserError::UnexpectedEndOfInput(
+                        "chirality class".to_string(),
+                        self.span_from(self.position()),
+                    )),
+                }
+            }
+            _ => Ok(Some(Chirality::TH1)),
+        }
+    }
+
+    /// Parse a chirality index (1 or 2 digit number) and map it via `f`.
+    /// Returns an error if the number is outside `[min, max]` or if `f` returns None.
+    /// `start` is the position of the `@`.
+    fn parse_chirality_index(
+        &mut self,
+        start: usize,
+        min: u32,
+        max: u32,
+        f: impl FnOnce(u32) -> Option<Chirality>,
+    ) -> Result<Option<Chirality>, ParserError> {
+        let first = self.next().ok_or_else(|| {
+            ParserError::UnexpectedEndOfInput(
+                "chirality index".to_string(),
+                self.span_from(self.position()),
+            )
+        })?;
+        let first_digit = first.to_digit(10).ok_or_else(|| {
+            ParserError::InvalidChiralityClass(first.to_string(), self.span_from(start))
+        })?;
+
+        let n = if let Some(&next_c) = self.peek() {
+            if let Some(second_digit) = next_c.to_digit(10) {
+                self.next();
+                first_digit * 10 + second_digit
+            } else {
+                first_digit
+            }
+        } else {
+            first_digit
+        };
+
+        if n < min || n > max {
+            return Err(ParserError::InvalidChiralityClass(
+                n.to_string(),
+                self.span_from(start),
+            ));
+        }
+
+        f(n).map(Some)
+            .ok_or_else(|| ParserError::InvalidChiralityClass(n.to_string(), self.span_from(start)))
+    }
+
+    fn parse_hydrogen(&mut self) -> Result<Option<u8>, ParserError> {
+        let start = self.position();
+        match self.peek() {
+            None => Err(ParserError::UnexpectedEndOfInput(
+                "]".to_string(),
+                self.span_from(start),
+            )),
+            Some(&'H') => {
+                self.next();
+                let mut builder = String::new();
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    builder.push(self.next().unwrap());
+                }
+                if builder.is_empty() {
+                    Ok(Some(1))
+                } else {
+                    let span = self.span_from(start);
+                    Ok(Some(builder.parse::<u8>().map_err(|_| {
+                        ParserError::HydrogenOutOfRange(builder, span)
+                    })?))
+                }
+            }
+            _ => Ok(Some(0)),
+        }
+    }
+
+    fn parse_charge(&mut self) -> Result<i8, ParserError> {
+        let start = self.position();
+        let mut charge: i8 = 0;
+        let mut builder = String::new();
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || *c == '+' || *c == '-')
+        {
+            match self.next() {
+                Some('+') => charge += 1,
+                Some('-') => charge -= 1,
+                Some(c) if c.is_ascii_digit() => {
+                    builder.push(c);
+                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        builder.push(self.next().unwrap());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let span = self.span_from(start);
+        if builder.is_empty() {
+            Ok(charge)
+        } else if charge > 0 {
+            builder
+                .parse::<i8>()
+                .map_err(|_| ParserError::ChargeOutOfRange(builder, span))
+        } else if charge < 0 {
+            Ok(0 - builder
+                .parse::<i8>()
+                .map_err(|_| ParserError::ChargeOutOfRange(builder, span))?)
+        } else {
+            Err(ParserError::ChargeWithoutSign(span))
+        }
+    }
+}
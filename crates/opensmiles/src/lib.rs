@@ -39,9 +39,15 @@ pub type NodeIndex = u32;
 
 pub mod ast;
 mod error;
+mod lexer;
+pub mod molfile;
 pub mod parser;
 #[cfg(feature = "parallel")]
 pub mod parser_parallel;
+pub mod repair;
+mod sha256;
+pub mod smarts;
+pub mod smi;
 
 // Re-export public API
 pub use ast::*;
@@ -49,3 +55,4 @@ pub use error::*;
 pub use parser::*;
 #[cfg(feature = "parallel")]
 pub use parser_parallel::*;
+pub use repair::{parse_lenient, Repair, RepairKind};
@@ -0,0 +1,31 @@
+//! MDL Molfile connection tables and SD files.
+//!
+//! [`Molecule::to_molfile`](crate::Molecule::to_molfile) writes a molecule as
+//! a V2000 or V3000 Molfile: atom and bond blocks, charges, isotopes, atom
+//! classes as atom-atom mapping numbers and tetrahedral parities. SMILES
+//! carries no coordinates, so every atom sits at the origin unless
+//! [`MolfileOptions::coordinates`] supplies them.
+//!
+//! [`parse_molfile`] reads either version back, deriving hydrogen counts and
+//! stereo, and [`SdfReader`] streams the records of an SD file with their
+//! data items.
+//!
+//! # Example
+//!
+//! ```
+//! use opensmiles::molfile::MolfileVersion;
+//! use opensmiles::parse;
+//!
+//! let molfile = parse("CC(=O)[O-]").unwrap().to_molfile(MolfileVersion::V2000);
+//! assert!(molfile.contains("  4  3  0  0  0  0  0  0  0  0999 V2000"));
+//! assert!(molfile.contains("M  CHG  1   4  -1"));
+//! assert!(molfile.ends_with("M  END\n"));
+//! ```
+
+mod reader;
+mod sdf;
+mod writer;
+
+pub use self::reader::parse_molfile;
+pub use self::sdf::{SdfReader, SdfReaderOptions, SdfRecord};
+pub use self::writer::{MolfileOptions, MolfileVersion};
@@ -0,0 +1,1035 @@
+//! Molfile reader.
+//!
+//! Both versions are first read into a [`Table`] of atoms and bonds as
+//! written, then turned into a [`Molecule`]:
+//!
+//! - hydrogen counts follow the MDL valence model, corrected by radicals and
+//!   by the valence field when present;
+//! - bonds of type 4 become aromatic bonds between aromatic atoms;
+//! - tetrahedral centres take their configuration from 3D coordinates, from
+//!   wedge bonds in 2D, or else from the atom parity;
+//! - double bonds take `/`/`\` marks from their coordinates.
+//!
+//! Stereo is then kept only where the canonical ranking sees a
+//! stereocentre or a stereo double bond, or on ring centres with a relative
+//! configuration, so that a wedge drawn on a `CH2` or the geometry of a
+//! `C=CH2` leaves no mark.
+
+use std::collections::HashMap;
+
+use crate::ast::canonical::{canonical_ranking, Ranking};
+use crate::ast::stereo;
+use crate::{
+    AtomSymbol, Bond, BondType, Chirality, Molecule, MoleculeBuilder, MolfileError, NodeError,
+    NodeIndex, OrganicAtom,
+};
+
+/// Atom as read from an atom block.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TableAtom {
+    pub(super) line: usize,
+    pub(super) element: AtomSymbol,
+    pub(super) isotope: Option<u16>,
+    pub(super) charge: i8,
+    /// 0 none, 1 singlet, 2 doublet, 3 triplet.
+    pub(super) radical: u8,
+    /// Total valence from the valence field.
+    pub(super) valence: Option<u8>,
+    pub(super) class: Option<u16>,
+    /// MDL parity: 1 clockwise, 2 anticlockwise, 0 or 3 none.
+    pub(super) parity: u8,
+    pub(super) position: [f64; 3],
+}
+
+/// Bond as read from a bond block, numbered from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TableBond {
+    pub(super) line: usize,
+    pub(super) source: NodeIndex,
+    pub(super) target: NodeIndex,
+    pub(super) kind: BondType,
+    /// V2000 bond stereo: 1 wedge, 6 hash, 4 either for single bonds; 3
+    /// either for double bonds.
+    pub(super) stereo: u8,
+}
+
+/// Connection table as written, before hydrogens and stereo are derived.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Table {
+    pub(super) atoms: Vec<TableAtom>,
+    pub(super) bonds: Vec<TableBond>,
+}
+
+/// Reads a V2000 or V3000 Molfile into a [`Molecule`].
+///
+/// Atoms keep the order of the atom block and bonds that of the bond block.
+/// Charges and isotopes come from the atom block or from `M  CHG`,
+/// `M  ISO` lines (`CHG=` and `MASS=` in V3000), radicals (`M  RAD`,
+/// `RAD=`) remove hydrogens, and atom-atom mapping numbers become atom
+/// classes. `D` and `T` read as `[2H]` and `[3H]`; query atoms (`A`, `Q`,
+/// `L`, `R#`, `*`) read as wildcards.
+///
+/// Molecules have no query bonds, so bond types 5 to 8 read as the closest
+/// bond: "single or double" and "any" as single, "single or aromatic" and
+/// "double or aromatic" as aromatic between atoms that can be aromatic.
+///
+/// Stereo comes from 3D coordinates, wedge and hash bonds in 2D, or the
+/// atom parity when the coordinates say nothing; double bonds outside small
+/// rings get `/` and `\` from their coordinates unless marked "either".
+///
+/// # Errors
+///
+/// Returns a [`MolfileError`] with the offending line when the connection
+/// table cannot be read.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::molfile::{parse_molfile, MolfileVersion};
+/// use opensmiles::parse;
+///
+/// let alanine = parse("N[C@@H](C)C(=O)O").unwrap();
+/// let molfile = alanine.to_molfile(MolfileVersion::V2000);
+/// let read = parse_molfile(&molfile).unwrap();
+/// assert!(read.is_isomorphic_with_stereo(&alanine));
+/// ```
+pub fn parse_molfile(text: &str) -> Result<Molecule, MolfileError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (table, _) = read_table(&lines, 1)?;
+    table.to_molecule()
+}
+
+/// Reads the connection table starting at the header of `lines`, whose
+/// first line is line `first` of the input. Returns the table and the index
+/// of the line after `M  END` (or the end of `lines` without one).
+pub(super) fn read_table(lines: &[&str], first: usize) -> Result<(Table, usize), MolfileError> {
+    let counts = *lines
+        .get(3)
+        .ok_or(MolfileError::UnexpectedEnd(first + lines.len()))?;
+    if counts.contains("V3000") {
+        read_v3000(lines, first)
+    } else {
+        read_v2000(lines, first)
+    }
+}
+
+/// Returns the trimmed columns `range` of a fixed-column line, empty past
+/// its end.
+fn columns(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len()))
+        .or_else(|| line.get(start..))
+        .unwrap_or("")
+        .trim()
+}
+
+/// Reads an integer field, empty meaning zero.
+fn number<T: std::str::FromStr + Default>(field: &str) -> Option<T> {
+    if field.is_empty() {
+        Some(T::default())
+    } else {
+        field.parse().ok()
+    }
+}
+
+/// Reads an atom symbol into an element and the isotope it implies.
+fn element(symbol: &str, line: usize) -> Result<(AtomSymbol, Option<u16>), MolfileError> {
+    match symbol {
+        "D" => Ok((AtomSymbol::H, Some(2))),
+        "T" => Ok((AtomSymbol::H, Some(3))),
+        "A" | "Q" | "L" | "R" | "R#" | "*" | "LP" => Ok((AtomSymbol::Wildcard, None)),
+        _ => symbol
+            .parse()
+            .map(|element| (element, None))
+            .map_err(|_| MolfileError::UnknownElement(line, symbol.to_string())),
+    }
+}
+
+/// Converts a Molfile bond type.
+fn bond_kind(code: u8) -> Option<BondType> {
+    match code {
+        1 | 5 | 8 => Some(BondType::Simple),
+        2 => Some(BondType::Double),
+        3 => Some(BondType::Triple),
+        4 | 6 | 7 => Some(BondType::Aromatic),
+        _ => None,
+    }
+}
+
+fn read_v2000(lines: &[&str], first: usize) -> Result<(Table, usize), MolfileError> {
+    let line_no = |i: usize| first + i;
+    let counts = lines[3];
+    let (Some(n_atoms), Some(n_bonds)) = (
+        number::<usize>(columns(counts, 0, 3)),
+        number::<usize>(columns(counts, 3, 6)),
+    ) else {
+        return Err(MolfileError::InvalidCounts(line_no(3)));
+    };
+    let n_lists = number::<usize>(columns(counts, 6, 9)).unwrap_or(0);
+
+    let mut atoms = Vec::with_capacity(n_atoms);
+    for i in 4..4 + n_atoms {
+        let line = *lines
+            .get(i)
+            .ok_or(MolfileError::UnexpectedEnd(line_no(i)))?;
+        let invalid = || MolfileError::InvalidAtom(line_no(i));
+        let coordinate = |start: usize| columns(line, start, start + 10).parse::<f64>();
+        let (Ok(x), Ok(y), Ok(z)) = (coordinate(0), coordinate(10), coordinate(20)) else {
+            return Err(invalid());
+        };
+        let (element, mut isotope) = element(columns(line, 31, 34), line_no(i))?;
+        let mass_difference: i32 = number(columns(line, 34, 36)).ok_or_else(invalid)?;
+        if mass_difference != 0 {
+            let mass = element.standard_mass().round() as i32 + mass_difference;
+            isotope = Some(u16::try_from(mass).map_err(|_| invalid())?);
+        }
+        let (charge, radical) = match number::<u8>(columns(line, 36, 39)).ok_or_else(invalid)? {
+            0 => (0, 0),
+            4 => (0, 2),
+            code @ 1..=7 => (4 - code as i8, 0),
+            _ => return Err(invalid()),
+        };
+        let parity = number(columns(line, 39, 42)).ok_or_else(invalid)?;
+        let valence = match number::<u8>(columns(line, 48, 51)).ok_or_else(invalid)? {
+            0 => None,
+            15 => Some(0),
+            v => Some(v),
+        };
+        let class = match number::<u16>(columns(line, 60, 63)).ok_or_else(invalid)? {
+            0 => None,
+            class => Some(class),
+        };
+        atoms.push(TableAtom {
+            line: line_no(i),
+            element,
+            isotope,
+            charge,
+            radical,
+            valence,
+            class,
+            parity,
+            position: [x, y, z],
+        });
+    }
+
+    let mut bonds = Vec::with_capacity(n_bonds);
+    for i in 4 + n_atoms..4 + n_atoms + n_bonds {
+        let line = *lines
+            .get(i)
+            .ok_or(MolfileError::UnexpectedEnd(line_no(i)))?;
+        let invalid = || MolfileError::InvalidBond(line_no(i));
+        let field = |start: usize| number::<usize>(columns(line, start, start + 3));
+        let (Some(a), Some(b), Some(code), Some(stereo)) = (field(0), field(3), field(6), field(9))
+        else {
+            return Err(invalid());
+        };
+        let kind = u8::try_from(code)
+            .ok()
+            .and_then(bond_kind)
+            .ok_or_else(invalid)?;
+        bonds.push(TableBond {
+            line: line_no(i),
+            source: atom_index(a, n_atoms, line_no(i))?,
+            target: atom_index(b, n_atoms, line_no(i))?,
+            kind,
+            stereo: stereo as u8,
+        });
+        if a == b {
+            return Err(invalid());
+        }
+    }
+
+    let mut table = Table { atoms, bonds };
+    let mut i = 4 + n_atoms + n_bonds + n_lists;
+    let mut reset = false;
+    while i < lines.len() {
+        let line = lines[i];
+        let tag = line.get(..6).unwrap_or(line);
+        i += 1;
+        match tag {
+            "M  END" => return Ok((table, i)),
+            "M  CHG" | "M  RAD" | "M  ISO" => {
+                let entries = property_entries(line, line_no(i - 1))?;
+                if tag != "M  ISO" && !reset {
+                    // Any charge or radical line replaces the atom block
+                    // values.
+                    for atom in &mut table.atoms {
+                        atom.charge = 0;
+                        atom.radical = 0;
+                    }
+                    reset = true;
+                }
+                for (atom, value) in entries {
+                    let invalid = || MolfileError::InvalidProperty(line_no(i - 1));
+                    let atom = &mut table.atoms
+                        [atom_index(atom as usize, n_atoms, line_no(i - 1))? as usize];
+                    match tag {
+                        "M  CHG" => atom.charge = i8::try_from(value).map_err(|_| invalid())?,
+                        "M  RAD" => atom.radical = u8::try_from(value).map_err(|_| invalid())?,
+                        _ => atom.isotope = Some(u16::try_from(value).map_err(|_| invalid())?),
+                    }
+                }
+            }
+            // Atom aliases and group abbreviations take a second line.
+            _ if line.starts_with("A  ") || line.starts_with("G  ") => i += 1,
+            _ if line.starts_with("S  SKP") => {
+                i += number::<usize>(columns(line, 6, 9)).unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    Ok((table, lines.len()))
+}
+
+/// Reads the `(atom, value)` pairs of an `M  CHG`-style line.
+fn property_entries(line: &str, line_no: usize) -> Result<Vec<(i64, i64)>, MolfileError> {
+    let invalid = || MolfileError::InvalidProperty(line_no);
+    let fields: Vec<i64> = line[6..]
+        .split_whitespace()
+        .map(|f| f.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let (&count, pairs) = fields.split_first().ok_or_else(invalid)?;
+    if count < 0 || pairs.len() < 2 * count as usize {
+        return Err(invalid());
+    }
+    Ok(pairs[..2 * count as usize]
+        .chunks(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect())
+}
+
+/// Converts a 1-based atom number.
+fn atom_index(number: usize, atoms: usize, line: usize) -> Result<NodeIndex, MolfileError> {
+    if (1..=atoms).contains(&number) {
+        Ok((number - 1) as NodeIndex)
+    } else {
+        Err(MolfileError::UnknownAtom(line, number))
+    }
+}
+
+/// Splits a V3000 line into fields, keeping quoted strings and parenthesized
+/// lists together.
+fn v3000_fields(text: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted && depth > 0 => depth -= 1,
+            c if c.is_whitespace() && !quoted && depth == 0 => {
+                if let Some(s) = start.take() {
+                    fields.push(&text[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        fields.push(&text[s..]);
+    }
+    fields
+}
+
+fn read_v3000(lines: &[&str], first: usize) -> Result<(Table, usize), MolfileError> {
+    // Join continued lines and drop the `M  V30 ` prefix.
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    let mut end = lines.len();
+    let mut continued = false;
+    for (i, line) in lines.iter().enumerate().skip(4) {
+        if line.starts_with("M  END") {
+            end = i + 1;
+            break;
+        }
+        let Some(content) = line.strip_prefix("M  V30 ") else {
+            continue;
+        };
+        let (content, continues) = match content.trim_end().strip_suffix('-') {
+            Some(content) => (content, true),
+            None => (content.trim_end(), false),
+        };
+        match entries.last_mut() {
+            Some((_, text)) if continued => text.push_str(content),
+            _ => entries.push((first + i, content.to_string())),
+        }
+        continued = continues;
+    }
+
+    let mut atoms = Vec::new();
+    let mut bonds = Vec::new();
+    let mut numbers: HashMap<u32, NodeIndex> = HashMap::new();
+    let mut block: Option<&str> = None;
+    let mut seen_ctab = false;
+    let mut bond_numbers: Vec<(usize, u32, u32)> = Vec::new();
+    for (line, text) in &entries {
+        let line = *line;
+        let fields = v3000_fields(text);
+        match (fields.first().copied(), fields.get(1).copied()) {
+            (Some("BEGIN"), Some(name)) => {
+                if name == "CTAB" {
+                    seen_ctab = true;
+                } else if block.is_none() {
+                    block = Some(name);
+                }
+                continue;
+            }
+            (Some("END"), Some(name)) => {
+                if block == Some(name) {
+                    block = None;
+                }
+                continue;
+            }
+            (Some("COUNTS"), _) if block.is_none() => {
+                let valid = fields.len() >= 3
+                    && fields[1].parse::<usize>().is_ok()
+                    && fields[2].parse::<usize>().is_ok();
+                if !valid {
+                    return Err(MolfileError::InvalidCounts(line));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        match block {
+            Some("ATOM") => {
+                let invalid = || MolfileError::InvalidAtom(line);
+                if fields.len() < 6 {
+                    return Err(invalid());
+                }
+                let number: u32 = fields[0].parse().map_err(|_| invalid())?;
+                let (element, isotope) = element(fields[1], line)?;
+                let mut position = [0.0; 3];
+                for (k, value) in position.iter_mut().enumerate() {
+                    *value = fields[2 + k].parse().map_err(|_| invalid())?;
+                }
+                let class = match fields[5].parse::<u16>().map_err(|_| invalid())? {
+                    0 => None,
+                    class => Some(class),
+                };
+                let mut atom = TableAtom {
+                    line,
+                    element,
+                    isotope,
+                    charge: 0,
+                    radical: 0,
+                    valence: None,
+                    class,
+                    parity: 0,
+                    position,
+                };
+                for property in &fields[6..] {
+                    let Some((key, value)) = property.split_once('=') else {
+                        return Err(invalid());
+                    };
+                    match key {
+                        "CHG" => atom.charge = value.parse().map_err(|_| invalid())?,
+                        "RAD" => atom.radical = value.parse().map_err(|_| invalid())?,
+                        "MASS" => atom.isotope = Some(value.parse().map_err(|_| invalid())?),
+                        "CFG" => atom.parity = value.parse().map_err(|_| invalid())?,
+                        "VAL" => {
+                            atom.valence = match value.parse::<i16>().map_err(|_| invalid())? {
+                                -1 => Some(0),
+                                0 => None,
+                                v => Some(u8::try_from(v).map_err(|_| invalid())?),
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                numbers.insert(number, atoms.len() as NodeIndex);
+                atoms.push(atom);
+            }
+            Some("BOND") => {
+                let invalid = || MolfileError::InvalidBond(line);
+                if fields.len() < 4 {
+                    return Err(invalid());
+                }
+                let code: u8 = fields[1].parse().map_err(|_| invalid())?;
+                let kind = bond_kind(code).ok_or_else(invalid)?;
+                let (Ok(a), Ok(b)) = (fields[2].parse::<u32>(), fields[3].parse::<u32>()) else {
+                    return Err(invalid());
+                };
+                let mut stereo = 0;
+                for property in &fields[4..] {
+                    if let Some(value) = property.strip_prefix("CFG=") {
+                        stereo = match (value, kind) {
+                            ("1", _) => 1,
+                            ("2", BondType::Double) => 3,
+                            ("2", _) => 4,
+                            ("3", _) => 6,
+                            _ => return Err(invalid()),
+                        };
+                    }
+                }
+                if a == b {
+                    return Err(invalid());
+                }
+                bond_numbers.push((line, a, b));
+                bonds.push(TableBond {
+                    line,
+                    source: 0,
+                    target: 0,
+                    kind,
+                    stereo,
+                });
+            }
+            _ => {}
+        }
+    }
+    if !seen_ctab {
+        return Err(MolfileError::UnexpectedEnd(first + lines.len()));
+    }
+    // Bonds may come before atoms they refer to only in broken files, but
+    // atom numbers need not be contiguous.
+    for (bond, (line, a, b)) in bonds.iter_mut().zip(bond_numbers) {
+        let index = |n: u32| {
+            numbers
+                .get(&n)
+                .copied()
+                .ok_or(MolfileError::UnknownAtom(line, n as usize))
+        };
+        bond.source = index(a)?;
+        bond.target = index(b)?;
+    }
+    Ok((Table { atoms, bonds }, end))
+}
+
+/// Default valences of the MDL valence model, by element and charge.
+fn default_valences(element: AtomSymbol, charge: i8) -> &'static [u8] {
+    use OrganicAtom::*;
+    match (element, charge) {
+        (AtomSymbol::Organic(B) | AtomSymbol::Al, 0) => &[3],
+        (AtomSymbol::Organic(B) | AtomSymbol::Al, -1) => &[4],
+        (AtomSymbol::Organic(B), 1) => &[2],
+        (AtomSymbol::Organic(C) | AtomSymbol::Si | AtomSymbol::Ge, 0) => &[4],
+        (AtomSymbol::Organic(C) | AtomSymbol::Si | AtomSymbol::Ge, -1 | 1) => &[3],
+        (AtomSymbol::Organic(N), 0) => &[3],
+        (AtomSymbol::Organic(P) | AtomSymbol::As, 0) => &[3, 5],
+        (AtomSymbol::Organic(N | P) | AtomSymbol::As, 1) => &[4],
+        (AtomSymbol::Organic(N | P) | AtomSymbol::As, -1) => &[2],
+        (AtomSymbol::Organic(O), 0) => &[2],
+        (AtomSymbol::Organic(O), 1) => &[3],
+        (AtomSymbol::Organic(O), -1) => &[1],
+        (AtomSymbol::Organic(S) | AtomSymbol::Se | AtomSymbol::Te, 0) => &[2, 4, 6],
+        (AtomSymbol::Organic(S) | AtomSymbol::Se | AtomSymbol::Te, 1) => &[3, 5],
+        (AtomSymbol::Organic(S) | AtomSymbol::Se | AtomSymbol::Te, -1) => &[1, 3, 5],
+        (AtomSymbol::Organic(F | Cl | Br | I), 0) => &[1],
+        (AtomSymbol::Organic(F | Cl | Br | I), 1) => &[2],
+        _ => &[],
+    }
+}
+
+/// Returns the 3D vector from `a` to `b`.
+fn vector(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [b[0] - a[0], b[1] - a[1], b[2] - a[2]]
+}
+
+fn cross(u: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn dot(u: [f64; 3], v: [f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+/// Volumes and dot products below this are taken as degenerate geometry.
+const EPSILON: f64 = 1e-6;
+
+impl Table {
+    /// Returns the neighbours of every atom with the index of the bond.
+    fn neighbours(&self) -> Vec<Vec<(NodeIndex, usize)>> {
+        let mut neighbours = vec![Vec::new(); self.atoms.len()];
+        for (i, bond) in self.bonds.iter().enumerate() {
+            neighbours[bond.source as usize].push((bond.target, i));
+            neighbours[bond.target as usize].push((bond.source, i));
+        }
+        neighbours
+    }
+
+    /// Builds the molecule: hydrogens and aromaticity, then stereo.
+    pub(super) fn to_molecule(&self) -> Result<Molecule, MolfileError> {
+        let n = self.atoms.len();
+        let neighbours = self.neighbours();
+
+        // Aromatic bonds are only kept between atoms that can be aromatic.
+        let mut kinds: Vec<BondType> = self
+            .bonds
+            .iter()
+            .map(|bond| {
+                let aromatic_ends = self.atoms[bond.source as usize].element.can_be_aromatic()
+                    && self.atoms[bond.target as usize].element.can_be_aromatic();
+                match bond.kind {
+                    BondType::Aromatic if !aromatic_ends => BondType::Simple,
+                    kind => kind,
+                }
+            })
+            .collect();
+        let aromatic: Vec<bool> = (0..n)
+            .map(|a| {
+                neighbours[a]
+                    .iter()
+                    .any(|&(_, b)| kinds[b] == BondType::Aromatic)
+            })
+            .collect();
+
+        let mut hydrogens = Vec::with_capacity(n);
+        for (a, atom) in self.atoms.iter().enumerate() {
+            let order: u32 = neighbours[a]
+                .iter()
+                .map(|&(_, b)| u32::from(kinds[b].bond_order_x2_for_implicit_h()) / 2)
+                .sum();
+            let count = match atom.valence {
+                Some(valence) => u32::from(valence).saturating_sub(order),
+                None if atom.element == AtomSymbol::H => 0,
+                None => {
+                    let target = order + u32::from(aromatic[a]);
+                    let removed = match atom.radical {
+                        0 => 0,
+                        2 => 1,
+                        _ => 2,
+                    };
+                    default_valences(atom.element, atom.charge)
+                        .iter()
+                        .map(|&v| u32::from(v))
+                        .find(|&v| v >= target)
+                        .map_or(0, |v| (v - target).saturating_sub(removed))
+                }
+            };
+            if count > 9 {
+                return Err(MolfileError::UnsupportedAtom(
+                    atom.line,
+                    NodeError::InvalidHydrogen(count.min(255) as u8).into(),
+                ));
+            }
+            hydrogens.push(count as u8);
+        }
+
+        let no_stereo = vec![None; n];
+        let plain = self.build(&aromatic, &hydrogens, &kinds, &no_stereo)?;
+        let mut chirality = self.chirality(&neighbours, &hydrogens);
+        let marks = self.double_bond_marks(&plain, &neighbours, &mut kinds);
+        if chirality.iter().all(Option::is_none) && marks.is_empty() {
+            return Ok(plain);
+        }
+
+        // Keep only the stereo the canonical ranking can tell apart, and the
+        // ring centres whose configuration is relative to another one.
+        let molecule = self.build(&aromatic, &hydrogens, &kinds, &chirality)?;
+        let ranking = canonical_ranking(&molecule, &molecule.writer_graph());
+        let labels = &ranking.labels;
+        let relative = self.relative_centres(&plain, &neighbours, &hydrogens, &chirality, &ranking);
+        let mut changed = false;
+        for (atom, centre) in chirality.iter_mut().enumerate() {
+            if centre.is_some() && labels[atom].0 == 0 && !relative[atom] {
+                *centre = None;
+                changed = true;
+            }
+        }
+        for (bond, double_bonds) in marks {
+            let labelled = double_bonds.iter().any(|&d| {
+                let double = &self.bonds[d];
+                labels[double.source as usize].1 != 0 && labels[double.target as usize].1 != 0
+            });
+            if !labelled {
+                kinds[bond] = BondType::Simple;
+                changed = true;
+            }
+        }
+        if changed {
+            self.build(&aromatic, &hydrogens, &kinds, &chirality)
+        } else {
+            Ok(molecule)
+        }
+    }
+
+    /// Flags the ring centres whose only symmetric neighbours are ring atoms
+    /// and which share a ring with another such centre, as in
+    /// 1,4-dimethylcyclohexane: their configurations are meaningful relative
+    /// to each other even though neither is a stereocentre on its own.
+    fn relative_centres(
+        &self,
+        plain: &Molecule,
+        neighbours: &[Vec<(NodeIndex, usize)>],
+        hydrogens: &[u8],
+        chirality: &[Option<(Chirality, Vec<NodeIndex>)>],
+        ranking: &Ranking,
+    ) -> Vec<bool> {
+        let n = self.atoms.len();
+        let mut relative = vec![false; n];
+        if chirality.iter().flatten().count() < 2 {
+            return relative;
+        }
+        let rings = plain.ring_info();
+        let classes = &ranking.classes;
+        let candidate = |atom: usize| {
+            if chirality[atom].is_none() || !rings.is_in_ring(atom as NodeIndex) {
+                return false;
+            }
+            let around = &neighbours[atom];
+            let explicit = around
+                .iter()
+                .filter(|&&(v, _)| self.atoms[v as usize].element == AtomSymbol::H)
+                .count();
+            if usize::from(hydrogens[atom]) + explicit > 1 {
+                return false;
+            }
+            around.iter().enumerate().all(|(i, &(v, _))| {
+                around[i + 1..].iter().all(|&(w, _)| {
+                    classes[v as usize] != classes[w as usize]
+                        || (rings.is_in_ring(v) && rings.is_in_ring(w))
+                })
+            })
+        };
+        let candidates: Vec<bool> = (0..n).map(candidate).collect();
+        for ring in rings.sssr() {
+            let members: Vec<NodeIndex> = ring
+                .nodes
+                .iter()
+                .copied()
+                .filter(|&atom| candidates[atom as usize])
+                .collect();
+            if members.len() > 1 {
+                for atom in members {
+                    relative[atom as usize] = true;
+                }
+            }
+        }
+        relative
+    }
+
+    /// Builds a molecule with the given hydrogen counts, bond kinds and
+    /// chirality written against a neighbour order.
+    fn build(
+        &self,
+        aromatic: &[bool],
+        hydrogens: &[u8],
+        kinds: &[BondType],
+        chirality: &[Option<(Chirality, Vec<NodeIndex>)>],
+    ) -> Result<Molecule, MolfileError> {
+        let mut builder = MoleculeBuilder::new();
+        let mut written = HashMap::new();
+        for (a, atom) in self.atoms.iter().enumerate() {
+            let order: u8 = self
+                .bonds
+                .iter()
+                .zip(kinds)
+                .filter(|(bond, _)| bond.source as usize == a || bond.target as usize == a)
+                .map(|(_, kind)| kind.bond_order_x2_for_implicit_h() / 2)
+                .fold(0u8, u8::saturating_add);
+            let implicit = match atom.element {
+                AtomSymbol::Organic(organic) => {
+                    atom.charge == 0
+                        && atom.isotope.is_none()
+                        && atom.class.is_none()
+                        && atom.radical == 0
+                        && chirality[a].is_none()
+                        && organic.implicit_hydrogens(order, aromatic[a]) == hydrogens[a]
+                }
+                _ => false,
+            };
+            let centre = chirality[a].as_ref();
+            builder
+                .add_atom(
+                    atom.element,
+                    atom.charge,
+                    atom.isotope,
+                    Some(aromatic[a]),
+                    (!implicit).then_some(hydrogens[a]),
+                    atom.class,
+                    centre.map(|(c, _)| *c),
+                )
+                .map_err(|e| MolfileError::UnsupportedAtom(atom.line, e.into()))?;
+            if let Some((_, order)) = centre {
+                written.insert(a as NodeIndex, order.clone());
+            }
+        }
+        for (bond, &kind) in self.bonds.iter().zip(kinds) {
+            builder.add_bond(bond.source, bond.target, kind);
+        }
+        builder.normalize_chirality(written);
+        builder.build().map_err(|e| {
+            let line = self.atoms.first().map_or(0, |a| a.line);
+            MolfileError::UnsupportedAtom(line, e)
+        })
+    }
+
+    /// Returns, for every tetrahedral centre, its chirality against a
+    /// neighbour order where the atom itself stands for its implicit
+    /// hydrogen or lone pair.
+    fn chirality(
+        &self,
+        neighbours: &[Vec<(NodeIndex, usize)>],
+        hydrogens: &[u8],
+    ) -> Vec<Option<(Chirality, Vec<NodeIndex>)>> {
+        let three_d = self.atoms.iter().any(|a| a.position[2].abs() > EPSILON);
+        (0..self.atoms.len())
+            .map(|a| {
+                let center = a as NodeIndex;
+                let degree = neighbours[a].len();
+                let implicit = hydrogens[a] > 0 || degree == 3;
+                if degree + usize::from(implicit) != 4 || hydrogens[a] > 1 {
+                    return None;
+                }
+                let mut order: Vec<NodeIndex> = neighbours[a].iter().map(|&(v, _)| v).collect();
+                if implicit {
+                    order.push(center);
+                }
+                let wedged = neighbours[a].iter().any(|&(_, b)| {
+                    self.bonds[b].source == center && matches!(self.bonds[b].stereo, 1 | 6)
+                });
+                if three_d || wedged {
+                    let chirality = self.geometric_chirality(center, &neighbours[a], three_d)?;
+                    return Some((chirality, order));
+                }
+                let parity = match self.atoms[a].parity {
+                    1 => Chirality::TH1,
+                    2 => Chirality::TH2,
+                    _ => return None,
+                };
+                // MDL parity reads the neighbours in atom order with
+                // hydrogens last, from the side opposite the last one; `@`
+                // reads them from the first one.
+                let last =
+                    |v: NodeIndex| v == center || self.atoms[v as usize].element == AtomSymbol::H;
+                order.sort_by_key(|&v| (last(v), v));
+                order.rotate_right(1);
+                Some((parity, order))
+            })
+            .collect()
+    }
+
+    /// Returns the configuration of a centre against its neighbours in
+    /// `neighbours` order followed by its implicit hydrogen, if any.
+    fn geometric_chirality(
+        &self,
+        center: NodeIndex,
+        neighbours: &[(NodeIndex, usize)],
+        three_d: bool,
+    ) -> Option<Chirality> {
+        let origin = self.atoms[center as usize].position;
+        let mut vectors: Vec<[f64; 3]> = neighbours
+            .iter()
+            .map(|&(v, b)| {
+                let mut u = vector(origin, self.atoms[v as usize].position);
+                let bond = &self.bonds[b];
+                if !three_d && bond.source == center {
+                    let length = dot(u, u).sqrt();
+                    u[2] = match bond.stereo {
+                        1 => length,
+                        6 => -length,
+                        _ => 0.0,
+                    };
+                }
+                u
+            })
+            .collect();
+        if vectors.len() == 3 {
+            // The implicit hydrogen points away from the other neighbours.
+            let mut h = [0.0; 3];
+            for u in &vectors {
+                let length = dot(*u, *u).sqrt();
+                if length < EPSILON {
+                    return None;
+                }
+                for k in 0..3 {
+                    h[k] -= u[k] / length;
+                }
+            }
+            vectors.push(h);
+        }
+        let (a, b, c, d) = (vectors[0], vectors[1], vectors[2], vectors[3]);
+        let volume = dot(vector(a, b), cross(vector(a, c), vector(a, d)));
+        // Seen from the first neighbour, the others turn anticlockwise (`@`)
+        // when the volume is negative.
+        if volume < -EPSILON {
+            Some(Chirality::TH1)
+        } else if volume > EPSILON {
+            Some(Chirality::TH2)
+        } else {
+            None
+        }
+    }
+
+    /// Marks a single bond at each end of every double bond whose geometry
+    /// is known. Returns the marked bonds with the double bonds they serve.
+    fn double_bond_marks(
+        &self,
+        plain: &Molecule,
+        neighbours: &[Vec<(NodeIndex, usize)>],
+        kinds: &mut [BondType],
+    ) -> HashMap<usize, Vec<usize>> {
+        let mut marks: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut rings = None;
+        for (d, double) in self.bonds.iter().enumerate() {
+            if kinds[d] != BondType::Double || double.stereo == 3 {
+                continue;
+            }
+            let (a, b) = (double.source, double.target);
+            // An end and its substituent through a single bond, preferring
+            // one already marked.
+            let end = |atom: NodeIndex, other: NodeIndex, kinds: &[BondType]| {
+                let singles = neighbours[atom as usize].iter().filter(|&&(v, bond)| {
+                    v != other
+                        && matches!(
+                            kinds[bond],
+                            BondType::Simple | BondType::Up | BondType::Down
+                        )
+                });
+                singles
+                    .clone()
+                    .find(|&&(_, bond)| kinds[bond] != BondType::Simple)
+                    .or_else(|| singles.clone().next())
+                    .copied()
+            };
+            let (Some((sa, bond_a)), Some((sb, bond_b))) = (end(a, b, kinds), end(b, a, kinds))
+            else {
+                continue;
+            };
+            let position = |v: NodeIndex| self.atoms[v as usize].position;
+            let axis = vector(position(a), position(b));
+            let n = cross(axis, vector(position(a), position(sa)));
+            let m = cross(axis, vector(position(b), position(sb)));
+            let alignment = dot(n, m);
+            if alignment.abs() < EPSILON {
+                continue;
+            }
+            let rings = rings.get_or_insert_with(|| plain.ring_info());
+            if rings
+                .smallest_bond_ring_size(d)
+                .is_some_and(|size| size < 8)
+            {
+                continue;
+            }
+            let cis = alignment > 0.0;
+            let side = |bond: usize, center: NodeIndex| {
+                let marked = Bond::new(
+                    kinds[bond],
+                    self.bonds[bond].source,
+                    self.bonds[bond].target,
+                );
+                stereo::side_of(&marked, center)
+            };
+            let (side_a, side_b) = (side(bond_a, a), side(bond_b, b));
+            let above_a = side_a.or(side_b.map(|s| s == cis)).unwrap_or(true);
+            let above_b = above_a == cis;
+            if side_b.is_some_and(|s| s != above_b) {
+                continue;
+            }
+            for (bond, center, above) in [(bond_a, a, above_a), (bond_b, b, above_b)] {
+                if kinds[bond] == BondType::Simple {
+                    let up = (self.bonds[bond].source == center) == above;
+                    kinds[bond] = if up { BondType::Up } else { BondType::Down };
+                }
+                marks.entry(bond).or_default().push(d);
+            }
+        }
+        marks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const ACETATE: &str = "\
+acetate
+  test
+
+  4  3  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.2990    0.7500    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.5981    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    1.2990    2.2500    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  2  0
+  2  4  1  0
+M  END
+";
+
+    #[test]
+    fn fixed_columns() {
+        assert_eq!(columns("  1  2  1  0", 3, 6), "2");
+        assert_eq!(columns("  1  2", 6, 9), "");
+        assert_eq!(columns("  1  2 1", 6, 9), "1");
+        assert_eq!(number::<u8>(""), Some(0));
+        assert_eq!(number::<u8>("x"), None);
+    }
+
+    #[test]
+    fn reads_v2000() {
+        let lines: Vec<&str> = ACETATE.lines().collect();
+        let (table, end) = read_table(&lines, 1).unwrap();
+        assert_eq!(end, lines.len());
+        assert_eq!(table.atoms.len(), 4);
+        assert_eq!(table.atoms[3].charge, -1);
+        assert_eq!(table.bonds[1].kind, BondType::Double);
+        let molecule = table.to_molecule().unwrap();
+        assert!(molecule.is_isomorphic(&parse("CC(=O)[O-]").unwrap()));
+    }
+
+    #[test]
+    fn property_lines_replace_atom_block_charges() {
+        let text = ACETATE.replace("M  END", "M  CHG  1   3   1\nM  ISO  1   1  13\nM  END");
+        let molecule = parse_molfile(&text).unwrap();
+        assert_eq!(molecule.nodes()[2].atom().charge(), 1);
+        assert_eq!(molecule.nodes()[3].atom().charge(), 0);
+        assert_eq!(molecule.nodes()[0].atom().isotope(), Some(13));
+        let text = ACETATE.replace("M  END", "M  CHG  1   9   1\nM  END");
+        assert_eq!(parse_molfile(&text), Err(MolfileError::UnknownAtom(12, 9)));
+    }
+
+    #[test]
+    fn v3000_fields_keep_groups() {
+        assert_eq!(
+            v3000_fields("1 C 0 0 0 0 ATTCHORD=(2 1 3) FIELDDATA=\"a b\""),
+            [
+                "1",
+                "C",
+                "0",
+                "0",
+                "0",
+                "0",
+                "ATTCHORD=(2 1 3)",
+                "FIELDDATA=\"a b\""
+            ]
+        );
+    }
+
+    #[test]
+    fn mdl_hydrogen_counts() {
+        for (smiles, hydrogens) in [
+            ("C[N+](C)(C)C", vec![3, 0, 3, 3, 3]),
+            ("C[O-]", vec![3, 0]),
+            ("CS(=O)(=O)C", vec![3, 0, 0, 0, 3]),
+            ("[Na+].[Cl-]", vec![0, 0]),
+            ("c1ccncc1", vec![1, 1, 1, 0, 1, 1]),
+        ] {
+            let molecule = parse(smiles).unwrap();
+            let read =
+                parse_molfile(&molecule.to_molfile(crate::molfile::MolfileVersion::V2000)).unwrap();
+            let counts: Vec<u8> = read.nodes().iter().map(|n| n.hydrogens()).collect();
+            assert_eq!(counts, hydrogens, "{smiles}");
+        }
+    }
+
+    #[test]
+    fn default_valences_follow_the_charge() {
+        assert_eq!(
+            default_valences(AtomSymbol::Organic(OrganicAtom::N), 1),
+            &[4]
+        );
+        assert_eq!(
+            default_valences(AtomSymbol::Organic(OrganicAtom::B), -1),
+            &[4]
+        );
+        assert!(default_valences(AtomSymbol::Na, 0).is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Streaming SDF reader.
+
+use std::io::{BufRead, Read};
+
+use super::reader::read_table;
+use crate::{Molecule, MolfileError};
+
+/// One record of an SD file: a molecule and its data items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfRecord {
+    /// First line of the Molfile.
+    pub title: String,
+    pub molecule: Molecule,
+    /// Data items in file order, as `(name, value)`; multi-line values keep
+    /// their line breaks.
+    pub properties: Vec<(String, String)>,
+}
+
+impl SdfRecord {
+    /// Returns the value of the first data item called `name`.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Options for [`SdfReader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfReaderOptions {
+    /// Largest record, in bytes, `$$$$` line excluded. A larger record is
+    /// skipped without being held in memory and yields
+    /// [`MolfileError::RecordTooLarge`].
+    pub max_record_size: usize,
+}
+
+impl Default for SdfReaderOptions {
+    fn default() -> Self {
+        SdfReaderOptions {
+            max_record_size: 64 << 20,
+        }
+    }
+}
+
+/// Bytes read at once while skipping a record that is too large.
+const SKIP_CHUNK: u64 = 8192;
+
+/// Reads the records of an SD file one at a time.
+///
+/// Only the current record is held in memory, up to
+/// [`SdfReaderOptions::max_record_size`]. A record that cannot be read
+/// yields an error carrying the line it failed on, counted from the start of
+/// the input, and reading resumes at the next `$$$$`. An I/O error yields
+/// [`MolfileError::Io`] and ends the iteration. Invalid UTF-8 is replaced
+/// rather than rejected.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use opensmiles::molfile::{MolfileVersion, SdfReader};
+/// use opensmiles::parse;
+///
+/// let mut sdf = String::new();
+/// for smiles in ["CCO", "c1ccccc1"] {
+///     sdf.push_str(&parse(smiles).unwrap().to_molfile(MolfileVersion::V2000));
+///     sdf.push_str(&format!("> <SMILES>\n{}\n\n$$$$\n", smiles));
+/// }
+/// sdf.push_str("broken\n\n\n  x\n$$$$\n");
+///
+/// let records: Vec<_> = SdfReader::new(Cursor::new(sdf)).collect();
+/// assert_eq!(records.len(), 3);
+/// let benzene = records[1].as_ref().unwrap();
+/// assert_eq!(benzene.property("SMILES"), Some("c1ccccc1"));
+/// assert_eq!(benzene.molecule.nodes().len(), 6);
+/// assert!(records[2].is_err());
+/// ```
+pub struct SdfReader<R> {
+    reader: R,
+    options: SdfReaderOptions,
+    /// Line number of the next line to read.
+    line: usize,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> SdfReader<R> {
+    /// Creates a reader over an SD file, with the default options.
+    pub fn new(reader: R) -> SdfReader<R> {
+        SdfReader::with_options(reader, SdfReaderOptions::default())
+    }
+
+    /// Creates a reader over an SD file, with the given options.
+    pub fn with_options(reader: R, options: SdfReaderOptions) -> SdfReader<R> {
+        SdfReader {
+            reader,
+            options,
+            line: 1,
+            buffer: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the lines of the next record, up to and excluding `$$$$`.
+    /// Returns the line number of its first line with its lines, or `None`
+    /// for them when the record is too large; `None` at the end of the
+    /// input.
+    #[allow(clippy::type_complexity)]
+    fn next_lines(&mut self) -> Result<Option<(usize, Option<Vec<String>>)>, MolfileError> {
+        let first = self.line;
+        let max = self.options.max_record_size;
+        let mut lines = Vec::new();
+        let mut size = 0;
+        let mut too_large = false;
+        let mut line_start = true;
+        loop {
+            // Never read more than the record may still hold, so that a
+            // record without line breaks is not buffered whole either.
+            let limit = if too_large {
+                SKIP_CHUNK
+            } else {
+                (max - size) as u64 + 1
+            }
+            .max(8);
+            self.buffer.clear();
+            let read = self
+                .reader
+                .by_ref()
+                .take(limit)
+                .read_until(b'\n', &mut self.buffer)
+                .map_err(|e| MolfileError::Io(e.to_string()))?;
+            if read == 0 {
+                if too_large {
+                    return Ok(Some((first, None)));
+                }
+                let blank = lines.iter().all(|l: &String| l.trim().is_empty());
+                return Ok((!blank).then_some((first, Some(lines))));
+            }
+            if line_start && self.buffer.starts_with(b"$$$$") {
+                self.line += 1;
+                return Ok(Some((first, (!too_large).then_some(lines))));
+            }
+            let truncated = read as u64 == limit && !self.buffer.ends_with(b"\n");
+            if !truncated {
+                self.line += 1;
+            }
+            line_start = !truncated;
+            size += read;
+            if too_large {
+                continue;
+            }
+            if size > max {
+                too_large = true;
+                lines = Vec::new();
+                continue;
+            }
+            let text = String::from_utf8_lossy(&self.buffer);
+            lines.push(text.trim_end_matches(['\n', '\r']).to_string());
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SdfReader<R> {
+    type Item = Result<SdfRecord, MolfileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_lines() {
+            Ok(Some((first, Some(lines)))) => Some(parse_record(&lines, first)),
+            Ok(Some((first, None))) => Some(Err(MolfileError::RecordTooLarge(
+                first,
+                self.options.max_record_size,
+            ))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Reads one record whose first line is line `first` of the input.
+fn parse_record(lines: &[String], first: usize) -> Result<SdfRecord, MolfileError> {
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let (table, end) = read_table(&lines, first)?;
+    let molecule = table.to_molecule()?;
+
+    let mut properties = Vec::new();
+    let mut i = end;
+    while i < lines.len() {
+        let header = lines[i];
+        i += 1;
+        if !header.starts_with('>') {
+            continue;
+        }
+        let name = match (header.find('<'), header.rfind('>')) {
+            (Some(start), Some(stop)) if stop > start => &header[start + 1..stop],
+            _ => "",
+        };
+        let mut value = Vec::new();
+        while i < lines.len() && !lines[i].is_empty() {
+            value.push(lines[i]);
+            i += 1;
+        }
+        properties.push((name.to_string(), value.join("\n")));
+    }
+    Ok(SdfRecord {
+        title: lines[0].to_string(),
+        molecule,
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molfile::MolfileVersion;
+    use crate::parse;
+    use std::io::Cursor;
+
+    #[test]
+    fn data_items() {
+        let mut sdf = parse("CO").unwrap().to_molfile(MolfileVersion::V2000);
+        sdf.push_str(
+            ">  <NAME>  (1)\nmethanol\n\n> 25  <NOTE>\nline one\nline two\n\n> <EMPTY>\n\n$$$$\n",
+        );
+        let records: Vec<_> = SdfReader::new(Cursor::new(sdf)).collect();
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(
+            record.properties,
+            vec![
+                ("NAME".to_string(), "methanol".to_string()),
+                ("NOTE".to_string(), "line one\nline two".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(record.property("NOTE"), Some("line one\nline two"));
+        assert_eq!(record.property("MISSING"), None);
+    }
+
+    #[test]
+    fn last_record_without_terminator() {
+        let sdf = parse("CC").unwrap().to_molfile(MolfileVersion::V2000) + "\n\n";
+        let records: Vec<_> = SdfReader::new(Cursor::new(sdf)).collect();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].is_ok());
+        assert_eq!(SdfReader::new(Cursor::new("\n\n")).count(), 0);
+    }
+
+    #[test]
+    fn errors_count_lines_from_the_start() {
+        let good = parse("CC").unwrap().to_molfile(MolfileVersion::V2000) + "$$$$\n";
+        let bad = good.replace("  1  2  1  0", "  1  7  1  0");
+        let sdf = format!("{good}{bad}{good}");
+        let records: Vec<_> = SdfReader::new(Cursor::new(sdf)).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].is_ok() && records[2].is_ok());
+        // The second record starts at line 10; its bond is its seventh line.
+        assert_eq!(records[1], Err(MolfileError::UnknownAtom(16, 7)));
+    }
+
+    #[test]
+    fn records_over_the_size_limit() {
+        let good = parse("CC").unwrap().to_molfile(MolfileVersion::V2000) + "$$$$\n";
+        let options = SdfReaderOptions {
+            max_record_size: good.len(),
+        };
+        let big = format!("{}\n$$$$\n", "x".repeat(10 * good.len()));
+        let sdf = format!("{good}{big}{good}");
+        let records: Vec<_> = SdfReader::with_options(Cursor::new(sdf), options.clone()).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].is_ok() && records[2].is_ok());
+        let max = good.len();
+        assert_eq!(records[1], Err(MolfileError::RecordTooLarge(10, max)));
+
+        // Without a terminator or even a line break, the input ends the record.
+        let sdf = format!("{good}{}", "x".repeat(10 * good.len()));
+        let records: Vec<_> = SdfReader::with_options(Cursor::new(sdf), options).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], Err(MolfileError::RecordTooLarge(10, max)));
+    }
+}
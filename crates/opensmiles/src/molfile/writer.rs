@@ -0,0 +1,433 @@
+//! Molfile writer.
+//!
+//! Both versions share one description of each atom and bond, built by
+//! [`Table::new`]; only the layout differs. V2000 keeps charges and isotopes
+//! in `M  CHG` and `M  ISO` lines, V3000 in `CHG=` and `MASS=` properties.
+
+use std::fmt::Write;
+
+use crate::ast::stereo;
+use crate::{AtomSymbol, BondType, Chirality, Molecule, NodeIndex};
+
+/// Connection table format written by [`Molecule::to_molfile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MolfileVersion {
+    /// Fixed-column format, limited to 999 atoms and bonds.
+    #[default]
+    V2000,
+    /// Extended format without size limits.
+    V3000,
+}
+
+/// Options for [`Molecule::to_molfile_with`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MolfileOptions {
+    /// First line of the Molfile.
+    pub title: String,
+    /// `[x, y, z]` of every atom, in atom order; `None` puts every atom at
+    /// the origin.
+    pub coordinates: Option<Vec<[f64; 3]>>,
+    /// Write aromatic bonds as bond type 4 instead of Kekulé single and
+    /// double bonds.
+    pub aromatic_bonds: bool,
+}
+
+/// Atom as written to the atom block.
+struct TableAtom {
+    symbol: String,
+    position: [f64; 3],
+    charge: i8,
+    isotope: Option<u16>,
+    /// MDL parity: 1 clockwise, 2 anticlockwise, 0 none.
+    parity: u8,
+    /// Total valence, for atoms whose hydrogen count was given explicitly
+    /// and differs from the valence model.
+    valence: Option<u8>,
+    class: Option<u16>,
+}
+
+/// Connection table shared by both layouts. Atoms and bonds are numbered
+/// from 1 in the output.
+struct Table {
+    atoms: Vec<TableAtom>,
+    bonds: Vec<(u8, NodeIndex, NodeIndex)>,
+    chiral: bool,
+}
+
+impl Molecule {
+    /// Writes the molecule as an MDL Molfile, with every atom at the origin
+    /// and Kekulé bond orders.
+    ///
+    /// See [`to_molfile_with`](Self::to_molfile_with) for the details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::molfile::MolfileVersion;
+    /// use opensmiles::parse;
+    ///
+    /// let molfile = parse("c1ccccc1").unwrap().to_molfile(MolfileVersion::V3000);
+    /// assert!(molfile.contains("M  V30 COUNTS 6 6 0 0 0"));
+    /// assert!(molfile.contains("M  V30 1 2 1 2"));
+    /// ```
+    pub fn to_molfile(&self, version: MolfileVersion) -> String {
+        self.to_molfile_with(version, &MolfileOptions::default())
+    }
+
+    /// Writes the molecule as an MDL Molfile.
+    ///
+    /// Aromatic bonds are written as Kekulé single and double bonds, or as
+    /// bond type 4 when [`MolfileOptions::aromatic_bonds`] is set or the
+    /// molecule cannot be kekulized. Atom classes become atom-atom mapping
+    /// numbers, and `@`/`@@` centres get the MDL parity of their neighbours
+    /// taken in atom order, hydrogens last; the chiral flag is set when the
+    /// molecule has such a centre. The hydrogen count of bracket atoms is
+    /// kept through the valence field when the valence model would give
+    /// another one. `/` and `\` bonds are written as plain single bonds:
+    /// double-bond geometry needs coordinates.
+    ///
+    /// A V2000 Molfile has fixed columns, so a molecule with more than 999
+    /// atoms or bonds is written as V3000, as is one whose atom classes
+    /// would not fit the three-digit mapping column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`MolfileOptions::coordinates`] does not give exactly one
+    /// position per atom.
+    pub fn to_molfile_with(&self, version: MolfileVersion, options: &MolfileOptions) -> String {
+        if let Some(coordinates) = &options.coordinates {
+            assert_eq!(
+                coordinates.len(),
+                self.nodes().len(),
+                "one position per atom is required"
+            );
+        }
+        let table = Table::new(self, options);
+        let fits_v2000 = table.atoms.len() <= 999
+            && table.bonds.len() <= 999
+            && table.atoms.iter().all(|a| a.class.unwrap_or(0) <= 999);
+        let three_d = table.atoms.iter().any(|a| a.position[2] != 0.0);
+
+        let mut out = String::new();
+        out.push_str(options.title.lines().next().unwrap_or(""));
+        out.push('\n');
+        let _ = writeln!(
+            out,
+            "  {:>8}          {}",
+            "osmiles",
+            if three_d { "3D" } else { "2D" }
+        );
+        out.push('\n');
+        if version == MolfileVersion::V2000 && fits_v2000 {
+            table.write_v2000(&mut out);
+        } else {
+            table.write_v3000(&mut out);
+        }
+        out.push_str("M  END\n");
+        out
+    }
+}
+
+impl Table {
+    fn new(molecule: &Molecule, options: &MolfileOptions) -> Table {
+        let kekule = if options.aromatic_bonds {
+            None
+        } else {
+            molecule.kekulize().ok()
+        };
+        let bonds = kekule.as_ref().unwrap_or(molecule).bonds();
+
+        let atoms = molecule
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(atom, node)| TableAtom {
+                symbol: node.atom().element().to_string(),
+                position: options
+                    .coordinates
+                    .as_ref()
+                    .map_or([0.0; 3], |positions| positions[atom]),
+                charge: node.atom().charge(),
+                isotope: node.atom().isotope(),
+                parity: parity(molecule, atom as NodeIndex),
+                valence: valence(molecule, atom as NodeIndex),
+                class: node.class(),
+            })
+            .collect::<Vec<_>>();
+
+        let bonds = bonds
+            .iter()
+            .filter_map(|bond| {
+                let order = match bond.kind() {
+                    BondType::Simple | BondType::Up | BondType::Down => 1,
+                    BondType::Double => 2,
+                    BondType::Triple => 3,
+                    // Molfiles have no quadruple bond; 8 is "any".
+                    BondType::Quadruple => 8,
+                    BondType::Aromatic => 4,
+                    BondType::Disconnected => return None,
+                };
+                Some((order, bond.source() + 1, bond.target() + 1))
+            })
+            .collect();
+
+        let chiral = atoms.iter().any(|a| a.parity != 0);
+        Table {
+            atoms,
+            bonds,
+            chiral,
+        }
+    }
+
+    fn write_v2000(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "{:3}{:3}  0  0{:3}  0  0  0  0  0999 V2000",
+            self.atoms.len(),
+            self.bonds.len(),
+            u8::from(self.chiral)
+        );
+        for atom in &self.atoms {
+            let [x, y, z] = atom.position;
+            let valence = match atom.valence {
+                None => 0,
+                Some(0) => 15,
+                Some(v) => v,
+            };
+            let _ = writeln!(
+                out,
+                "{:10.4}{:10.4}{:10.4} {:<3} 0  0{:3}  0  0{:3}  0  0  0{:3}  0  0",
+                x,
+                y,
+                z,
+                atom.symbol,
+                atom.parity,
+                valence,
+                atom.class.unwrap_or(0)
+            );
+        }
+        for &(order, a, b) in &self.bonds {
+            let _ = writeln!(out, "{:3}{:3}{:3}  0", a, b, order);
+        }
+
+        let charges: Vec<(usize, i32)> = self
+            .atoms
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.charge != 0)
+            .map(|(i, a)| (i + 1, a.charge.into()))
+            .collect();
+        let isotopes: Vec<(usize, i32)> = self
+            .atoms
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| a.isotope.map(|m| (i + 1, m.into())))
+            .collect();
+        for (tag, entries) in [("CHG", charges), ("ISO", isotopes)] {
+            for chunk in entries.chunks(8) {
+                let _ = write!(out, "M  {}{:3}", tag, chunk.len());
+                for (atom, value) in chunk {
+                    let _ = write!(out, " {:3} {:3}", atom, value);
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    fn write_v3000(&self, out: &mut String) {
+        out.push_str("  0  0  0     0  0            999 V3000\n");
+        out.push_str("M  V30 BEGIN CTAB\n");
+        let _ = writeln!(
+            out,
+            "M  V30 COUNTS {} {} 0 0 {}",
+            self.atoms.len(),
+            self.bonds.len(),
+            u8::from(self.chiral)
+        );
+        out.push_str("M  V30 BEGIN ATOM\n");
+        for (i, atom) in self.atoms.iter().enumerate() {
+            let [x, y, z] = atom.position;
+            let _ = write!(
+                out,
+                "M  V30 {} {} {:.4} {:.4} {:.4} {}",
+                i + 1,
+                atom.symbol,
+                x,
+                y,
+                z,
+                atom.class.unwrap_or(0)
+            );
+            if atom.charge != 0 {
+                let _ = write!(out, " CHG={}", atom.charge);
+            }
+            if let Some(mass) = atom.isotope {
+                let _ = write!(out, " MASS={}", mass);
+            }
+            if atom.parity != 0 {
+                let _ = write!(out, " CFG={}", atom.parity);
+            }
+            match atom.valence {
+                Some(0) => out.push_str(" VAL=-1"),
+                Some(v) => {
+                    let _ = write!(out, " VAL={}", v);
+                }
+                None => {}
+            }
+            out.push('\n');
+        }
+        out.push_str("M  V30 END ATOM\n");
+        out.push_str("M  V30 BEGIN BOND\n");
+        for (i, &(order, a, b)) in self.bonds.iter().enumerate() {
+            let _ = writeln!(out, "M  V30 {} {} {} {}", i + 1, order, a, b);
+        }
+        out.push_str("M  V30 END BOND\n");
+        out.push_str("M  V30 END CTAB\n");
+    }
+}
+
+/// Returns the MDL parity of a tetrahedral centre: looking with the
+/// highest-numbered neighbour pointing away, the others in increasing order
+/// turn clockwise (1) or anticlockwise (2). Hydrogens, implicit or not,
+/// count as the highest-numbered neighbours.
+fn parity(molecule: &Molecule, atom: NodeIndex) -> u8 {
+    let node = &molecule.nodes()[atom as usize];
+    let Some(chirality) = node
+        .chirality()
+        .filter(|&c| stereo::is_tetrahedral(Some(c)))
+    else {
+        return 0;
+    };
+    let bonds = molecule.bonds();
+    let degree = stereo::reference_order(atom, bonds, false).len();
+    let implicit = node.hydrogens() > 0 || degree == 3;
+    let reference = stereo::reference_order(atom, bonds, implicit);
+    if reference.len() != 4 {
+        return 0;
+    }
+    let last =
+        |v: NodeIndex| v == atom || *molecule.nodes()[v as usize].atom().element() == AtomSymbol::H;
+    let mut sorted = reference.clone();
+    sorted.sort_by_key(|&v| (last(v), v));
+    // `@` with the highest neighbour first is clockwise seen from the other
+    // side.
+    sorted.rotate_right(1);
+    match stereo::is_odd_permutation(&reference, &sorted) {
+        Some(odd) if (chirality == Chirality::TH1) != odd => 1,
+        Some(_) => 2,
+        None => 0,
+    }
+}
+
+/// Returns the total valence to write for an atom whose hydrogen count was
+/// given in brackets, unless the valence model of an uncharged organic atom
+/// gives the same count.
+fn valence(molecule: &Molecule, atom: NodeIndex) -> Option<u8> {
+    let node = &molecule.nodes()[atom as usize];
+    let hydrogens = node.bracket_hydrogens()?;
+    if *node.atom().element() == AtomSymbol::H {
+        return None;
+    }
+    let order_x2: u32 = molecule
+        .bonds()
+        .iter()
+        .filter(|b| b.source() == atom || b.target() == atom)
+        .map(|b| u32::from(b.kind().bond_order_x2_for_implicit_h()))
+        .sum();
+    let order = u8::try_from(order_x2 / 2).unwrap_or(u8::MAX);
+    if let AtomSymbol::Organic(organic) = node.atom().element() {
+        if node.atom().charge() == 0
+            && organic.implicit_hydrogens(order, node.aromatic()) == hydrogens
+        {
+            return None;
+        }
+    }
+    Some(order.saturating_add(hydrogens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn atom_lines(molfile: &str) -> Vec<&str> {
+        molfile
+            .lines()
+            .skip(4)
+            .take_while(|l| l.len() > 60)
+            .collect()
+    }
+
+    #[test]
+    fn v2000_layout() {
+        let molfile = parse("[13CH3]C(=O)[O-]")
+            .unwrap()
+            .to_molfile(MolfileVersion::V2000);
+        let lines: Vec<&str> = molfile.lines().collect();
+        assert_eq!(lines[0], "");
+        assert_eq!(lines[1], "   osmiles          2D");
+        assert_eq!(lines[3], "  4  3  0  0  0  0  0  0  0  0999 V2000");
+        assert_eq!(
+            lines[4],
+            "    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0"
+        );
+        assert_eq!(lines[8], "  1  2  1  0");
+        assert_eq!(lines[9], "  2  3  2  0");
+        assert_eq!(lines[11], "M  CHG  1   4  -1");
+        assert_eq!(lines[12], "M  ISO  1   1  13");
+        assert_eq!(lines[13], "M  END");
+        assert_eq!(lines.len(), 14);
+    }
+
+    #[test]
+    fn properties_span_lines_of_eight() {
+        let smiles = ["[Na+]"; 10].join(".");
+        let molfile = parse(&smiles).unwrap().to_molfile(MolfileVersion::V2000);
+        let charges: Vec<&str> = molfile
+            .lines()
+            .filter(|l| l.starts_with("M  CHG"))
+            .collect();
+        assert_eq!(charges.len(), 2);
+        assert!(charges[0].starts_with("M  CHG  8   1   1   2   1"));
+        assert_eq!(charges[1], "M  CHG  2   9   1  10   1");
+        // No bonds are written for the dots.
+        assert!(molfile.contains(" 10  0  0  0  0"));
+    }
+
+    #[test]
+    fn parities_follow_mdl_rules() {
+        // Seen from the hydrogen, N, C and C of L-alanine turn anticlockwise,
+        // so they turn clockwise with the hydrogen pointing away.
+        for (smiles, expected) in [
+            ("N[C@@H](C)C(=O)O", 1),
+            ("N[C@H](C)C(=O)O", 2),
+            ("[H][C@](N)(C)C(=O)O", 1),
+            ("C[C@H](N)C(=O)O", 2),
+            ("F[C@](Cl)(Br)I", 2),
+            ("F[C@@](Cl)(Br)I", 1),
+        ] {
+            let molecule = parse(smiles).unwrap();
+            let centre = molecule
+                .nodes()
+                .iter()
+                .position(|n| n.chirality().is_some())
+                .unwrap();
+            assert_eq!(parity(&molecule, centre as NodeIndex), expected, "{smiles}");
+        }
+        assert_eq!(parity(&parse("CC").unwrap(), 0), 0);
+    }
+
+    #[test]
+    fn valences_only_when_needed() {
+        let molecule = parse("[CH2]=C[NH3+].[Na+].C[C@H](O)N").unwrap();
+        let valences: Vec<Option<u8>> = (0..molecule.nodes().len() as NodeIndex)
+            .map(|atom| valence(&molecule, atom))
+            .collect();
+        assert_eq!(
+            valences,
+            vec![None, None, Some(4), Some(0), None, None, None, None]
+        );
+        let lines =
+            atom_lines(&parse("[CH2]").unwrap().to_molfile(MolfileVersion::V2000)).join("\n");
+        assert!(lines.contains("C   0  0  0  0  0  2"), "{lines}");
+    }
+}
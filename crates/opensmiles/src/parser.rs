@@ -1,65 +1,190 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::Peekable;
-use std::str::{Chars, FromStr};
+use std::str::Chars;
 
-use crate::error::ParserError;
+use crate::ast::stereo::is_tetrahedral;
+use crate::error::{LimitKind, ParserError, Span};
+use crate::lexer::Lexer;
+use crate::repair::{repair_structure, Repair};
 use crate::{
-    AtomSymbol, BondType, Chirality, Molecule, MoleculeBuilder, NodeError, NodeIndex, OrganicAtom,
+    AtomError, AtomSymbol, BondType, Chirality, Molecule, MoleculeBuilder, NodeError, NodeIndex,
 };
 
+/// Options for [`parse_with`].
+///
+/// The defaults are those of [`parse`]: OpenSMILES rules, with Hückel
+/// validation only when the `huckel-validation` feature is enabled.
+///
+/// The `max_*` limits guard against hostile input: each is checked before
+/// the parser grows past it, and going over one gives a
+/// [`ParserError::LimitExceeded`].
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::{parse, parse_with, ParseOptions};
+///
+/// let strict = ParseOptions {
+///     allow_title: false,
+///     max_charge: 4,
+///     ..Default::default()
+/// };
+/// assert!(parse("CCO ethanol").is_ok());
+/// assert!(parse_with("CCO ethanol", &strict).is_err());
+/// assert!(parse_with("[Fe+6]", &strict).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Reject aromatic rings that break Hückel's 4n+2 rule (see
+    /// [`require_valid_aromaticity`](crate::ast::aromaticity::require_valid_aromaticity)).
+    pub huckel_validation: bool,
+    /// Accept ring bonds written with different symbols at both ends, such
+    /// as `C=1CCCCC-1`. The symbol written at the opening digit wins.
+    pub allow_mismatched_ring_bonds: bool,
+    /// A space, tab or line break ends the SMILES, as the OpenSMILES
+    /// specification has it. When off, whitespace is an unexpected character.
+    pub whitespace_terminates: bool,
+    /// Accept text after the whitespace that ends the SMILES, such as the
+    /// title in `CCO ethanol`. When off, only whitespace may follow.
+    pub allow_title: bool,
+    /// Largest absolute charge of a bracket atom.
+    pub max_charge: u8,
+    /// Largest hydrogen count of a bracket atom.
+    pub max_hydrogens: u8,
+    /// Largest isotope of a bracket atom.
+    pub max_isotope: u16,
+    /// Longest input, in bytes, title included.
+    pub max_input_length: usize,
+    /// Most atoms in the molecule.
+    pub max_atoms: usize,
+    /// Most bonds in the molecule, ring bonds included.
+    pub max_bonds: usize,
+    /// Deepest nesting of branches.
+    pub max_branch_depth: usize,
+    /// Most ring bonds open at once.
+    pub max_open_rings: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            huckel_validation: cfg!(feature = "huckel-validation"),
+            allow_mismatched_ring_bonds: false,
+            whitespace_terminates: true,
+            allow_title: true,
+            max_charge: 15,
+            max_hydrogens: 9,
+            max_isotope: 999,
+            max_input_length: usize::MAX,
+            max_atoms: usize::MAX,
+            max_bonds: usize::MAX,
+            max_branch_depth: usize::MAX,
+            max_open_rings: usize::MAX,
+        }
+    }
+}
+
+/// Ring bonds left open: (node_index, bond_type_at_open, span of the bond and digits).
+type OpenRings = HashMap<u8, (NodeIndex, Option<BondType>, Span)>;
+
+/// A branch being parsed, from its `(` to its `)`.
+struct Branch {
+    open: usize,                                                // byte offset of the `(`
+    parent: Option<NodeIndex>,                                  // atom the branch hangs from
+    leading: bool, // written before any atom of its level, as in `(C)C`
+    pending_bond: Option<(BondType, Span)>, // bond written before the `(`, for after the `)`
+    start: NodeIndex, // first atom of the branch
+    deferred_ring_bonds: Vec<(NodeIndex, NodeIndex, BondType)>, // (opener, closer, bond_type)
+}
+
+/// A single-pass SMILES parser: branches are kept on a stack rather than
+/// parsed recursively, so that the work is linear in the input and deep
+/// nesting cannot overflow the stack.
 struct Parser<'a> {
     chars: Peekable<Chars<'a>>,
-    position: usize,
+    options: &'a ParseOptions,
+    position: usize, // Byte offset in the input
     builder: MoleculeBuilder,
-    next_bond_type: Option<BondType>,
-    next_bond_source: Option<NodeIndex>,
-    branch_bond_type: Option<BondType>,
-    cycles_target: HashMap<u8, (NodeIndex, Option<BondType>)>, // (node_index, bond_type_at_open)
-    node_offset: NodeIndex, // Offset for global node indexing (used in branches)
-    deferred_ring_bonds: Vec<(NodeIndex, NodeIndex, BondType)>, // (main_target, local_source, bond_type)
+    next_bond: Option<(BondType, Span)>, // bond symbol waiting for the next atom
+    leading_bond: Option<Span>,          // bond or dot written before the first atom
+    previous: Option<NodeIndex>,         // atom the next one bonds to
+    branches: Vec<Branch>,               // open branches, innermost last
+    cycles_target: OpenRings,
+    parents: Vec<Option<NodeIndex>>, // atom each atom was bonded to when written, by index
+    ring_bonded: HashSet<(NodeIndex, NodeIndex)>, // ring-bonded pairs, lower index first
+    bond_count: usize,
+    chiral_neighbours: HashMap<NodeIndex, Vec<NodeIndex>>, // written neighbour order of @/@@ atoms
+    atom_spans: Vec<Span>,                                 // span of every atom, by index
+}
+
+impl Lexer for Parser<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Placeholder for a ring-closure partner that is not known yet.
+fn ring_placeholder(cycle_number: u8) -> NodeIndex {
+    NodeIndex::MAX - cycle_number as NodeIndex
+}
+
+/// Returns the pair of atoms of a bond, lower index first.
+fn pair(a: NodeIndex, b: NodeIndex) -> (NodeIndex, NodeIndex) {
+    (a.min(b), a.max(b))
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
+    fn new(input: &'a str, options: &'a ParseOptions) -> Self {
         Parser {
             chars: input.chars().peekable(),
+            options,
             position: 0,
             builder: MoleculeBuilder::new(),
-            next_bond_type: None,
-            next_bond_source: None,
-            branch_bond_type: None,
+            next_bond: None,
+            leading_bond: None,
+            previous: None,
+            branches: Vec::new(),
             cycles_target: HashMap::new(),
-            node_offset: 0,
-            deferred_ring_bonds: Vec::new(),
+            parents: Vec::new(),
+            ring_bonded: HashSet::new(),
+            bond_count: 0,
+            chiral_neighbours: HashMap::new(),
+            atom_spans: Vec::new(),
         }
     }
 
-    fn new_with_offset(
-        input: &'a str,
-        position_offset: usize,
-        node_offset: NodeIndex,
-        cycles_target: HashMap<u8, (NodeIndex, Option<BondType>)>,
-    ) -> Self {
-        Parser {
-            chars: input.chars().peekable(),
-            position: position_offset,
-            builder: MoleculeBuilder::new(),
-            next_bond_type: None,
-            next_bond_source: None,
-            branch_bond_type: None,
-            cycles_target,
-            node_offset,
-            deferred_ring_bonds: Vec::new(),
-        }
+    /// Returns the span of `c`, just consumed.
+    fn char_span(&self, c: char) -> Span {
+        self.span_from(self.position - c.len_utf8())
     }
 
-    fn next(&mut self) -> Option<char> {
-        self.position += 1;
-        self.chars.next()
+    /// Returns the first atom of the innermost open branch, or of the
+    /// molecule.
+    fn level_start(&self) -> NodeIndex {
+        self.branches.last().map_or(0, |branch| branch.start)
     }
 
-    fn peek(&mut self) -> Option<&char> {
-        self.chars.peek()
+    /// Returns the number of atoms parsed so far.
+    fn atom_count(&self) -> NodeIndex {
+        // `count_atom` keeps the count within `NodeIndex`.
+        self.builder.nodes().len() as NodeIndex
+    }
+
+    /// Returns whether two atoms are both aromatic, and so joined by an
+    /// aromatic bond when no bond symbol is written.
+    fn both_aromatic(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        let nodes = self.builder.nodes();
+        nodes[a as usize].aromatic() == Some(true) && nodes[b as usize].aromatic() == Some(true)
     }
 
     #[allow(clippy::type_complexity)]
@@ -68,37 +193,45 @@ impl<'a> Parser<'a> {
     ) -> Result<
         (
             MoleculeBuilder,
-            Option<BondType>,                           // branch_bond_type
-            Option<BondType>, // next_bond_type (for dangling bond detection)
-            HashMap<u8, (NodeIndex, Option<BondType>)>, // cycles_target
-            Vec<(NodeIndex, NodeIndex, BondType)>, // deferred_ring_bonds
+            HashMap<NodeIndex, Vec<NodeIndex>>, // chiral_neighbours
+            Vec<Span>,                          // atom_spans
         ),
         ParserError,
     > {
         while let Some(c) = self.next() {
             // Atom
+            let start = self.position - c.len_utf8();
             if c.is_ascii_alphabetic() || c == '*' {
                 let elem = self.parse_element_symbol(c, false)?;
                 // Aromaticity is indicated by lowercase letters (c, n, o, etc.)
                 // Wildcard '*' outside brackets is non-aromatic by default
                 let aromatic = Some(c.is_ascii_lowercase());
+                let span = self.span_from(start);
+                self.count_atom(span)?;
                 self.builder
-                    .add_atom(elem, 0, None, aromatic, None, None, None)?;
+                    .add_atom(elem, 0, None, aromatic, None, None, None)
+                    .map_err(|e| ParserError::NodeError(e, span))?;
+                self.atom_spans.push(span);
                 self.connect_current_atom()?;
             // Brackets Atom
             } else if c == '[' {
                 let (elem, charge, isotope, aromatic, hydrogen, class, chirality) =
-                    self.parse_bracket_atom()?;
+                    self.parse_bracket_atom(start)?;
+                let span = self.span_from(start);
+                self.check_limits(charge, isotope, hydrogen, span)?;
+                self.count_atom(span)?;
                 self.builder
-                    .add_atom(elem, charge, isotope, aromatic, hydrogen, class, chirality)?;
+                    .add_atom(elem, charge, isotope, aromatic, hydrogen, class, chirality)
+                    .map_err(|e| ParserError::NodeError(e, span))?;
+                self.atom_spans.push(span);
                 self.connect_current_atom()?;
 
             // Dot separator (disconnected fragments) — resets the chain
             } else if c == '.' {
-                self.next_bond_type = None;
-                self.next_bond_source = None;
+                self.next_bond = None;
+                self.previous = None;
                 if self.builder.nodes().is_empty() {
-                    self.branch_bond_type = Some(BondType::Disconnected);
+                    self.leading_bond = Some(self.char_span(c));
                 }
 
             // Explicit bond
@@ -110,265 +243,250 @@ impl<'a> Parser<'a> {
                 || c == '/'
                 || c == '\\'
             {
-                self.next_bond_type = Some(BondType::try_from(&c)?);
+                let span = self.char_span(c);
+                let kind = BondType::try_from(&c).map_err(|e| ParserError::BondError(e, span))?;
+                self.next_bond = Some((kind, span));
                 if self.builder.nodes().is_empty() {
-                    self.branch_bond_type = self.next_bond_type;
+                    self.leading_bond = Some(span);
                 }
 
             // Branches
             } else if c == '(' {
-                self.parse_branch()?;
+                self.open_branch(c, start)?;
+            } else if c == ')' {
+                self.close_branch(c, start)?;
             // cycles
             } else if c == '%' || c.is_ascii_digit() {
                 let cycle_number: u8 = if c == '%' {
-                    let first = self.next().ok_or(ParserError::UnexpectedEndOfInput(
-                        "cycle number".to_string(),
-                    ))?;
-                    let second = self.next().ok_or(ParserError::UnexpectedEndOfInput(
-                        "cycle number".to_string(),
-                    ))?;
-                    let first_u8: u8 = first
-                        .to_digit(10)
-                        .ok_or(ParserError::UnexpectedCharacter(first, self.position))?
-                        as u8;
-                    let second_u8: u8 = second
-                        .to_digit(10)
-                        .ok_or(ParserError::UnexpectedCharacter(second, self.position))?
-                        as u8;
+                    let mut digit = || {
+                        let d = self.next().ok_or_else(|| {
+                            ParserError::UnexpectedEndOfInput(
+                                "cycle number".to_string(),
+                                Span::at(self.position),
+                            )
+                        })?;
+                        d.to_digit(10)
+                            .map(|d| d as u8)
+                            .ok_or_else(|| ParserError::UnexpectedCharacter(d, self.char_span(d)))
+                    };
+                    let first_u8 = digit()?;
+                    let second_u8 = digit()?;
                     first_u8 * 10 + second_u8
                 } else {
                     c.to_digit(10).expect("Unreachable error") as u8
                 };
+                // The ring bond, with the bond symbol written before it
+                let ring_span = match self.next_bond {
+                    Some((_, span)) => span.to(self.span_from(start)),
+                    None => self.span_from(start),
+                };
 
                 // If the key already exists, close the ring
-                if let Some((target, bond_type_at_open)) =
+                if let Some((target, bond_type_at_open, open_span)) =
                     self.cycles_target.get(&cycle_number).copied()
                 {
-                    let local_index = self.get_current_atom_index()?;
-                    let global_source = self.node_offset + local_index;
-                    let bond_type_at_close = self.next_bond_type.take();
+                    // The ring closes on the last atom written at this level
+                    if self.atom_count() <= self.level_start() {
+                        return Err(ParserError::UnexpectedCharacter(c, self.span_from(start)));
+                    }
+                    let source = self.atom_count() - 1;
+                    let bond_type_at_close = self.next_bond.take().map(|(kind, _)| kind);
 
                     // An atom cannot be bonded to itself (e.g., C11)
-                    if global_source == target {
-                        return Err(ParserError::SelfBond(cycle_number));
+                    if source == target {
+                        return Err(ParserError::SelfBond(cycle_number, open_span, ring_span));
                     }
 
                     // Two atoms cannot be joined by more than one bond (e.g., C12CCCCC12)
-                    if self.has_bond_between(target, global_source) {
-                        return Err(ParserError::DuplicateBond(target, global_source));
+                    if self.is_bonded(target, source) {
+                        return Err(ParserError::DuplicateBond(
+                            target, source, open_span, ring_span,
+                        ));
                     }
 
                     // Validate ring bond types match if both are explicitly specified
+                    let mut bond_type_at_close = bond_type_at_close;
                     if let (Some(open), Some(close)) = (bond_type_at_open, bond_type_at_close) {
                         if open != close {
-                            return Err(ParserError::MismatchedRingBond(cycle_number));
+                            if !self.options.allow_mismatched_ring_bonds {
+                                return Err(ParserError::MismatchedRingBond(
+                                    cycle_number,
+                                    open_span,
+                                    ring_span,
+                                ));
+                            }
+                            bond_type_at_close = None;
+                        }
+                    }
+                    self.count_bond(ring_span)?;
+
+                    // A directional symbol reads from the atom it is written on to the
+                    // partner: `C/1...C1` gives opener → closer.
+                    let flip = |kind: BondType| match kind {
+                        BondType::Up => BondType::Down,
+                        BondType::Down => BondType::Up,
+                        other => other,
+                    };
+
+                    if let Some(order) = self.chiral_neighbours.get_mut(&source) {
+                        order.push(target);
+                    }
+                    if let Some(order) = self.chiral_neighbours.get_mut(&target) {
+                        if let Some(slot) = order
+                            .iter_mut()
+                            .find(|slot| **slot == ring_placeholder(cycle_number))
+                        {
+                            *slot = source;
                         }
                     }
 
-                    // Check if the target is from a parent parser (before our node_offset)
-                    if target < self.node_offset {
-                        // Defer this bond - it connects to a node in the parent
-                        // Use explicit bond type if specified, otherwise Simple
-                        // (aromaticity will be determined by parent when creating the bond)
-                        let ring_bond_type = bond_type_at_open
-                            .or(bond_type_at_close)
-                            .unwrap_or(BondType::Simple);
-                        self.deferred_ring_bonds
-                            .push((target, local_index, ring_bond_type));
+                    let implicit = if self.both_aromatic(source, target) {
+                        BondType::Aromatic
                     } else {
-                        // Target is within this parser's nodes
-                        // Determine bond type from explicit specification or aromaticity
-                        let ring_bond_type =
-                            bond_type_at_open.or(bond_type_at_close).unwrap_or_else(|| {
-                                // Adjust target index for local builder space
-                                let local_target = target - self.node_offset;
-                                let source_aromatic =
-                                    self.builder.nodes()[local_index as usize].aromatic();
-                                let target_aromatic =
-                                    self.builder.nodes()[local_target as usize].aromatic();
-                                if source_aromatic == Some(true) && target_aromatic == Some(true) {
-                                    BondType::Aromatic
-                                } else {
-                                    BondType::Simple
-                                }
-                            });
-                        self.connect_ring_closure(target, ring_bond_type)?;
+                        BondType::Simple
+                    };
+                    self.ring_bonded.insert(pair(target, source));
+                    match self.branches.last_mut() {
+                        // A ring opened before the branch is bonded when the
+                        // branch closes, opener → closer.
+                        Some(branch) if target < branch.start => {
+                            let ring_bond_type = bond_type_at_open
+                                .or(bond_type_at_close.map(flip))
+                                .unwrap_or(implicit);
+                            branch
+                                .deferred_ring_bonds
+                                .push((target, source, ring_bond_type));
+                        }
+                        // Otherwise the bond is stored closer → opener.
+                        _ => {
+                            let ring_bond_type = bond_type_at_close
+                                .or(bond_type_at_open.map(flip))
+                                .unwrap_or(implicit);
+                            self.builder.add_bond(source, target, ring_bond_type);
+                        }
                     }
                     // Remove the key so it can be reused
                     self.cycles_target.remove(&cycle_number);
                 // Otherwise, this is the start of a new ring
                 } else {
-                    let local_index = self
-                        .next_bond_source
-                        .ok_or(ParserError::UnexpectedCharacter(c, self.position))?;
-                    let global_index = self.node_offset + local_index;
-                    let bond_type_at_open = self.next_bond_type.take();
+                    let level_start = self.level_start();
+                    let index = self
+                        .previous
+                        .filter(|&previous| previous >= level_start)
+                        .ok_or_else(|| {
+                            ParserError::UnexpectedCharacter(c, self.span_from(start))
+                        })?;
+                    if self.cycles_target.len() >= self.options.max_open_rings {
+                        return Err(ParserError::LimitExceeded {
+                            kind: LimitKind::OpenRings,
+                            limit: self.options.max_open_rings,
+                            span: ring_span,
+                        });
+                    }
+                    let bond_type_at_open = self.next_bond.take().map(|(kind, _)| kind);
+                    if let Some(order) = self.chiral_neighbours.get_mut(&index) {
+                        order.push(ring_placeholder(cycle_number));
+                    }
                     self.cycles_target
-                        .insert(cycle_number, (global_index, bond_type_at_open));
+                        .insert(cycle_number, (index, bond_type_at_open, ring_span));
                 }
             // Whitespace terminates the SMILES string (OpenSMILES spec)
-            } else if c == ' ' || c == '\t' || c == '\n' || c == '\r' {
+            } else if (c == ' ' || c == '\t' || c == '\n' || c == '\r')
+                && self.options.whitespace_terminates
+            {
+                if !self.options.allow_title {
+                    self.reject_title()?;
+                }
                 break;
             } else {
-                return Err(ParserError::UnexpectedCharacter(c, self.position));
+                return Err(ParserError::UnexpectedCharacter(c, self.char_span(c)));
             }
         }
 
-        Ok((
-            self.builder,
-            self.branch_bond_type,
-            self.next_bond_type,
-            self.cycles_target,
-            self.deferred_ring_bonds,
-        ))
-    }
-
-    fn parse_branch(&mut self) -> Result<(), ParserError> {
-        let mut s = String::new();
-        let mut parenthesis_count: i32 = 1;
-        let position = self.position;
-        while let Some(c) = self.next() {
-            if c == '(' {
-                parenthesis_count += 1;
-            }
-
-            if c == ')' {
-                parenthesis_count -= 1;
-
-                if parenthesis_count == 0 {
-                    break;
-                }
-            }
-            s.push(c);
-        }
-        if parenthesis_count > 0 {
-            return Err(ParserError::UnclosedParenthesis);
-        }
-        if parenthesis_count < 0 {
-            return Err(ParserError::UnopenedParenthesis);
+        if let Some(branch) = self.branches.first() {
+            return Err(ParserError::UnclosedParenthesis(Span::new(
+                branch.open,
+                branch.open + 1,
+            )));
         }
-        if s.is_empty() {
-            return Err(ParserError::EmptyBranch);
+
+        // Check for unclosed rings
+        if !self.cycles_target.is_empty() {
+            let mut open: Vec<(Span, u8)> = self
+                .cycles_target
+                .into_iter()
+                .map(|(ring, (_, _, span))| (span, ring))
+                .collect();
+            open.sort_unstable_by_key(|(span, _)| span.start);
+            let (spans, rings) = open.into_iter().unzip();
+            return Err(ParserError::UnclosedRing(rings, spans));
         }
 
-        // Calculate the global node offset for the branch
-        let branch_node_offset = self
-            .node_offset
-            .checked_add(self.builder.nodes().len() as NodeIndex)
-            .ok_or(ParserError::TooManyNodes)?;
-
-        // Pass cycles_target to branch so rings can span branch boundaries
-        let branch_parser = Parser::new_with_offset(
-            &s,
-            position,
-            branch_node_offset,
-            std::mem::take(&mut self.cycles_target),
-        );
-        let (branch_builder, branch_bond_type, _, updated_cycles, deferred_bonds) =
-            branch_parser.parse()?;
-
-        // Restore the updated cycles_target
-        self.cycles_target = updated_cycles;
-
-        // Add the branch to the main builder
-        // A dot at the start of a branch means disconnected — no bond to parent
-        let bond_type = branch_bond_type.unwrap_or(BondType::Simple);
-        let connect_source = if bond_type == BondType::Disconnected {
-            None
-        } else {
-            self.next_bond_source
-        };
-        self.builder
-            .add_branch(branch_builder, bond_type, connect_source);
-
-        // Create deferred ring bonds (rings opened in parent, closed in branch)
-        for (main_target, branch_local_source, mut ring_bond_type) in deferred_bonds {
-            // branch_local_source needs to be adjusted to main molecule space
-            let main_source = branch_node_offset + branch_local_source;
-
-            // Fix: if no explicit bond type was specified and both atoms are aromatic,
-            // the implicit bond should be Aromatic, not Simple
-            if ring_bond_type == BondType::Simple {
-                let target_aromatic = self.builder.nodes()[main_target as usize].aromatic();
-                let source_aromatic = self.builder.nodes()[main_source as usize].aromatic();
-                if target_aromatic == Some(true) && source_aromatic == Some(true) {
-                    ring_bond_type = BondType::Aromatic;
-                }
-            }
+        // Check for bond at start (e.g., "-C")
+        if let Some(span) = self.leading_bond {
+            return Err(ParserError::BondWithoutPrecedingAtom(span));
+        }
 
-            self.builder
-                .add_bond(main_target, main_source, ring_bond_type);
+        // Check for dangling bond at end (e.g., "C=")
+        if let Some((_, span)) = self.next_bond {
+            return Err(ParserError::BondWithoutFollowingAtom(span));
         }
 
-        if self.next_bond_source.is_none() {
-            self.next_bond_source = Some(0);
+        Ok((self.builder, self.chiral_neighbours, self.atom_spans))
+    }
+
+    /// Opens a branch on the previous atom; `c` is its `(`, at byte `open`.
+    fn open_branch(&mut self, c: char, open: usize) -> Result<(), ParserError> {
+        if self.branches.len() >= self.options.max_branch_depth {
+            return Err(ParserError::LimitExceeded {
+                kind: LimitKind::BranchDepth,
+                limit: self.options.max_branch_depth,
+                span: self.char_span(c),
+            });
         }
+        self.branches.push(Branch {
+            open,
+            parent: self.previous,
+            leading: self.atom_count() == self.level_start(),
+            pending_bond: self.next_bond.take(),
+            start: self.atom_count(),
+            deferred_ring_bonds: Vec::new(),
+        });
         Ok(())
     }
 
-    /// Parse element symbol. `in_bracket` controls whether all two-letter elements
-    /// are allowed (true) or only organic subset Cl/Br (false).
-    ///
-    /// Returns the parsed `AtomSymbol` directly, avoiding intermediate String allocations.
-    fn parse_element_symbol(
-        &mut self,
-        c: char,
-        in_bracket: bool,
-    ) -> Result<AtomSymbol, ParserError> {
-        if c == '*' {
-            return Ok(AtomSymbol::Wildcard);
+    /// Closes the innermost branch; `c` is its `)`, at byte `close`.
+    fn close_branch(&mut self, c: char, close: usize) -> Result<(), ParserError> {
+        let Some(branch) = self.branches.pop() else {
+            return Err(ParserError::UnexpectedCharacter(c, self.char_span(c)));
+        };
+        if close == branch.open + 1 {
+            return Err(ParserError::EmptyBranch(self.span_from(branch.open)));
         }
-
-        if c.is_ascii_uppercase() {
-            if let Some(&next_c) = self.peek() {
-                if next_c.is_ascii_lowercase() {
-                    if in_bracket {
-                        // In brackets, all valid two-letter elements are allowed
-                        let buf = [c as u8, next_c as u8];
-                        let two_letter = std::str::from_utf8(&buf).unwrap();
-                        if let Ok(sym) = AtomSymbol::from_str(two_letter) {
-                            self.next();
-                            return Ok(sym);
-                        }
-                    } else {
-                        // Outside brackets, only Cl and Br are valid two-letter elements
-                        match (c, next_c) {
-                            ('C', 'l') => {
-                                self.next();
-                                return Ok(AtomSymbol::Organic(OrganicAtom::Cl));
-                            }
-                            ('B', 'r') => {
-                                self.next();
-                                return Ok(AtomSymbol::Organic(OrganicAtom::Br));
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        } else if in_bracket && c.is_ascii_lowercase() {
-            // Aromatic two-letter symbols: se, as, te (OpenSMILES spec)
-            if let Some(&next_c) = self.peek() {
-                if next_c.is_ascii_lowercase() {
-                    let buf = [c as u8, next_c as u8];
-                    let two_letter = std::str::from_utf8(&buf).unwrap();
-                    if let Ok(sym) = AtomSymbol::from_str(two_letter) {
-                        self.next();
-                        return Ok(sym);
-                    }
-                }
-            }
+        if let Some((_, span)) = self.next_bond {
+            return Err(ParserError::BondWithoutFollowingAtom(span));
         }
-
-        // Single character element
-        let buf = [c.to_ascii_uppercase() as u8];
-        let s = std::str::from_utf8(&buf).unwrap();
-        AtomSymbol::from_str(s).map_err(|e| ParserError::NodeError(NodeError::AtomError(e)))
+        // Ring bonds closed in the branch to atoms before it come after the
+        // bonds of the branch.
+        for (opener, closer, bond_type) in branch.deferred_ring_bonds {
+            self.builder.add_bond(opener, closer, bond_type);
+        }
+        // The chain goes on from the parent, or else from the branch itself.
+        self.previous =
+            if (branch.leading || branch.parent.is_none()) && self.atom_count() > branch.start {
+                Some(branch.start)
+            } else {
+                branch.parent
+            };
+        self.next_bond = branch.pending_bond;
+        Ok(())
     }
 
+    /// Parses a bracket atom whose `[` is at byte `start`.
     #[allow(clippy::type_complexity)]
     fn parse_bracket_atom(
         &mut self,
+        start: usize,
     ) -> Result<
         (
             AtomSymbol,
@@ -383,11 +501,16 @@ impl<'a> Parser<'a> {
     > {
         let isotope = self.parse_isotope();
 
-        let first_char = self.next().ok_or(ParserError::UnexpectedEndOfInput(
-            "Element identifier".to_string(),
-        ))?;
+        let first_char = self.next().ok_or_else(|| {
+            ParserError::UnexpectedEndOfInput(
+                "Element identifier".to_string(),
+                Span::at(self.position),
+            )
+        })?;
         if !first_char.is_alphabetic() && first_char != '*' {
-            return Err(ParserError::MissingElementInBracketAtom);
+            return Err(ParserError::MissingElementInBracketAtom(
+                self.span_from(start),
+            ));
         }
         let elem = self.parse_element_symbol(first_char, true)?;
 
@@ -414,7 +537,7 @@ impl<'a> Parser<'a> {
                 }
                 Some(&c) => {
                     self.next();
-                    return Err(ParserError::UnexpectedCharacter(c, self.position));
+                    return Err(ParserError::UnexpectedCharacter(c, self.char_span(c)));
                 }
             }
         }
@@ -423,15 +546,20 @@ impl<'a> Parser<'a> {
 
         match self.next() {
             Some(']') => (),
-            None => Err(ParserError::UnexpectedEndOfInput("]".to_string()))?,
-            Some(c) => Err(ParserError::UnexpectedCharacter(c, self.position))?,
+            None => Err(ParserError::UnexpectedEndOfInput(
+                "]".to_string(),
+                Span::at(self.position),
+            ))?,
+            Some(c) => Err(ParserError::UnexpectedCharacter(c, self.char_span(c)))?,
         }
 
         // A hydrogen atom cannot have a hydrogen count (e.g., [HH1] is illegal)
         if elem == AtomSymbol::H {
             if let Some(h) = hydrogen {
                 if h > 0 {
-                    return Err(ParserError::HydrogenWithHydrogenCount);
+                    return Err(ParserError::HydrogenWithHydrogenCount(
+                        self.span_from(start),
+                    ));
                 }
             }
         }
@@ -442,277 +570,122 @@ impl<'a> Parser<'a> {
         Ok((elem, charge, isotope, aromatic, hydrogen, class, chirality))
     }
 
-    fn parse_isotope(&mut self) -> Option<u16> {
-        let mut builder = String::new();
-        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-            builder.push(self.next().unwrap());
-        }
-        builder.parse::<u16>().ok()
-    }
-
-    fn parse_class(&mut self) -> Option<u16> {
-        if self.peek().is_some_and(|c| *c == ':') {
-            self.next();
-            let mut builder = String::new();
-            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-                builder.push(self.next().unwrap());
-            }
-            builder.parse::<u16>().ok()
-        } else {
-            None
+    /// Checks a bracket atom against the limits of the options.
+    ///
+    /// Hydrogen counts are reported as molecule errors, like the other node
+    /// checks made when the molecule is built.
+    fn check_limits(
+        &self,
+        charge: i8,
+        isotope: Option<u16>,
+        hydrogens: Option<u8>,
+        span: Span,
+    ) -> Result<(), ParserError> {
+        if charge.unsigned_abs() > self.options.max_charge {
+            return Err(ParserError::NodeError(
+                AtomError::InvalidCharge(charge).into(),
+                span,
+            ));
         }
-    }
-
-    fn parse_chirality(&mut self) -> Result<Option<Chirality>, ParserError> {
-        if self.peek() != Some(&'@') {
-            return Ok(None);
+        if let Some(isotope) = isotope.filter(|&i| i > self.options.max_isotope) {
+            return Err(ParserError::NodeError(
+                AtomError::InvalidIsotope(isotope).into(),
+                span,
+            ));
         }
-        self.next(); // consume first '@'
-
-        match self.peek() {
-            Some(&'@') => {
-                self.next();
-                Ok(Some(Chirality::TH2))
-            }
-            Some(&'T') => {
-                self.next();
-                match self.next() {
-                    Some('H') => self.parse_chirality_index(1, 2, |n| match n {
-                        1 => Some(Chirality::TH1),
-                        2 => Some(Chirality::TH2),
-                        _ => None,
-                    }),
-                    Some('B') => self.parse_chirality_index(1, 20, |n| Chirality::tb(n as u8)),
-                    Some(c) => Err(ParserError::InvalidChiralitySpec(
-                        format!("@T{}", c),
-                        self.position,
-                    )),
-                    None => Err(ParserError::UnexpectedEndOfInput(
-                        "chirality class".to_string(),
-                    )),
-                }
-            }
-            Some(&'A') => {
-                self.next();
-                match self.next() {
-                    Some('L') => self.parse_chirality_index(1, 2, |n| match n {
-                        1 => Some(Chirality::AL1),
-                        2 => Some(Chirality::AL2),
-                        _ => None,
-                    }),
-                    Some(c) => Err(ParserError::InvalidChiralitySpec(
-                        format!("@A{}", c),
-                        self.position,
-                    )),
-                    None => Err(ParserError::UnexpectedEndOfInput(
-                        "chirality class".to_string(),
-                    )),
-                }
-            }
-            Some(&'S') => {
-                self.next();
-                match self.next() {
-                    Some('P') => self.parse_chirality_index(1, 3, |n| match n {
-                        1 => Some(Chirality::SP1),
-                        2 => Some(Chirality::SP2),
-                        3 => Some(Chirality::SP3),
-                        _ => None,
-                    }),
-                    Some(c) => Err(ParserError::InvalidChiralitySpec(
-                        format!("@S{}", c),
-                        self.position,
-                    )),
-                    None => Err(ParserError::UnexpectedEndOfInput(
-                        "chirality class".to_string(),
-                    )),
-                }
-            }
-            Some(&'O') => {
-                self.next();
-                match self.next() {
-                    Some('H') => self.parse_chirality_index(1, 30, |n| Chirality::oh(n as u8)),
-                    Some(c) => Err(ParserError::InvalidChiralitySpec(
-                        format!("@O{}", c),
-                        self.position,
-                    )),
-                    None => Err(ParserError::UnexpectedEndOfInput(
-                        "chirality class".to_string(),
-                    )),
-                }
-            }
-            _ => Ok(Some(Chirality::TH1)),
+        if let Some(hydrogens) = hydrogens.filter(|&h| h > self.options.max_hydrogens) {
+            return Err(ParserError::MoleculeError(
+                NodeError::InvalidHydrogen(hydrogens).into(),
+                span,
+            ));
         }
+        Ok(())
     }
 
-    /// Parse a chirality index (1 or 2 digit number) and map it via `f`.
-    /// Returns an error if the number is outside `[min, max]` or if `f` returns None.
-    fn parse_chirality_index(
-        &mut self,
-        min: u32,
-        max: u32,
-        f: impl FnOnce(u32) -> Option<Chirality>,
-    ) -> Result<Option<Chirality>, ParserError> {
-        let first = self.next().ok_or(ParserError::UnexpectedEndOfInput(
-            "chirality index".to_string(),
-        ))?;
-        let pos = self.position;
-        let first_digit = first
-            .to_digit(10)
-            .ok_or(ParserError::InvalidChiralityClass(first.to_string(), pos))?;
-
-        let n = if let Some(&next_c) = self.peek() {
-            if let Some(second_digit) = next_c.to_digit(10) {
-                self.next();
-                first_digit * 10 + second_digit
-            } else {
-                first_digit
-            }
-        } else {
-            first_digit
-        };
-
-        if n < min || n > max {
-            return Err(ParserError::InvalidChiralityClass(
-                n.to_string(),
-                self.position,
-            ));
+    /// Checks that the atom at `span` stays within the atom limit.
+    fn count_atom(&self, span: Span) -> Result<(), ParserError> {
+        if self.atom_spans.len() >= NodeIndex::MAX as usize {
+            return Err(ParserError::TooManyNodes(span));
         }
-
-        f(n).map(Some)
-            .ok_or_else(|| ParserError::InvalidChiralityClass(n.to_string(), self.position))
+        if self.atom_spans.len() >= self.options.max_atoms {
+            return Err(ParserError::LimitExceeded {
+                kind: LimitKind::Atoms,
+                limit: self.options.max_atoms,
+                span,
+            });
+        }
+        Ok(())
     }
 
-    fn parse_hydrogen(&mut self) -> Result<Option<u8>, ParserError> {
-        match self.peek() {
-            None => Err(ParserError::UnexpectedEndOfInput("]".to_string())),
-            Some(&'H') => {
-                self.next();
-                let mut builder = String::new();
-                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-                    builder.push(self.next().unwrap());
-                }
-                if builder.is_empty() {
-                    Ok(Some(1))
-                } else {
-                    Ok(Some(
-                        builder
-                            .parse::<u8>()
-                            .map_err(|_| ParserError::HydrogenOutOfRange(builder))?,
-                    ))
-                }
-            }
-            _ => Ok(Some(0)),
+    /// Counts a bond, written at `span`, against the bond limit.
+    fn count_bond(&mut self, span: Span) -> Result<(), ParserError> {
+        if self.bond_count >= self.options.max_bonds {
+            return Err(ParserError::LimitExceeded {
+                kind: LimitKind::Bonds,
+                limit: self.options.max_bonds,
+                span,
+            });
         }
+        self.bond_count += 1;
+        Ok(())
     }
 
-    fn parse_charge(&mut self) -> Result<i8, ParserError> {
-        let mut charge: i8 = 0;
-        let mut builder = String::new();
-        while self
-            .peek()
-            .is_some_and(|c| c.is_ascii_digit() || *c == '+' || *c == '-')
-        {
-            match self.next() {
-                Some('+') => charge += 1,
-                Some('-') => charge -= 1,
-                Some(c) if c.is_ascii_digit() => {
-                    builder.push(c);
-                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-                        builder.push(self.next().unwrap());
-                    }
-                }
-                _ => (),
+    /// Consumes the rest of the input, which must be whitespace.
+    fn reject_title(&mut self) -> Result<(), ParserError> {
+        while let Some(c) = self.next() {
+            if !c.is_whitespace() {
+                let start = self.position - c.len_utf8();
+                while self.next().is_some() {}
+                return Err(ParserError::TitleNotAllowed(self.span_from(start)));
             }
         }
-
-        if builder.is_empty() {
-            Ok(charge)
-        } else if charge > 0 {
-            builder
-                .parse::<i8>()
-                .map_err(|_| ParserError::ChargeOutOfRange(builder))
-        } else if charge < 0 {
-            Ok(0 - builder
-                .parse::<i8>()
-                .map_err(|_| ParserError::ChargeOutOfRange(builder))?)
-        } else {
-            Err(ParserError::ChargeWithoutSign)
-        }
+        Ok(())
     }
 
     fn connect_current_atom(&mut self) -> Result<(), ParserError> {
-        if self.builder.nodes().is_empty() {
-            return Err(ParserError::NoAtomToBond);
+        let current = self.atom_count() - 1;
+        self.parents.push(self.previous);
+        if let Some(previous) = self.previous {
+            // The bond into a branch is written at its `(`.
+            let span = match self.branches.last() {
+                Some(branch) if branch.start == current => Span::new(branch.open, branch.open + 1),
+                _ => self.atom_spans[current as usize],
+            };
+            self.count_bond(span)?;
+            self.add_bond_between(previous, current);
+            if let Some(order) = self.chiral_neighbours.get_mut(&previous) {
+                order.push(current);
+            }
+        } else if let Some((_, span)) = self.next_bond {
+            // A bond after a dot (e.g., "C.=C") has no atom before it.
+            return Err(ParserError::BondWithoutFollowingAtom(span));
         }
-        let current_atom = self.get_current_atom_index()?;
-
-        if let Some(src) = self.next_bond_source {
-            self.add_bond_between(src, current_atom);
+        if is_tetrahedral(self.builder.nodes()[current as usize].chirality()) {
+            let mut order: Vec<NodeIndex> = self.previous.into_iter().collect();
+            // The atom itself stands for its implicit hydrogen (or lone pair).
+            order.push(current);
+            self.chiral_neighbours.insert(current, order);
         }
-        self.next_bond_source = Some(current_atom);
+        self.previous = Some(current);
         Ok(())
     }
 
-    fn connect_ring_closure(
-        &mut self,
-        target: NodeIndex,
-        bond_type: BondType,
-    ) -> Result<(), ParserError> {
-        if self.builder.nodes().is_empty() {
-            return Err(ParserError::NoAtomToBond);
-        }
-        let current_atom = self.get_current_atom_index()?;
-        // `target` is a global atom index; convert to local branch-builder index.
-        // This is always valid because `connect_ring_closure` is only called when
-        // `target >= self.node_offset` (see ring-closure dispatch above).
-        let local_target = target - self.node_offset;
-        self.builder.add_bond(current_atom, local_target, bond_type);
-        Ok(())
-    }
-
-    fn get_current_atom_index(&self) -> Result<NodeIndex, ParserError> {
-        let current_atom: NodeIndex = (self.builder.nodes().len() - 1)
-            .try_into()
-            .map_err(|_| ParserError::TooManyNodes)?;
-        Ok(current_atom)
+    /// Whether `a` and `b` are already bonded. Outside ring closures, an
+    /// atom is only ever bonded to the atom it was attached to.
+    fn is_bonded(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        let (first, last) = pair(a, b);
+        self.parents[last as usize] == Some(first) || self.ring_bonded.contains(&(first, last))
     }
 
     fn add_bond_between(&mut self, source: NodeIndex, target: NodeIndex) {
         // Explicit bonds take priority, otherwise determine implicit bond type
-        let bond_type = self.next_bond_type.take().unwrap_or(
-            if self.builder.nodes()[source as usize].aromatic() == Some(true)
-                && self.builder.nodes()[target as usize].aromatic() == Some(true)
-            {
-                BondType::Aromatic
-            } else {
-                BondType::Simple
-            },
-        );
-
+        let bond_type = match self.next_bond.take() {
+            Some((kind, _)) => kind,
+            None if self.both_aromatic(source, target) => BondType::Aromatic,
+            None => BondType::Simple,
+        };
         self.builder.add_bond(source, target, bond_type);
     }
-
-    /// Check if a bond already exists between two atoms (in either direction).
-    fn has_bond_between(&self, a: NodeIndex, b: NodeIndex) -> bool {
-        // Check local bonds
-        for bond in self.builder.bonds() {
-            let (s, t) = (
-                self.node_offset + bond.source(),
-                self.node_offset + bond.target(),
-            );
-            if (s == a && t == b) || (s == b && t == a) {
-                return true;
-            }
-        }
-        // Check deferred ring bonds
-        for &(target, source, _) in &self.deferred_ring_bonds {
-            let s = self.node_offset + source;
-            if (s == a && target == b) || (s == b && target == a) {
-                return true;
-            }
-        }
-        false
-    }
 }
 
 /// Parses a SMILES string into a [`Molecule`].
@@ -737,33 +710,83 @@ impl<'a> Parser<'a> {
 /// assert!(parse("C(C").is_err()); // unclosed parenthesis
 /// ```
 pub fn parse(input: &str) -> Result<Molecule, ParserError> {
-    let parser = Parser::new(input);
-    let (builder, branch_bond_type, next_bond_type, cycles_target, _) = parser.parse()?;
-
-    // Check for unclosed rings at the top level
-    if !cycles_target.is_empty() {
-        return Err(ParserError::UnclosedRing(
-            cycles_target.into_keys().collect(),
-        ));
-    }
+    parse_with(input, &ParseOptions::default())
+}
 
-    // Check for bond at start (e.g., "-C") - only invalid at top level
-    if branch_bond_type.is_some() {
-        return Err(ParserError::BondWithoutPrecedingAtom);
+/// Parses a SMILES string into a [`Molecule`] with the given options.
+///
+/// # Errors
+///
+/// Returns a [`ParserError`] if the input is not valid SMILES under
+/// `options`.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::{parse_with, BondType, ParseOptions};
+///
+/// let options = ParseOptions {
+///     allow_mismatched_ring_bonds: true,
+///     ..Default::default()
+/// };
+/// let molecule = parse_with("C=1CCCCC-1", &options).unwrap();
+/// assert_eq!(molecule.bonds().last().unwrap().kind(), BondType::Double);
+/// ```
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Molecule, ParserError> {
+    parse_inner(input, options, None)
+}
+
+/// Parses as [`parse_with`] does and, given `repairs`, applies the
+/// structural repairs of [`parse_lenient`](crate::parse_lenient) before
+/// building the molecule.
+pub(crate) fn parse_inner(
+    input: &str,
+    options: &ParseOptions,
+    repairs: Option<&mut Vec<Repair>>,
+) -> Result<Molecule, ParserError> {
+    check_input_length(input, options)?;
+    let (mut builder, chiral_neighbours, atom_spans) = Parser::new(input, options).parse()?;
+
+    builder.normalize_chirality(chiral_neighbours);
+    if let Some(repairs) = repairs {
+        repair_structure(&mut builder, &atom_spans, repairs);
     }
+    let molecule = builder
+        .build_locating()
+        .map_err(|(e, atom)| ParserError::MoleculeError(e, atom_spans[atom as usize]))?;
 
-    // Check for dangling bond at end (e.g., "C=")
-    // Note: if branch_bond_type was Some, we already returned above
-    if next_bond_type.is_some() {
-        return Err(ParserError::BondWithoutFollowingAtom);
+    if options.huckel_validation {
+        crate::ast::aromaticity::require_valid_aromaticity(&molecule)
+            .map_err(|e| locate(e, &atom_spans, input))?;
     }
 
-    let molecule = builder.build()?;
+    Ok(molecule)
+}
 
-    #[cfg(feature = "huckel-validation")]
-    {
-        crate::ast::aromaticity::require_valid_aromaticity(&molecule)?;
+/// Checks `input` against the input length limit, before parsing it.
+pub(crate) fn check_input_length(input: &str, options: &ParseOptions) -> Result<(), ParserError> {
+    if input.len() > options.max_input_length {
+        return Err(ParserError::LimitExceeded {
+            kind: LimitKind::InputLength,
+            limit: options.max_input_length,
+            span: Span::new(options.max_input_length, input.len()),
+        });
     }
+    Ok(())
+}
 
-    Ok(molecule)
+/// Turns a molecule error into a parser error spanning the atoms it names,
+/// or the whole input.
+fn locate(error: crate::MoleculeError, atom_spans: &[Span], input: &str) -> ParserError {
+    let atoms: &[NodeIndex] = match &error {
+        crate::MoleculeError::HuckelViolation { ring, .. } => ring,
+        crate::MoleculeError::KekulizationFailed { atoms } => atoms,
+        _ => &[],
+    };
+    let span = atoms
+        .iter()
+        .map(|&atom| atom_spans[atom as usize])
+        .reduce(Span::to)
+        .unwrap_or(Span::new(0, input.len()));
+    ParserError::MoleculeError(error, span)
 }
@@ -13,11 +13,19 @@
 //! assert_eq!(results.len(), 3);
 //! assert!(results.iter().all(|r| r.is_ok()));
 //! ```
+//!
+//! Files too large to hold in memory go through [`parse_stream`], which reads
+//! them in chunks and parses the chunks on the Rayon thread pool.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{channel, Receiver, Sender};
 
 use rayon::prelude::*;
 
 use crate::error::ParserError;
-use crate::parser::parse;
+use crate::parser::{parse, parse_with, ParseOptions};
+use crate::smi::{Line, SmilesReader, SmilesReaderOptions, SmilesRecord};
 use crate::Molecule;
 
 /// Parse multiple SMILES strings in parallel.
@@ -50,6 +58,29 @@ pub fn parse_batch(inputs: &[&str]) -> Vec<Result<Molecule, ParserError>> {
     inputs.par_iter().map(|s| parse(s)).collect()
 }
 
+/// Parse multiple SMILES strings in parallel with the given options.
+///
+/// # Example
+///
+/// ```rust
+/// use opensmiles::{parse_batch_with, ParseOptions};
+///
+/// let options = ParseOptions {
+///     allow_title: false,
+///     ..Default::default()
+/// };
+/// let results = parse_batch_with(&["CCO", "CCO ethanol"], &options);
+///
+/// assert!(results[0].is_ok());
+/// assert!(results[1].is_err());
+/// ```
+pub fn parse_batch_with(
+    inputs: &[&str],
+    options: &ParseOptions,
+) -> Vec<Result<Molecule, ParserError>> {
+    inputs.par_iter().map(|s| parse_with(s, options)).collect()
+}
+
 /// Parse multiple SMILES strings in parallel, returning only successful results.
 ///
 /// This is useful when you want to process a large dataset and filter out invalid entries.
@@ -105,9 +136,23 @@ pub struct BatchParseStats {
     pub error_count: usize,
     /// Total number of inputs
     pub total_count: usize,
+    /// Number of failed parses by [error kind](ParserError::kind)
+    pub error_kinds: BTreeMap<&'static str, usize>,
 }
 
 impl BatchParseStats {
+    /// Counts one parse result.
+    pub fn add(&mut self, result: &Result<Molecule, ParserError>) {
+        self.total_count += 1;
+        match result {
+            Ok(_) => self.success_count += 1,
+            Err(error) => {
+                self.error_count += 1;
+                *self.error_kinds.entry(error.kind()).or_insert(0) += 1;
+            }
+        }
+    }
+
     /// Success rate as a percentage (0.0 to 100.0)
     pub fn success_rate(&self) -> f64 {
         if self.total_count == 0 {
@@ -138,23 +183,251 @@ pub fn parse_batch_with_stats(
 
     let mut molecules = Vec::new();
     let mut errors = Vec::new();
+    let mut stats = BatchParseStats::default();
 
     for (i, result) in results {
+        stats.add(&result);
         match result {
             Ok(mol) => molecules.push(mol),
             Err(e) => errors.push((i, e)),
         }
     }
 
-    let stats = BatchParseStats {
-        success_count: molecules.len(),
-        error_count: errors.len(),
-        total_count: inputs.len(),
-    };
-
     (molecules, errors, stats)
 }
 
+/// Options for [`parse_stream`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamOptions {
+    /// How lines are split into the SMILES and the other columns, and how
+    /// the SMILES are parsed.
+    pub reader: SmilesReaderOptions,
+    /// Number of lines parsed together as one task.
+    pub chunk_size: usize,
+    /// Number of chunks read ahead of the consumer, at most. Reading stops
+    /// while that many chunks are being parsed or wait to be consumed, so
+    /// that about `chunk_size * max_chunks` records are held in memory
+    /// whatever the size of the input.
+    pub max_chunks: usize,
+    /// Yield the records in input order. When off, a chunk is yielded as
+    /// soon as it is parsed, so a slow line does not hold back the others.
+    pub ordered: bool,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            reader: SmilesReaderOptions::default(),
+            chunk_size: 1024,
+            max_chunks: 4 * rayon::current_num_threads(),
+            ordered: true,
+        }
+    }
+}
+
+/// Parses a SMILES file in parallel, chunk by chunk, with bounded memory.
+///
+/// Lines are read and split as by [`SmilesReader`] on the calling thread,
+/// and parsed on the Rayon thread pool. The returned iterator yields one
+/// record per molecule line, in input order unless
+/// [`StreamOptions::ordered`] is off; an I/O error is yielded after the
+/// records read before it and ends the iteration.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use opensmiles::{parse_stream, StreamOptions};
+///
+/// let file = "CCO ethanol\nC1CC broken\nc1ccccc1 benzene\n".repeat(1000);
+/// let mut stream = parse_stream(Cursor::new(file), StreamOptions::default());
+/// let titles: Vec<_> = stream
+///     .by_ref()
+///     .take(3)
+///     .map(|record| record.unwrap().title.unwrap())
+///     .collect();
+/// assert_eq!(titles, ["ethanol", "broken", "benzene"]);
+///
+/// assert_eq!(stream.by_ref().count(), 2997);
+/// assert_eq!(stream.stats().error_count, 1000);
+/// assert_eq!(stream.stats().error_kinds["UnclosedRing"], 1000);
+/// ```
+pub fn parse_stream<R: BufRead>(reader: R, options: StreamOptions) -> ParseStream<R> {
+    let (sender, receiver) = channel();
+    ParseStream {
+        parse: options.reader.parse.clone(),
+        reader: SmilesReader::with_options(reader, options.reader),
+        chunk_size: options.chunk_size.max(1),
+        max_chunks: options.max_chunks.max(1),
+        ordered: options.ordered,
+        sender,
+        receiver,
+        read_chunks: 0,
+        yielded_chunks: 0,
+        in_flight: 0,
+        parsed: BTreeMap::new(),
+        current: Vec::new().into_iter(),
+        error: None,
+        exhausted: false,
+        stats: BatchParseStats::default(),
+    }
+}
+
+/// Parses a SMILES file as [`parse_stream`] does, calling `callback` with
+/// every record, and returns the statistics of the whole file.
+///
+/// # Errors
+///
+/// Returns the first I/O error, after the records read before it have been
+/// handed to `callback`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use opensmiles::{parse_stream_with, StreamOptions};
+///
+/// let mut atoms = 0;
+/// let stats = parse_stream_with(Cursor::new("CCO\nC(\nCC\n"), StreamOptions::default(), |record| {
+///     if let Ok(molecule) = record.molecule {
+///         atoms += molecule.nodes().len();
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(atoms, 5);
+/// assert_eq!(stats.success_count, 2);
+/// ```
+pub fn parse_stream_with<R, F>(
+    reader: R,
+    options: StreamOptions,
+    mut callback: F,
+) -> io::Result<BatchParseStats>
+where
+    R: BufRead,
+    F: FnMut(SmilesRecord),
+{
+    let mut stream = parse_stream(reader, options);
+    for record in stream.by_ref() {
+        callback(record?);
+    }
+    Ok(stream.stats)
+}
+
+/// Iterator over the records of a SMILES file, parsed in parallel; see
+/// [`parse_stream`].
+pub struct ParseStream<R> {
+    reader: SmilesReader<R>,
+    parse: ParseOptions,
+    chunk_size: usize,
+    max_chunks: usize,
+    ordered: bool,
+    sender: Sender<(usize, Vec<SmilesRecord>)>,
+    receiver: Receiver<(usize, Vec<SmilesRecord>)>,
+    /// Number of chunks sent to the thread pool.
+    read_chunks: usize,
+    /// Number of chunks taken as `current`.
+    yielded_chunks: usize,
+    /// Chunks read but not taken as `current` yet.
+    in_flight: usize,
+    /// Parsed chunks by index, waiting for their turn when ordered.
+    parsed: BTreeMap<usize, Vec<SmilesRecord>>,
+    current: std::vec::IntoIter<SmilesRecord>,
+    /// I/O error to yield once the records read before it are out.
+    error: Option<io::Error>,
+    exhausted: bool,
+    stats: BatchParseStats,
+}
+
+impl<R: BufRead> ParseStream<R> {
+    /// Returns the statistics of the records yielded so far.
+    pub fn stats(&self) -> &BatchParseStats {
+        &self.stats
+    }
+
+    /// Returns the number of lines read so far, which runs ahead of the
+    /// records yielded by up to [`StreamOptions::max_chunks`] chunks.
+    pub fn lines_read(&self) -> usize {
+        self.reader.lines_read()
+    }
+
+    /// Returns the number of bytes read so far, to report progress against
+    /// the size of a file.
+    pub fn bytes_read(&self) -> u64 {
+        self.reader.bytes_read()
+    }
+
+    /// Reads and sends chunks to the thread pool until `max_chunks` are in
+    /// flight or the input ends.
+    fn fill(&mut self) {
+        while !self.exhausted && self.in_flight < self.max_chunks {
+            let mut chunk: Vec<Line> = Vec::with_capacity(self.chunk_size);
+            while chunk.len() < self.chunk_size {
+                match self.reader.next_line() {
+                    Some(Ok(line)) => chunk.push(line),
+                    Some(Err(error)) => {
+                        self.error = Some(error);
+                        self.exhausted = true;
+                        break;
+                    }
+                    None => {
+                        self.exhausted = true;
+                        break;
+                    }
+                }
+            }
+            if chunk.is_empty() {
+                break;
+            }
+            let index = self.read_chunks;
+            let sender = self.sender.clone();
+            let options = self.parse.clone();
+            rayon::spawn(move || {
+                let records = chunk.into_iter().map(|line| line.parse(&options)).collect();
+                // The stream may have been dropped; its records are not wanted.
+                let _ = sender.send((index, records));
+            });
+            self.read_chunks += 1;
+            self.in_flight += 1;
+        }
+    }
+
+    /// Waits for the next chunk to yield.
+    fn next_chunk(&mut self) -> Vec<SmilesRecord> {
+        let wanted = self.yielded_chunks;
+        loop {
+            if self.ordered {
+                if let Some(records) = self.parsed.remove(&wanted) {
+                    return records;
+                }
+            } else if let Some((_, records)) = self.parsed.pop_first() {
+                return records;
+            }
+            let (index, records) = self.receiver.recv().expect("the stream holds a sender");
+            self.parsed.insert(index, records);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ParseStream<R> {
+    type Item = io::Result<SmilesRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.current.next() {
+                self.stats.add(&record.molecule);
+                return Some(Ok(record));
+            }
+            self.fill();
+            if self.in_flight == 0 {
+                return self.error.take().map(Err);
+            }
+            self.current = self.next_chunk().into_iter();
+            self.yielded_chunks += 1;
+            self.in_flight -= 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +454,22 @@ mod tests {
         assert!(results[2].is_ok());
     }
 
+    #[test]
+    fn test_parse_batch_with_options() {
+        let inputs = vec!["[NH4+]", "[Fe+3]", "C=1CC-1"];
+        let options = ParseOptions {
+            max_charge: 2,
+            allow_mismatched_ring_bonds: true,
+            ..Default::default()
+        };
+        let results = parse_batch_with(&inputs, &options);
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert!(parse_batch(&inputs)[2].is_err());
+    }
+
     #[test]
     fn test_parse_batch_ok() {
         let inputs = vec!["C", "invalid[", "CC", "also-bad"];
@@ -285,10 +574,121 @@ mod tests {
             success_count: 3,
             error_count: 1,
             total_count: 4,
+            ..Default::default()
         };
         assert!((stats.success_rate() - 75.0).abs() < f64::EPSILON);
 
         let empty_stats = BatchParseStats::default();
         assert_eq!(empty_stats.success_rate(), 0.0);
     }
+
+    fn file(lines: usize) -> String {
+        (0..lines)
+            .map(|i| match i % 5 {
+                0 => format!("C1CC line{}\n", i),
+                1 => format!("C(C line{}\n", i),
+                _ => format!("{} line{}\n", "C".repeat(1 + i % 9), i),
+            })
+            .collect()
+    }
+
+    fn small_chunks(ordered: bool) -> StreamOptions {
+        StreamOptions {
+            chunk_size: 7,
+            max_chunks: 3,
+            ordered,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_stream_matches_the_reader() {
+        let text = file(500);
+        let expected: Vec<_> = SmilesReader::new(io::Cursor::new(&text))
+            .map(Result::unwrap)
+            .collect();
+        let ordered: Vec<_> = parse_stream(io::Cursor::new(&text), small_chunks(true))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(ordered, expected);
+
+        let mut unordered: Vec<_> = parse_stream(io::Cursor::new(&text), small_chunks(false))
+            .map(Result::unwrap)
+            .collect();
+        unordered.sort_by_key(|record| record.line);
+        assert_eq!(unordered, expected);
+    }
+
+    #[test]
+    fn test_parse_stream_stats() {
+        let mut stream = parse_stream(io::Cursor::new(file(500)), small_chunks(true));
+        assert_eq!(stream.by_ref().count(), 500);
+        let stats = stream.stats();
+        assert_eq!(stats.total_count, 500);
+        assert_eq!(stats.success_count, 300);
+        assert_eq!(stats.error_kinds["UnclosedRing"], 100);
+        assert_eq!(stats.error_kinds["UnclosedParenthesis"], 100);
+        assert_eq!(stream.lines_read(), 500);
+        assert_eq!(stream.bytes_read(), file(500).len() as u64);
+    }
+
+    #[test]
+    fn test_parse_stream_reads_ahead_by_a_bounded_amount() {
+        let mut stream = parse_stream(io::Cursor::new(file(500)), small_chunks(true));
+        for yielded in 1..=500 {
+            stream.next().unwrap().unwrap();
+            assert!(
+                stream.lines_read() <= yielded + 7 * 4,
+                "{}",
+                stream.lines_read()
+            );
+        }
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_stream_with_callback() {
+        let mut lines = Vec::new();
+        let stats = parse_stream_with(io::Cursor::new(file(50)), small_chunks(true), |record| {
+            lines.push(record.line)
+        })
+        .unwrap();
+        assert_eq!(lines, (1..=50).collect::<Vec<_>>());
+        assert_eq!(stats.error_count, 20);
+    }
+
+    /// Fails after its first chunk of text.
+    struct Failing(Option<String>);
+
+    impl io::Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let text = self
+                .0
+                .take()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "gone"))?;
+            buf[..text.len()].copy_from_slice(text.as_bytes());
+            Ok(text.len())
+        }
+    }
+
+    #[test]
+    fn test_parse_stream_io_error_comes_last() {
+        let reader = io::BufReader::new(Failing(Some(file(20))));
+        let results: Vec<_> = parse_stream(reader, small_chunks(false)).collect();
+        assert_eq!(results.len(), 21);
+        assert!(results[..20].iter().all(Result::is_ok));
+        assert!(results[20].is_err());
+
+        let reader = io::BufReader::new(Failing(Some(file(20))));
+        let error = parse_stream_with(reader, small_chunks(true), |_| {}).unwrap_err();
+        assert_eq!(error.to_string(), "gone");
+    }
+
+    #[test]
+    fn test_batch_stats_error_kinds() {
+        let (_, _, stats) = parse_batch_with_stats(&["C", "C1", "C(", "C2", "CC"]);
+        assert_eq!(stats.error_kinds.len(), 2);
+        assert_eq!(stats.error_kinds["UnclosedRing"], 2);
+        assert_eq!(stats.error_kinds["UnclosedParenthesis"], 1);
+    }
 }
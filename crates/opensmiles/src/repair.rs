@@ -0,0 +1,407 @@
+//! Lenient parsing: common SMILES defects are repaired instead of rejected.
+//!
+//! [`parse_lenient`] applies the fixes below and reports each of them as a
+//! [`Repair`], with the span of the original input it concerns. Any other
+//! error is returned as [`parse_with`](crate::parse_with) would.
+//!
+//! | Defect | Example | Fix |
+//! |--------|---------|-----|
+//! | Ring bond never closed | `C1CC` | The digit, and the bond symbol before it, are dropped |
+//! | Branch never closed | `C(C` | A `)` is added at the end of the SMILES |
+//! | `)` without `(` | `CC)C` | The `)` is dropped |
+//! | Empty branch | `C()C` | The `()` are dropped |
+//! | Bond without atom on one side | `C=`, `-C` | The bond symbol is dropped |
+//! | Aromatic atom outside any ring | `Cc` | The atom and its bonds become aliphatic |
+//! | Carbon with more than four bonds | `C=C(C)(C)C` | Its multiple bonds, then its bracket hydrogens, are lowered |
+//!
+//! A carbon that is still over four bonds once its multiple bonds and
+//! hydrogens are gone, as in `C(C)(C)(C)(C)C`, is kept as written.
+
+use std::fmt;
+
+use crate::ast::rings::ring_atoms;
+use crate::error::{ParserError, Span};
+use crate::parser::{check_input_length, parse_inner, ParseOptions};
+use crate::{AtomSymbol, Bond, BondType, Molecule, MoleculeBuilder, NodeIndex, OrganicAtom};
+
+/// What a [`Repair`] changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RepairKind {
+    /// A ring bond that was never closed was dropped.
+    DroppedRingBond(u8),
+    /// A branch that was never closed was closed at the end of the SMILES.
+    ClosedBranch,
+    /// A `)` without matching `(` was dropped.
+    DroppedParenthesis,
+    /// An empty branch was dropped.
+    DroppedEmptyBranch,
+    /// A bond symbol without an atom on one side was dropped.
+    DroppedBond,
+    /// An aromatic atom outside any ring was made aliphatic.
+    Dearomatized,
+    /// The bond orders or bracket hydrogens of a carbon with more than four
+    /// bonds were lowered.
+    ReducedValence,
+}
+
+/// A fix applied by [`parse_lenient`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Repair {
+    /// What was changed.
+    pub kind: RepairKind,
+    /// The text of the input the fix concerns: what was dropped, the `(` of
+    /// a branch that was closed, or the atom that was changed.
+    pub span: Span,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RepairKind::DroppedRingBond(ring) => write!(f, "dropped unclosed ring {}", ring)?,
+            RepairKind::ClosedBranch => write!(f, "closed unclosed branch")?,
+            RepairKind::DroppedParenthesis => write!(f, "dropped unopened parenthesis")?,
+            RepairKind::DroppedEmptyBranch => write!(f, "dropped empty branch")?,
+            RepairKind::DroppedBond => write!(f, "dropped dangling bond")?,
+            RepairKind::Dearomatized => write!(f, "made acyclic aromatic atom aliphatic")?,
+            RepairKind::ReducedValence => write!(f, "reduced valence of carbon")?,
+        }
+        write!(f, " at {}", self.span)
+    }
+}
+
+/// Parses a SMILES string, repairing the defects listed in the [module
+/// documentation](self) instead of rejecting them.
+///
+/// Returns the molecule with the repairs applied, in the order they were
+/// made; a valid SMILES gives the same molecule as [`parse_with`](crate::parse_with) and no
+/// repair.
+///
+/// # Errors
+///
+/// Returns a [`ParserError`] if the input has a defect that cannot be
+/// repaired.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::{parse, parse_lenient, ParseOptions, RepairKind, Span};
+///
+/// let (molecule, repairs) = parse_lenient("C1CC(C", &ParseOptions::default()).unwrap();
+/// assert_eq!(molecule, parse("CCC(C)").unwrap());
+/// assert_eq!(repairs[0].kind, RepairKind::ClosedBranch);
+/// assert_eq!(repairs[0].span, Span::new(4, 5));
+/// assert_eq!(repairs[1].kind, RepairKind::DroppedRingBond(1));
+/// assert_eq!(repairs[1].span, Span::new(1, 2));
+/// ```
+pub fn parse_lenient(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Molecule, Vec<Repair>), ParserError> {
+    // Closing branches lengthens the text: only the input counts.
+    check_input_length(input, options)?;
+    let options = &ParseOptions {
+        max_input_length: usize::MAX,
+        ..options.clone()
+    };
+    let mut text = Text::new(input);
+    let mut repairs = Vec::new();
+    // Every round drops text or closes all branches, which happens once.
+    for _ in 0..input.len() + 2 {
+        let mut structural = Vec::new();
+        let error = match parse_inner(&text.text, options, Some(&mut structural)) {
+            Ok(molecule) => {
+                repairs.extend(structural.into_iter().map(|repair| Repair {
+                    span: text.original(repair.span),
+                    ..repair
+                }));
+                return Ok((molecule, repairs));
+            }
+            Err(error) => error,
+        };
+        let mut edits: Vec<(Span, RepairKind)> = match &error {
+            ParserError::UnclosedRing(rings, spans) => spans
+                .iter()
+                .zip(rings)
+                .map(|(span, ring)| (*span, RepairKind::DroppedRingBond(*ring)))
+                .collect(),
+            ParserError::UnexpectedCharacter(')', span)
+            | ParserError::UnopenedParenthesis(span) => {
+                // Every other `)` without `(` goes too, rather than one per
+                // round.
+                let mut unopened = text.scan(options).unopened;
+                if !unopened.contains(&span.start) {
+                    unopened = vec![span.start];
+                }
+                unopened
+                    .into_iter()
+                    .map(|i| (Span::new(i, i + 1), RepairKind::DroppedParenthesis))
+                    .collect()
+            }
+            ParserError::EmptyBranch(span) => vec![(*span, RepairKind::DroppedEmptyBranch)],
+            ParserError::BondWithoutPrecedingAtom(span)
+            | ParserError::BondWithoutFollowingAtom(span) => {
+                vec![(*span, RepairKind::DroppedBond)]
+            }
+            ParserError::UnclosedParenthesis(_) => {
+                let open = text.close_branches(options);
+                repairs.extend(open.into_iter().map(|span| Repair {
+                    kind: RepairKind::ClosedBranch,
+                    span,
+                }));
+                continue;
+            }
+            _ => return Err(error),
+        };
+        edits.sort_unstable_by_key(|(span, _)| span.start);
+        repairs.extend(edits.iter().map(|&(span, kind)| Repair {
+            kind,
+            span: text.original(span),
+        }));
+        text.remove(edits.into_iter().map(|(span, _)| span));
+    }
+    parse_inner(&text.text, options, None).map(|molecule| (molecule, repairs))
+}
+
+/// The input being repaired, with the original span of every byte.
+struct Text {
+    text: String,
+    /// Span in the original input of every byte of `text`, then the empty
+    /// span at its end. Added bytes have the empty span where they were
+    /// inserted.
+    origin: Vec<Span>,
+}
+
+impl Text {
+    fn new(input: &str) -> Text {
+        let mut origin: Vec<Span> = (0..input.len()).map(|i| Span::new(i, i + 1)).collect();
+        origin.push(Span::at(input.len()));
+        Text {
+            text: input.to_string(),
+            origin,
+        }
+    }
+
+    /// Returns the span of the original input that `span` of the text
+    /// comes from.
+    fn original(&self, span: Span) -> Span {
+        let start = self.origin[span.start].start;
+        if span.is_empty() {
+            return Span::at(start);
+        }
+        Span::new(start, self.origin[span.end - 1].end.max(start))
+    }
+
+    /// Removes `spans`, which are sorted and do not overlap.
+    fn remove(&mut self, spans: impl IntoIterator<Item = Span>) {
+        let mut text = String::with_capacity(self.text.len());
+        let mut origin = Vec::with_capacity(self.origin.len());
+        let mut kept = 0;
+        for span in spans {
+            text.push_str(&self.text[kept..span.start]);
+            origin.extend_from_slice(&self.origin[kept..span.start]);
+            kept = span.end;
+        }
+        text.push_str(&self.text[kept..]);
+        origin.extend_from_slice(&self.origin[kept..]);
+        self.text = text;
+        self.origin = origin;
+    }
+
+    /// Finds the parentheses that are not matched, outside bracket atoms
+    /// and before the end of the SMILES.
+    fn scan(&self, options: &ParseOptions) -> Scan {
+        let end = match self.text.find([' ', '\t', '\n', '\r']) {
+            Some(end) if options.whitespace_terminates => end,
+            _ => self.text.len(),
+        };
+        let mut scan = Scan {
+            end,
+            open: Vec::new(),
+            unopened: Vec::new(),
+        };
+        let mut bracket = false;
+        for (i, c) in self.text[..end].char_indices() {
+            match c {
+                '[' => bracket = true,
+                ']' => bracket = false,
+                '(' if !bracket => scan.open.push(i),
+                ')' if !bracket && scan.open.is_empty() => scan.unopened.push(i),
+                ')' if !bracket => {
+                    scan.open.pop();
+                }
+                _ => {}
+            }
+        }
+        scan
+    }
+
+    /// Closes every open branch at the end of the SMILES and returns the
+    /// original spans of their `(`.
+    fn close_branches(&mut self, options: &ParseOptions) -> Vec<Span> {
+        let Scan { end, open, .. } = self.scan(options);
+        let spans = open
+            .iter()
+            .map(|&i| self.original(Span::new(i, i + 1)))
+            .collect();
+        let at = Span::at(self.origin[end].start);
+        self.text.insert_str(end, &")".repeat(open.len()));
+        self.origin
+            .splice(end..end, std::iter::repeat(at).take(open.len()));
+        spans
+    }
+}
+
+/// The unmatched parentheses of a [`Text`].
+struct Scan {
+    /// End of the SMILES, before any whitespace that terminates it.
+    end: usize,
+    /// Offsets of the `(` that are never closed.
+    open: Vec<usize>,
+    /// Offsets of the `)` without `(`.
+    unopened: Vec<usize>,
+}
+
+/// Applies the structural fixes of the module documentation to a parsed
+/// molecule, before its hydrogens are computed. `atom_spans` holds the span
+/// of every atom.
+pub(crate) fn repair_structure(
+    builder: &mut MoleculeBuilder,
+    atom_spans: &[Span],
+    repairs: &mut Vec<Repair>,
+) {
+    let n = builder.nodes().len();
+    let edges: Vec<_> = builder
+        .bonds()
+        .iter()
+        .map(|bond| {
+            (bond.kind() != BondType::Disconnected).then_some((bond.source(), bond.target()))
+        })
+        .collect();
+    let in_ring = ring_atoms(n, &edges);
+
+    let mut dearomatized = vec![false; n];
+    for (atom, node) in builder.nodes_mut().iter_mut().enumerate() {
+        if node.aromatic() == Some(true) && !in_ring[atom] {
+            node.set_aromatic(false);
+            dearomatized[atom] = true;
+            repairs.push(Repair {
+                kind: RepairKind::Dearomatized,
+                span: atom_spans[atom],
+            });
+        }
+    }
+    for bond in builder.bonds_mut() {
+        if bond.kind() == BondType::Aromatic
+            && (dearomatized[bond.source() as usize] || dearomatized[bond.target() as usize])
+        {
+            *bond = Bond::new(BondType::Simple, bond.source(), bond.target());
+        }
+    }
+
+    let mut incident = vec![Vec::new(); n];
+    for (i, bond) in builder.bonds().iter().enumerate() {
+        incident[bond.source() as usize].push(i);
+        if bond.target() != bond.source() {
+            incident[bond.target() as usize].push(i);
+        }
+    }
+    for (atom, &span) in atom_spans.iter().enumerate().take(n) {
+        if reduce_valence(builder, atom as NodeIndex, &incident[atom]) {
+            repairs.push(Repair {
+                kind: RepairKind::ReducedValence,
+                span,
+            });
+        }
+    }
+}
+
+/// Lowers the multiple bonds, then the bracket hydrogens, of an uncharged
+/// carbon with more than four bonds, and tells whether anything changed.
+/// `incident` holds the indices of its bonds.
+fn reduce_valence(builder: &mut MoleculeBuilder, atom: NodeIndex, incident: &[usize]) -> bool {
+    let node = &builder.nodes()[atom as usize];
+    if *node.atom().element() != AtomSymbol::Organic(OrganicAtom::C) || node.atom().charge() != 0 {
+        return false;
+    }
+    let order: u32 = incident
+        .iter()
+        .map(|&i| u32::from(builder.bonds()[i].kind().bond_order_x2_for_implicit_h()) / 2)
+        .sum();
+    let hydrogens = node.hydrogens().unwrap_or(0);
+    let before = (order + u32::from(hydrogens)).saturating_sub(4);
+    let mut excess = before;
+
+    // Highest order first, the bond written last among equals.
+    while excess > 0 {
+        let Some(&i) = incident.iter().max_by_key(|&&i| {
+            let kind = builder.bonds()[i].kind();
+            (lowered(kind).is_some(), kind.bond_order_priority())
+        }) else {
+            break;
+        };
+        let bond = &builder.bonds()[i];
+        let (source, target) = (bond.source(), bond.target());
+        let Some(kind) = lowered(bond.kind()) else {
+            break;
+        };
+        builder.bonds_mut()[i] = Bond::new(kind, source, target);
+        excess -= 1;
+    }
+    let node = &mut builder.nodes_mut()[atom as usize];
+    if let Some(hydrogens) = node.hydrogens().filter(|_| excess > 0) {
+        let removed = hydrogens.min(u8::try_from(excess).unwrap_or(u8::MAX));
+        node.set_hydrogens(hydrogens - removed);
+        excess -= u32::from(removed);
+    }
+    excess < before
+}
+
+/// Returns the bond type one order below `kind`, for multiple bonds.
+fn lowered(kind: BondType) -> Option<BondType> {
+    match kind {
+        BondType::Quadruple => Some(BondType::Triple),
+        BondType::Triple => Some(BondType::Double),
+        BondType::Double => Some(BondType::Simple),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_keeps_original_offsets() {
+        let mut text = Text::new("C1C(C x");
+        text.remove([Span::new(1, 2)]);
+        assert_eq!(text.text, "CC(C x");
+        assert_eq!(text.original(Span::new(2, 3)), Span::new(3, 4));
+        let open = text.close_branches(&ParseOptions::default());
+        assert_eq!(text.text, "CC(C) x");
+        assert_eq!(open, [Span::new(3, 4)]);
+        assert_eq!(text.original(Span::new(4, 5)), Span::at(5));
+    }
+
+    #[test]
+    fn brackets_do_not_open_branches() {
+        let mut text = Text::new("C([C(C");
+        assert_eq!(text.close_branches(&ParseOptions::default()).len(), 1);
+        assert_eq!(text.text, "C([C(C)");
+    }
+
+    #[test]
+    fn lowered_bonds() {
+        assert_eq!(lowered(BondType::Triple), Some(BondType::Double));
+        assert_eq!(lowered(BondType::Aromatic), None);
+        assert_eq!(lowered(BondType::Up), None);
+    }
+
+    #[test]
+    fn repairs_are_displayed() {
+        let repair = Repair {
+            kind: RepairKind::DroppedRingBond(2),
+            span: Span::new(3, 4),
+        };
+        assert_eq!(repair.to_string(), "dropped unclosed ring 2 at 3..4");
+    }
+}
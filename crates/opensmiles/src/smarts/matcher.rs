@@ -0,0 +1,666 @@
+//! Substructure matching: VF2-style backtracking of a query over a molecule.
+//!
+//! Query atoms are matched in a fixed order where every atom but the first
+//! of each component follows one of its neighbours, so candidates come from
+//! the neighbours of an atom already mapped rather than from the whole
+//! molecule. A candidate is kept when its atom expression holds and every
+//! bond to an already mapped query atom exists in the molecule and satisfies
+//! its bond expression. Non-bonded query atoms may be bonded in the
+//! molecule, as SMARTS requires.
+//!
+//! Aromaticity is perceived on the molecule first, with the Daylight model
+//! used for identity, so `c1ccccc1` also matches benzene written in Kekulé
+//! form.
+//!
+//! Atom tests are memoized per query atom and molecule atom, which also
+//! bounds the cost of recursive SMARTS: each `$(...)` is matched at most once
+//! per molecule atom.
+
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use super::query::{AtomExpr, AtomPrimitive, BondExpr, BondPrimitive, QueryExpr, QueryMolecule};
+use crate::ast::stereo;
+use crate::{AtomSymbol, BondType, Chirality, Molecule, NodeIndex, RingInfo};
+
+/// Options for [`Molecule::substructure_matches_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstructureOptions {
+    /// Stop after this many matches; `None` returns them all.
+    pub max_matches: Option<usize>,
+    /// Keep a single match per set of molecule atoms, dropping the matches
+    /// that only differ by a symmetry of the query (the three oxygens of a
+    /// `C(O)(O)O` query on a phosphate, say).
+    pub unique: bool,
+    /// Require the `@` and `@@` centres of the query to have the same
+    /// configuration in the molecule, which must specify it. When off,
+    /// chirality primitives always hold.
+    pub chirality: bool,
+}
+
+impl Default for SubstructureOptions {
+    fn default() -> Self {
+        SubstructureOptions {
+            max_matches: None,
+            unique: true,
+            chirality: false,
+        }
+    }
+}
+
+/// A query compiled for matching.
+struct Plan<'q> {
+    query: &'q QueryMolecule,
+    /// Neighbours of each query atom, with the index of the joining bond.
+    adjacency: Vec<Vec<(usize, usize)>>,
+    /// Query atoms in matching order.
+    order: Vec<usize>,
+    /// For each position in `order`, an earlier atom bonded to it.
+    anchor: Vec<Option<usize>>,
+    /// Tetrahedral centres: atom, chirality and written neighbour order.
+    stereo: Vec<(usize, Chirality, Vec<usize>)>,
+}
+
+impl<'q> Plan<'q> {
+    fn new(query: &'q QueryMolecule) -> Plan<'q> {
+        let n = query.atoms().len();
+        let mut adjacency: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+        for (i, bond) in query.bonds().iter().enumerate() {
+            let (s, t) = (bond.source() as usize, bond.target() as usize);
+            adjacency[s].push((t, i));
+            adjacency[t].push((s, i));
+        }
+
+        // Breadth-first from the first atom of each component, so that a
+        // recursive query starts with the atom it is rooted at.
+        let mut order = Vec::with_capacity(n);
+        let mut anchor = Vec::with_capacity(n);
+        let mut seen = vec![false; n];
+        for start in 0..n {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            order.push(start);
+            anchor.push(None);
+            let mut head = order.len() - 1;
+            while head < order.len() {
+                let atom = order[head];
+                head += 1;
+                for &(v, _) in &adjacency[atom] {
+                    if !seen[v] {
+                        seen[v] = true;
+                        order.push(v);
+                        anchor.push(Some(atom));
+                    }
+                }
+            }
+        }
+
+        let centres: Vec<(usize, Chirality)> = query
+            .atoms()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, atom)| {
+                required_chirality(atom.expr())
+                    .filter(|&c| stereo::is_tetrahedral(Some(c)))
+                    .map(|c| (i, c))
+            })
+            .collect();
+        let stereo = if centres.is_empty() {
+            Vec::new()
+        } else {
+            let mut written = query.written_neighbours(|atom| {
+                adjacency[atom].len() == 3 || required_hydrogens(query.atoms()[atom].expr())
+            });
+            centres
+                .into_iter()
+                .map(|(atom, c)| (atom, c, std::mem::take(&mut written[atom])))
+                .collect()
+        };
+
+        Plan {
+            query,
+            adjacency,
+            order,
+            anchor,
+            stereo,
+        }
+    }
+}
+
+/// Returns the chirality the expression requires, if it is one of its
+/// conjuncts.
+fn required_chirality(expr: &AtomExpr) -> Option<Chirality> {
+    match expr {
+        QueryExpr::Primitive(AtomPrimitive::Chirality(c)) => Some(*c),
+        QueryExpr::And(es) | QueryExpr::LowAnd(es) => es.iter().find_map(required_chirality),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the expression requires attached hydrogens.
+fn required_hydrogens(expr: &AtomExpr) -> bool {
+    match expr {
+        QueryExpr::Primitive(AtomPrimitive::TotalHydrogens(n)) => *n > 0,
+        QueryExpr::And(es) | QueryExpr::LowAnd(es) => es.iter().any(required_hydrogens),
+        _ => false,
+    }
+}
+
+/// The molecule side of a search, with lazily computed properties.
+struct Target {
+    /// The searched molecule, with aromaticity perceived.
+    molecule: Molecule,
+    adjacency: Vec<Vec<(NodeIndex, usize)>>,
+    explicit_h: Vec<u8>,
+    rings: OnceCell<RingInfo>,
+    ring_counts: OnceCell<Vec<u8>>,
+    valences: OnceCell<Vec<u32>>,
+}
+
+impl Target {
+    fn new(molecule: &Molecule) -> Target {
+        let molecule = molecule.perceived_aromaticity();
+        let n = molecule.nodes().len();
+        let mut adjacency: Vec<Vec<(NodeIndex, usize)>> = vec![Vec::new(); n];
+        let mut explicit_h = vec![0u8; n];
+        for (i, bond) in molecule.bonds().iter().enumerate() {
+            if bond.kind() == BondType::Disconnected {
+                continue;
+            }
+            let (s, t) = (bond.source(), bond.target());
+            adjacency[s as usize].push((t, i));
+            adjacency[t as usize].push((s, i));
+            for (a, b) in [(s, t), (t, s)] {
+                if *molecule.nodes()[b as usize].atom().element() == AtomSymbol::H {
+                    explicit_h[a as usize] = explicit_h[a as usize].saturating_add(1);
+                }
+            }
+        }
+        Target {
+            molecule,
+            adjacency,
+            explicit_h,
+            rings: OnceCell::new(),
+            ring_counts: OnceCell::new(),
+            valences: OnceCell::new(),
+        }
+    }
+
+    fn rings(&self) -> &RingInfo {
+        self.rings.get_or_init(|| self.molecule.ring_info())
+    }
+
+    /// Number of SSSR rings containing each atom.
+    fn ring_counts(&self) -> &[u8] {
+        self.ring_counts.get_or_init(|| {
+            let mut counts = vec![0u8; self.adjacency.len()];
+            for ring in self.rings().sssr() {
+                for &atom in &ring.nodes {
+                    counts[atom as usize] = counts[atom as usize].saturating_add(1);
+                }
+            }
+            counts
+        })
+    }
+
+    /// Sum of bond orders of each atom, hydrogens included. Aromatic bonds
+    /// count with their Kekulé order; in a molecule that cannot be
+    /// kekulized they count as 1.5, rounded down per atom.
+    fn valences(&self) -> &[u32] {
+        self.valences.get_or_init(|| {
+            let kekule = self
+                .molecule
+                .bonds()
+                .iter()
+                .any(|b| b.kind() == BondType::Aromatic)
+                .then(|| self.molecule.kekulize().ok())
+                .flatten();
+            let molecule = kekule.as_ref().unwrap_or(&self.molecule);
+            let mut electrons = vec![0u32; self.adjacency.len()];
+            for bond in molecule.bonds() {
+                let e = bond.kind().electrons_involved() as u32;
+                electrons[bond.source() as usize] += e;
+                electrons[bond.target() as usize] += e;
+            }
+            electrons
+                .iter()
+                .zip(self.molecule.nodes())
+                .map(|(e, node)| e / 2 + node.hydrogens() as u32)
+                .collect()
+        })
+    }
+
+    /// Returns the index of the bond joining `a` and `b`, if any.
+    fn bond_between(&self, a: NodeIndex, b: NodeIndex) -> Option<usize> {
+        self.adjacency[a as usize]
+            .iter()
+            .find(|&&(v, _)| v == b)
+            .map(|&(_, bond)| bond)
+    }
+
+    fn bond_matches(&self, expr: Option<&BondExpr>, bond: usize) -> bool {
+        let kind = self.molecule.bonds()[bond].kind();
+        let single = matches!(kind, BondType::Simple | BondType::Up | BondType::Down);
+        let Some(expr) = expr else {
+            return single || kind == BondType::Aromatic;
+        };
+        expr.evaluate(&mut |primitive| match primitive {
+            // Double-bond geometry is not compared: `/` and `\` match any
+            // single bond.
+            BondPrimitive::Single | BondPrimitive::Up | BondPrimitive::Down => single,
+            BondPrimitive::Double => kind == BondType::Double,
+            BondPrimitive::Triple => kind == BondType::Triple,
+            BondPrimitive::Quadruple => kind == BondType::Quadruple,
+            BondPrimitive::Aromatic => kind == BondType::Aromatic,
+            BondPrimitive::Any => true,
+            BondPrimitive::Ring => self.rings().ring_bond(bond),
+        })
+    }
+}
+
+/// A search of one query, and the queries it nests, over one molecule.
+struct Search<'a> {
+    target: Target,
+    /// The query at index 0, then every recursive query.
+    plans: Vec<Plan<'a>>,
+    recursive: HashMap<*const QueryMolecule, usize>,
+    chirality: bool,
+    /// Per plan, memoized atom tests: 0 unknown, 1 false, 2 true.
+    atom_memo: RefCell<Vec<Vec<u8>>>,
+    /// Per recursive plan, memoized rooted matches.
+    root_memo: RefCell<Vec<Vec<u8>>>,
+}
+
+impl<'a> Search<'a> {
+    fn new(molecule: &'a Molecule, query: &'a QueryMolecule, chirality: bool) -> Search<'a> {
+        let mut plans = vec![Plan::new(query)];
+        let mut recursive = HashMap::new();
+        let mut next = 0;
+        while next < plans.len() {
+            let atoms = plans[next].query.atoms();
+            next += 1;
+            let mut pending = Vec::new();
+            for atom in atoms {
+                collect_recursive(atom.expr(), &mut pending);
+            }
+            for nested in pending {
+                recursive
+                    .entry(nested as *const QueryMolecule)
+                    .or_insert_with(|| {
+                        plans.push(Plan::new(nested));
+                        plans.len() - 1
+                    });
+            }
+        }
+        let count = plans.len();
+        Search {
+            target: Target::new(molecule),
+            plans,
+            recursive,
+            chirality,
+            atom_memo: RefCell::new(vec![Vec::new(); count]),
+            root_memo: RefCell::new(vec![Vec::new(); count]),
+        }
+    }
+
+    fn atom_matches(&self, plan: usize, q: usize, t: NodeIndex) -> bool {
+        let n = self.target.adjacency.len();
+        let slot = q * n + t as usize;
+        if let Some(&known) = self.atom_memo.borrow()[plan].get(slot) {
+            if known != 0 {
+                return known == 2;
+            }
+        }
+        let expr = self.plans[plan].query.atoms()[q].expr();
+        let result = expr.evaluate(&mut |primitive| self.primitive_matches(primitive, t));
+        let mut memo = self.atom_memo.borrow_mut();
+        let memo = &mut memo[plan];
+        if memo.is_empty() {
+            memo.resize(self.plans[plan].order.len() * n, 0);
+        }
+        memo[slot] = if result { 2 } else { 1 };
+        result
+    }
+
+    fn primitive_matches(&self, primitive: &AtomPrimitive, t: NodeIndex) -> bool {
+        let i = t as usize;
+        let node = &self.target.molecule.nodes()[i];
+        let atom = node.atom();
+        let degree = self.target.adjacency[i].len();
+        match primitive {
+            AtomPrimitive::Any => true,
+            AtomPrimitive::Aromatic => node.aromatic(),
+            AtomPrimitive::Aliphatic => !node.aromatic(),
+            AtomPrimitive::Element { element, aromatic } => {
+                atom.element() == element && node.aromatic() == *aromatic
+            }
+            AtomPrimitive::AtomicNumber(n) => atom.element().element_data().atomic_number == *n,
+            AtomPrimitive::Isotope(n) => atom.isotope() == Some(*n),
+            AtomPrimitive::Charge(c) => atom.charge() == *c,
+            AtomPrimitive::TotalHydrogens(n) => {
+                node.hydrogens() as usize + self.target.explicit_h[i] as usize == *n as usize
+            }
+            AtomPrimitive::Degree(n) => degree == *n as usize,
+            AtomPrimitive::Connectivity(n) => degree + node.hydrogens() as usize == *n as usize,
+            AtomPrimitive::Valence(n) => self.target.valences()[i] == *n as u32,
+            AtomPrimitive::RingMembership(None) | AtomPrimitive::RingSize(None) => {
+                self.target.rings().is_in_ring(t)
+            }
+            AtomPrimitive::RingMembership(Some(n)) => self.target.ring_counts()[i] == *n,
+            AtomPrimitive::RingSize(Some(n)) => {
+                self.target.rings().smallest_ring_size(t).unwrap_or(0) == *n as usize
+            }
+            // The parity of `@` and `@@` is checked once the neighbours are
+            // mapped; other classes are compared as written.
+            AtomPrimitive::Chirality(c) => {
+                !self.chirality
+                    || match node.chirality() {
+                        Some(tc) if stereo::is_tetrahedral(Some(*c)) => {
+                            stereo::is_tetrahedral(Some(tc))
+                        }
+                        tc => tc == Some(*c),
+                    }
+            }
+            AtomPrimitive::Recursive(query) => {
+                let plan = self.recursive[&(&**query as *const QueryMolecule)];
+                self.rooted_match(plan, t)
+            }
+        }
+    }
+
+    /// Returns `true` if the plan has a match whose first atom is `t`.
+    fn rooted_match(&self, plan: usize, t: NodeIndex) -> bool {
+        let i = t as usize;
+        if let Some(&known) = self.root_memo.borrow()[plan].get(i) {
+            if known != 0 {
+                return known == 2;
+            }
+        }
+        let mut found = false;
+        self.run(plan, Some(t), &mut |_| {
+            found = true;
+            false
+        });
+        let mut memo = self.root_memo.borrow_mut();
+        let memo = &mut memo[plan];
+        if memo.is_empty() {
+            memo.resize(self.target.adjacency.len(), 0);
+        }
+        memo[i] = if found { 2 } else { 1 };
+        found
+    }
+
+    /// Enumerates the matches of a plan, optionally with its first atom
+    /// fixed, until `on_match` returns `false`. A match lists the molecule
+    /// atom of every query atom.
+    fn run(
+        &self,
+        plan: usize,
+        root: Option<NodeIndex>,
+        on_match: &mut dyn FnMut(&[NodeIndex]) -> bool,
+    ) {
+        let p = &self.plans[plan];
+        let size = p.order.len();
+        if size == 0 {
+            return;
+        }
+        let mut map: Vec<Option<NodeIndex>> = vec![None; size];
+        let mut used = vec![false; self.target.adjacency.len()];
+        let mut stack = vec![(self.candidates(p, 0, &map, root), 0)];
+        while !stack.is_empty() {
+            let depth = stack.len() - 1;
+            let (candidates, next) = &mut stack[depth];
+            let q = p.order[depth];
+            if let Some(t) = map[q].take() {
+                used[t as usize] = false;
+            }
+            let Some(&t) = candidates.get(*next) else {
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if used[t as usize] || !self.feasible(plan, q, t, &map) {
+                continue;
+            }
+            map[q] = Some(t);
+            used[t as usize] = true;
+            if depth + 1 < size {
+                let candidates = self.candidates(p, depth + 1, &map, None);
+                stack.push((candidates, 0));
+                continue;
+            }
+            let image: Vec<NodeIndex> = map.iter().map(|t| t.unwrap()).collect();
+            if (!self.chirality || self.stereo_holds(p, &image)) && !on_match(&image) {
+                return;
+            }
+        }
+    }
+
+    /// Candidate molecule atoms for the query atom at `depth`.
+    fn candidates(
+        &self,
+        plan: &Plan,
+        depth: usize,
+        map: &[Option<NodeIndex>],
+        root: Option<NodeIndex>,
+    ) -> Vec<NodeIndex> {
+        match (plan.anchor[depth], root) {
+            (Some(anchor), _) => {
+                let image = map[anchor].expect("anchors are mapped first");
+                self.target.adjacency[image as usize]
+                    .iter()
+                    .map(|&(v, _)| v)
+                    .collect()
+            }
+            (None, Some(root)) if depth == 0 => vec![root],
+            (None, _) => (0..self.target.adjacency.len() as NodeIndex).collect(),
+        }
+    }
+
+    fn feasible(&self, plan: usize, q: usize, t: NodeIndex, map: &[Option<NodeIndex>]) -> bool {
+        let p = &self.plans[plan];
+        if self.target.adjacency[t as usize].len() < p.adjacency[q].len()
+            || !self.atom_matches(plan, q, t)
+        {
+            return false;
+        }
+        p.adjacency[q].iter().all(|&(neighbour, bond)| {
+            let Some(image) = map[neighbour] else {
+                return true;
+            };
+            self.target
+                .bond_between(t, image)
+                .is_some_and(|b| self.target.bond_matches(p.query.bonds()[bond].expr(), b))
+        })
+    }
+
+    /// Checks the tetrahedral centres of a complete match.
+    fn stereo_holds(&self, plan: &Plan, image: &[NodeIndex]) -> bool {
+        let molecule = &self.target.molecule;
+        plan.stereo.iter().all(|(q, chirality, written)| {
+            let t = image[*q];
+            let node = &molecule.nodes()[t as usize];
+            let Some(actual) = node
+                .chirality()
+                .filter(|&c| stereo::is_tetrahedral(Some(c)))
+            else {
+                return false;
+            };
+            // Fewer than four neighbours do not define a configuration.
+            if written.len() != 4 {
+                return true;
+            }
+            let degree = stereo::reference_order(t, molecule.bonds(), false).len();
+            let implicit = node.hydrogens() > 0 || degree == 3;
+            let reference = stereo::reference_order(t, molecule.bonds(), implicit);
+            let mut mapped: Vec<NodeIndex> = written
+                .iter()
+                .map(|&v| if v == *q { t } else { image[v] })
+                .collect();
+            // A query hydrogen or lone pair may stand for a molecule atom
+            // the query leaves unmapped.
+            if !implicit {
+                if let Some(slot) = mapped.iter().position(|&v| v == t) {
+                    match reference.iter().find(|v| !mapped.contains(v)) {
+                        Some(&free) => mapped[slot] = free,
+                        None => return false,
+                    }
+                }
+            }
+            match stereo::is_odd_permutation(&mapped, &reference) {
+                Some(true) => stereo::invert(*chirality) == actual,
+                Some(false) => *chirality == actual,
+                None => false,
+            }
+        })
+    }
+}
+
+fn collect_recursive<'q>(expr: &'q AtomExpr, out: &mut Vec<&'q QueryMolecule>) {
+    match expr {
+        QueryExpr::Primitive(AtomPrimitive::Recursive(query)) => out.push(query),
+        QueryExpr::Primitive(_) => {}
+        QueryExpr::Not(e) => collect_recursive(e, out),
+        QueryExpr::And(es) | QueryExpr::Or(es) | QueryExpr::LowAnd(es) => {
+            for e in es {
+                collect_recursive(e, out);
+            }
+        }
+    }
+}
+
+impl Molecule {
+    /// Returns every match of `query` in the molecule, with the default
+    /// [`SubstructureOptions`]: one match per set of atoms, chirality
+    /// ignored.
+    ///
+    /// A match lists, for each query atom, the index of the molecule atom it
+    /// maps to. A molecule can serve as the query through
+    /// [`QueryMolecule::from`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opensmiles::parse;
+    /// use opensmiles::smarts::{parse_smarts, QueryMolecule};
+    ///
+    /// let aspirin = parse("CC(=O)Oc1ccccc1C(=O)O").unwrap();
+    /// let acid = parse_smarts("C(=O)[OX2H1]").unwrap();
+    /// assert_eq!(aspirin.substructure_matches(&acid), vec![vec![10, 11, 12]]);
+    ///
+    /// let benzene = QueryMolecule::from(&parse("c1ccccc1").unwrap());
+    /// assert!(aspirin.has_substructure(&benzene));
+    /// ```
+    pub fn substructure_matches(&self, query: &QueryMolecule) -> Vec<Vec<NodeIndex>> {
+        self.substructure_matches_with(query, &SubstructureOptions::default())
+    }
+
+    /// Returns the matches of `query` in the molecule, as
+    /// [`substructure_matches`](Self::substructure_matches) does, under the
+    /// given options.
+    pub fn substructure_matches_with(
+        &self,
+        query: &QueryMolecule,
+        options: &SubstructureOptions,
+    ) -> Vec<Vec<NodeIndex>> {
+        let mut matches = Vec::new();
+        if options.max_matches == Some(0) {
+            return matches;
+        }
+        let mut seen = HashSet::new();
+        self.for_each_match(query, options.chirality, &mut |image| {
+            if options.unique {
+                let mut atoms = image.to_vec();
+                atoms.sort_unstable();
+                if !seen.insert(atoms) {
+                    return true;
+                }
+            }
+            matches.push(image.to_vec());
+            options.max_matches != Some(matches.len())
+        });
+        matches
+    }
+
+    /// Calls `on_match` with every match of `query`, symmetric duplicates
+    /// included, until it returns `false`.
+    pub(crate) fn for_each_match(
+        &self,
+        query: &QueryMolecule,
+        chirality: bool,
+        on_match: &mut dyn FnMut(&[NodeIndex]) -> bool,
+    ) {
+        Search::new(self, query, chirality).run(0, None, on_match);
+    }
+
+    /// Returns `true` if `query` matches the molecule at least once.
+    ///
+    /// Chirality is ignored; use
+    /// [`substructure_matches_with`](Self::substructure_matches_with) to
+    /// take it into account.
+    pub fn has_substructure(&self, query: &QueryMolecule) -> bool {
+        let options = SubstructureOptions {
+            max_matches: Some(1),
+            ..SubstructureOptions::default()
+        };
+        !self.substructure_matches_with(query, &options).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::smarts::parse_smarts;
+
+    fn count(smiles: &str, smarts: &str) -> usize {
+        parse(smiles)
+            .unwrap()
+            .substructure_matches(&parse_smarts(smarts).unwrap())
+            .len()
+    }
+
+    #[test]
+    fn matching_order_follows_bonds() {
+        let query = parse_smarts("C(C)(C)O.N").unwrap();
+        let plan = Plan::new(&query);
+        assert_eq!(plan.order, vec![0, 1, 2, 3, 4]);
+        assert_eq!(plan.anchor, vec![None, Some(0), Some(0), Some(0), None]);
+    }
+
+    #[test]
+    fn unwritten_bonds_match_single_or_aromatic() {
+        assert_eq!(count("CC", "CC"), 1);
+        assert_eq!(count("C=C", "CC"), 0);
+        assert_eq!(count("c1ccccc1", "cc"), 6);
+        assert_eq!(count("C=C", "C~C"), 1);
+    }
+
+    #[test]
+    fn atom_properties() {
+        assert_eq!(count("CCO", "[CH3]"), 1);
+        assert_eq!(count("[H]OC", "[OH1]"), 1);
+        assert_eq!(count("[H]OC", "[OX2]"), 1);
+        assert_eq!(count("[H]OC", "[OD2]"), 1);
+        assert_eq!(count("C1CC1C", "[R]"), 3);
+        assert_eq!(count("C1CC1C", "[r3]"), 3);
+        assert_eq!(count("C1CC1C", "[R0]"), 1);
+        assert_eq!(count("c1ccc2ccccc2c1", "[R2]"), 2);
+        assert_eq!(count("c1ccncc1", "[v4]"), 5);
+        assert_eq!(count("c1ccncc1", "[nv3]"), 1);
+        assert_eq!(count("C[N+](C)(C)C", "[#7+]"), 1);
+    }
+
+    #[test]
+    fn recursive_queries_are_memoized() {
+        let query = parse_smarts("[$(CO)]").unwrap();
+        let molecule = parse("OCCO").unwrap();
+        let search = Search::new(&molecule, &query, false);
+        assert_eq!(search.plans.len(), 2);
+        assert!(search.atom_matches(0, 0, 1));
+        assert!(!search.atom_matches(0, 0, 0));
+        assert_eq!(search.root_memo.borrow()[1], vec![1, 2, 0, 0]);
+    }
+}
@@ -0,0 +1,32 @@
+//! SMARTS: substructure query patterns.
+//!
+//! [`parse_smarts`] reads a pattern such as `[CX3](=O)[OX2H1]` into a
+//! [`QueryMolecule`], whose atoms and bonds carry logical expressions
+//! ([`AtomExpr`], [`BondExpr`]) over primitives. Queries write back to
+//! SMARTS through [`Display`](std::fmt::Display).
+//!
+//! [`Molecule::substructure_matches`](crate::Molecule::substructure_matches)
+//! and [`Molecule::has_substructure`](crate::Molecule::has_substructure)
+//! search a molecule for a query; a [`Molecule`](crate::Molecule) converts
+//! into a query with [`QueryMolecule::from`].
+//!
+//! # Example
+//!
+//! ```
+//! use opensmiles::smarts::parse_smarts;
+//!
+//! let amide = parse_smarts("C(=O)[NX3;H2,H1;!$(NC=O)]").unwrap();
+//! assert_eq!(amide.atoms().len(), 3);
+//! assert_eq!(amide.to_string(), "C(=O)[N&X3;H2,H1;!$(NC=O)]");
+//! ```
+
+mod matcher;
+mod parser;
+mod query;
+
+pub use self::matcher::SubstructureOptions;
+pub use self::parser::parse_smarts;
+pub use self::query::{
+    AtomExpr, AtomPrimitive, BondExpr, BondPrimitive, QueryAtom, QueryBond, QueryExpr,
+    QueryMolecule,
+};
@@ -0,0 +1,645 @@
+//! SMARTS parser.
+//!
+//! The graph syntax (chains, branches, ring closures, dots) is read in a
+//! single pass; atom and bond expressions are read by a precedence parser
+//! shared by both, with primitives lexed by the same rules as SMILES
+//! bracket atoms (see [`Lexer`]).
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use super::query::{
+    AtomPrimitive, BondExpr, BondPrimitive, QueryAtom, QueryBond, QueryExpr, QueryMolecule,
+};
+use crate::lexer::Lexer;
+use crate::{AtomSymbol, NodeIndex, SmartsError};
+
+struct SmartsParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// Characters consumed, as reported in [`SmartsError`]s.
+    column: usize,
+    /// Bytes consumed, as the spans of lexer errors count them.
+    offset: usize,
+    /// Set when the bracket being read starts with a hydrogen atom (`[H]`,
+    /// `[2H+]`) rather than a hydrogen count.
+    hydrogen_atom: bool,
+}
+
+impl Lexer for SmartsParser<'_> {
+    fn next(&mut self) -> Option<char> {
+        self.column += 1;
+        let c = self.chars.next();
+        self.offset += c.map_or(0, char::len_utf8);
+        c
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn position(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Reads one primitive, or returns `None` (consuming nothing) if the next
+/// character does not start one.
+type PrimitiveReader<'a, P> = fn(&mut SmartsParser<'a>) -> Result<Option<P>, SmartsError>;
+
+/// Wraps several operands in `variant`, or returns a single one as is.
+fn collapse<P>(
+    mut operands: Vec<QueryExpr<P>>,
+    variant: fn(Vec<QueryExpr<P>>) -> QueryExpr<P>,
+) -> QueryExpr<P> {
+    if operands.len() == 1 {
+        operands.pop().expect("one operand")
+    } else {
+        variant(operands)
+    }
+}
+
+impl<'a> SmartsParser<'a> {
+    fn new(input: &'a str) -> Self {
+        SmartsParser {
+            chars: input.chars().peekable(),
+            column: 0,
+            offset: 0,
+            hydrogen_atom: false,
+        }
+    }
+
+    /// Reads a pattern. A nested pattern (`$(...)`) stops before the `)`
+    /// that closes it.
+    fn parse_pattern(&mut self, nested: bool) -> Result<QueryMolecule, SmartsError> {
+        let mut atoms: Vec<QueryAtom> = Vec::new();
+        let mut bonds: Vec<QueryBond> = Vec::new();
+        let mut previous: Option<NodeIndex> = None;
+        let mut branches: Vec<NodeIndex> = Vec::new();
+        let mut pending: Option<BondExpr> = None;
+        let mut rings: BTreeMap<u8, (NodeIndex, Option<BondExpr>)> = BTreeMap::new();
+
+        while let Some(&c) = self.peek() {
+            match c {
+                '[' | '*' => {}
+                c if c.is_ascii_alphabetic() => {}
+                '(' => {
+                    self.next();
+                    let atom = previous.ok_or(SmartsError::UnexpectedCharacter(c, self.column))?;
+                    if pending.is_some() {
+                        return Err(SmartsError::UnexpectedCharacter(c, self.column));
+                    }
+                    if self.peek() == Some(&')') {
+                        return Err(SmartsError::EmptyBranch);
+                    }
+                    branches.push(atom);
+                    continue;
+                }
+                ')' => {
+                    if branches.is_empty() {
+                        if nested {
+                            break;
+                        }
+                        return Err(SmartsError::UnopenedParenthesis);
+                    }
+                    self.next();
+                    if pending.is_some() {
+                        return Err(SmartsError::BondWithoutFollowingAtom);
+                    }
+                    previous = branches.pop();
+                    continue;
+                }
+                '.' => {
+                    self.next();
+                    if pending.is_some() {
+                        return Err(SmartsError::BondWithoutFollowingAtom);
+                    }
+                    previous = None;
+                    continue;
+                }
+                '%' | '0'..='9' => {
+                    self.next();
+                    let number = self.parse_ring_number(c)?;
+                    let atom = previous.ok_or(SmartsError::UnexpectedCharacter(c, self.column))?;
+                    let here = pending.take();
+                    if let Some((opener, there)) = rings.remove(&number) {
+                        if opener == atom {
+                            return Err(SmartsError::SelfBond(number));
+                        }
+                        // Both expressions read opener → closer.
+                        let here = here.map(|e| e.map(&BondPrimitive::reversed));
+                        let expr = match (there, here) {
+                            (Some(a), Some(b)) if a != b => {
+                                return Err(SmartsError::MismatchedRingBond(number))
+                            }
+                            (a, b) => a.or(b),
+                        };
+                        bonds.push(QueryBond::new(opener, atom, expr));
+                    } else {
+                        rings.insert(number, (atom, here));
+                    }
+                    continue;
+                }
+                '-' | '=' | '#' | '$' | ':' | '~' | '@' | '/' | '\\' | '!' => {
+                    if previous.is_none() {
+                        return Err(SmartsError::BondWithoutPrecedingAtom);
+                    }
+                    if pending.is_some() {
+                        return Err(SmartsError::UnexpectedCharacter(c, self.column + 1));
+                    }
+                    pending = Some(self.parse_expr(Self::bond_primitive)?);
+                    continue;
+                }
+                ' ' | '\t' | '\n' | '\r' if !nested => break,
+                // The caller reports the missing `)`.
+                ']' if nested => break,
+                _ => {
+                    self.next();
+                    return Err(SmartsError::UnexpectedCharacter(c, self.column));
+                }
+            }
+
+            // An atom, bracketed or not.
+            let atom = self.parse_atom()?;
+            let index = atoms.len() as NodeIndex;
+            atoms.push(atom);
+            match previous {
+                Some(source) => bonds.push(QueryBond::new(source, index, pending.take())),
+                None if pending.is_some() => return Err(SmartsError::BondWithoutPrecedingAtom),
+                None => {}
+            }
+            previous = Some(index);
+        }
+
+        if !branches.is_empty() {
+            return Err(SmartsError::UnclosedParenthesis);
+        }
+        if !rings.is_empty() {
+            return Err(SmartsError::UnclosedRing(rings.into_keys().collect()));
+        }
+        if pending.is_some() {
+            return Err(SmartsError::BondWithoutFollowingAtom);
+        }
+        if atoms.is_empty() {
+            return Err(SmartsError::Empty);
+        }
+        Ok(QueryMolecule::new(atoms, bonds))
+    }
+
+    fn parse_ring_number(&mut self, c: char) -> Result<u8, SmartsError> {
+        if c != '%' {
+            return Ok(c as u8 - b'0');
+        }
+        let mut number = 0;
+        for _ in 0..2 {
+            let digit = self
+                .next()
+                .ok_or(SmartsError::UnexpectedEndOfInput("ring number".to_string()))?;
+            let value = digit
+                .to_digit(10)
+                .ok_or(SmartsError::UnexpectedCharacter(digit, self.column))?;
+            number = number * 10 + value as u8;
+        }
+        Ok(number)
+    }
+
+    /// Reads an atom outside brackets (organic subset, `*`, `a`, `A`) or a
+    /// bracket atom.
+    fn parse_atom(&mut self) -> Result<QueryAtom, SmartsError> {
+        let c = self.next().expect("atom start was peeked");
+        if c == '[' {
+            return self.parse_bracket_atom();
+        }
+        let primitive = match c {
+            '*' => AtomPrimitive::Any,
+            'a' => AtomPrimitive::Aromatic,
+            'A' => AtomPrimitive::Aliphatic,
+            _ => {
+                let position = self.column;
+                let element = self.parse_element_symbol(c, false)?;
+                let aromatic = c.is_ascii_lowercase();
+                let valid = match element {
+                    AtomSymbol::Organic(_) => !aromatic || element.can_be_aromatic(),
+                    _ => false,
+                };
+                if !valid {
+                    return Err(SmartsError::UnexpectedCharacter(c, position));
+                }
+                AtomPrimitive::Element { element, aromatic }
+            }
+        };
+        Ok(QueryAtom::new(QueryExpr::Primitive(primitive), None))
+    }
+
+    /// Reads a bracket atom after its `[`.
+    fn parse_bracket_atom(&mut self) -> Result<QueryAtom, SmartsError> {
+        // `H` right after `[` (and an optional isotope) is a hydrogen atom
+        // when nothing but a charge or class follows: `[H]`, `[2H]`, `[H+]`.
+        let mut ahead = self.chars.clone().skip_while(|c| c.is_ascii_digit());
+        self.hydrogen_atom =
+            ahead.next() == Some('H') && matches!(ahead.next(), Some(']' | '+' | '-' | ':'));
+
+        let expr = self.parse_expr(Self::atom_primitive)?;
+        self.hydrogen_atom = false;
+        let class = self.parse_class();
+        match self.next() {
+            Some(']') => Ok(QueryAtom::new(expr, class)),
+            Some(c) => Err(SmartsError::UnexpectedCharacter(c, self.column)),
+            None => Err(SmartsError::UnexpectedEndOfInput("]".to_string())),
+        }
+    }
+
+    /// Reads `a;b`, the loosest operator.
+    fn parse_expr<P>(&mut self, read: PrimitiveReader<'a, P>) -> Result<QueryExpr<P>, SmartsError> {
+        let mut operands = vec![self.parse_or(read)?];
+        while self.peek() == Some(&';') {
+            self.next();
+            operands.push(self.parse_or(read)?);
+        }
+        Ok(collapse(operands, QueryExpr::LowAnd))
+    }
+
+    /// Reads `a,b`.
+    fn parse_or<P>(&mut self, read: PrimitiveReader<'a, P>) -> Result<QueryExpr<P>, SmartsError> {
+        let mut operands = vec![self.parse_and(read)?];
+        while self.peek() == Some(&',') {
+            self.next();
+            operands.push(self.parse_and(read)?);
+        }
+        Ok(collapse(operands, QueryExpr::Or))
+    }
+
+    /// Reads `a&b` or `ab`.
+    fn parse_and<P>(&mut self, read: PrimitiveReader<'a, P>) -> Result<QueryExpr<P>, SmartsError> {
+        let first = self.parse_unary(read)?;
+        let mut operands = vec![first];
+        loop {
+            if self.peek() == Some(&'&') {
+                self.next();
+                operands.push(self.parse_unary(read)?);
+            } else if let Some(operand) = self.try_parse_unary(read)? {
+                operands.push(operand);
+            } else {
+                break;
+            }
+        }
+        Ok(collapse(operands, QueryExpr::And))
+    }
+
+    /// Reads `!a` or a primitive, which must be present.
+    fn parse_unary<P>(
+        &mut self,
+        read: PrimitiveReader<'a, P>,
+    ) -> Result<QueryExpr<P>, SmartsError> {
+        self.try_parse_unary(read)?
+            .ok_or(SmartsError::MissingPrimitive(self.column + 1))
+    }
+
+    fn try_parse_unary<P>(
+        &mut self,
+        read: PrimitiveReader<'a, P>,
+    ) -> Result<Option<QueryExpr<P>>, SmartsError> {
+        if self.peek() == Some(&'!') {
+            self.next();
+            let operand = self.parse_unary(read)?;
+            return Ok(Some(QueryExpr::Not(Box::new(operand))));
+        }
+        Ok(read(self)?.map(QueryExpr::Primitive))
+    }
+
+    fn bond_primitive(&mut self) -> Result<Option<BondPrimitive>, SmartsError> {
+        let primitive = match self.peek() {
+            Some('-') => BondPrimitive::Single,
+            Some('=') => BondPrimitive::Double,
+            Some('#') => BondPrimitive::Triple,
+            Some('$') => BondPrimitive::Quadruple,
+            Some(':') => BondPrimitive::Aromatic,
+            Some('/') => BondPrimitive::Up,
+            Some('\\') => BondPrimitive::Down,
+            Some('~') => BondPrimitive::Any,
+            Some('@') => BondPrimitive::Ring,
+            _ => return Ok(None),
+        };
+        self.next();
+        Ok(Some(primitive))
+    }
+
+    /// Reads an optional count after a primitive letter.
+    fn parse_count(&mut self) -> Result<Option<u8>, SmartsError> {
+        let mut digits = String::new();
+        while let Some(&c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(c);
+            self.next();
+        }
+        if digits.is_empty() {
+            return Ok(None);
+        }
+        digits
+            .parse()
+            .map(Some)
+            .map_err(|_| SmartsError::NumberOutOfRange(digits))
+    }
+
+    /// Reads a two-letter element starting with `first` (already consumed),
+    /// if the next character completes one.
+    fn two_letter_element(&mut self, first: char) -> Option<AtomSymbol> {
+        let second = *self.peek().filter(|c| c.is_ascii_lowercase())?;
+        let element = AtomSymbol::from_str(&format!("{}{}", first, second)).ok()?;
+        self.next();
+        Some(element)
+    }
+
+    fn atom_primitive(&mut self) -> Result<Option<AtomPrimitive>, SmartsError> {
+        let Some(&c) = self.peek() else {
+            return Ok(None);
+        };
+        let aliphatic = |element| AtomPrimitive::Element {
+            element,
+            aromatic: false,
+        };
+        let primitive = match c {
+            '0'..='9' => {
+                let isotope = self
+                    .parse_isotope()
+                    .ok_or(SmartsError::NumberOutOfRange("isotope".to_string()))?;
+                AtomPrimitive::Isotope(isotope)
+            }
+            '#' => {
+                self.next();
+                let number = self
+                    .parse_count()?
+                    .ok_or(SmartsError::MissingPrimitive(self.column + 1))?;
+                AtomPrimitive::AtomicNumber(number)
+            }
+            '*' => {
+                self.next();
+                AtomPrimitive::Any
+            }
+            '+' | '-' => AtomPrimitive::Charge(self.parse_charge()?),
+            '@' => {
+                let chirality = self.parse_chirality()?.expect("'@' was peeked");
+                AtomPrimitive::Chirality(chirality)
+            }
+            '$' => {
+                self.next();
+                match self.next() {
+                    Some('(') => {}
+                    Some(c) => return Err(SmartsError::UnexpectedCharacter(c, self.column)),
+                    None => return Err(SmartsError::UnexpectedEndOfInput("(".to_string())),
+                }
+                let query = self.parse_pattern(true)?;
+                match self.next() {
+                    Some(')') => AtomPrimitive::Recursive(Box::new(query)),
+                    _ => return Err(SmartsError::UnclosedParenthesis),
+                }
+            }
+            // Hydrogen is never aromatic: `[H]` is `[#1]`.
+            'H' if self.hydrogen_atom => {
+                self.next();
+                self.hydrogen_atom = false;
+                AtomPrimitive::AtomicNumber(1)
+            }
+            'H' | 'D' | 'X' | 'R' | 'A' => {
+                self.next();
+                if let Some(element) = self.two_letter_element(c) {
+                    aliphatic(element)
+                } else {
+                    let count = self.parse_count()?;
+                    match c {
+                        'H' => AtomPrimitive::TotalHydrogens(count.unwrap_or(1)),
+                        'D' => AtomPrimitive::Degree(count.unwrap_or(1)),
+                        'X' => AtomPrimitive::Connectivity(count.unwrap_or(1)),
+                        'R' => AtomPrimitive::RingMembership(count),
+                        _ => match count {
+                            None => AtomPrimitive::Aliphatic,
+                            Some(_) => {
+                                return Err(SmartsError::UnexpectedCharacter(c, self.column))
+                            }
+                        },
+                    }
+                }
+            }
+            'r' => {
+                self.next();
+                AtomPrimitive::RingSize(self.parse_count()?)
+            }
+            'v' => {
+                self.next();
+                AtomPrimitive::Valence(self.parse_count()?.unwrap_or(1))
+            }
+            'a' => {
+                self.next();
+                if self.peek() == Some(&'s') {
+                    self.next();
+                    AtomPrimitive::Element {
+                        element: AtomSymbol::As,
+                        aromatic: true,
+                    }
+                } else {
+                    AtomPrimitive::Aromatic
+                }
+            }
+            'b' | 'c' | 'n' | 'o' | 'p' | 's' | 't' => {
+                self.next();
+                let element = match (c, self.peek()) {
+                    ('s', Some(&'e')) => AtomSymbol::Se,
+                    ('t', Some(&'e')) => AtomSymbol::Te,
+                    ('t', _) => return Err(SmartsError::UnexpectedCharacter(c, self.column)),
+                    _ => AtomSymbol::from_str(&c.to_string())
+                        .map_err(|_| SmartsError::UnexpectedCharacter(c, self.column))?,
+                };
+                if matches!(element, AtomSymbol::Se | AtomSymbol::Te) {
+                    self.next();
+                }
+                AtomPrimitive::Element {
+                    element,
+                    aromatic: true,
+                }
+            }
+            c if c.is_ascii_uppercase() => {
+                self.next();
+                aliphatic(self.parse_element_symbol(c, true)?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(primitive))
+    }
+}
+
+/// Parses a SMARTS pattern into a [`QueryMolecule`].
+///
+/// Supports atom primitives `*`, `a`, `A`, element symbols, `#n`, isotopes,
+/// charges, `H`, `D`, `X`, `v`, `R`, `r`, `@`/`@@` and recursive `$(...)`;
+/// bond primitives `-`, `=`, `#`, `$`, `:`, `/`, `\`, `~` and `@`; and the
+/// operators `!`, `&` (or juxtaposition), `,` and `;`. As in SMILES,
+/// whitespace ends the pattern.
+///
+/// Two-letter element symbols are read greedily: `[Cr]` is chromium and
+/// `[Ho]` holmium. `H` is a hydrogen atom (`#1`) at the start of a bracket
+/// followed only by a charge or class (`[H]`, `[2H]`, `[H+]`) and a
+/// hydrogen count anywhere else (`[CH2]`, `[*H]`).
+///
+/// # Errors
+///
+/// Returns a [`SmartsError`] if the input is not valid SMARTS.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::smarts::{parse_smarts, AtomPrimitive, QueryExpr};
+///
+/// let query = parse_smarts("[#7;R]").unwrap();
+/// let QueryExpr::LowAnd(operands) = query.atoms()[0].expr() else {
+///     unreachable!()
+/// };
+/// assert_eq!(operands[0], QueryExpr::Primitive(AtomPrimitive::AtomicNumber(7)));
+/// assert_eq!(operands[1], QueryExpr::Primitive(AtomPrimitive::RingMembership(None)));
+/// ```
+pub fn parse_smarts(input: &str) -> Result<QueryMolecule, SmartsError> {
+    SmartsParser::new(input).parse_pattern(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::query::AtomExpr;
+    use super::*;
+    use crate::{Chirality, OrganicAtom};
+
+    /// Parses `input` as a single atom expression, without brackets.
+    fn parse_atom_expr(input: &str) -> Result<AtomExpr, SmartsError> {
+        let query = parse_smarts(&format!("[{}]", input))?;
+        Ok(query.atoms()[0].expr().clone())
+    }
+
+    fn primitive(p: AtomPrimitive) -> AtomExpr {
+        QueryExpr::Primitive(p)
+    }
+
+    fn carbon(aromatic: bool) -> AtomExpr {
+        primitive(AtomPrimitive::Element {
+            element: AtomSymbol::Organic(OrganicAtom::C),
+            aromatic,
+        })
+    }
+
+    #[test]
+    fn operator_precedence() {
+        // `&` binds tighter than `,`, which binds tighter than `;`.
+        let expr = parse_atom_expr("C,c&R;!X4").unwrap();
+        let expected = QueryExpr::LowAnd(vec![
+            QueryExpr::Or(vec![
+                carbon(false),
+                QueryExpr::And(vec![
+                    carbon(true),
+                    primitive(AtomPrimitive::RingMembership(None)),
+                ]),
+            ]),
+            QueryExpr::Not(Box::new(primitive(AtomPrimitive::Connectivity(4)))),
+        ]);
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn juxtaposition_is_and() {
+        let expr = parse_atom_expr("CH2+").unwrap();
+        assert_eq!(
+            expr,
+            QueryExpr::And(vec![
+                carbon(false),
+                primitive(AtomPrimitive::TotalHydrogens(2)),
+                primitive(AtomPrimitive::Charge(1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn hydrogen_atom_or_count() {
+        let h = primitive(AtomPrimitive::AtomicNumber(1));
+        assert_eq!(parse_atom_expr("H").unwrap(), h);
+        assert_eq!(
+            parse_atom_expr("2H").unwrap(),
+            QueryExpr::And(vec![primitive(AtomPrimitive::Isotope(2)), h.clone()])
+        );
+        assert_eq!(
+            parse_atom_expr("*H").unwrap(),
+            QueryExpr::And(vec![
+                primitive(AtomPrimitive::Any),
+                primitive(AtomPrimitive::TotalHydrogens(1))
+            ])
+        );
+        assert_eq!(
+            parse_atom_expr("Hg").unwrap(),
+            primitive(AtomPrimitive::Element {
+                element: AtomSymbol::Hg,
+                aromatic: false
+            })
+        );
+    }
+
+    #[test]
+    fn chirality_and_recursion() {
+        let expr = parse_atom_expr("C@@H").unwrap();
+        let QueryExpr::And(operands) = expr else {
+            panic!("expected a conjunction")
+        };
+        assert_eq!(
+            operands[1],
+            primitive(AtomPrimitive::Chirality(Chirality::TH2))
+        );
+
+        let expr = parse_atom_expr("$(C=O)").unwrap();
+        let QueryExpr::Primitive(AtomPrimitive::Recursive(query)) = expr else {
+            panic!("expected a recursive SMARTS")
+        };
+        assert_eq!(query.atoms().len(), 2);
+    }
+
+    #[test]
+    fn ring_bond_expressions_read_opener_to_closer() {
+        let query = parse_smarts("C/1CCCC1").unwrap();
+        let ring = query.bonds().last().unwrap();
+        assert_eq!((ring.source(), ring.target()), (0, 4));
+        assert_eq!(ring.expr(), Some(&QueryExpr::Primitive(BondPrimitive::Up)));
+
+        let query = parse_smarts("C1CCCC/1").unwrap();
+        let ring = query.bonds().last().unwrap();
+        assert_eq!(
+            ring.expr(),
+            Some(&QueryExpr::Primitive(BondPrimitive::Down))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_smarts(""), Err(SmartsError::Empty));
+        assert_eq!(parse_smarts("[C,]"), Err(SmartsError::MissingPrimitive(4)));
+        assert_eq!(parse_smarts("C(C"), Err(SmartsError::UnclosedParenthesis));
+        assert_eq!(parse_smarts("CC)"), Err(SmartsError::UnopenedParenthesis));
+        assert_eq!(
+            parse_smarts("C1CC"),
+            Err(SmartsError::UnclosedRing(vec![1]))
+        );
+        assert_eq!(
+            parse_smarts("C=1CC-1"),
+            Err(SmartsError::MismatchedRingBond(1))
+        );
+        assert_eq!(
+            parse_smarts("C="),
+            Err(SmartsError::BondWithoutFollowingAtom)
+        );
+        assert_eq!(
+            parse_smarts("=C"),
+            Err(SmartsError::BondWithoutPrecedingAtom)
+        );
+        assert_eq!(parse_smarts("C()"), Err(SmartsError::EmptyBranch));
+        assert_eq!(
+            parse_smarts("K"),
+            Err(SmartsError::UnexpectedCharacter('K', 1))
+        );
+        assert_eq!(
+            parse_smarts("[X999]"),
+            Err(SmartsError::NumberOutOfRange("999".to_string()))
+        );
+        assert_eq!(parse_smarts("[$(C]"), Err(SmartsError::UnclosedParenthesis));
+    }
+}
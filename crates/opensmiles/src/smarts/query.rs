@@ -0,0 +1,732 @@
+//! Query molecules: the SMARTS syntax tree and its serialization.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::parser::parse_smarts;
+use crate::ast::stereo;
+use crate::{AtomSymbol, BondType, Chirality, Molecule, NodeIndex, OrganicAtom, SmartsError};
+
+/// A logical expression over SMARTS primitives.
+///
+/// From the tightest to the loosest binding, SMARTS writes `!` (negation),
+/// `&` or juxtaposition (`And`), `,` (`Or`) and `;` (`LowAnd`). `And` and
+/// `LowAnd` mean the same thing and only differ in how they are written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryExpr<P> {
+    /// A single primitive test.
+    Primitive(P),
+    /// `!e`: true when the operand is false.
+    Not(Box<QueryExpr<P>>),
+    /// `a&b` (or `ab`): true when every operand is.
+    And(Vec<QueryExpr<P>>),
+    /// `a,b`: true when any operand is.
+    Or(Vec<QueryExpr<P>>),
+    /// `a;b`: true when every operand is.
+    LowAnd(Vec<QueryExpr<P>>),
+}
+
+/// An atom expression, the content of a SMARTS atom.
+pub type AtomExpr = QueryExpr<AtomPrimitive>;
+
+/// A bond expression, written between two SMARTS atoms.
+pub type BondExpr = QueryExpr<BondPrimitive>;
+
+impl<P> QueryExpr<P> {
+    /// Evaluates the expression, deciding each primitive with `test`.
+    pub fn evaluate(&self, test: &mut impl FnMut(&P) -> bool) -> bool {
+        match self {
+            QueryExpr::Primitive(p) => test(p),
+            QueryExpr::Not(e) => !e.evaluate(test),
+            QueryExpr::And(es) | QueryExpr::LowAnd(es) => es.iter().all(|e| e.evaluate(test)),
+            QueryExpr::Or(es) => es.iter().any(|e| e.evaluate(test)),
+        }
+    }
+
+    /// Returns a copy with every primitive replaced by `f(primitive)`.
+    pub(crate) fn map(&self, f: &impl Fn(&P) -> P) -> QueryExpr<P> {
+        let all = |es: &[QueryExpr<P>]| es.iter().map(|e| e.map(f)).collect();
+        match self {
+            QueryExpr::Primitive(p) => QueryExpr::Primitive(f(p)),
+            QueryExpr::Not(e) => QueryExpr::Not(Box::new(e.map(f))),
+            QueryExpr::And(es) => QueryExpr::And(all(es)),
+            QueryExpr::Or(es) => QueryExpr::Or(all(es)),
+            QueryExpr::LowAnd(es) => QueryExpr::LowAnd(all(es)),
+        }
+    }
+
+    /// Returns `true` if the expression can be written as is: SMARTS has no
+    /// parentheses, so an operand must bind tighter than its operator.
+    fn writable(&self) -> bool {
+        use QueryExpr::*;
+        match self {
+            Primitive(_) => true,
+            Not(e) => matches!(**e, Primitive(_) | Not(_)) && e.writable(),
+            And(es) => es
+                .iter()
+                .all(|e| matches!(e, Primitive(_) | Not(_) | And(_)) && e.writable()),
+            Or(es) => es.iter().all(|e| !matches!(e, LowAnd(_)) && e.writable()),
+            LowAnd(es) => es.iter().all(|e| e.writable()),
+        }
+    }
+
+    /// Disjunctive normal form: a list of terms, each a list of possibly
+    /// negated primitives.
+    fn disjunctive_normal_form(&self, negated: bool) -> Vec<Vec<(bool, &P)>> {
+        match self {
+            QueryExpr::Primitive(p) => vec![vec![(negated, p)]],
+            QueryExpr::Not(e) => e.disjunctive_normal_form(!negated),
+            QueryExpr::And(es) | QueryExpr::LowAnd(es) if !negated => product(es, negated),
+            QueryExpr::And(es) | QueryExpr::LowAnd(es) => union(es, negated),
+            QueryExpr::Or(es) if !negated => union(es, negated),
+            QueryExpr::Or(es) => product(es, negated),
+        }
+    }
+}
+
+/// Conjunction of operands in normal form: every combination of terms.
+fn product<P>(operands: &[QueryExpr<P>], negated: bool) -> Vec<Vec<(bool, &P)>> {
+    operands.iter().fold(vec![Vec::new()], |terms, operand| {
+        let others = operand.disjunctive_normal_form(negated);
+        let mut combined = Vec::with_capacity(terms.len() * others.len());
+        for term in &terms {
+            for other in &others {
+                let mut term = term.clone();
+                term.extend(other.iter().copied());
+                combined.push(term);
+            }
+        }
+        combined
+    })
+}
+
+/// Disjunction of operands in normal form: all their terms.
+fn union<P>(operands: &[QueryExpr<P>], negated: bool) -> Vec<Vec<(bool, &P)>> {
+    operands
+        .iter()
+        .flat_map(|operand| operand.disjunctive_normal_form(negated))
+        .collect()
+}
+
+fn write_joined<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    sep: &str,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(sep)?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+/// Writes the expression with the SMARTS operators. An expression that
+/// needs parentheses, which SMARTS lacks, is written in disjunctive normal
+/// form instead.
+impl<P: fmt::Display> fmt::Display for QueryExpr<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.writable() {
+            let terms = self.disjunctive_normal_form(false);
+            for (i, term) in terms.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",")?;
+                }
+                for (j, (negated, p)) in term.iter().enumerate() {
+                    if j > 0 {
+                        f.write_str("&")?;
+                    }
+                    if *negated {
+                        f.write_str("!")?;
+                    }
+                    write!(f, "{}", p)?;
+                }
+            }
+            return Ok(());
+        }
+        match self {
+            QueryExpr::Primitive(p) => write!(f, "{}", p),
+            QueryExpr::Not(e) => write!(f, "!{}", e),
+            QueryExpr::And(es) => write_joined(f, es, "&"),
+            QueryExpr::Or(es) => write_joined(f, es, ","),
+            QueryExpr::LowAnd(es) => write_joined(f, es, ";"),
+        }
+    }
+}
+
+/// A test on a single atom.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtomPrimitive {
+    /// `*`: any atom.
+    Any,
+    /// `a`: any aromatic atom.
+    Aromatic,
+    /// `A`: any aliphatic atom.
+    Aliphatic,
+    /// An element symbol: `C` is aliphatic carbon, `c` aromatic carbon.
+    Element {
+        /// The element.
+        element: AtomSymbol,
+        /// `true` when written in lowercase.
+        aromatic: bool,
+    },
+    /// `#n`: the atomic number, aromatic or not.
+    AtomicNumber(u8),
+    /// A mass number written before the symbol (`[13C]`).
+    Isotope(u16),
+    /// `+n` / `-n`: the formal charge.
+    Charge(i8),
+    /// `Hn`: the total number of attached hydrogens.
+    TotalHydrogens(u8),
+    /// `Dn`: the number of explicit connections.
+    Degree(u8),
+    /// `Xn`: the number of connections, hydrogens included.
+    Connectivity(u8),
+    /// `vn`: the sum of bond orders, hydrogens included.
+    Valence(u8),
+    /// `Rn`: the number of smallest rings the atom belongs to; `R` alone
+    /// means in any ring.
+    RingMembership(Option<u8>),
+    /// `rn`: the size of the smallest ring containing the atom; `r` alone
+    /// means in any ring.
+    RingSize(Option<u8>),
+    /// `@`, `@@`, …: the chirality.
+    Chirality(Chirality),
+    /// `$(...)`: the atom is the first atom of a match of the query.
+    Recursive(Box<QueryMolecule>),
+}
+
+impl AtomPrimitive {
+    /// Returns `true` if the primitive alone can be written without brackets.
+    fn writes_bare(&self) -> bool {
+        match self {
+            AtomPrimitive::Any | AtomPrimitive::Aromatic | AtomPrimitive::Aliphatic => true,
+            AtomPrimitive::Element {
+                element: AtomSymbol::Organic(organic),
+                aromatic,
+            } => !aromatic || organic_aromatic(*organic),
+            _ => false,
+        }
+    }
+}
+
+/// Organic-subset elements with a lowercase aromatic symbol.
+fn organic_aromatic(organic: OrganicAtom) -> bool {
+    matches!(
+        organic,
+        OrganicAtom::B
+            | OrganicAtom::C
+            | OrganicAtom::N
+            | OrganicAtom::O
+            | OrganicAtom::P
+            | OrganicAtom::S
+    )
+}
+
+impl fmt::Display for AtomPrimitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtomPrimitive::Any => f.write_str("*"),
+            AtomPrimitive::Aromatic => f.write_str("a"),
+            AtomPrimitive::Aliphatic => f.write_str("A"),
+            // `H` would read as a hydrogen count.
+            AtomPrimitive::Element {
+                element: AtomSymbol::H,
+                ..
+            } => f.write_str("#1"),
+            AtomPrimitive::Element { element, aromatic } => {
+                let symbol = element.to_string();
+                if *aromatic {
+                    f.write_str(&symbol.to_ascii_lowercase())
+                } else {
+                    f.write_str(&symbol)
+                }
+            }
+            AtomPrimitive::AtomicNumber(n) => write!(f, "#{}", n),
+            AtomPrimitive::Isotope(n) => write!(f, "{}", n),
+            AtomPrimitive::Charge(c) if *c >= 0 => write!(f, "+{}", c),
+            AtomPrimitive::Charge(c) => write!(f, "-{}", c.unsigned_abs()),
+            AtomPrimitive::TotalHydrogens(n) => write!(f, "H{}", n),
+            AtomPrimitive::Degree(n) => write!(f, "D{}", n),
+            AtomPrimitive::Connectivity(n) => write!(f, "X{}", n),
+            AtomPrimitive::Valence(n) => write!(f, "v{}", n),
+            AtomPrimitive::RingMembership(None) => f.write_str("R"),
+            AtomPrimitive::RingMembership(Some(n)) => write!(f, "R{}", n),
+            AtomPrimitive::RingSize(None) => f.write_str("r"),
+            AtomPrimitive::RingSize(Some(n)) => write!(f, "r{}", n),
+            AtomPrimitive::Chirality(c) => write!(f, "{}", c),
+            AtomPrimitive::Recursive(query) => write!(f, "$({})", query),
+        }
+    }
+}
+
+/// A test on a single bond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondPrimitive {
+    /// `-`: single bond (directional bonds included).
+    Single,
+    /// `=`: double bond.
+    Double,
+    /// `#`: triple bond.
+    Triple,
+    /// `$`: quadruple bond.
+    Quadruple,
+    /// `:`: aromatic bond.
+    Aromatic,
+    /// `/`: single bond, the second atom above the first.
+    Up,
+    /// `\`: single bond, the second atom below the first.
+    Down,
+    /// `~`: any bond.
+    Any,
+    /// `@`: ring bond.
+    Ring,
+}
+
+impl BondPrimitive {
+    /// Returns the primitive read from the other end of the bond.
+    pub(crate) fn reversed(&self) -> BondPrimitive {
+        match self {
+            BondPrimitive::Up => BondPrimitive::Down,
+            BondPrimitive::Down => BondPrimitive::Up,
+            other => *other,
+        }
+    }
+}
+
+impl fmt::Display for BondPrimitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BondPrimitive::Single => "-",
+            BondPrimitive::Double => "=",
+            BondPrimitive::Triple => "#",
+            BondPrimitive::Quadruple => "$",
+            BondPrimitive::Aromatic => ":",
+            BondPrimitive::Up => "/",
+            BondPrimitive::Down => "\\",
+            BondPrimitive::Any => "~",
+            BondPrimitive::Ring => "@",
+        })
+    }
+}
+
+/// An atom of a [`QueryMolecule`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryAtom {
+    expr: AtomExpr,
+    class: Option<u16>,
+}
+
+impl QueryAtom {
+    /// Creates a query atom from its expression and atom class (`[C:1]`).
+    pub fn new(expr: AtomExpr, class: Option<u16>) -> QueryAtom {
+        QueryAtom { expr, class }
+    }
+
+    /// Returns the expression the atom must satisfy.
+    pub fn expr(&self) -> &AtomExpr {
+        &self.expr
+    }
+
+    /// Returns the atom class, if specified (e.g. `[C:1]` → `Some(1)`).
+    pub fn class(&self) -> Option<u16> {
+        self.class
+    }
+}
+
+impl fmt::Display for QueryAtom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let QueryExpr::Primitive(p) = &self.expr {
+            if self.class.is_none() && p.writes_bare() {
+                return write!(f, "{}", p);
+            }
+        }
+        write!(f, "[{}", self.expr)?;
+        if let Some(class) = self.class {
+            write!(f, ":{}", class)?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// A bond of a [`QueryMolecule`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryBond {
+    source: NodeIndex,
+    target: NodeIndex,
+    expr: Option<BondExpr>,
+}
+
+impl QueryBond {
+    /// Creates a query bond. `expr` reads from `source` to `target`; `None`
+    /// stands for an unwritten bond, which matches single or aromatic bonds.
+    pub fn new(source: NodeIndex, target: NodeIndex, expr: Option<BondExpr>) -> QueryBond {
+        QueryBond {
+            source,
+            target,
+            expr,
+        }
+    }
+
+    /// Returns the index of the first atom.
+    pub fn source(&self) -> NodeIndex {
+        self.source
+    }
+
+    /// Returns the index of the second atom.
+    pub fn target(&self) -> NodeIndex {
+        self.target
+    }
+
+    /// Returns the bond expression, or `None` if none was written.
+    pub fn expr(&self) -> Option<&BondExpr> {
+        self.expr.as_ref()
+    }
+}
+
+/// A parsed SMARTS pattern: atoms and bonds carrying logical expressions
+/// instead of concrete values.
+///
+/// # Example
+///
+/// ```
+/// use opensmiles::smarts::QueryMolecule;
+///
+/// let acid: QueryMolecule = "[CX3](=O)[OX2H1]".parse().unwrap();
+/// assert_eq!(acid.atoms().len(), 3);
+/// assert_eq!(acid.to_string(), "[C&X3](=O)[O&X2&H1]");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryMolecule {
+    atoms: Vec<QueryAtom>,
+    bonds: Vec<QueryBond>,
+}
+
+impl QueryMolecule {
+    /// Creates a query from its atoms and bonds.
+    pub fn new(atoms: Vec<QueryAtom>, bonds: Vec<QueryBond>) -> QueryMolecule {
+        QueryMolecule { atoms, bonds }
+    }
+
+    /// Returns the query atoms.
+    pub fn atoms(&self) -> &[QueryAtom] {
+        &self.atoms
+    }
+
+    /// Returns the query bonds.
+    pub fn bonds(&self) -> &[QueryBond] {
+        &self.bonds
+    }
+
+    /// Writes the bond expression read from `from`, if any.
+    fn write_bond(&self, f: &mut fmt::Formatter<'_>, bond: usize, from: usize) -> fmt::Result {
+        let bond = &self.bonds[bond];
+        match &bond.expr {
+            Some(expr) if bond.source as usize == from => write!(f, "{}", expr),
+            Some(expr) => write!(f, "{}", expr.map(&BondPrimitive::reversed)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Depth-first spanning forest of a query, in atom order: tree bonds are
+/// written as chain or branch bonds, the others as ring closures.
+struct Forest {
+    incident: Vec<Vec<usize>>,
+    order: Vec<usize>,
+    parent: Vec<Option<usize>>,
+    children: Vec<Vec<(usize, usize)>>,
+    tree: Vec<bool>,
+    roots: Vec<usize>,
+}
+
+impl QueryMolecule {
+    /// Returns the atom at the other end of `bond`.
+    fn other(&self, bond: usize, atom: usize) -> usize {
+        let b = &self.bonds[bond];
+        if b.source as usize == atom {
+            b.target as usize
+        } else {
+            b.source as usize
+        }
+    }
+
+    fn forest(&self) -> Forest {
+        let n = self.atoms.len();
+        let mut incident: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (i, bond) in self.bonds.iter().enumerate() {
+            incident[bond.source as usize].push(i);
+            incident[bond.target as usize].push(i);
+        }
+
+        let mut order = vec![usize::MAX; n];
+        let mut parent = vec![None; n];
+        let mut children: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+        let mut tree = vec![false; self.bonds.len()];
+        let mut roots = Vec::new();
+        let mut visited = 0;
+        for start in 0..n {
+            if order[start] != usize::MAX {
+                continue;
+            }
+            roots.push(start);
+            order[start] = visited;
+            visited += 1;
+            let mut stack = vec![(start, 0)];
+            while let Some((atom, next)) = stack.last_mut() {
+                let atom = *atom;
+                let Some(&bond) = incident[atom].get(*next) else {
+                    stack.pop();
+                    continue;
+                };
+                *next += 1;
+                let v = self.other(bond, atom);
+                if order[v] == usize::MAX {
+                    order[v] = visited;
+                    visited += 1;
+                    tree[bond] = true;
+                    parent[v] = Some(atom);
+                    children[atom].push((bond, v));
+                    stack.push((v, 0));
+                }
+            }
+        }
+        Forest {
+            incident,
+            order,
+            parent,
+            children,
+            tree,
+            roots,
+        }
+    }
+
+    /// Returns the neighbours of every atom in the order
+    /// [`Display`](fmt::Display) writes them: the preceding atom, ring
+    /// closures, then branches and the next atom. `@` and `@@` refer to this
+    /// order. When `implicit(atom)` holds, the atom itself stands for its
+    /// implicit hydrogen or lone pair, right after the preceding atom.
+    pub(crate) fn written_neighbours(&self, implicit: impl Fn(usize) -> bool) -> Vec<Vec<usize>> {
+        let forest = self.forest();
+        (0..self.atoms.len())
+            .map(|atom| {
+                let rings = forest.incident[atom]
+                    .iter()
+                    .filter(|&&b| !forest.tree[b])
+                    .map(|&b| self.other(b, atom));
+                let children = forest.children[atom].iter().map(|&(_, child)| child);
+                let hydrogen = implicit(atom).then_some(atom);
+                forest.parent[atom]
+                    .into_iter()
+                    .chain(hydrogen)
+                    .chain(rings)
+                    .chain(children)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// A step of the iterative SMARTS writer.
+enum Step {
+    Atom { atom: usize, bond: Option<usize> },
+    Open,
+    Close,
+}
+
+/// Writes the query as SMARTS, one dot-separated component at a time, in
+/// atom order: a pattern read from a string is written back the same way
+/// up to operator spelling (`[CH2]` becomes `[C&H2]`).
+impl fmt::Display for QueryMolecule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Forest {
+            incident,
+            order,
+            children,
+            tree,
+            roots,
+            ..
+        } = self.forest();
+        let other = |bond: usize, atom: usize| self.other(bond, atom);
+
+        let mut ring_numbers = vec![0u8; self.bonds.len()];
+        let mut in_use: Vec<bool> = Vec::new();
+        for (i, &root) in roots.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            let mut steps = vec![Step::Atom {
+                atom: root,
+                bond: None,
+            }];
+            while let Some(step) = steps.pop() {
+                let (atom, bond) = match step {
+                    Step::Open => {
+                        f.write_str("(")?;
+                        continue;
+                    }
+                    Step::Close => {
+                        f.write_str(")")?;
+                        continue;
+                    }
+                    Step::Atom { atom, bond } => (atom, bond),
+                };
+                if let Some(bond) = bond {
+                    self.write_bond(f, bond, other(bond, atom))?;
+                }
+                write!(f, "{}", self.atoms[atom])?;
+
+                for &ring in incident[atom].iter().filter(|&&b| !tree[b]) {
+                    let number = if order[other(ring, atom)] > order[atom] {
+                        let free = in_use.iter().position(|used| !used).unwrap_or_else(|| {
+                            in_use.push(false);
+                            in_use.len() - 1
+                        });
+                        in_use[free] = true;
+                        ring_numbers[ring] = free as u8 + 1;
+                        self.write_bond(f, ring, atom)?;
+                        ring_numbers[ring]
+                    } else {
+                        in_use[ring_numbers[ring] as usize - 1] = false;
+                        ring_numbers[ring]
+                    };
+                    if number < 10 {
+                        write!(f, "{}", number)?;
+                    } else {
+                        write!(f, "%{}", number)?;
+                    }
+                }
+
+                if let Some((&(bond, last), rest)) = children[atom].split_last() {
+                    steps.push(Step::Atom {
+                        atom: last,
+                        bond: Some(bond),
+                    });
+                    for &(bond, child) in rest.iter().rev() {
+                        steps.push(Step::Close);
+                        steps.push(Step::Atom {
+                            atom: child,
+                            bond: Some(bond),
+                        });
+                        steps.push(Step::Open);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for QueryMolecule {
+    type Err = SmartsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_smarts(s)
+    }
+}
+
+/// Uses a molecule as a query pattern.
+///
+/// Each atom matches its element and aromaticity, plus its charge and
+/// isotope when set; hydrogen counts are left free, so `CO` matches the
+/// `C-O` of ethanol. Bonds match their exact type, directional bonds
+/// counting as single. Aromaticity is perceived first, as on the molecules
+/// searched, so `C1=CC=CC=C1` gives the same query as `c1ccccc1`. Tetrahedral centres keep their configuration, which
+/// only takes part in matching with
+/// [`SubstructureOptions::chirality`](super::SubstructureOptions::chirality).
+impl From<&Molecule> for QueryMolecule {
+    fn from(molecule: &Molecule) -> Self {
+        QueryMolecule::from_molecule(&molecule.perceived_aromaticity(), false)
+    }
+}
+
+impl QueryMolecule {
+    /// Builds the query of a molecule (see the [`From`] implementation).
+    /// When `exact` is set, every atom also requires its charge and total
+    /// hydrogen count, as isomorphism does.
+    pub(crate) fn from_molecule(molecule: &Molecule, exact: bool) -> QueryMolecule {
+        let bonds = molecule
+            .bonds()
+            .iter()
+            .filter_map(|bond| {
+                let primitive = match bond.kind() {
+                    BondType::Simple | BondType::Up | BondType::Down => BondPrimitive::Single,
+                    BondType::Double => BondPrimitive::Double,
+                    BondType::Triple => BondPrimitive::Triple,
+                    BondType::Quadruple => BondPrimitive::Quadruple,
+                    BondType::Aromatic => BondPrimitive::Aromatic,
+                    BondType::Disconnected => return None,
+                };
+                Some(QueryBond::new(
+                    bond.source(),
+                    bond.target(),
+                    Some(QueryExpr::Primitive(primitive)),
+                ))
+            })
+            .collect();
+        let mut query = QueryMolecule::new(Vec::new(), bonds);
+        query.atoms = molecule
+            .nodes()
+            .iter()
+            .map(|node| QueryAtom::new(QueryExpr::And(Vec::new()), node.class()))
+            .collect();
+
+        // The parity is stored against the molecule's reference order and
+        // read by the query against its written order.
+        let written = query.written_neighbours(|atom| {
+            let node = &molecule.nodes()[atom];
+            let degree = stereo::reference_order(atom as NodeIndex, molecule.bonds(), false).len();
+            node.hydrogens() > 0 || degree == 3
+        });
+
+        let mut explicit_h = vec![0usize; molecule.nodes().len()];
+        for bond in query.bonds() {
+            for (a, b) in [(bond.source, bond.target), (bond.target, bond.source)] {
+                if *molecule.nodes()[b as usize].atom().element() == AtomSymbol::H {
+                    explicit_h[a as usize] += 1;
+                }
+            }
+        }
+
+        for (i, node) in molecule.nodes().iter().enumerate() {
+            let atom = node.atom();
+            let mut primitives = Vec::new();
+            if let Some(isotope) = atom.isotope() {
+                primitives.push(AtomPrimitive::Isotope(isotope));
+            }
+            primitives.push(match *atom.element() {
+                AtomSymbol::Wildcard => AtomPrimitive::Any,
+                element => AtomPrimitive::Element {
+                    element,
+                    aromatic: node.aromatic(),
+                },
+            });
+            if exact || atom.charge() != 0 {
+                primitives.push(AtomPrimitive::Charge(atom.charge()));
+            }
+            if exact {
+                let hydrogens = node.hydrogens() as usize + explicit_h[i];
+                primitives.push(AtomPrimitive::TotalHydrogens(hydrogens as u8));
+            }
+            if let Some(chirality) = node.chirality() {
+                let center = i as NodeIndex;
+                let order: Vec<NodeIndex> = written[i].iter().map(|&v| v as NodeIndex).collect();
+                let implicit = order.contains(&center);
+                let reference = stereo::reference_order(center, molecule.bonds(), implicit);
+                let chirality = match stereo::is_odd_permutation(&reference, &order) {
+                    Some(true) => stereo::invert(chirality),
+                    _ => chirality,
+                };
+                primitives.push(AtomPrimitive::Chirality(chirality));
+            }
+
+            query.atoms[i].expr = if primitives.len() == 1 {
+                QueryExpr::Primitive(primitives.remove(0))
+            } else {
+                QueryExpr::And(primitives.into_iter().map(QueryExpr::Primitive).collect())
+            };
+        }
+        query
+    }
+}
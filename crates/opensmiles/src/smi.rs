@@ -0,0 +1,336 @@
+//! Streaming reader for SMILES files.
+//!
+//! A SMILES file holds one molecule per line: the SMILES, then optional
+//! columns such as a name or an identifier (`CCO ethanol`). [`SmilesReader`]
+//! reads such files one line at a time, so that files of millions of lines
+//! are processed in constant memory, and yields a [`SmilesRecord`] for every
+//! molecule, whether it parses or not.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Cursor;
+//! use opensmiles::smi::SmilesReader;
+//!
+//! let file = "# solvents\nCCO ethanol\nC1CC ring\nCC(=O)C acetone\n";
+//! let records: Vec<_> = SmilesReader::new(Cursor::new(file))
+//!     .map(|record| record.unwrap())
+//!     .collect();
+//!
+//! assert_eq!(records.len(), 3);
+//! assert_eq!(records[0].title.as_deref(), Some("ethanol"));
+//! assert!(records[1].molecule.is_err());
+//! assert_eq!(records[2].line, 4);
+//! ```
+
+use std::io::{self, BufRead};
+
+use crate::{parse_with, Molecule, ParseOptions, ParserError};
+
+/// How the columns of a line are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delimiter {
+    /// The Daylight convention: the SMILES ends at the first space or tab,
+    /// and the rest of the line, trimmed, is the title.
+    #[default]
+    Whitespace,
+    /// Every tab starts a new column.
+    Tab,
+    /// Every space starts a new column.
+    Space,
+    /// Every comma starts a new column; a column between double quotes may
+    /// hold commas, and `""` stands for a quote inside it.
+    Comma,
+}
+
+/// Options for [`SmilesReader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmilesReaderOptions {
+    /// Column separator.
+    pub delimiter: Delimiter,
+    /// The first line that is not a comment names the columns (see
+    /// [`SmilesReader::header`]) instead of holding a molecule.
+    pub header: bool,
+    /// Lines starting with this prefix are skipped; `None` reads every line.
+    pub comment: Option<String>,
+    /// Index of the column holding the SMILES. A line without that column
+    /// reads as the empty SMILES.
+    pub smiles_column: usize,
+    /// How the SMILES are parsed.
+    pub parse: ParseOptions,
+}
+
+impl Default for SmilesReaderOptions {
+    fn default() -> Self {
+        SmilesReaderOptions {
+            delimiter: Delimiter::Whitespace,
+            header: false,
+            comment: Some("#".to_string()),
+            smiles_column: 0,
+            parse: ParseOptions::default(),
+        }
+    }
+}
+
+/// One line of a SMILES file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmilesRecord {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// The SMILES as written.
+    pub smiles: String,
+    /// The first of [`columns`](Self::columns), if any.
+    pub title: Option<String>,
+    /// The columns other than the SMILES, in file order. With
+    /// [`Delimiter::Whitespace`], this is the title alone.
+    pub columns: Vec<String>,
+    /// The parsed molecule.
+    pub molecule: Result<Molecule, ParserError>,
+}
+
+/// Reads the records of a SMILES file one at a time.
+///
+/// Blank lines and comments are skipped. A line whose SMILES does not parse
+/// still yields a record, with the error in
+/// [`molecule`](SmilesRecord::molecule), and reading goes on with the next
+/// line. An I/O error is yielded as such and ends the iteration. Invalid
+/// UTF-8 is replaced rather than rejected.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use opensmiles::smi::{Delimiter, SmilesReader, SmilesReaderOptions};
+///
+/// let csv = "id,smiles,name\n1,CCO,ethanol\n2,c1ccccc1,\"benzene, pure\"\n";
+/// let options = SmilesReaderOptions {
+///     delimiter: Delimiter::Comma,
+///     header: true,
+///     smiles_column: 1,
+///     ..Default::default()
+/// };
+/// let mut reader = SmilesReader::with_options(Cursor::new(csv), options);
+/// let benzene = reader.nth(1).unwrap().unwrap();
+///
+/// assert_eq!(benzene.smiles, "c1ccccc1");
+/// assert_eq!(benzene.columns, ["2", "benzene, pure"]);
+/// assert_eq!(reader.header().unwrap(), ["id", "smiles", "name"]);
+/// ```
+pub struct SmilesReader<R> {
+    reader: R,
+    options: SmilesReaderOptions,
+    /// Line number of the last line read.
+    line: usize,
+    /// Bytes read so far.
+    bytes: u64,
+    buffer: Vec<u8>,
+    header: Option<Vec<String>>,
+    done: bool,
+}
+
+impl<R: BufRead> SmilesReader<R> {
+    /// Creates a reader with the default options: whitespace-separated
+    /// columns, no header and `#` comments.
+    pub fn new(reader: R) -> SmilesReader<R> {
+        SmilesReader::with_options(reader, SmilesReaderOptions::default())
+    }
+
+    /// Creates a reader with the given options.
+    pub fn with_options(reader: R, options: SmilesReaderOptions) -> SmilesReader<R> {
+        SmilesReader {
+            reader,
+            options,
+            line: 0,
+            bytes: 0,
+            buffer: Vec::new(),
+            header: None,
+            done: false,
+        }
+    }
+
+    /// Returns the column names, once the header line has been read.
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    /// Returns the number of lines read so far, comments included.
+    pub fn lines_read(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Reads the next molecule line without parsing it.
+    pub(crate) fn next_line(&mut self) -> Option<io::Result<Line>> {
+        while !self.done {
+            match self.next_columns() {
+                Ok(Some(columns)) if self.options.header && self.header.is_none() => {
+                    self.header = Some(columns);
+                }
+                Ok(Some(mut columns)) => {
+                    let smiles = if self.options.smiles_column < columns.len() {
+                        columns.remove(self.options.smiles_column)
+                    } else {
+                        String::new()
+                    };
+                    return Some(Ok(Line {
+                        line: self.line,
+                        smiles,
+                        columns,
+                    }));
+                }
+                Ok(None) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+
+    /// Reads the next line that is neither blank nor a comment, and returns
+    /// its columns, or `None` at the end of the input.
+    fn next_columns(&mut self) -> io::Result<Option<Vec<String>>> {
+        loop {
+            self.buffer.clear();
+            let read = self.reader.read_until(b'\n', &mut self.buffer)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            self.bytes += read as u64;
+            let text = String::from_utf8_lossy(&self.buffer);
+            let text = text.trim_end_matches(['\n', '\r']);
+            let skipped = text.trim().is_empty()
+                || self
+                    .options
+                    .comment
+                    .as_deref()
+                    .is_some_and(|prefix| text.starts_with(prefix));
+            if !skipped {
+                return Ok(Some(split(text, self.options.delimiter)));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SmilesReader<R> {
+    type Item = io::Result<SmilesRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.next_line()?;
+        Some(line.map(|line| line.parse(&self.options.parse)))
+    }
+}
+
+/// A molecule line, not parsed yet.
+pub(crate) struct Line {
+    line: usize,
+    smiles: String,
+    columns: Vec<String>,
+}
+
+impl Line {
+    /// Parses the SMILES of the line.
+    pub(crate) fn parse(self, options: &ParseOptions) -> SmilesRecord {
+        SmilesRecord {
+            line: self.line,
+            molecule: parse_with(&self.smiles, options),
+            title: self.columns.first().cloned(),
+            smiles: self.smiles,
+            columns: self.columns,
+        }
+    }
+}
+
+/// Splits a line into trimmed columns.
+fn split(text: &str, delimiter: Delimiter) -> Vec<String> {
+    let separator = match delimiter {
+        Delimiter::Whitespace => {
+            let text = text.trim();
+            return match text.split_once([' ', '\t']) {
+                Some((smiles, title)) => vec![smiles.to_string(), title.trim().to_string()],
+                None => vec![text.to_string()],
+            };
+        }
+        Delimiter::Comma => return split_quoted(text),
+        Delimiter::Tab => '\t',
+        Delimiter::Space => ' ',
+    };
+    text.split(separator)
+        .map(|column| column.trim().to_string())
+        .collect()
+}
+
+/// Splits a comma-separated line, honouring double quotes.
+fn split_quoted(text: &str) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut column = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                column.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => columns.push(std::mem::take(&mut column)),
+            _ => column.push(c),
+        }
+    }
+    columns.push(column);
+    columns
+        .into_iter()
+        .map(|column| column.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_keeps_the_whole_title() {
+        assert_eq!(split("CCO", Delimiter::Whitespace), ["CCO"]);
+        assert_eq!(
+            split("  CCO\t ethyl  alcohol ", Delimiter::Whitespace),
+            ["CCO", "ethyl  alcohol"]
+        );
+    }
+
+    #[test]
+    fn separated_columns() {
+        assert_eq!(
+            split("CCO\tethanol\t\t42", Delimiter::Tab),
+            ["CCO", "ethanol", "", "42"]
+        );
+        assert_eq!(
+            split("CCO ethyl alcohol", Delimiter::Space),
+            ["CCO", "ethyl", "alcohol"]
+        );
+        assert_eq!(
+            split("CCO, \"a \"\"b\"\", c\",", Delimiter::Comma),
+            ["CCO", "a \"b\", c", ""]
+        );
+    }
+
+    #[test]
+    fn missing_smiles_column() {
+        let options = SmilesReaderOptions {
+            delimiter: Delimiter::Tab,
+            smiles_column: 2,
+            ..Default::default()
+        };
+        let records: Vec<_> =
+            SmilesReader::with_options(io::Cursor::new("1\tx\n"), options).collect();
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(record.smiles, "");
+        assert_eq!(record.columns, ["1", "x"]);
+        assert!(record.molecule.as_ref().unwrap().nodes().is_empty());
+    }
+}
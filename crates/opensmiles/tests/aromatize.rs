@@ -0,0 +1,161 @@
+//! Tests for aromaticity perception with `Molecule::aromatize()`.
+
+use opensmiles::{parse, AromaticityModel, BondType, Molecule};
+
+fn aromatize(smiles: &str, model: AromaticityModel) -> Molecule {
+    parse(smiles)
+        .unwrap()
+        .aromatize(model)
+        .unwrap_or_else(|e| panic!("{smiles}: {e}"))
+}
+
+fn aromatic_atoms(smiles: &str, model: AromaticityModel) -> usize {
+    aromatize(smiles, model)
+        .nodes()
+        .iter()
+        .filter(|n| n.aromatic())
+        .count()
+}
+
+#[test]
+fn daylight_benzene_from_kekule() {
+    let mol = aromatize("C1=CC=CC=C1", AromaticityModel::Daylight);
+    assert!(mol.nodes().iter().all(|n| n.aromatic()));
+    assert!(mol.bonds().iter().all(|b| b.kind() == BondType::Aromatic));
+    assert_eq!(mol.to_string(), "c1ccccc1");
+}
+
+#[test]
+fn daylight_heterocycles() {
+    use AromaticityModel::Daylight;
+    assert_eq!(aromatic_atoms("C1=CC=NC=C1", Daylight), 6);
+    assert_eq!(aromatic_atoms("C1=CNC=C1", Daylight), 5);
+    assert_eq!(aromatic_atoms("C1=COC=C1", Daylight), 5);
+    assert_eq!(aromatic_atoms("C1=CSC=C1", Daylight), 5);
+    assert_eq!(aromatic_atoms("CN1C=CC=C1", Daylight), 5);
+    assert_eq!(aromatic_atoms("[CH-]1C=CC=C1", Daylight), 5);
+    // 2-pyridone: the exocyclic C=O gives no electron.
+    assert_eq!(aromatic_atoms("O=C1C=CC=CN1", Daylight), 6);
+}
+
+#[test]
+fn daylight_non_aromatic_rings() {
+    use AromaticityModel::Daylight;
+    assert_eq!(aromatic_atoms("C1=CC=CC1", Daylight), 0); // cyclopentadiene
+    assert_eq!(aromatic_atoms("C1=CC=CC=CC=C1", Daylight), 0); // cyclooctatetraene
+    assert_eq!(aromatic_atoms("C1CCCCC1", Daylight), 0);
+    assert_eq!(aromatic_atoms("C=C1C=CC=C1", Daylight), 0); // fulvene
+}
+
+#[test]
+fn daylight_fused_systems() {
+    use AromaticityModel::Daylight;
+    assert_eq!(aromatic_atoms("C1=CC=C2C=CC=CC2=C1", Daylight), 10);
+    assert_eq!(aromatic_atoms("C1=CC=C2C(=C1)C=CN2", Daylight), 9);
+    // Azulene: the rings hold 7 and 5 electrons, the perimeter 10.
+    let azulene = aromatize("C1=CC=C2C=CC=C2C=C1", Daylight);
+    assert!(azulene.nodes().iter().all(|n| n.aromatic()));
+    assert!(azulene
+        .bonds()
+        .iter()
+        .all(|b| b.kind() == BondType::Aromatic));
+}
+
+#[test]
+fn daylight_keeps_bonds_outside_aromatic_rings() {
+    let mol = aromatize("C1=CC=C(C=C1)C1=CC=CC=C1", AromaticityModel::Daylight);
+    assert_eq!(mol.nodes().iter().filter(|n| n.aromatic()).count(), 12);
+    assert_eq!(
+        mol.bonds()
+            .iter()
+            .filter(|b| b.kind() == BondType::Simple)
+            .count(),
+        1
+    );
+    // Biphenylene: the four-membered ring is not aromatic.
+    let mol = aromatize("C1=CC=C2C(=C1)C1=CC=CC=C21", AromaticityModel::Daylight);
+    assert_eq!(
+        mol.bonds()
+            .iter()
+            .filter(|b| b.kind() == BondType::Simple)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn simple_carbocycle_model() {
+    use AromaticityModel::SimpleCarbocycle;
+    assert_eq!(aromatic_atoms("C1=CC=CC=C1", SimpleCarbocycle), 6);
+    assert_eq!(aromatic_atoms("[CH-]1C=CC=C1", SimpleCarbocycle), 5);
+    assert_eq!(aromatic_atoms("C1=CC=NC=C1", SimpleCarbocycle), 0);
+    assert_eq!(aromatic_atoms("C1=CNC=C1", SimpleCarbocycle), 0);
+    // Only the benzene ring of indole qualifies.
+    assert_eq!(aromatic_atoms("C1=CC=C2C(=C1)C=CN2", SimpleCarbocycle), 6);
+    assert_eq!(aromatic_atoms("C1=CC=C2C=CC=C2C=C1", SimpleCarbocycle), 0);
+}
+
+#[test]
+fn mdl_model() {
+    use AromaticityModel::Mdl;
+    assert_eq!(aromatic_atoms("C1=CC=NC=C1", Mdl), 6);
+    assert_eq!(aromatic_atoms("C1=CC=C2C=CC=CC2=C1", Mdl), 10);
+    assert_eq!(aromatic_atoms("C1=CNC=C1", Mdl), 0);
+    assert_eq!(aromatic_atoms("C1=COC=C1", Mdl), 0);
+    assert_eq!(aromatic_atoms("O=C1C=CC=CN1", Mdl), 0);
+    // Indole: the benzene ring only.
+    assert_eq!(aromatic_atoms("C1=CC=C2C(=C1)C=CN2", Mdl), 6);
+}
+
+#[test]
+fn aromatize_lowercase_input() {
+    // Aromatic input is kekulized first, so both forms agree.
+    for model in [
+        AromaticityModel::Daylight,
+        AromaticityModel::SimpleCarbocycle,
+        AromaticityModel::Mdl,
+    ] {
+        for (aromatic, kekule) in [
+            ("c1ccccc1", "C1=CC=CC=C1"),
+            ("c1ccc2c(c1)[nH]cc2", "C1=CC=C2C(=C1)C=CN2"),
+            ("Cn1cnc2c1c(=O)n(c(=O)n2C)C", "CN1C=NC2=C1C(=O)N(C(=O)N2C)C"),
+        ] {
+            assert_eq!(
+                aromatize(aromatic, model).to_canonical_smiles(),
+                aromatize(kekule, model).to_canonical_smiles(),
+                "{model:?} {aromatic}"
+            );
+        }
+    }
+    // Pyrrole is not aromatic in the MDL model.
+    let mol = aromatize("c1cc[nH]c1", AromaticityModel::Mdl);
+    assert!(mol.nodes().iter().all(|n| !n.aromatic()));
+}
+
+#[test]
+fn aromatize_kekule_round_trip() {
+    let mol = aromatize("c1ccc2ccccc2c1", AromaticityModel::Daylight);
+    let kekule = mol.kekulize().unwrap();
+    let again = kekule.aromatize(AromaticityModel::Daylight).unwrap();
+    assert_eq!(mol, again);
+}
+
+#[test]
+fn canonical_smiles_agree_for_kekule_and_aromatic_forms() {
+    let canonical = |s: &str| parse(s).unwrap().to_canonical_smiles();
+    assert_eq!(
+        canonical("CN1C=NC2=C1C(=O)N(C(=O)N2C)C"),
+        canonical("Cn1cnc2c1c(=O)n(c(=O)n2C)C")
+    );
+}
+
+// Per-ring Hückel validation rejects lowercase azulene at parse time.
+#[test]
+#[cfg(not(feature = "huckel-validation"))]
+fn canonical_azulene_kekule_and_aromatic() {
+    let canonical = |s: &str| parse(s).unwrap().to_canonical_smiles();
+    assert_eq!(
+        canonical("C1=CC=C2C=CC=C2C=C1"),
+        canonical("c1ccc2cccc2cc1")
+    );
+}
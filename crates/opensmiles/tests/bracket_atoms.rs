@@ -7,7 +7,7 @@
 //! - Classes/atom mapping (`[C:1]`, `[N:2]`, etc.)
 //! - All possible combinations of these attributes
 
-use opensmiles::{parse, AtomSymbol, MoleculeError, NodeError, OrganicAtom, ParserError};
+use opensmiles::{parse, AtomSymbol, MoleculeError, NodeError, OrganicAtom, ParserError, Span};
 
 // ============================================================================
 // Explicit hydrogens
@@ -257,7 +257,9 @@ fn parse_bracket_atom_hydrogen_and_class() {
 fn parse_bracket_atom_out_of_range_hydrogen() {
     let result = parse("[CH256]");
     dbg!(&result);
-    assert!(matches!(&result, Err(ParserError::HydrogenOutOfRange(s)) if s == "256"));
+    assert!(
+        matches!(&result, Err(ParserError::HydrogenOutOfRange(s, span)) if s == "256" && *span == Span::new(2, 6))
+    );
 }
 
 #[test]
@@ -266,9 +268,10 @@ fn parse_bracket_atom_too_much_hydrogen() {
     dbg!(&result);
     assert!(matches!(
         &result,
-        Err(ParserError::MoleculeError(MoleculeError::NodeError(
-            NodeError::InvalidHydrogen(10)
-        )))
+        Err(ParserError::MoleculeError(
+            MoleculeError::NodeError(NodeError::InvalidHydrogen(10)),
+            span
+        )) if *span == Span::new(0, 6)
     ));
 }
 
@@ -407,9 +410,9 @@ fn parse_bracket_lanthanide() {
 fn parse_bracket_unexpected_char() {
     // [Fe] = iron
     match parse("[C+X]") {
-        Err(ParserError::UnexpectedCharacter(c, pos)) => {
+        Err(ParserError::UnexpectedCharacter(c, span)) => {
             assert_eq!(c, 'X');
-            assert_eq!(pos, 4);
+            assert_eq!(span, Span::new(3, 4));
         }
         other => panic!("Expected UnexpectedCharacter, got {:?}", other),
     }
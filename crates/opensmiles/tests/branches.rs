@@ -6,7 +6,7 @@
 //! - Nested branches `CC(C(C)C)C`
 //! - Branches with different bond types
 
-use opensmiles::{parse, BondType, ParserError};
+use opensmiles::{parse, BondType, ParserError, Span};
 
 #[test]
 fn parse_simple_branch() {
@@ -116,14 +116,15 @@ fn parse_empty_branch() {
 #[test]
 fn error_position_in_branch() {
     // C([C+X])C - X is an invalid character in the bracket atom
-    // Position: C=1, (=2, [=3, C=4, +=5, X=6
-    // The error should indicate position 6 (absolute position, not relative to branch)
+    // Byte offsets: C=0, (=1, [=2, C=3, +=4, X=5
+    // The error should span byte 5 (absolute offset, not relative to branch)
     match parse("C([C+X])C") {
-        Err(ParserError::UnexpectedCharacter(c, pos)) => {
+        Err(ParserError::UnexpectedCharacter(c, span)) => {
             assert_eq!(c, 'X', "Expected unexpected character 'X'");
             assert_eq!(
-                pos, 6,
-                "Position should be absolute (6), not relative to branch"
+                span,
+                Span::new(5, 6),
+                "Span should be absolute (5..6), not relative to branch"
             );
         }
         Ok(_) => panic!("Expected error, but parsing succeeded"),
@@ -247,3 +248,152 @@ fn parse_branch_with_internal_bonds() {
     assert_eq!(bonds[3].source(), 0);
     assert_eq!(bonds[3].target(), 4);
 }
+
+#[test]
+fn parse_branch_bond_does_not_apply_to_ring_closure() {
+    // `(=C1)`: the double bond joins the branch to its parent, the ring bond is single
+    let molecule = parse("C1=CC=CC(=C1)").expect("Failed to parse");
+
+    let ring_bond = molecule
+        .bonds()
+        .iter()
+        .find(|b| b.source() == 0 && b.target() == 5)
+        .expect("ring closure bond");
+    assert_eq!(ring_bond.kind(), BondType::Simple);
+    let branch_bond = molecule
+        .bonds()
+        .iter()
+        .find(|b| b.source() == 4 && b.target() == 5)
+        .expect("branch bond");
+    assert_eq!(branch_bond.kind(), BondType::Double);
+}
+
+#[test]
+fn parse_branch_bond_stays_on_the_branch() {
+    let kinds = |smiles: &str| {
+        let molecule = parse(smiles).expect("Failed to parse");
+        let kind = |a: u32, b: u32| {
+            molecule
+                .bonds()
+                .iter()
+                .find(|bond| (bond.source(), bond.target()) == (a, b))
+                .map(|bond| bond.kind())
+                .expect("bond")
+        };
+        (kind(4, 5), kind(0, 5))
+    };
+    // (branch bond, ring bond) when the first atom of the branch closes a ring
+    assert_eq!(kinds("C1CCCC(=C1)"), (BondType::Double, BondType::Simple));
+    assert_eq!(kinds("C1CCCC(#C1)"), (BondType::Triple, BondType::Simple));
+    assert_eq!(kinds("C1CCCC(=C=1)"), (BondType::Double, BondType::Double));
+    assert_eq!(kinds("C=1CCCC(=C1)"), (BondType::Double, BondType::Double));
+    assert_eq!(
+        parse("C1CCCC(=C1)C").unwrap().to_canonical_smiles(),
+        parse("CC1=CCCCC1").unwrap().to_canonical_smiles()
+    );
+}
+
+#[test]
+fn parse_aromatic_branch_bond_is_aromatic() {
+    let molecule = parse("c1ccc(cc1)").expect("Failed to parse");
+
+    // Branch atom 4 is attached to atom 3 with an implicit bond between two aromatic atoms
+    let branch_bond = molecule
+        .bonds()
+        .iter()
+        .find(|b| b.source() == 3 && b.target() == 4)
+        .expect("branch bond");
+    assert_eq!(branch_bond.kind(), BondType::Aromatic);
+}
+
+#[test]
+fn parse_implicit_branch_bonds_follow_aromaticity() {
+    let kind = |smiles: &str, a: u32, b: u32| {
+        let molecule = parse(smiles).expect("Failed to parse");
+        molecule
+            .bonds()
+            .iter()
+            .find(|bond| (bond.source(), bond.target()) == (a, b))
+            .map(|bond| bond.kind())
+            .expect("bond")
+    };
+    // Between two aromatic atoms, inside a ring or between rings, as along
+    // a chain
+    assert_eq!(kind("c1ccc2c(c1)cccc2", 4, 5), BondType::Aromatic);
+    assert_eq!(kind("c1ccc(c2ccccc2)cc1", 3, 4), BondType::Aromatic);
+    // A written bond, or an aliphatic atom on either side, is single
+    assert_eq!(kind("c1ccc(-c2ccccc2)cc1", 3, 4), BondType::Simple);
+    assert_eq!(kind("c1ccc(C)cc1", 3, 4), BondType::Simple);
+    assert_eq!(kind("CC(c1ccccc1)C", 1, 2), BondType::Simple);
+
+    let naphthalene = parse("c1ccc2c(c1)cccc2").unwrap();
+    assert!(naphthalene
+        .bonds()
+        .iter()
+        .all(|bond| bond.kind() == BondType::Aromatic));
+    assert_eq!(
+        naphthalene.to_canonical_smiles(),
+        parse("c1ccc2ccccc2c1").unwrap().to_canonical_smiles()
+    );
+}
+
+#[test]
+fn parse_ring_closed_in_nested_branch() {
+    // The ring opened on atom 0 closes two branches deeper, on atom 6
+    let molecule = parse("C1CC(CC(CC1))").expect("Failed to parse");
+
+    assert_eq!(molecule.bonds().len(), 7);
+    assert!(molecule
+        .bonds()
+        .iter()
+        .any(|b| (b.source(), b.target()) == (0, 6)));
+    assert!(molecule.nodes().iter().all(|n| n.hydrogens() == 2));
+}
+
+#[test]
+fn parse_deeply_nested_branches() {
+    let depth = 100_000;
+    let input = format!("{}C{}", "C(".repeat(depth), ")".repeat(depth));
+    let molecule = parse(&input).expect("Failed to parse deep nesting");
+
+    assert_eq!(molecule.nodes().len(), depth + 1);
+    assert_eq!(molecule.bonds().len(), depth);
+}
+
+#[test]
+fn error_dangling_bond_in_branch() {
+    assert_eq!(
+        parse("C(C=)C"),
+        Err(ParserError::BondWithoutFollowingAtom(Span::new(3, 4)))
+    );
+    assert_eq!(
+        parse("C(=)C"),
+        Err(ParserError::BondWithoutFollowingAtom(Span::new(2, 3)))
+    );
+}
+
+#[test]
+fn error_bond_after_dot() {
+    assert_eq!(
+        parse("C.=C"),
+        Err(ParserError::BondWithoutFollowingAtom(Span::new(2, 3)))
+    );
+    assert_eq!(
+        parse("C.#C"),
+        Err(ParserError::BondWithoutFollowingAtom(Span::new(2, 3)))
+    );
+}
+
+#[test]
+fn error_ring_duplicating_branch_bond() {
+    // The branch already bonds atoms 0 and 1
+    assert_eq!(
+        parse("C1(C1)"),
+        Err(ParserError::DuplicateBond(
+            0,
+            1,
+            Span::new(1, 2),
+            Span::new(4, 5)
+        ))
+    );
+}
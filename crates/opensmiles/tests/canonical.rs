@@ -0,0 +1,276 @@
+use opensmiles::parse;
+
+fn canonical(smiles: &str) -> String {
+    parse(smiles).unwrap().to_canonical_smiles()
+}
+
+fn assert_same_canonical(forms: &[&str]) {
+    let expected = canonical(forms[0]);
+    for form in &forms[1..] {
+        assert_eq!(canonical(form), expected, "{} vs {}", forms[0], form);
+    }
+}
+
+#[test]
+fn canonical_ethanol_atom_orders() {
+    assert_same_canonical(&["OCC", "CCO", "C(O)C", "C(C)O", "[CH3][CH2][OH]"]);
+    assert_eq!(canonical("CCO"), "OCC");
+}
+
+#[test]
+fn canonical_explicit_hydrogens() {
+    assert_same_canonical(&["OCC", "[H]OCC", "[H]C([H])([H])C([H])([H])O[H]"]);
+}
+
+#[test]
+fn canonical_ring_numbering() {
+    assert_same_canonical(&["C1CCCCC1", "C%10CCCCC%10", "C1CCC2.C2C1", "C(CCC1)CC1"]);
+}
+
+#[test]
+fn canonical_benzene_kekule_and_aromatic() {
+    assert_same_canonical(&["c1ccccc1", "C1=CC=CC=C1", "C=1C=CC=CC=1"]);
+}
+
+#[test]
+fn canonical_naphthalene_kekule_and_aromatic() {
+    assert_same_canonical(&[
+        "c1ccc2ccccc2c1",
+        "C1=CC2=CC=CC=C2C=C1",
+        "C1=CC=C2C(=C1)C=CC=C2",
+        "c1cccc2c1cccc2",
+    ]);
+}
+
+#[test]
+fn canonical_pyrrole_forms() {
+    assert_same_canonical(&["c1cc[nH]c1", "[nH]1cccc1", "C1=CNC=C1"]);
+}
+
+#[test]
+fn canonical_indole_forms() {
+    assert_same_canonical(&[
+        "c1ccc2c(c1)[nH]cc2",
+        "C1=CC=C2C(=C1)C=CN2",
+        "c12ccccc1cc[nH]2",
+    ]);
+}
+
+#[test]
+fn canonical_aspirin_forms() {
+    assert_same_canonical(&[
+        "CC(=O)Oc1ccccc1C(=O)O",
+        "OC(=O)c1ccccc1OC(C)=O",
+        "c1cccc(C(O)=O)c1OC(=O)C",
+    ]);
+}
+
+#[test]
+fn canonical_disconnected_components() {
+    assert_same_canonical(&["[Na+].[Cl-]", "[Cl-].[Na+]"]);
+    assert_same_canonical(&["CCO.O", "O.OCC", "O.C(O)C"]);
+    assert_eq!(canonical("[Cl-].[Na+]").matches('.').count(), 1);
+}
+
+#[test]
+fn canonical_distinguishes_isomers() {
+    assert_ne!(canonical("CCO"), canonical("COC"));
+    assert_ne!(canonical("Cc1ccccc1C"), canonical("Cc1cccc(C)c1"));
+    assert_ne!(canonical("[13CH4]"), canonical("C"));
+    assert_ne!(canonical("[NH4+]"), canonical("N"));
+    assert_ne!(canonical("[CH3:1]O"), canonical("CO"));
+}
+
+#[test]
+fn canonical_regular_graphs() {
+    // Highly symmetric graphs where refinement alone cannot separate atoms.
+    assert_same_canonical(&["C12C3C4C1C5C2C3C45", "C1(C2C3C14)C5C2C3C45"]);
+    assert_same_canonical(&["C1CC2CCC1CC2", "C1CC2CCC1CC2"]);
+}
+
+#[test]
+fn canonical_output_is_a_fixed_point() {
+    for smiles in [
+        "CC(=O)Oc1ccccc1C(=O)O",
+        "CN1C=NC2=C1C(=O)N(C(=O)N2C)C",
+        "c1ccc2c(c1)[nH]cc2",
+        "C1CC2(CC1)CCCC2",
+        "[Na+].[Cl-]",
+        "OC(=O)CC(O)(CC(=O)O)C(=O)O",
+    ] {
+        let once = canonical(smiles);
+        assert_eq!(canonical(&once), once, "{smiles}");
+    }
+}
+
+#[test]
+fn canonical_many_rings_uses_two_digit_numbers() {
+    let mol = parse("C1CC2CC3CC4CC5CC6CC7CC8CC9CC%10CC%11CCC%11C%10C9C8C7C6C5C4C3C2C1").unwrap();
+    let once = mol.to_canonical_smiles();
+    assert!(once.contains('%'));
+    assert_eq!(canonical(&once), once);
+}
+
+#[test]
+fn canonical_tetrahedral_start_atom_independent() {
+    // The same stereoisomer written from every atom and neighbour order.
+    assert_same_canonical(&[
+        "[C@H](F)(Cl)Br",
+        "F[C@@H](Cl)Br",
+        "Cl[C@H](F)Br",
+        "Br[C@@H](F)Cl",
+        "[C@@H](Cl)(F)Br",
+        "F[C@@]([H])(Cl)Br",
+    ]);
+    assert_same_canonical(&[
+        "N[C@@H](C)C(=O)O",
+        "C[C@H](N)C(=O)O",
+        "OC(=O)[C@@H](N)C",
+        "[C@H](N)(C)C(=O)O",
+    ]);
+}
+
+#[test]
+fn canonical_distinguishes_enantiomers() {
+    assert_ne!(canonical("[C@H](F)(Cl)Br"), canonical("[C@@H](F)(Cl)Br"));
+    assert_ne!(canonical("N[C@@H](C)C(=O)O"), canonical("N[C@H](C)C(=O)O"));
+    assert_ne!(canonical("N[C@@H](C)C(=O)O"), canonical("NC(C)C(=O)O"));
+}
+
+#[test]
+fn canonical_ring_closure_stereocentres() {
+    assert_same_canonical(&["C[C@H]1CCCCO1", "O1CCCC[C@@H]1C", "[C@H]1(C)CCCCO1"]);
+    // The cis isomer is meso: both mirror-image spellings are one molecule.
+    assert_same_canonical(&["C[C@H]1CCCC[C@H]1C", "C[C@@H]1CCCC[C@@H]1C"]);
+    // The trans isomer is chiral.
+    assert_ne!(
+        canonical("C[C@@H]1CCCC[C@H]1C"),
+        canonical("C[C@H]1CCCC[C@@H]1C")
+    );
+    assert_ne!(
+        canonical("C[C@H]1CCCC[C@H]1C"),
+        canonical("C[C@@H]1CCCC[C@H]1C")
+    );
+}
+
+#[test]
+fn canonical_ring_relative_stereo() {
+    // Centres only stereogenic through their ring keep their relative
+    // configuration.
+    let cis = [
+        "C[C@H]1CC[C@@H](C)CC1",
+        "C[C@@H]1CC[C@H](C)CC1",
+        "C1C[C@@H](C)CC[C@H]1C",
+    ];
+    let trans = [
+        "C[C@H]1CC[C@H](C)CC1",
+        "C[C@@H]1CC[C@@H](C)CC1",
+        "C1C[C@@H](C)CC[C@@H]1C",
+    ];
+    assert_same_canonical(&cis);
+    assert_same_canonical(&trans);
+    assert_ne!(canonical(cis[0]), canonical(trans[0]));
+    assert_ne!(canonical(cis[0]), canonical("CC1CCC(C)CC1"));
+
+    let cis_decalin = [
+        "C1CC[C@H]2CCCC[C@@H]2C1",
+        "C1CC[C@@H]2CCCC[C@H]2C1",
+        "[C@H]12CCCC[C@@H]1CCCC2",
+    ];
+    let trans_decalin = [
+        "C1CC[C@H]2CCCC[C@H]2C1",
+        "C1CC[C@@H]2CCCC[C@@H]2C1",
+        "[C@H]12CCCC[C@H]1CCCC2",
+    ];
+    assert_same_canonical(&cis_decalin);
+    assert_same_canonical(&trans_decalin);
+    assert_ne!(canonical(cis_decalin[0]), canonical(trans_decalin[0]));
+
+    // Whichever centre is ranked first, 1,3,5-trimethylcyclohexane reads
+    // the same.
+    let all_cis = [
+        "C[C@@H]1C[C@H](C)C[C@H](C)C1",
+        "C[C@H]1C[C@@H](C)C[C@@H](C)C1",
+    ];
+    let one_trans = [
+        "C[C@H]1C[C@H](C)C[C@H](C)C1",
+        "C[C@@H]1C[C@@H](C)C[C@H](C)C1",
+        "C[C@H]1C[C@H](C)C[C@@H](C)C1",
+        "C[C@@H]1C[C@@H](C)C[C@@H](C)C1",
+        "C[C@H]1C[C@@H](C)C[C@H](C)C1",
+        "C[C@@H]1C[C@H](C)C[C@@H](C)C1",
+    ];
+    assert_same_canonical(&all_cis);
+    assert_same_canonical(&one_trans);
+    assert_ne!(canonical(all_cis[0]), canonical(one_trans[0]));
+
+    // The output describes the same isomer as its input.
+    for smiles in [cis, trans, cis_decalin, trans_decalin]
+        .iter()
+        .flatten()
+        .chain(&all_cis)
+        .chain(&one_trans)
+    {
+        let once = canonical(smiles);
+        assert_eq!(canonical(&once), once, "{smiles}");
+    }
+
+    // A single ring centre is no stereocentre.
+    assert_eq!(canonical("C[C@H]1CCCCC1"), canonical("CC1CCCCC1"));
+}
+
+#[test]
+fn canonical_double_bond_stereo() {
+    assert_same_canonical(&["F/C=C/F", "F\\C=C\\F", "C(\\F)=C/F"]);
+    assert_same_canonical(&["F/C=C\\F", "F\\C=C/F", "C(/F)=C/F"]);
+    assert_ne!(canonical("F/C=C/F"), canonical("F/C=C\\F"));
+    assert_ne!(canonical("F/C=C/F"), canonical("FC=CF"));
+    assert_same_canonical(&["C/C=C/C(=O)O", "OC(=O)/C=C/C"]);
+}
+
+#[test]
+fn canonical_stereo_output_round_trips() {
+    for smiles in [
+        "[C@H](F)(Cl)Br",
+        "C[C@H]1CCCCO1",
+        "C[C@@H]1CCCC[C@H]1C",
+        "C[C@H]1CC[C@@H](C)CC1",
+        "C1CC[C@H]2CCCC[C@H]2C1",
+        "F/C=C\\F",
+        "N[C@@H](CC1=CC=CC=C1)C(=O)O",
+        "C/C=C/C=C\\C",
+    ] {
+        let once = canonical(smiles);
+        assert_eq!(canonical(&once), once, "{smiles}");
+        let mol = parse(smiles).unwrap();
+        assert_eq!(canonical(&mol.to_string()), once, "{smiles}");
+    }
+}
+
+#[test]
+fn canonical_components_of_equal_degree() {
+    // Every atom has degree two and two hydrogens: the ranking must not
+    // depend on which ring is written first.
+    assert_same_canonical(&["C1CC1.C1CCCCC1", "C1CCCCC1.C1CC1", "C1CCCCC1.C(C1)C1"]);
+    assert_same_canonical(&["C1CCC1.C1CCCC1", "C1CCCC1.C1CCC1"]);
+    assert_same_canonical(&["O.C1CC1.O", "C1CC1.O.O", "O.O.C1CC1"]);
+    // Two cages whose atoms all have three ring neighbours and one hydrogen.
+    assert_same_canonical(&[
+        "C12C3C4C1C5C2C3C45.C12C3C1C23",
+        "C12C3C1C23.C12C3C4C1C5C2C3C45",
+    ]);
+}
+
+#[test]
+fn canonical_ranks_of_a_long_chain() {
+    let chain = parse(&"C".repeat(16_000)).unwrap();
+    let start = std::time::Instant::now();
+    let ranks = chain.canonical_ranks();
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    // One end first, then along the chain.
+    let ends = (ranks[0].min(ranks[15_999]), ranks[0].max(ranks[15_999]));
+    assert_eq!(ends, (0, 1));
+    let mut sorted = ranks.clone();
+    sorted.sort_unstable();
+    assert!(sorted.iter().enumerate().all(|(i, &r)| r == i as u32));
+}
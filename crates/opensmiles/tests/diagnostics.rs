@@ -0,0 +1,104 @@
+//! Tests for the spans of parse errors and their rendering.
+
+use opensmiles::{parse, ParserError, Span};
+
+/// Returns the text each span of the error points at.
+fn pointed(input: &str, error: &ParserError) -> String {
+    let span = error.span().expect("parse errors have a span");
+    input[span.start..span.end].to_string()
+}
+
+#[test]
+fn every_error_points_at_its_text() {
+    for (input, text) in [
+        ("C(CC", "("),
+        ("C()C", "()"),
+        ("=CC", "="),
+        ("CC#", "#"),
+        ("C1CC", "1"),
+        ("C%12CC", "%12"),
+        ("C=1CC", "=1"),
+        ("C[C+X]C", "X"),
+        ("CC[NH4", ""),
+        ("[HH1]", "[HH1]"),
+        ("[C+20]", "[C+20]"),
+        ("[CH10]", "[CH10]"),
+        ("[C@TX]", "@TX"),
+        ("[C@TH3]", "@TH3"),
+        ("[Cx]", "x"),
+        ("C%1x", "x"),
+        ("[+]", "[+"),
+        ("CQ", "Q"),
+        ("C(C)1", "1"),
+    ] {
+        let error = parse(input).unwrap_err();
+        assert_eq!(pointed(input, &error), text, "{input}: {error:?}");
+    }
+}
+
+#[test]
+fn spans_count_bytes() {
+    let input = "CCé";
+    let error = parse(input).unwrap_err();
+    assert_eq!(
+        error,
+        ParserError::UnexpectedCharacter('é', Span::new(2, 4))
+    );
+    // Branches are parsed with absolute offsets.
+    let error = parse("C(C(C(é)))").unwrap_err();
+    assert_eq!(error.span(), Some(Span::new(6, 8)));
+}
+
+#[test]
+fn ring_errors_carry_both_ends() {
+    assert_eq!(
+        parse("CC11").unwrap_err(),
+        ParserError::SelfBond(1, Span::new(2, 3), Span::new(3, 4))
+    );
+    match parse("C-1CC(C=1)").unwrap_err() {
+        ParserError::MismatchedRingBond(1, open, close) => {
+            assert_eq!((open, close), (Span::new(1, 3), Span::new(7, 9)));
+        }
+        other => panic!("expected a mismatched ring bond, got {other:?}"),
+    }
+    match parse("C2CC3C.C3").unwrap_err() {
+        ParserError::UnclosedRing(rings, spans) => {
+            assert_eq!(rings, [2]);
+            assert_eq!(spans, [Span::new(1, 2)]);
+        }
+        other => panic!("expected an unclosed ring, got {other:?}"),
+    }
+    match parse("C3CC1CC2").unwrap_err() {
+        ParserError::UnclosedRing(rings, spans) => {
+            assert_eq!(rings, [3, 1, 2], "rings come in the order they were opened");
+            assert_eq!(spans, [Span::new(1, 2), Span::new(4, 5), Span::new(7, 8)]);
+        }
+        other => panic!("expected an unclosed ring, got {other:?}"),
+    }
+}
+
+#[test]
+fn rendering() {
+    let input = "C1CC(C=O";
+    assert_eq!(
+        parse(input).unwrap_err().render(input),
+        "error: missing closing parenthesis ')'\n  \
+         | C1CC(C=O\n  \
+         |     ^ branch opened here, never closed\n"
+    );
+    let input = "OC(=O)C1CCC";
+    assert_eq!(
+        parse(input).unwrap_err().render(input),
+        "error: unclosed ring(s): [1]\n  \
+         | OC(=O)C1CCC\n  \
+         |        ^ ring 1 opened here, never closed\n"
+    );
+    let input = "C/1CCCC\\1";
+    assert_eq!(
+        parse(input).unwrap_err().render(input),
+        "error: mismatched bond types for ring 1\n  \
+         | C/1CCCC\\1\n  \
+         |  ^^ ring 1 opened with this bond\n  \
+         |        ^^ and closed with another one\n"
+    );
+}
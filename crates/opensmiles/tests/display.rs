@@ -1,4 +1,4 @@
-use opensmiles::parse;
+use opensmiles::{parse, Molecule, StereoOutput, WriteOptions};
 
 #[test]
 fn display_simple_methane() {
@@ -12,6 +12,68 @@ fn display_bracket_methane() {
     assert_eq!(format!("{}", mol), "C");
 }
 
+#[test]
+fn write_kept_bracket_hydrogens() {
+    let options = WriteOptions {
+        keep_bracket_hydrogens: true,
+        ..WriteOptions::default()
+    };
+    for smiles in [
+        "[CH3]C",
+        "[CH4]",
+        "O[CH2]C",
+        "[nH]1cccc1",
+        "[C]C",
+        "F[C@H](Cl)Br",
+    ] {
+        let mol = parse(smiles).unwrap();
+        assert_eq!(mol.to_smiles_with(&options), smiles);
+        assert_eq!(parse(&mol.to_smiles_with(&options)).unwrap(), mol);
+    }
+    // Hydrogens folded from [H] atoms are added to the bracket count.
+    assert_eq!(
+        parse("[H][CH2]C").unwrap().to_smiles_with(&options),
+        "[CH3]C"
+    );
+    assert_eq!(
+        parse("F[C@@H](Cl)Br")
+            .unwrap()
+            .to_smiles_with(&WriteOptions {
+                stereo: StereoOutput::Omitted,
+                ..options
+            }),
+        "F[CH](Cl)Br"
+    );
+}
+
+#[test]
+fn display_every_component() {
+    for smiles in ["CC.O", "O.CC", "[Na+].[Cl-]", "C.C.C", "c1ccccc1.C2CC2"] {
+        assert_eq!(parse(smiles).unwrap().to_string(), smiles);
+    }
+    // Components come in the order of their first atom.
+    let molecule = parse("C.N.O").unwrap();
+    let nodes = molecule.nodes().iter().rev().cloned().collect();
+    assert_eq!(Molecule::new(nodes, Vec::new()).to_string(), "O.N.C");
+}
+
+#[test]
+fn display_reuses_ring_numbers_past_99() {
+    let rings = 150;
+    let smiles: String = (0..rings).map(|_| "C1CC1").collect();
+    let molecule = parse(&smiles).unwrap();
+    let written = molecule.to_string();
+    // Fresh numbers up to %99, then the lowest free one.
+    assert!(written.contains("C%99CC%99"), "{written}");
+    assert!(written.ends_with("C1CC1C1CC1"), "{written}");
+    let reparsed = parse(&written).unwrap();
+    assert_eq!(reparsed.nodes().len(), 3 * rings);
+    assert_eq!(
+        reparsed.to_canonical_smiles(),
+        molecule.to_canonical_smiles()
+    );
+}
+
 #[test]
 fn display_ethane() {
     let mol = parse("CC").unwrap();
@@ -122,8 +184,10 @@ fn display_cis_2_butene() {
 
 #[test]
 fn display_chiral_carbon() {
+    // Starting on F moves the hydrogen after F: the parity is inverted to keep
+    // the same stereoisomer.
     let mol = parse("[C@@H](F)(Cl)Br").unwrap();
-    assert_eq!(format!("{}", mol), "F[C@@H](Cl)Br");
+    assert_eq!(format!("{}", mol), "F[C@H](Cl)Br");
 }
 
 #[test]
@@ -237,3 +301,24 @@ fn display_standard_form_chirality() {
     let mol2 = parse("F/C(/F)=C/F").unwrap();
     assert_eq!(format!("{}", mol2), "FC(F)=CF");
 }
+
+#[test]
+fn display_cage_in_bounded_time() {
+    // A ring of twelve diamonds has 4096 relevant cycles of 24 atoms, which
+    // aromaticity perception must not list.
+    let mut smiles = String::from("C1%10(C2)C");
+    for diamond in 1..12 {
+        let (close, open) = if diamond % 2 == 1 { (2, 3) } else { (3, 2) };
+        if diamond == 11 {
+            smiles.push_str(&format!("C{close}(C%10)C1"));
+        } else {
+            smiles.push_str(&format!("C{close}(C{open})C"));
+        }
+    }
+    let molecule = parse(&smiles).unwrap();
+    let start = std::time::Instant::now();
+    let written = molecule.to_string();
+    let canonical = molecule.to_canonical_smiles();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(parse(&written).unwrap().to_canonical_smiles(), canonical);
+}
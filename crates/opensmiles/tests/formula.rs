@@ -0,0 +1,109 @@
+//! Tests for molecular formulas (`Molecule::formula()`).
+
+use opensmiles::{parse, AtomSymbol, Formula, FormulaError, OrganicAtom};
+
+fn formula(smiles: &str) -> String {
+    parse(smiles).unwrap().formula().to_string()
+}
+
+#[test]
+fn implicit_and_bracket_hydrogens() {
+    assert_eq!(formula("CCO"), "C2H6O");
+    assert_eq!(formula("c1ccccc1"), "C6H6");
+    assert_eq!(formula("[CH3][CH2][OH]"), "C2H6O");
+    assert_eq!(formula("c1cc[nH]c1"), "C4H5N");
+    assert_eq!(formula("CN1C=NC2=C1C(=O)N(C(=O)N2C)C"), "C8H10N4O2");
+}
+
+#[test]
+fn explicit_hydrogen_atoms() {
+    assert_eq!(formula("[H]C([H])([H])[H]"), "CH4");
+    assert_eq!(formula("[H][H]"), "H2");
+    assert_eq!(formula("[2H]C([2H])([2H])O"), "CH[2H]3O");
+}
+
+#[test]
+fn charges() {
+    assert_eq!(formula("[O-]c1ccccc1[N+](=O)[O-]"), "C6H4NO3-");
+    assert_eq!(formula("[NH4+]"), "H4N+");
+    assert_eq!(formula("[O-]S(=O)(=O)[O-]"), "O4S-2");
+    assert_eq!(formula("[Fe+3]"), "Fe+3");
+}
+
+#[test]
+fn formula_without_carbon_is_alphabetical() {
+    assert_eq!(formula("O"), "H2O");
+    assert_eq!(formula("OS(=O)(=O)O"), "H2O4S");
+    assert_eq!(formula("[Na+].[Cl-]"), "ClNa");
+}
+
+#[test]
+fn isotopes() {
+    assert_eq!(formula("[13CH4]"), "[13C]H4");
+    assert_eq!(formula("C[13CH2]O"), "C[13C]H6O");
+    let f = parse("C[13CH2]O").unwrap().formula();
+    assert_eq!(f.count(AtomSymbol::Organic(OrganicAtom::C)), 2);
+    assert_eq!(f.atom_count(), 9);
+}
+
+#[test]
+fn parsed_formula_matches_smiles() {
+    let parsed: Formula = "C2H5OH".parse().unwrap();
+    assert_eq!(parsed, parse("OCC").unwrap().formula());
+    let parsed: Formula = "C6H5NO2-".parse().unwrap();
+    assert_eq!(parsed.charge(), -1);
+    assert_eq!(parsed.to_string(), "C6H5NO2-");
+    let parsed: Formula = "Ca(OH)2".parse().unwrap();
+    assert_eq!(parsed, parse("[Ca+2].[OH-].[OH-]").unwrap().formula());
+    assert_eq!(
+        "(CH3)3COH".parse::<Formula>().unwrap(),
+        parse("CC(C)(C)O").unwrap().formula()
+    );
+}
+
+#[test]
+fn formula_round_trips_through_display() {
+    for smiles in [
+        "CCO",
+        "[13CH3]C(=O)[O-]",
+        "[NH4+]",
+        "[2H]O[2H]",
+        "Cl[Pt](Cl)(N)N",
+    ] {
+        let f = parse(smiles).unwrap().formula();
+        assert_eq!(f.to_string().parse::<Formula>().unwrap(), f, "{smiles}");
+    }
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!("".parse::<Formula>(), Err(FormulaError::Empty));
+    assert_eq!(
+        "C2)".parse::<Formula>(),
+        Err(FormulaError::UnexpectedCharacter(')', 2))
+    );
+    assert_eq!(
+        "[13]C".parse::<Formula>(),
+        Err(FormulaError::UnexpectedCharacter(']', 3))
+    );
+    assert_eq!(
+        "C99999999999".parse::<Formula>(),
+        Err(FormulaError::CountTooLarge(1))
+    );
+}
+
+#[test]
+fn double_bond_equivalents() {
+    let dbe = |s: &str| parse(s).unwrap().formula().double_bond_equivalents();
+    assert_eq!(dbe("CCCC"), Some(0.0));
+    assert_eq!(dbe("C=CC=C"), Some(2.0));
+    assert_eq!(dbe("c1ccccc1"), Some(4.0));
+    assert_eq!(dbe("c1ccc2ccccc2c1"), Some(7.0));
+    assert_eq!(dbe("C#N"), Some(2.0));
+    assert_eq!(dbe("ClC(Cl)Cl"), Some(0.0));
+    assert_eq!(dbe("c1cc[nH+]cc1"), Some(4.0));
+    assert_eq!(dbe("[O-]c1ccccc1"), Some(4.0));
+    assert_eq!(dbe("[CH3]"), Some(0.5));
+    assert_eq!(dbe("Cl[Pt](Cl)(N)N"), None);
+    assert_eq!(dbe("*C"), None);
+}
@@ -0,0 +1,91 @@
+//! Tests for connected components (`Molecule::fragments()`).
+
+use opensmiles::{parse, Molecule};
+
+fn smiles(mol: &Molecule) -> String {
+    mol.to_canonical_smiles()
+}
+
+#[test]
+fn single_component() {
+    let mol = parse("CCO").unwrap();
+    let fragments = mol.fragments();
+    assert_eq!(fragments.len(), 1);
+    assert_eq!(fragments[0].molecule(), &mol);
+    assert_eq!(fragments[0].atoms(), &[0, 1, 2]);
+}
+
+#[test]
+fn salt_is_split_into_ions() {
+    let mol = parse("[Na+].[Cl-]").unwrap();
+    let fragments = mol.fragments();
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments[0].molecule().to_string(), "[Na+]");
+    assert_eq!(fragments[1].molecule().to_string(), "[Cl-]");
+    assert_eq!(fragments[1].atoms(), &[1]);
+}
+
+#[test]
+fn atom_mapping_follows_components() {
+    // A ring closure can join atoms written on both sides of a dot.
+    let mol = parse("C1CC.O.C1.N").unwrap();
+    let fragments = mol.fragments();
+    assert_eq!(fragments.len(), 3);
+    assert_eq!(fragments[0].atoms(), &[0, 1, 2, 4]);
+    assert_eq!(fragments[1].atoms(), &[3]);
+    assert_eq!(fragments[2].atoms(), &[5]);
+    assert_eq!(
+        smiles(fragments[0].molecule()),
+        smiles(&parse("CCCC").unwrap())
+    );
+    for fragment in &fragments {
+        for (local, &original) in fragment.atoms().iter().enumerate() {
+            assert_eq!(
+                fragment.molecule().nodes()[local],
+                mol.nodes()[original as usize]
+            );
+        }
+    }
+}
+
+#[test]
+fn fragments_keep_stereochemistry() {
+    let mol = parse("[Na+].N[C@@H](C)C(=O)[O-]").unwrap();
+    let alanine = mol.largest_fragment().unwrap().into_molecule();
+    assert_eq!(
+        smiles(&alanine),
+        smiles(&parse("N[C@@H](C)C(=O)[O-]").unwrap())
+    );
+    assert_ne!(
+        smiles(&alanine),
+        smiles(&parse("N[C@H](C)C(=O)[O-]").unwrap())
+    );
+}
+
+#[test]
+fn largest_fragment_counts_heavy_atoms() {
+    let mol = parse("[H][H].[H][H].[H][H].O").unwrap();
+    assert_eq!(mol.largest_fragment().unwrap().atoms(), &[6]);
+    let mol = parse("Cl.c1ccccc1N").unwrap();
+    assert_eq!(mol.largest_fragment().unwrap().atoms().len(), 7);
+    // Ties go to the first fragment.
+    let mol = parse("[K+].[Br-]").unwrap();
+    assert_eq!(mol.largest_fragment().unwrap().atoms(), &[0]);
+}
+
+#[test]
+fn component_of_atoms() {
+    let mol = parse("CC.O.C1CC1").unwrap();
+    assert_eq!(mol.component_of(0), Some(0));
+    assert_eq!(mol.component_of(1), Some(0));
+    assert_eq!(mol.component_of(2), Some(1));
+    assert_eq!(mol.component_of(5), Some(2));
+    assert_eq!(mol.component_of(6), None);
+}
+
+#[test]
+fn leading_dot() {
+    let mol = parse("C(.O)C").unwrap();
+    assert_eq!(mol.fragments().len(), 2);
+    assert_eq!(mol.component_of(1), Some(1));
+}
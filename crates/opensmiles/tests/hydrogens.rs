@@ -0,0 +1,180 @@
+//! Tests for explicit hydrogen addition and removal.
+
+use opensmiles::{parse, AtomSymbol, OrganicAtom};
+
+fn canonical(smiles: &str) -> String {
+    parse(smiles).unwrap().to_canonical_smiles()
+}
+
+fn hydrogen_atoms(smiles: &str) -> usize {
+    parse(smiles)
+        .unwrap()
+        .add_explicit_hydrogens()
+        .nodes()
+        .iter()
+        .filter(|n| *n.atom().element() == AtomSymbol::H)
+        .count()
+}
+
+#[test]
+fn every_counted_hydrogen_becomes_an_atom() {
+    assert_eq!(hydrogen_atoms("C"), 4);
+    assert_eq!(hydrogen_atoms("CCO"), 6);
+    assert_eq!(hydrogen_atoms("c1ccccc1"), 6);
+    assert_eq!(hydrogen_atoms("[NH4+]"), 4);
+    assert_eq!(hydrogen_atoms("[H]C"), 4);
+
+    let mol = parse("CC(=O)O").unwrap().add_explicit_hydrogens();
+    assert!(mol.nodes().iter().all(|n| n.hydrogens() == 0));
+    assert_eq!(mol.nodes().len(), 8);
+    // Existing atoms keep their index; hydrogens follow their heavy atom.
+    assert_eq!(
+        *mol.nodes()[2].atom().element(),
+        AtomSymbol::Organic(OrganicAtom::O)
+    );
+    let last = mol.bonds().last().unwrap();
+    assert_eq!((last.source(), last.target()), (3, 7));
+}
+
+#[test]
+fn adding_hydrogens_keeps_the_molecule() {
+    for smiles in [
+        "CCO",
+        "c1ccc2[nH]ccc2c1",
+        "N[C@@H](C)C(=O)O",
+        "N[C@H](C)C(=O)O",
+        "[C@@H](F)(Cl)Br",
+        "C1CC[C@H]2CCCC[C@@H]2C1",
+        "F/C=C/F",
+        "C/C=C\\C",
+    ] {
+        let mol = parse(smiles).unwrap();
+        let explicit = mol.add_explicit_hydrogens();
+        assert_eq!(
+            explicit.to_canonical_smiles(),
+            mol.to_canonical_smiles(),
+            "{smiles}"
+        );
+        assert_eq!(explicit.remove_explicit_hydrogens(), mol, "{smiles}");
+    }
+}
+
+#[test]
+fn adding_hydrogens_to_a_long_chain() {
+    let mol = parse(&("C[C@H](F)".repeat(5000) + "Cl")).unwrap();
+    let hydrogens: usize = mol.nodes().iter().map(|n| n.hydrogens() as usize).sum();
+    let start = std::time::Instant::now();
+    let explicit = mol.add_explicit_hydrogens();
+    let folded = explicit.remove_explicit_hydrogens();
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    assert_eq!(explicit.nodes().len(), mol.nodes().len() + hydrogens);
+    assert_eq!(folded, mol);
+}
+
+#[test]
+fn removing_hydrogens_folds_them_into_the_count() {
+    let mol = parse("[H]OC([H])([H])[H]")
+        .unwrap()
+        .remove_explicit_hydrogens();
+    assert_eq!(mol.nodes().len(), 2);
+    assert_eq!(mol.nodes()[0].hydrogens(), 1);
+    assert_eq!(mol.nodes()[1].hydrogens(), 3);
+    assert_eq!(mol.bonds().len(), 1);
+    assert_eq!(mol.to_string(), "OC");
+}
+
+#[test]
+fn special_hydrogens_are_kept() {
+    for smiles in ["[2H]C", "[H][H]", "[H+]", "[H:1]C", "[H-].[Na+]"] {
+        let mol = parse(smiles).unwrap();
+        assert_eq!(mol.remove_explicit_hydrogens(), mol, "{smiles}");
+    }
+}
+
+#[test]
+fn removing_hydrogens_keeps_tetrahedral_centres() {
+    for (explicit, implicit) in [
+        ("[H][C@](F)(Cl)Br", "[C@H](F)(Cl)Br"),
+        ("F[C@]([H])(Cl)Br", "F[C@H](Cl)Br"),
+        ("F[C@](Cl)([H])Br", "F[C@@H](Cl)Br"),
+        ("F[C@](Cl)(Br)[H]", "F[C@H](Cl)Br"),
+        ("N[C@@]([H])(C)C(=O)O", "N[C@@H](C)C(=O)O"),
+        ("[H][C@@]1(C)CCCCO1", "[C@@H]1(C)CCCCO1"),
+    ] {
+        let removed = parse(explicit).unwrap().remove_explicit_hydrogens();
+        assert_eq!(
+            removed.to_canonical_smiles(),
+            canonical(implicit),
+            "{explicit}"
+        );
+        assert_eq!(
+            removed.nodes().len(),
+            parse(implicit).unwrap().nodes().len()
+        );
+    }
+}
+
+#[test]
+fn removing_hydrogens_keeps_double_bond_stereo() {
+    // The mark on the hydrogen moves to the other substituent.
+    let removed = parse("[H]/C(F)=C/F").unwrap().remove_explicit_hydrogens();
+    assert_eq!(removed.to_canonical_smiles(), canonical("F/C=C\\F"));
+    let removed = parse("F/C=C(\\[H])F").unwrap().remove_explicit_hydrogens();
+    assert_eq!(removed.to_canonical_smiles(), canonical("F/C=C/F"));
+}
+
+#[test]
+fn bracket_and_implicit_counts_are_told_apart() {
+    let mol = parse("C[CH2]C[C]").unwrap();
+    let nodes = mol.nodes();
+    assert_eq!(nodes[0].implicit_hydrogens(), 3);
+    assert_eq!(nodes[0].bracket_hydrogens(), None);
+    assert_eq!(nodes[1].implicit_hydrogens(), 0);
+    assert_eq!(nodes[1].bracket_hydrogens(), Some(2));
+    assert_eq!(nodes[3].bracket_hydrogens(), Some(0));
+    assert!(nodes
+        .iter()
+        .all(|n| n.hydrogens() == n.implicit_hydrogens() + n.bracket_hydrogens().unwrap_or(0)));
+
+    // Aromatic atoms keep the origin of their count through kekulization.
+    let pyrrole = parse("c1cc[nH]c1").unwrap().kekulize().unwrap();
+    assert_eq!(pyrrole.nodes()[0].implicit_hydrogens(), 1);
+    assert_eq!(pyrrole.nodes()[3].bracket_hydrogens(), Some(1));
+}
+
+#[test]
+fn equality_ignores_the_origin_of_counts() {
+    let bracket = parse("[CH3][CH3]").unwrap();
+    let implicit = parse("CC").unwrap();
+    assert_eq!(bracket.nodes()[0], implicit.nodes()[0]);
+    assert_eq!(bracket, implicit);
+    assert_ne!(parse("[CH2]C").unwrap(), implicit);
+}
+
+#[test]
+fn total_hydrogens_count_explicit_neighbours() {
+    let mol = parse("[H]C([H])=C([2H])[CH2].[H]").unwrap();
+    assert_eq!(mol.explicit_h_neighbours(1), 2);
+    assert_eq!(mol.total_hydrogens(1), 2);
+    assert_eq!(mol.explicit_h_neighbours(3), 1);
+    assert_eq!(mol.total_hydrogens(3), 1);
+    assert_eq!(mol.total_hydrogens(5), 2);
+    // A dot is not a bond.
+    assert_eq!(mol.explicit_h_neighbours(5), 0);
+}
+
+#[test]
+fn writer_brackets_counts_the_valence_model_does_not_imply() {
+    for (smiles, written) in [
+        ("[CH4]", "C"),
+        ("[CH3]C", "CC"),
+        ("C[CH]C", "C[CH]C"),
+        ("[H]C([H])([H])[H]", "C"),
+        ("[H][CH2]C", "CC"),
+        ("[H][CH]C", "[CH2]C"),
+        ("[OH2]", "O"),
+        ("[NH2]", "[NH2]"),
+    ] {
+        assert_eq!(parse(smiles).unwrap().to_string(), written, "{smiles}");
+    }
+}
@@ -0,0 +1,178 @@
+//! Tests for identity keys.
+
+use opensmiles::{parse, IDENTITY_KEY_VERSION};
+
+fn key(smiles: &str) -> String {
+    parse(smiles).unwrap().identity_key()
+}
+
+/// Second block of every molecule without isotopes or stereo.
+const UNLABELLED: &str = "VSBDIUGVGL";
+
+#[test]
+fn golden_keys() {
+    // Keys of a given version never change: these pin the algorithm.
+    for (smiles, expected) in [
+        ("C", "XURAMRCKZMFQHC-VSBDIUGVGL-B"),
+        ("CO", "QHKIGLHPUMNLIN-VSBDIUGVGL-B"),
+        ("CCO", "BBBYVZKRVGNZGF-VSBDIUGVGL-B"),
+        ("C1CC1", "HUTNTQKZOQNTCA-VSBDIUGVGL-B"),
+        ("c1ccccc1", "BRVEUGAEKVRWNP-VSBDIUGVGL-B"),
+        ("c1ccncc1", "UFIRESTEPXFJLU-VSBDIUGVGL-B"),
+        ("[Na+].[Cl-]", "JKDSCYIMQVPUEW-VSBDIUGVGL-B"),
+        ("[13CH4]", "XURAMRCKZMFQHC-CWOBOPYDGN-B"),
+        ("NC(C)C(=O)O", "JQJWKKRQYORMLM-VSBDIUGVGL-B"),
+        ("N[C@@H](C)C(=O)O", "JQJWKKRQYORMLM-OOYUPNRUWI-B"),
+        ("N[C@H](C)C(=O)O", "JQJWKKRQYORMLM-AKPIOEIIBA-B"),
+        ("FC=CF", "TCJBXVNXNQYFWM-VSBDIUGVGL-B"),
+        ("F/C=C/F", "TCJBXVNXNQYFWM-VBMCDIUYRK-B"),
+        ("F/C=C\\F", "TCJBXVNXNQYFWM-IDKVLIWPVC-B"),
+    ] {
+        assert_eq!(key(smiles), expected, "{smiles}");
+    }
+}
+
+#[test]
+fn golden_keys_of_reordered_inputs() {
+    for (forms, expected) in [
+        (
+            &["CC(=O)Oc1ccccc1C(=O)O", "OC(=O)c1ccccc1OC(C)=O"][..],
+            "KTBYQEOKFRURHU-VSBDIUGVGL-B",
+        ),
+        (
+            &["C1CC1.C1CCCCC1", "C1CCCCC1.C1CC1"],
+            "VSHDYWKGPTCBFH-VSBDIUGVGL-B",
+        ),
+        (
+            &["CC1CCC(C)CC1", "C1CC(C)CCC1C"],
+            "DCGFCQKRRHQEWK-VSBDIUGVGL-B",
+        ),
+        // The same cage, which refinement alone cannot rank.
+        (
+            &["C12C3C4C(C43)C5C1C25", "C12C3C4C5C4C5C1C23"],
+            "YTNEECNKABRFNL-VSBDIUGVGL-B",
+        ),
+    ] {
+        for smiles in forms {
+            assert_eq!(key(smiles), expected, "{smiles}");
+        }
+    }
+}
+
+#[test]
+fn golden_keys_of_ring_cis_trans_isomers() {
+    for (forms, expected) in [
+        // cis- and trans-1,4-dimethylcyclohexane
+        (
+            &[
+                "C[C@H]1CC[C@@H](C)CC1",
+                "C[C@@H]1CC[C@H](C)CC1",
+                "C1C[C@@H](C)CC[C@H]1C",
+            ][..],
+            "DCGFCQKRRHQEWK-PXQIXZZTYF-B",
+        ),
+        (
+            &[
+                "C[C@H]1CC[C@H](C)CC1",
+                "C[C@@H]1CC[C@@H](C)CC1",
+                "C1C[C@@H](C)CC[C@@H]1C",
+            ],
+            "DCGFCQKRRHQEWK-XLQCYILJTS-B",
+        ),
+        // cis- and trans-decalin
+        (
+            &["C1CC[C@H]2CCCC[C@@H]2C1", "[C@H]12CCCC[C@@H]1CCCC2"],
+            "UIBHOEPUULHPCX-TJCUWSGEQJ-B",
+        ),
+        (
+            &["C1CC[C@H]2CCCC[C@H]2C1", "[C@H]12CCCC[C@H]1CCCC2"],
+            "UIBHOEPUULHPCX-WWGSCKQTLT-B",
+        ),
+        // all-cis and cis,trans-1,3,5-trimethylcyclohexane
+        (
+            &[
+                "C[C@@H]1C[C@H](C)C[C@H](C)C1",
+                "C[C@H]1C[C@@H](C)C[C@@H](C)C1",
+            ],
+            "BYXLXGHPTGJJOR-ZPIJXMXWML-B",
+        ),
+        (
+            &[
+                "C[C@H]1C[C@@H](C)C[C@H](C)C1",
+                "C[C@@H]1C[C@H](C)C[C@@H](C)C1",
+                "C[C@H]1C[C@H](C)C[C@H](C)C1",
+                "C[C@@H]1C[C@@H](C)C[C@@H](C)C1",
+            ],
+            "BYXLXGHPTGJJOR-VMZCZUNVHS-B",
+        ),
+    ] {
+        for smiles in forms {
+            assert_eq!(key(smiles), expected, "{smiles}");
+        }
+    }
+}
+
+#[test]
+fn keys_have_a_fixed_format() {
+    for smiles in ["C", "CC(=O)Oc1ccccc1C(=O)O", "[2H]O[2H]", "F/C=C/[C@H](N)O"] {
+        let key = key(smiles);
+        let blocks: Vec<&str> = key.split('-').collect();
+        assert_eq!(key.len(), 27, "{key}");
+        assert_eq!(blocks.len(), 3, "{key}");
+        assert_eq!((blocks[0].len(), blocks[1].len()), (14, 10), "{key}");
+        assert!(blocks[..2]
+            .iter()
+            .all(|b| b.bytes().all(|c| c.is_ascii_uppercase())));
+        assert_eq!(blocks[2], IDENTITY_KEY_VERSION.to_string());
+    }
+}
+
+#[test]
+fn keys_ignore_the_written_form() {
+    for group in [
+        &["CCO", "OCC", "C(O)C", "[CH3][CH2][OH]", "[H]OC([H])([H])C"][..],
+        &["c1ccccc1", "C1=CC=CC=C1", "C=1C=CC=CC=1"],
+        &["[Na+].[Cl-]", "[Cl-].[Na+]"],
+        &["N[C@@H](C)C(=O)O", "C[C@H](N)C(O)=O", "OC(=O)[C@H](C)N"],
+        &["F/C=C/F", "F\\C=C\\F", "C(\\F)=C/F"],
+        &["CC[NH3+:4]", "[NH3+]CC"],
+    ] {
+        for smiles in &group[1..] {
+            assert_eq!(key(group[0]), key(smiles), "{} and {}", group[0], smiles);
+        }
+    }
+}
+
+#[test]
+fn stereoisomers_share_the_first_block() {
+    for (a, b) in [
+        ("N[C@@H](C)C(=O)O", "N[C@H](C)C(=O)O"),
+        ("F/C=C/F", "F/C=C\\F"),
+        ("C", "[13CH4]"),
+        ("O", "[2H]O[2H]"),
+        ("C[C@H](O)[C@H](N)C", "C[C@H](O)[C@@H](N)C"),
+        (
+            "C[C@H]1C[C@H]2C[C@H](C)C[C@H]2C1",
+            "C[C@@H]1C[C@H]2C[C@H](C)C[C@H]2C1",
+        ),
+    ] {
+        let (a, b) = (key(a), key(b));
+        assert_eq!(a[..14], b[..14]);
+        assert_ne!(a, b);
+    }
+    // Different constitutions differ in the first block.
+    assert_ne!(key("CCO")[..14], key("COC")[..14]);
+    assert_ne!(key("CC(=O)O")[..14], key("CC(=O)[O-]")[..14]);
+    assert_ne!(key("C1CCC1")[..14], key("C=CCC")[..14]);
+}
+
+#[test]
+fn unlabelled_molecules_share_the_second_block() {
+    for smiles in ["CCO", "c1ccccc1", "[Na+].[Cl-]", "FC=CF", "FC(Cl)Br"] {
+        assert_eq!(&key(smiles)[15..25], UNLABELLED, "{smiles}");
+    }
+    // Stereo on a centre with equivalent neighbours is no stereo.
+    assert_eq!(&key("C[C@H](C)O")[15..25], UNLABELLED);
+    assert_eq!(&key("C[C@H]1CCCCC1")[15..25], UNLABELLED);
+    assert_ne!(&key("F[C@H](Cl)Br")[15..25], UNLABELLED);
+}
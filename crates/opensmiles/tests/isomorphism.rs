@@ -0,0 +1,119 @@
+//! Tests for graph isomorphism and identity-based keys.
+
+use std::collections::{HashMap, HashSet};
+
+use opensmiles::{parse, MoleculeKey};
+
+fn isomorphic(a: &str, b: &str) -> bool {
+    parse(a).unwrap().is_isomorphic(&parse(b).unwrap())
+}
+
+fn same_stereoisomer(a: &str, b: &str) -> bool {
+    parse(a)
+        .unwrap()
+        .is_isomorphic_with_stereo(&parse(b).unwrap())
+}
+
+#[test]
+fn atom_order_and_notation_do_not_matter() {
+    for (a, b) in [
+        ("CCO", "OCC"),
+        ("CC(=O)O", "OC(C)=O"),
+        ("c1ccccc1", "C1=CC=CC=C1"),
+        ("c1ccc2ccccc2c1", "C1=CC2=CC=CC=C2C=C1"),
+        ("[H]OC([H])([H])[H]", "CO"),
+        ("C1CCCCC1", "C%12CCCCC%12"),
+        ("[Na+].[Cl-]", "[Cl-].[Na+]"),
+        ("[CH3:1]O", "CO"),
+        ("", ""),
+    ] {
+        assert!(isomorphic(a, b), "{a} {b}");
+        assert!(isomorphic(b, a), "{b} {a}");
+    }
+}
+
+#[test]
+fn atom_and_bond_properties_are_compared() {
+    for (a, b) in [
+        ("CCO", "COC"),
+        ("CC", "C=C"),
+        ("CO", "C[O-]"),
+        ("CO", "[13CH3]O"),
+        ("C", "[CH3]"),
+        ("CC", "C.C"),
+        ("C1CCCCC1", "CCCCCC"),
+        ("c1ccccc1", "C1CCCCC1"),
+        ("C", "CC"),
+    ] {
+        assert!(!isomorphic(a, b), "{a} {b}");
+        assert!(!isomorphic(b, a), "{b} {a}");
+    }
+}
+
+#[test]
+fn regular_graphs_with_equal_signatures() {
+    // Two 2-regular graphs with the same atoms and bonds: a 6-ring against
+    // two 3-rings, and a 12-ring against two 6-rings.
+    assert!(!isomorphic("C1CCCCC1", "C1CC1.C1CC1"));
+    assert!(!isomorphic("C1CCCCCCCCCCC1", "C1CCCCC1.C1CCCCC1"));
+    // Decalin written from another atom.
+    assert!(isomorphic("C1CCC2CCCCC2C1", "C1CC2CCCCC2CC1"));
+}
+
+#[test]
+fn stereo_is_optional() {
+    let (l, d) = ("N[C@@H](C)C(=O)O", "N[C@H](C)C(=O)O");
+    assert!(isomorphic(l, d));
+    assert!(!same_stereoisomer(l, d));
+    assert!(same_stereoisomer(l, "C[C@H](N)C(=O)O"));
+    assert!(same_stereoisomer(l, "OC(=O)[C@@H](N)C"));
+    assert!(!same_stereoisomer(l, "NC(C)C(=O)O"));
+
+    let (e, z) = ("F/C=C/F", "F/C=C\\F");
+    assert!(isomorphic(e, z));
+    assert!(!same_stereoisomer(e, z));
+    assert!(same_stereoisomer(e, "F\\C=C\\F"));
+    assert!(same_stereoisomer(e, "C(\\F)=C/F"));
+    assert!(same_stereoisomer("F/C(Cl)=C/F", "Cl\\C(F)=C/F"));
+    assert!(!same_stereoisomer(e, "FC=CF"));
+
+    for smiles in ["N[C@@H](C)C(=O)O", "C/C=C/C=C\\C", "[H][C@@](F)(Cl)Br"] {
+        let molecule = parse(smiles).unwrap();
+        let canonical = parse(&molecule.to_canonical_smiles()).unwrap();
+        assert!(molecule.is_isomorphic_with_stereo(&canonical), "{smiles}");
+    }
+}
+
+#[test]
+fn keys_follow_chemical_identity() {
+    let key = |s: &str| MoleculeKey::new(parse(s).unwrap());
+    assert_eq!(key("CCO"), key("OCC"));
+    assert_ne!(key("CCO"), key("COC"));
+    assert_ne!(key("N[C@@H](C)C(=O)O"), key("N[C@H](C)C(=O)O"));
+
+    let set: HashSet<MoleculeKey> = [
+        "c1ccccc1",
+        "C1=CC=CC=C1",
+        "[H]c1ccccc1",
+        "Cc1ccccc1",
+        "c1ccccc1C",
+    ]
+    .iter()
+    .map(|s| key(s))
+    .collect();
+    assert_eq!(set.len(), 2);
+
+    let mut counts: HashMap<MoleculeKey, usize> = HashMap::new();
+    for smiles in ["CCO", "OCC", "C(C)O", "CC=O"] {
+        *counts.entry(key(smiles)).or_default() += 1;
+    }
+    assert_eq!(counts[&key("CCO")], 3);
+    assert_eq!(counts[&key("O=CC")], 1);
+
+    // The key keeps the molecule as given.
+    let molecule = parse("[H]OC").unwrap();
+    assert_eq!(
+        MoleculeKey::from(molecule.clone()).into_molecule(),
+        molecule
+    );
+}
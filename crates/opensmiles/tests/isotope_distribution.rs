@@ -0,0 +1,118 @@
+//! Tests for isotope patterns (`Molecule::isotope_distribution()`).
+
+use opensmiles::{parse, IsotopeDistributionOptions, IsotopePeak};
+
+fn nominal(smiles: &str) -> Vec<IsotopePeak> {
+    let options = IsotopeDistributionOptions {
+        merge_width: Some(0.5),
+        ..Default::default()
+    };
+    parse(smiles).unwrap().isotope_distribution(&options)
+}
+
+fn close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "{actual} != {expected}"
+    );
+}
+
+#[test]
+fn first_peak_is_the_monoisotopic_mass() {
+    let mol = parse("CN1C=NC2=C1C(=O)N(C(=O)N2C)C").unwrap();
+    let peaks = mol.isotope_distribution(&IsotopeDistributionOptions::default());
+    close(peaks[0].mz, mol.monoisotopic_mass(), 1e-9);
+    assert_eq!(peaks[0].intensity, 1.0);
+    assert!(peaks.windows(2).all(|w| w[0].mz < w[1].mz));
+    let total: f64 = peaks.iter().map(|p| p.probability).sum();
+    close(total, 1.0, 1e-3);
+}
+
+#[test]
+fn carbon_envelope() {
+    // The M+1 peak of C100H202 grows past the monoisotopic one:
+    // 100 x 1.08 % for 13C plus 202 x 0.0115 % for 2H.
+    let peaks = nominal(&"C".repeat(100));
+    close(peaks[1].probability / peaks[0].probability, 1.105, 0.001);
+    let base = peaks.iter().position(|p| p.intensity == 1.0).unwrap();
+    assert_eq!(base, 1);
+}
+
+#[test]
+fn halogen_patterns() {
+    // Two chlorines: 100 : 64 : 10.
+    let peaks = nominal("ClCCl");
+    let m = |nominal: f64| {
+        peaks
+            .iter()
+            .find(|p| (p.mz - nominal).abs() < 0.5)
+            .unwrap()
+            .intensity
+    };
+    close(m(84.0), 1.0, 1e-9);
+    close(m(86.0), 0.64, 0.005);
+    close(m(88.0), 0.105, 0.005);
+
+    // Bromine: 79Br and 81Br in about equal amounts.
+    let peaks = nominal("Brc1ccccc1");
+    let m2 = peaks.iter().find(|p| (p.mz - 158.0).abs() < 0.5).unwrap();
+    close(m2.intensity, 0.97, 0.01);
+}
+
+#[test]
+fn fine_structure_without_merging() {
+    let options = IsotopeDistributionOptions {
+        min_relative_intensity: 1e-6,
+        ..Default::default()
+    };
+    let peaks = parse("CCO").unwrap().isotope_distribution(&options);
+    // M+1 splits into 13C, 2H and 17O peaks.
+    let m1 = peaks.iter().filter(|p| (p.mz - 47.0).abs() < 0.5).count();
+    assert_eq!(m1, 3);
+}
+
+#[test]
+fn explicit_isotopes_are_fixed() {
+    let peaks = nominal("[13CH4]");
+    close(peaks[0].mz, 17.03465, 1e-5);
+    // No unlabelled carbon left: only the hydrogens contribute to M+1.
+    assert!(peaks.len() == 1 || peaks[1].intensity < 1e-3);
+
+    let labelled = nominal("[13CH3]C");
+    let natural = nominal("CC");
+    close(labelled[0].mz - natural[0].mz, 1.00335, 1e-5);
+    assert!(labelled[1].intensity < natural[1].intensity);
+}
+
+#[test]
+fn charge_states() {
+    let benzene = parse("c1ccccc1").unwrap();
+    let mz = |protons: i32| {
+        let options = IsotopeDistributionOptions {
+            protons,
+            ..Default::default()
+        };
+        benzene.isotope_distribution(&options)[0].mz
+    };
+    close(mz(0), 78.04695, 1e-5);
+    close(mz(1), 79.05423, 1e-5);
+    close(mz(2), 40.03075, 1e-5);
+    close(mz(-1), 77.03967, 1e-5);
+
+    // An intrinsic charge is kept: acetate is observed at its own mass.
+    let acetate = parse("CC(=O)[O-]").unwrap();
+    let peaks = acetate.isotope_distribution(&IsotopeDistributionOptions::default());
+    close(peaks[0].mz, 59.01385, 1e-5);
+}
+
+#[test]
+fn pruning_threshold() {
+    let mol = parse("CCCCCCCCCCCCCCCCCCCC(=O)O").unwrap();
+    let loose = mol.isotope_distribution(&IsotopeDistributionOptions {
+        min_relative_intensity: 0.05,
+        ..Default::default()
+    });
+    assert!(loose.iter().all(|p| p.intensity >= 0.05));
+    let strict = mol.isotope_distribution(&IsotopeDistributionOptions::default());
+    assert!(strict.len() > loose.len());
+}
@@ -0,0 +1,138 @@
+//! Tests for `Molecule::kekulize()`.
+
+use opensmiles::{parse, AromaticityModel, BondType, Molecule};
+
+fn kekulize(smiles: &str) -> Molecule {
+    parse(smiles)
+        .unwrap()
+        .kekulize()
+        .unwrap_or_else(|e| panic!("{smiles}: {e}"))
+}
+
+fn count(mol: &Molecule, kind: BondType) -> usize {
+    mol.bonds().iter().filter(|b| b.kind() == kind).count()
+}
+
+/// Every atom that was aromatic and had a free p electron gets exactly one
+/// double bond; no bond stays aromatic.
+fn assert_valid_kekule(smiles: &str, expected_doubles: usize) {
+    let mol = kekulize(smiles);
+    assert_eq!(count(&mol, BondType::Aromatic), 0, "{smiles}");
+    assert_eq!(count(&mol, BondType::Double), expected_doubles, "{smiles}");
+    assert!(mol.nodes().iter().all(|n| !n.aromatic()), "{smiles}");
+    for atom in 0..mol.nodes().len() as u32 {
+        let doubles = mol
+            .bonds()
+            .iter()
+            .filter(|b| b.kind() == BondType::Double && (b.source() == atom || b.target() == atom))
+            .count();
+        assert!(
+            doubles <= 1,
+            "{smiles}: atom {atom} has {doubles} double bonds"
+        );
+    }
+}
+
+#[test]
+fn kekulize_benzene() {
+    assert_valid_kekule("c1ccccc1", 3);
+}
+
+#[test]
+fn kekulize_heteroaromatics() {
+    assert_valid_kekule("c1ccncc1", 3);
+    assert_valid_kekule("c1cc[nH]c1", 2);
+    assert_valid_kekule("c1ccoc1", 2);
+    assert_valid_kekule("c1ccsc1", 2);
+    assert_valid_kekule("c1cnc[nH]1", 2);
+}
+
+#[test]
+fn kekulize_charged_atoms() {
+    assert_valid_kekule("c1cc[nH+]cc1", 3);
+    assert_valid_kekule("C[n+]1ccccc1", 3);
+    assert_valid_kekule("[cH-]1cccc1", 2);
+    assert_valid_kekule("c1cc[o+]cc1", 3);
+}
+
+#[test]
+fn kekulize_fused_systems() {
+    assert_valid_kekule("c1ccc2ccccc2c1", 5);
+    assert_valid_kekule("c1ccc2c(c1)[nH]cc2", 4);
+    assert_valid_kekule("c1ccc2cc3ccccc3cc2c1", 7);
+    assert_valid_kekule("c1ccc2c(c1)c1ccccc1c1ccccc21", 9); // triphenylene
+}
+
+// Azulene's rings hold 7 and 5 pi electrons each, so per-ring Hückel
+// validation rejects it at parse time.
+#[test]
+#[cfg(not(feature = "huckel-validation"))]
+fn kekulize_azulene() {
+    assert_valid_kekule("c1ccc2cccc2cc1", 5);
+}
+
+#[test]
+fn kekulize_exocyclic_double_bonds() {
+    // Caffeine: the carbonyl carbons keep their C=O and get no ring double bond.
+    let mol = kekulize("Cn1cnc2c1c(=O)n(c(=O)n2C)C");
+    assert_eq!(count(&mol, BondType::Double), 4);
+    assert_valid_kekule("O=c1cccc[nH]1", 3);
+}
+
+#[test]
+fn kekulize_keeps_non_aromatic_bonds() {
+    let mol = kekulize("c1ccccc1-c1ccccc1");
+    assert_eq!(count(&mol, BondType::Double), 6);
+    assert_eq!(count(&mol, BondType::Simple), 7);
+
+    let mol = kekulize("CC(=O)O");
+    assert_eq!(mol, parse("CC(=O)O").unwrap());
+}
+
+#[test]
+fn kekulize_preserves_atoms() {
+    let original = parse("[13cH]1cc[nH]c1[C@@H](F)Cl").unwrap();
+    let mol = original.kekulize().unwrap();
+    for (before, after) in original.nodes().iter().zip(mol.nodes()) {
+        assert_eq!(before.atom(), after.atom());
+        assert_eq!(before.hydrogens(), after.hydrogens());
+        assert_eq!(before.chirality(), after.chirality());
+    }
+}
+
+#[test]
+fn kekulize_matches_kekule_input() {
+    let kekule = kekulize("c1ccccc1").to_canonical_smiles();
+    assert_eq!(kekule, parse("C1=CC=CC=C1").unwrap().to_canonical_smiles());
+}
+
+// Without huckel-validation these inputs parse; with it they are rejected
+// before kekulization is reached.
+#[test]
+#[cfg(not(feature = "huckel-validation"))]
+fn kekulize_reports_failing_atoms() {
+    use opensmiles::MoleculeError;
+
+    // Pyrrole without its [nH]: five atoms each needing a double bond.
+    let err = parse("c1ccnc1").unwrap().kekulize().unwrap_err();
+    match err {
+        MoleculeError::KekulizationFailed { atoms } => assert_eq!(atoms.len(), 1),
+        other => panic!("unexpected error {other}"),
+    }
+
+    let err = parse("Cc1ccccc1.c1cccc1").unwrap().kekulize().unwrap_err();
+    let MoleculeError::KekulizationFailed { atoms } = err else {
+        panic!("unexpected error");
+    };
+    assert!(atoms.iter().all(|&a| a >= 7), "{atoms:?}");
+}
+
+#[test]
+fn kekulize_a_long_polyphenylene_in_bounded_time() {
+    let molecule = parse(&"c1ccc(cc1)".repeat(3000)).unwrap();
+    let start = std::time::Instant::now();
+    let kekule = molecule.kekulize().unwrap();
+    let aromatic = kekule.aromatize(AromaticityModel::Daylight).unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(aromatic.nodes().iter().all(|n| n.aromatic()));
+}
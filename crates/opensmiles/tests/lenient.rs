@@ -0,0 +1,171 @@
+//! Tests for lenient parsing with repairs.
+
+use opensmiles::{parse, parse_lenient, ParseOptions, ParserError, Repair, RepairKind, Span};
+
+fn lenient(input: &str) -> (String, Vec<(RepairKind, Span)>) {
+    let (molecule, repairs) = parse_lenient(input, &ParseOptions::default()).unwrap();
+    let repairs = repairs
+        .into_iter()
+        .map(|Repair { kind, span }| (kind, span))
+        .collect();
+    (molecule.to_string(), repairs)
+}
+
+fn smiles(input: &str) -> String {
+    parse(input).unwrap().to_string()
+}
+
+#[test]
+fn valid_input_needs_no_repair() {
+    for input in ["CCO", "c1ccccc1", "C[C@H](N)C(=O)O", "Cn1cccc1 title"] {
+        let (molecule, repairs) = parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert_eq!(molecule, parse(input).unwrap(), "{input}");
+        assert!(repairs.is_empty(), "{input}: {repairs:?}");
+    }
+}
+
+#[test]
+fn unclosed_rings_are_dropped() {
+    assert_eq!(
+        lenient("C1CC"),
+        (
+            smiles("CCC"),
+            vec![(RepairKind::DroppedRingBond(1), Span::new(1, 2))]
+        )
+    );
+    assert_eq!(
+        lenient("CC=%12CC3C"),
+        (
+            smiles("CCCCC"),
+            vec![
+                (RepairKind::DroppedRingBond(12), Span::new(2, 6)),
+                (RepairKind::DroppedRingBond(3), Span::new(8, 9)),
+            ]
+        )
+    );
+}
+
+#[test]
+fn branches_are_closed_and_stray_parentheses_dropped() {
+    assert_eq!(
+        lenient("CC(C(C ethyl"),
+        (
+            smiles("CC(C(C))"),
+            vec![
+                (RepairKind::ClosedBranch, Span::new(2, 3)),
+                (RepairKind::ClosedBranch, Span::new(4, 5)),
+            ]
+        )
+    );
+    assert_eq!(
+        lenient("CC(=O)O)"),
+        (
+            smiles("CC(=O)O"),
+            vec![(RepairKind::DroppedParenthesis, Span::new(7, 8))]
+        )
+    );
+    assert_eq!(
+        lenient("C(C)()C"),
+        (
+            smiles("C(C)C"),
+            vec![(RepairKind::DroppedEmptyBranch, Span::new(4, 6))]
+        )
+    );
+    assert_eq!(
+        lenient("C("),
+        (
+            smiles("C"),
+            vec![
+                (RepairKind::ClosedBranch, Span::new(1, 2)),
+                (RepairKind::DroppedEmptyBranch, Span::new(1, 2)),
+            ]
+        )
+    );
+}
+
+#[test]
+fn dangling_bonds_are_dropped() {
+    assert_eq!(
+        lenient("=CC#"),
+        (
+            smiles("CC"),
+            vec![
+                (RepairKind::DroppedBond, Span::new(0, 1)),
+                (RepairKind::DroppedBond, Span::new(3, 4)),
+            ]
+        )
+    );
+}
+
+#[test]
+fn acyclic_aromatic_atoms_become_aliphatic() {
+    assert_eq!(
+        lenient("c1ccccc1c"),
+        (
+            smiles("c1ccccc1C"),
+            vec![(RepairKind::Dearomatized, Span::new(8, 9))]
+        )
+    );
+    assert_eq!(
+        lenient("C[nH]C"),
+        (
+            smiles("C[NH]C"),
+            vec![(RepairKind::Dearomatized, Span::new(1, 5))]
+        )
+    );
+    // Dropping a ring bond may leave aromatic atoms outside any ring.
+    let (molecule, repairs) = lenient("c1ccc");
+    assert_eq!(molecule, smiles("CCCC"));
+    assert_eq!(repairs.len(), 5);
+}
+
+#[test]
+fn over_bonded_carbons_lose_bond_orders() {
+    assert_eq!(
+        lenient("C=C(C)(C)C"),
+        (
+            smiles("CC(C)(C)C"),
+            vec![(RepairKind::ReducedValence, Span::new(2, 3))]
+        )
+    );
+    assert_eq!(
+        lenient("[CH5]C"),
+        (
+            smiles("[CH3]C"),
+            vec![(RepairKind::ReducedValence, Span::new(0, 5))]
+        )
+    );
+    // Nothing to lower: the carbon is kept as written.
+    assert_eq!(
+        lenient("C(C)(C)(C)(C)C"),
+        (smiles("C(C)(C)(C)(C)C"), vec![])
+    );
+    // Charged carbons and other elements are left alone.
+    assert!(lenient("C=[C+](C)(C)C").1.is_empty());
+    assert!(lenient("O=S(=O)(O)O").1.is_empty());
+}
+
+#[test]
+fn other_errors_are_returned() {
+    let options = ParseOptions::default();
+    assert!(matches!(
+        parse_lenient("C1CC!", &options),
+        Err(ParserError::UnexpectedCharacter('!', span)) if span == Span::new(4, 5)
+    ));
+    assert!(parse_lenient("C11", &options).is_err());
+}
+
+#[test]
+fn readme_example() {
+    let (molecule, repairs) = parse_lenient("C1CC(c", &ParseOptions::default()).unwrap();
+    assert_eq!(molecule, parse("CCC(C)").unwrap());
+    let text: Vec<String> = repairs.iter().map(Repair::to_string).collect();
+    assert_eq!(
+        text,
+        [
+            "closed unclosed branch at 4..5",
+            "dropped unclosed ring 1 at 1..2",
+            "made acyclic aromatic atom aliphatic at 5..6"
+        ]
+    );
+}
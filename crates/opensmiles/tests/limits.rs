@@ -0,0 +1,151 @@
+//! Tests for the resource limits of the parse options.
+
+use opensmiles::{parse, parse_lenient, parse_with, LimitKind, ParseOptions, ParserError, Span};
+
+fn limit(kind: LimitKind, limit: usize, span: Span) -> ParserError {
+    ParserError::LimitExceeded { kind, limit, span }
+}
+
+#[test]
+fn input_length() {
+    let options = ParseOptions {
+        max_input_length: 8,
+        ..Default::default()
+    };
+    assert!(parse_with("CCO name", &options).is_ok());
+    assert_eq!(
+        parse_with("CCO ethanol", &options),
+        Err(limit(LimitKind::InputLength, 8, Span::new(8, 11)))
+    );
+    // The repairs of lenient parsing do not count.
+    let (_, repairs) = parse_lenient("CC(C(C", &options).unwrap();
+    assert_eq!(repairs.len(), 2);
+    assert!(parse_lenient("CC(C(CC(C", &options).is_err());
+}
+
+#[test]
+fn atoms() {
+    let options = ParseOptions {
+        max_atoms: 3,
+        ..Default::default()
+    };
+    assert!(parse_with("C(C)C", &options).is_ok());
+    assert_eq!(
+        parse_with("CC(C)[NH4+]", &options),
+        Err(limit(LimitKind::Atoms, 3, Span::new(5, 11)))
+    );
+    // Atoms are counted across branches and fragments.
+    assert!(parse_with("C(C(C))C", &options).is_err());
+    assert!(parse_with("C.C.C.C", &options).is_err());
+}
+
+#[test]
+fn bonds() {
+    let options = ParseOptions {
+        max_bonds: 6,
+        ..Default::default()
+    };
+    assert!(parse_with("c1ccccc1", &options).is_ok());
+    assert!(parse_with("C.C.C.C.C.C.C.C", &options).is_ok());
+    assert_eq!(
+        parse_with("C1CCCCC1C", &options),
+        Err(limit(LimitKind::Bonds, 6, Span::new(8, 9)))
+    );
+    // The bond into a branch and ring bonds closed inside it count too.
+    assert_eq!(
+        parse_with("C1CCCC(C)C1", &options),
+        Err(limit(LimitKind::Bonds, 6, Span::new(10, 11)))
+    );
+    assert_eq!(
+        parse_with("CCCCCCC(C)", &options),
+        Err(limit(LimitKind::Bonds, 6, Span::new(7, 8)))
+    );
+    assert_eq!(
+        parse_with("C1CCCCC(C1)", &options),
+        Err(limit(LimitKind::Bonds, 6, Span::new(9, 10)))
+    );
+}
+
+#[test]
+fn branch_depth() {
+    let options = ParseOptions {
+        max_branch_depth: 2,
+        ..Default::default()
+    };
+    assert!(parse_with("C(C(C)C)(C)C", &options).is_ok());
+    assert_eq!(
+        parse_with("C(C(C(C)))", &options),
+        Err(limit(LimitKind::BranchDepth, 2, Span::new(5, 6)))
+    );
+}
+
+#[test]
+fn deep_nesting_does_not_overflow() {
+    let depth = 100_000;
+    let input = format!("{}C{}", "C(".repeat(depth), ")".repeat(depth));
+    let molecule = parse(&input).unwrap();
+    assert_eq!(molecule.nodes().len(), depth + 1);
+    let options = ParseOptions {
+        max_branch_depth: 128,
+        ..Default::default()
+    };
+    assert_eq!(
+        parse_with(&input, &options),
+        Err(limit(LimitKind::BranchDepth, 128, Span::new(257, 258)))
+    );
+}
+
+#[test]
+fn lenient_parsing_of_large_inputs() {
+    let size = 100_000;
+    let (molecule, repairs) = parse_lenient(&"C".repeat(size), &ParseOptions::default()).unwrap();
+    assert_eq!(molecule.nodes().len(), size);
+    assert!(repairs.is_empty());
+
+    // One branch is closed per `(`, the last one being empty.
+    let (molecule, repairs) = parse_lenient(&"C(".repeat(size), &ParseOptions::default()).unwrap();
+    assert_eq!(molecule.nodes().len(), size);
+    assert_eq!(repairs.len(), size + 1);
+
+    let input = format!("C{}C", ")".repeat(size));
+    let (molecule, repairs) = parse_lenient(&input, &ParseOptions::default()).unwrap();
+    assert_eq!(molecule, parse("CC").unwrap());
+    assert_eq!(repairs.len(), size);
+    assert_eq!(repairs[size - 1].span, Span::new(size, size + 1));
+    let (_, repairs) = parse_lenient(&")".repeat(size), &ParseOptions::default()).unwrap();
+    assert_eq!(repairs.len(), size);
+}
+
+#[test]
+fn open_rings() {
+    let options = ParseOptions {
+        max_open_rings: 2,
+        ..Default::default()
+    };
+    assert!(parse_with("C12CC1CC2C3CC3", &options).is_ok());
+    assert_eq!(
+        parse_with("C12CC3CC123", &options),
+        Err(limit(LimitKind::OpenRings, 2, Span::new(5, 6)))
+    );
+    assert_eq!(
+        parse_with("C12CC=%10CC12", &options),
+        Err(limit(LimitKind::OpenRings, 2, Span::new(5, 9)))
+    );
+}
+
+#[test]
+fn rendering() {
+    let options = ParseOptions {
+        max_atoms: 2,
+        ..Default::default()
+    };
+    let input = "CCO";
+    assert_eq!(
+        parse_with(input, &options).unwrap_err().render(input),
+        "error: atom limit of 2 exceeded\n  | CCO\n  |   ^ more than 2 atoms\n"
+    );
+    assert_eq!(
+        parse_with(input, &options).unwrap_err().kind(),
+        "LimitExceeded"
+    );
+}
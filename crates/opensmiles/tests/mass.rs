@@ -0,0 +1,48 @@
+//! Tests for molecular masses (`average_mass()` and `monoisotopic_mass()`).
+
+use opensmiles::{parse, Formula};
+
+fn monoisotopic(smiles: &str) -> String {
+    format!("{:.5}", parse(smiles).unwrap().monoisotopic_mass())
+}
+
+fn average(smiles: &str) -> f64 {
+    parse(smiles).unwrap().average_mass()
+}
+
+#[test]
+fn monoisotopic_masses_include_implicit_hydrogens() {
+    assert_eq!(monoisotopic("c1ccccc1"), "78.04695");
+    assert_eq!(monoisotopic("CN1C=NC2=C1C(=O)N(C(=O)N2C)C"), "194.08038");
+    assert_eq!(monoisotopic("Brc1ccccc1"), "155.95746");
+}
+
+#[test]
+fn labelled_atoms_use_their_isotope_mass() {
+    assert_eq!(monoisotopic("[37Cl]c1ccccc1"), "114.00503");
+    assert_eq!(monoisotopic("[2H]C([2H])([2H])O"), "35.04504");
+    assert_eq!(monoisotopic("[13CH4]"), "17.03465");
+}
+
+#[test]
+fn charges_adjust_for_electron_mass() {
+    assert_eq!(monoisotopic("CC[OH2+]"), "47.04914");
+    assert_eq!(monoisotopic("CC(=O)[O-]"), "59.01385");
+    assert_eq!(monoisotopic("[O-]S(=O)(=O)[O-]"), "95.95283");
+}
+
+#[test]
+fn average_masses() {
+    assert!((average("O") - 18.01528).abs() < 1e-3);
+    assert!((average("CCO") - 46.06844).abs() < 1e-3);
+    assert!((average("CN1C=NC2=C1C(=O)N(C(=O)N2C)C") - 194.1906).abs() < 1e-2);
+    assert!((average("[Na+].[Cl-]") - 58.44).abs() < 1e-2);
+}
+
+#[test]
+fn formula_and_molecule_masses_agree() {
+    let mol = parse("OC(=O)c1ccccc1[N+](=O)[O-]").unwrap();
+    let formula: Formula = "C7H5NO4".parse().unwrap();
+    assert_eq!(mol.monoisotopic_mass(), formula.monoisotopic_mass());
+    assert_eq!(mol.average_mass(), formula.average_mass());
+}
@@ -0,0 +1,453 @@
+//! Tests for the Molfile writer and reader.
+
+use std::io::Cursor;
+
+use opensmiles::molfile::{parse_molfile, MolfileOptions, MolfileVersion, SdfReader};
+use opensmiles::{
+    parse, Atom, AtomSymbol, BondType, Molecule, MolfileError, Node, NodeError, OrganicAtom,
+};
+
+fn lines(molfile: &str) -> Vec<&str> {
+    molfile.lines().collect()
+}
+
+#[test]
+fn v2000_connection_table() {
+    let molfile = parse("[NH3+:7][C@@H](C)C(=O)[O-]")
+        .unwrap()
+        .to_molfile(MolfileVersion::V2000);
+    let lines = lines(&molfile);
+    assert_eq!(lines[3], "  6  5  0  0  1  0  0  0  0  0999 V2000");
+    assert_eq!(
+        lines[4],
+        "    0.0000    0.0000    0.0000 N   0  0  0  0  0  4  0  0  0  7  0  0"
+    );
+    assert_eq!(
+        lines[5],
+        "    0.0000    0.0000    0.0000 C   0  0  1  0  0  0  0  0  0  0  0  0"
+    );
+    assert_eq!(
+        &lines[10..15],
+        [
+            "  1  2  1  0",
+            "  2  3  1  0",
+            "  2  4  1  0",
+            "  4  5  2  0",
+            "  4  6  1  0"
+        ]
+    );
+    assert_eq!(lines[15], "M  CHG  2   1   1   6  -1");
+    assert_eq!(lines[16], "M  END");
+}
+
+#[test]
+fn v3000_connection_table() {
+    let molfile = parse("[NH3+:7][C@@H](C)C(=O)[15O-]")
+        .unwrap()
+        .to_molfile(MolfileVersion::V3000);
+    let lines = lines(&molfile);
+    assert_eq!(lines[3], "  0  0  0     0  0            999 V3000");
+    assert_eq!(
+        &lines[4..8],
+        [
+            "M  V30 BEGIN CTAB",
+            "M  V30 COUNTS 6 5 0 0 1",
+            "M  V30 BEGIN ATOM",
+            "M  V30 1 N 0.0000 0.0000 0.0000 7 CHG=1 VAL=4",
+        ]
+    );
+    assert_eq!(lines[8], "M  V30 2 C 0.0000 0.0000 0.0000 0 CFG=1");
+    assert_eq!(
+        lines[12],
+        "M  V30 6 O 0.0000 0.0000 0.0000 0 CHG=-1 MASS=15 VAL=1"
+    );
+    assert_eq!(lines[15], "M  V30 1 1 1 2");
+    assert_eq!(lines[18], "M  V30 4 2 4 5");
+    assert_eq!(
+        &lines[20..],
+        ["M  V30 END BOND", "M  V30 END CTAB", "M  END"]
+    );
+}
+
+#[test]
+fn aromatic_bonds_are_kekulized_by_default() {
+    let pyridine = parse("c1ccncc1").unwrap();
+    let orders = |molfile: &str| -> Vec<String> {
+        let bonds = molfile.lines().nth(3).unwrap()[3..6]
+            .trim()
+            .parse()
+            .unwrap();
+        molfile
+            .lines()
+            .skip(4 + bonds)
+            .take(bonds)
+            .map(|l| l[6..9].trim().to_string())
+            .collect()
+    };
+    let kekule = orders(&pyridine.to_molfile(MolfileVersion::V2000));
+    assert_eq!(kekule.iter().filter(|o| *o == "2").count(), 3);
+    assert_eq!(kekule.iter().filter(|o| *o == "1").count(), 3);
+
+    let options = MolfileOptions {
+        aromatic_bonds: true,
+        ..Default::default()
+    };
+    let aromatic = pyridine.to_molfile_with(MolfileVersion::V2000, &options);
+    assert!(orders(&aromatic).iter().all(|o| o == "4"));
+    // A ring that cannot be kekulized keeps its aromatic bonds.
+    #[cfg(not(feature = "huckel-validation"))]
+    {
+        let molfile = parse("c1cccc1").unwrap().to_molfile(MolfileVersion::V2000);
+        assert!(orders(&molfile).iter().all(|o| o == "4"));
+    }
+}
+
+#[test]
+fn title_and_coordinates() {
+    let options = MolfileOptions {
+        title: "water\nignored".to_string(),
+        coordinates: Some(vec![
+            [0.0, 0.0, 0.1173],
+            [0.0, 0.7572, -0.4692],
+            [0.0, -0.7572, -0.4692],
+        ]),
+        ..Default::default()
+    };
+    let molfile = parse("O([H])[H]")
+        .unwrap()
+        .to_molfile_with(MolfileVersion::V2000, &options);
+    let lines = lines(&molfile);
+    assert_eq!(lines[0], "water");
+    assert!(lines[1].ends_with("3D"));
+    assert!(lines[5].starts_with("    0.0000    0.7572   -0.4692 H  "));
+    let molfile = parse("CC").unwrap().to_molfile(MolfileVersion::V2000);
+    assert!(molfile.lines().nth(1).unwrap().ends_with("2D"));
+}
+
+#[test]
+#[should_panic(expected = "one position per atom")]
+fn coordinates_must_cover_every_atom() {
+    let options = MolfileOptions {
+        coordinates: Some(vec![[0.0; 3]]),
+        ..Default::default()
+    };
+    parse("CC")
+        .unwrap()
+        .to_molfile_with(MolfileVersion::V2000, &options);
+}
+
+#[test]
+fn large_molecules_fall_back_to_v3000() {
+    let chain = "C".repeat(1000);
+    let molfile = parse(&chain).unwrap().to_molfile(MolfileVersion::V2000);
+    assert!(molfile.contains("M  V30 COUNTS 1000 999 0 0 0"));
+    let molfile = parse(&"C".repeat(999))
+        .unwrap()
+        .to_molfile(MolfileVersion::V2000);
+    assert!(molfile.contains("999998  0  0  0  0  0  0  0  0999 V2000"));
+}
+
+#[test]
+fn atom_classes_fit_the_mapping_column() {
+    // The V2000 mapping column holds three digits, which is as far as atom
+    // classes go.
+    let molfile = parse("[CH4:999]")
+        .unwrap()
+        .to_molfile(MolfileVersion::V2000);
+    let atom = molfile.lines().nth(4).unwrap();
+    assert_eq!(&atom[60..63], "999", "{molfile}");
+    assert_eq!(
+        parse_molfile(&molfile).unwrap().nodes()[0].class(),
+        Some(999)
+    );
+    assert!(parse("[CH4:1000]").is_err());
+    let carbon = Atom::new(AtomSymbol::Organic(OrganicAtom::C), 0, None).unwrap();
+    assert_eq!(
+        Node::new(carbon, false, 4, Some(1000), None),
+        Err(NodeError::InvalidClass(1000))
+    );
+}
+
+#[test]
+fn round_trips_through_both_versions() {
+    for smiles in [
+        "CCO",
+        "CC(=O)[O-].[Na+]",
+        "c1ccccc1O",
+        "c1ccc2[nH]ccc2c1",
+        "[13CH3]C([2H])([2H])Cl",
+        "N[C@@H](C)C(=O)O",
+        "N[C@H](C)C(=O)O",
+        "[H][C@](F)(Cl)Br",
+        "F[C@]1(Cl)CC[C@@H](O)CC1",
+        "C[S@@](=O)CC",
+        "[CH3:1][OH:2]",
+        "[NH4+].[Cl-]",
+        "[CH2]",
+        "[Fe+2].[O-]C(=O)C",
+        "C#N",
+        "O=C1C=CC(=O)C=C1",
+    ] {
+        let molecule = parse(smiles).unwrap();
+        for version in [MolfileVersion::V2000, MolfileVersion::V3000] {
+            let read = parse_molfile(&molecule.to_molfile(version)).unwrap();
+            assert!(
+                read.is_isomorphic_with_stereo(&molecule),
+                "{smiles} read back as {read}"
+            );
+            let classes: Vec<_> = read.nodes().iter().map(|n| n.class()).collect();
+            let expected: Vec<_> = molecule.nodes().iter().map(|n| n.class()).collect();
+            assert_eq!(classes, expected, "{smiles}");
+        }
+    }
+}
+
+#[test]
+fn organic_atoms_read_without_brackets() {
+    let read = parse_molfile(&parse("CC(=O)O").unwrap().to_molfile(MolfileVersion::V2000)).unwrap();
+    assert_eq!(read, parse("CC(=O)O").unwrap());
+    assert!(read.nodes().iter().all(|n| n.bracket_hydrogens().is_none()));
+}
+
+/// L-alanine drawn in 2D, with a hash bond to the methyl group.
+const ALANINE_2D: &str = "\
+L-alanine
+  hand-drawn     2D
+
+  6  5  0  0  1  0  0  0  0  0999 V2000
+    0.0000    1.5000    0.0000 N   0  0  0  0  0  0  0  0  0  0  0  0
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -1.2990   -0.7500    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.2990   -0.7500    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.5981    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    1.2990   -2.2500    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  1  6
+  2  4  1  0
+  4  5  2  0
+  4  6  1  0
+M  END
+";
+
+#[test]
+fn wedges_give_chirality() {
+    // N at the top, methyl bottom left and behind, carboxyl bottom right:
+    // with the hydrogen in front, N -> COOH -> CH3 turns clockwise, which
+    // is S, L-alanine.
+    let l_alanine = parse("N[C@@H](C)C(=O)O").unwrap();
+    let read = parse_molfile(ALANINE_2D).unwrap();
+    assert!(read.is_isomorphic_with_stereo(&l_alanine), "{read}");
+    let wedge = ALANINE_2D.replace("  2  3  1  6", "  2  3  1  1");
+    let read = parse_molfile(&wedge).unwrap();
+    assert!(read.is_isomorphic_with_stereo(&parse("N[C@H](C)C(=O)O").unwrap()));
+    // Without a wedge the parity, here absent, decides.
+    let flat = ALANINE_2D.replace("  2  3  1  6", "  2  3  1  0");
+    let read = parse_molfile(&flat).unwrap();
+    assert!(read.nodes().iter().all(|n| n.chirality().is_none()));
+}
+
+#[test]
+fn double_bond_geometry() {
+    // (E)- and (Z)-1,2-difluoroethene in 2D.
+    let molfile = |fluorine_y: f64| {
+        let coordinates = vec![
+            [-1.3, 0.75, 0.0],
+            [-0.65, 0.0, 0.0],
+            [0.65, 0.0, 0.0],
+            [1.3, fluorine_y, 0.0],
+        ];
+        let options = MolfileOptions {
+            coordinates: Some(coordinates),
+            ..Default::default()
+        };
+        parse("FC=CF")
+            .unwrap()
+            .to_molfile_with(MolfileVersion::V2000, &options)
+    };
+    let trans = parse_molfile(&molfile(-0.75)).unwrap();
+    let cis = parse_molfile(&molfile(0.75)).unwrap();
+    assert!(
+        trans.is_isomorphic_with_stereo(&parse("F/C=C/F").unwrap()),
+        "{trans}"
+    );
+    assert!(
+        cis.is_isomorphic_with_stereo(&parse("F/C=C\\F").unwrap()),
+        "{cis}"
+    );
+    // Marked "either", or in a small ring, the bond keeps no geometry.
+    let either = molfile(0.75).replace("  2  3  2  0", "  2  3  2  3");
+    assert!(no_marks(&parse_molfile(&either).unwrap()));
+    // A terminal CH2 is no stereo double bond.
+    let vinyl = parse("C=CF").unwrap().to_molfile_with(
+        MolfileVersion::V2000,
+        &MolfileOptions {
+            coordinates: Some(vec![[0.0, 1.0, 0.0], [0.0, 0.0, 0.0], [1.0, -0.5, 0.0]]),
+            ..Default::default()
+        },
+    );
+    assert!(no_marks(&parse_molfile(&vinyl).unwrap()));
+}
+
+fn no_marks(molecule: &Molecule) -> bool {
+    molecule
+        .bonds()
+        .iter()
+        .all(|b| !matches!(b.kind(), BondType::Up | BondType::Down))
+}
+
+#[test]
+fn three_dimensional_chirality() {
+    // Bromochlorofluoromethane with the hydrogen explicit, from a
+    // tetrahedron: C at the origin.
+    let coordinates = vec![
+        [0.0, 0.0, 1.0],
+        [0.0, 0.0, 0.0],
+        [0.943, 0.0, -0.333],
+        [-0.471, 0.816, -0.333],
+        [-0.471, -0.816, -0.333],
+    ];
+    let molecule = parse("[H]C(F)(Cl)Br").unwrap();
+    let options = MolfileOptions {
+        coordinates: Some(coordinates),
+        ..Default::default()
+    };
+    let read = parse_molfile(&molecule.to_molfile_with(MolfileVersion::V3000, &options)).unwrap();
+    // Seen from the hydrogen, F, Cl and Br turn anticlockwise.
+    assert!(
+        read.is_isomorphic_with_stereo(&parse("[H][C@](F)(Cl)Br").unwrap()),
+        "{read}"
+    );
+    // Symmetric centres get no chirality from their geometry.
+    let methane = parse("[H]C([H])([H])F").unwrap();
+    let read = parse_molfile(&methane.to_molfile_with(MolfileVersion::V2000, &options)).unwrap();
+    assert!(read.nodes().iter().all(|n| n.chirality().is_none()));
+}
+
+#[test]
+fn radicals_and_valences() {
+    let methyl = "\
+methyl radical
+
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  RAD  1   1   2
+M  END
+";
+    let read = parse_molfile(methyl).unwrap();
+    assert_eq!(read.nodes()[0].hydrogens(), 3);
+    assert_eq!(read.to_string(), "[CH3]");
+    let carbene = methyl.replace("M  RAD  1   1   2", "M  RAD  1   1   3");
+    assert_eq!(parse_molfile(&carbene).unwrap().nodes()[0].hydrogens(), 2);
+    // Charge code 4 in the atom block is a doublet radical as well.
+    let doublet = methyl
+        .replace("C   0  0", "C   0  4")
+        .replace("M  RAD  1   1   2\n", "");
+    assert_eq!(parse_molfile(&doublet).unwrap().nodes()[0].hydrogens(), 3);
+}
+
+#[test]
+fn v3000_by_hand() {
+    let text = "\
+ethanolate
+
+
+  0  0  0     0  0            999 V3000
+M  V30 BEGIN CTAB
+M  V30 COUNTS 3 2 0 0 0
+M  V30 BEGIN ATOM
+M  V30 10 C 0 0 0 0
+M  V30 20 C 1.5 0 0 0 MASS=13
+M  V30 30 O 2.2 1.2 0 3 CHG=-1
+M  V30 END ATOM
+M  V30 BEGIN BOND
+M  V30 1 1 10 20
+M  V30 2 1 20 -
+M  V30 30
+M  V30 END BOND
+M  V30 BEGIN SGROUP
+M  V30 1 SUP 0 ATOMS=(1 3) LABEL=\"O-\"
+M  V30 END SGROUP
+M  V30 END CTAB
+M  END
+";
+    let read = parse_molfile(text).unwrap();
+    assert!(
+        read.is_isomorphic(&parse("C[13CH2][O-:3]").unwrap()),
+        "{read}"
+    );
+    assert_eq!(read.nodes()[2].class(), Some(3));
+}
+
+#[test]
+fn read_errors_point_at_the_line() {
+    let good = parse("CO").unwrap().to_molfile(MolfileVersion::V2000);
+    let cases = [
+        (
+            good.replace("  2  1  0", "  x  1  0"),
+            MolfileError::InvalidCounts(4),
+        ),
+        (
+            good.replace(" O ", " Xx"),
+            MolfileError::UnknownElement(6, "Xx".to_string()),
+        ),
+        (
+            good.replace("  1  2  1  0", "  1  2 11  0"),
+            MolfileError::InvalidBond(7),
+        ),
+        (
+            good.replace("  1  2  1  0", "  1  3  1  0"),
+            MolfileError::UnknownAtom(7, 3),
+        ),
+        (
+            good.lines().take(6).collect::<Vec<_>>().join("\n"),
+            MolfileError::UnexpectedEnd(7),
+        ),
+        ("CO\n".to_string(), MolfileError::UnexpectedEnd(2)),
+    ];
+    for (text, error) in cases {
+        assert_eq!(parse_molfile(&text), Err(error));
+    }
+}
+
+#[test]
+fn lone_ring_centres_lose_their_chirality() {
+    // Methylcyclohexane has no stereocentre; 1,4-dimethylcyclohexane has a
+    // relative configuration.
+    let read = |smiles: &str| {
+        parse_molfile(&parse(smiles).unwrap().to_molfile(MolfileVersion::V2000)).unwrap()
+    };
+    assert!(read("C[C@H]1CCCCC1")
+        .nodes()
+        .iter()
+        .all(|n| n.chirality().is_none()));
+    let cis = parse("C[C@H]1CC[C@@H](C)CC1").unwrap();
+    assert!(read("C[C@H]1CC[C@@H](C)CC1").is_isomorphic_with_stereo(&cis));
+}
+
+#[test]
+fn sd_files_recover_from_bad_records() {
+    let mut sdf = String::new();
+    for (name, smiles) in [
+        ("ethanol", "CCO"),
+        ("broken", ""),
+        ("acetate", "CC(=O)[O-]"),
+    ] {
+        if smiles.is_empty() {
+            sdf.push_str("broken\n\n\n  1  0  0  0  0  0  0  0  0  0999 V2000\nM  END\n");
+        } else {
+            sdf.push_str(&parse(smiles).unwrap().to_molfile(MolfileVersion::V3000));
+        }
+        sdf.push_str(&format!("> <NAME>\n{name}\n\n$$$$\n"));
+    }
+    let records: Vec<_> = SdfReader::new(Cursor::new(sdf)).collect();
+    assert_eq!(records.len(), 3);
+    let ethanol = records[0].as_ref().unwrap();
+    assert_eq!(ethanol.property("NAME"), Some("ethanol"));
+    assert_eq!(ethanol.molecule, parse("CCO").unwrap());
+    // The broken record starts at line 22 and lacks its atom line.
+    assert_eq!(records[1], Err(MolfileError::InvalidAtom(26)));
+    let acetate = records[2].as_ref().unwrap();
+    assert_eq!(acetate.property("NAME"), Some("acetate"));
+    assert_eq!(acetate.molecule.nodes()[3].atom().charge(), -1);
+}
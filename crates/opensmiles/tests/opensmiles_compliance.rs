@@ -3,7 +3,7 @@
 //! Tests for features required by the OpenSMILES spec that were previously
 //! missing or incorrect.
 
-use opensmiles::{parse, AtomSymbol, BondType, ParserError};
+use opensmiles::{parse, AtomSymbol, BondType, ParserError, Span};
 
 // ============================================================================
 // Aromatic two-letter bracket symbols: [se], [as], [te]
@@ -67,10 +67,10 @@ fn parse_selenophene() {
 fn reject_hydrogen_with_hydrogen_count() {
     let result = parse("[HH1]");
     assert!(result.is_err());
-    assert!(matches!(
+    assert_eq!(
         result.unwrap_err(),
-        ParserError::HydrogenWithHydrogenCount
-    ));
+        ParserError::HydrogenWithHydrogenCount(Span::new(0, 5))
+    );
 }
 
 #[test]
@@ -80,7 +80,7 @@ fn reject_hydrogen_with_implicit_h1() {
     assert!(result.is_err());
     assert!(matches!(
         result.unwrap_err(),
-        ParserError::HydrogenWithHydrogenCount
+        ParserError::HydrogenWithHydrogenCount(_)
     ));
 }
 
@@ -110,14 +110,17 @@ fn accept_molecular_hydrogen() {
 fn reject_self_bond() {
     let result = parse("C11");
     assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), ParserError::SelfBond(1)));
+    assert_eq!(
+        result.unwrap_err(),
+        ParserError::SelfBond(1, Span::new(1, 2), Span::new(2, 3))
+    );
 }
 
 #[test]
 fn reject_self_bond_zero() {
     let result = parse("C00");
     assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), ParserError::SelfBond(0)));
+    assert!(matches!(result.unwrap_err(), ParserError::SelfBond(0, ..)));
 }
 
 // ============================================================================
@@ -131,7 +134,7 @@ fn reject_duplicate_bond() {
     assert!(result.is_err());
     assert!(matches!(
         result.unwrap_err(),
-        ParserError::DuplicateBond(_, _)
+        ParserError::DuplicateBond(_, _, open, close) if open == Span::new(2, 3) && close == Span::new(9, 10)
     ));
 }
 
@@ -142,7 +145,7 @@ fn reject_duplicate_bond_two_char() {
     assert!(result.is_err());
     assert!(matches!(
         result.unwrap_err(),
-        ParserError::DuplicateBond(_, _)
+        ParserError::DuplicateBond(_, _, open, close) if open == Span::new(4, 7) && close == Span::new(15, 18)
     ));
 }
 
@@ -317,10 +320,10 @@ fn bond_at_ring_close() {
 fn mismatched_ring_bond_rejected() {
     let result = parse("C-1CCCCC=1");
     assert!(result.is_err());
-    assert!(matches!(
+    assert_eq!(
         result.unwrap_err(),
-        ParserError::MismatchedRingBond(1)
-    ));
+        ParserError::MismatchedRingBond(1, Span::new(1, 3), Span::new(8, 10))
+    );
 }
 
 // ============================================================================
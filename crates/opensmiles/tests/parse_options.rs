@@ -0,0 +1,133 @@
+//! Tests for runtime parse options.
+
+use opensmiles::{
+    parse, parse_with, AtomError, BondType, MoleculeError, NodeError, ParseOptions, ParserError,
+    Span,
+};
+
+#[test]
+fn defaults_match_parse() {
+    let options = ParseOptions::default();
+    for input in ["CCO", "CCO ethanol", "c1cc[se]cc1", "C=1CCCCC-1", "[Fe+15]"] {
+        assert_eq!(parse_with(input, &options), parse(input), "{input}");
+    }
+}
+
+#[test]
+fn huckel_validation_at_runtime() {
+    let lenient = ParseOptions {
+        huckel_validation: false,
+        ..Default::default()
+    };
+    let strict = ParseOptions {
+        huckel_validation: true,
+        ..Default::default()
+    };
+    assert!(parse_with("c1cc[se]cc1", &lenient).is_ok());
+    assert!(matches!(
+        parse_with("c1cc[se]cc1", &strict),
+        Err(ParserError::MoleculeError(
+            MoleculeError::HuckelViolation { .. },
+            span
+        )) if span == Span::new(0, 10)
+    ));
+    assert!(parse_with("c1ccccc1", &strict).is_ok());
+}
+
+#[test]
+fn huckel_validation_of_exocyclic_carbonyls() {
+    let strict = ParseOptions {
+        huckel_validation: true,
+        ..Default::default()
+    };
+    // The carbon of an exocyclic C=O gives no electron to its ring.
+    for input in [
+        "O=c1cccc[nH]1",
+        "O=c1cc[nH]cc1",
+        "Cn1cnc2c1c(=O)n(c(=O)n2C)C",
+        "O=c1[nH]cnc2[nH]cnc12",
+    ] {
+        assert!(parse_with(input, &strict).is_ok(), "{input}");
+    }
+}
+
+#[test]
+fn mismatched_ring_bonds_keep_the_opening_bond() {
+    let options = ParseOptions {
+        allow_mismatched_ring_bonds: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        parse("C=1CCCCC-1"),
+        Err(ParserError::MismatchedRingBond(1, ..))
+    ));
+    let molecule = parse_with("C=1CCCCC-1", &options).unwrap();
+    assert_eq!(molecule.bonds().len(), 6);
+    assert_eq!(molecule.bonds().last().unwrap().kind(), BondType::Double);
+    // Also across a branch.
+    let molecule = parse_with("C#1CC(CC-1)C", &options).unwrap();
+    let ring_bond = molecule
+        .bonds()
+        .iter()
+        .find(|bond| (bond.source(), bond.target()) == (0, 4))
+        .unwrap();
+    assert_eq!(ring_bond.kind(), BondType::Triple);
+}
+
+#[test]
+fn whitespace_and_titles() {
+    let no_title = ParseOptions {
+        allow_title: false,
+        ..Default::default()
+    };
+    assert!(parse_with("CCO \t\r\n", &no_title).is_ok());
+    assert_eq!(
+        parse_with("CCO  ethyl alcohol ", &no_title),
+        Err(ParserError::TitleNotAllowed(Span::new(5, 19)))
+    );
+
+    let no_whitespace = ParseOptions {
+        whitespace_terminates: false,
+        ..Default::default()
+    };
+    assert!(parse_with("CCO", &no_whitespace).is_ok());
+    assert_eq!(
+        parse_with("CCO ethanol", &no_whitespace),
+        Err(ParserError::UnexpectedCharacter(' ', Span::new(3, 4)))
+    );
+}
+
+#[test]
+fn bracket_atom_limits() {
+    let options = ParseOptions {
+        max_charge: 2,
+        max_hydrogens: 4,
+        max_isotope: 100,
+        ..Default::default()
+    };
+    assert!(parse_with("[NH4+]", &options).is_ok());
+    assert!(parse_with("[O-2]", &options).is_ok());
+    assert_eq!(
+        parse_with("C[Fe+3]", &options),
+        Err(ParserError::NodeError(
+            AtomError::InvalidCharge(3).into(),
+            Span::new(1, 7)
+        ))
+    );
+    assert_eq!(
+        parse_with("[SiH5]", &options),
+        Err(ParserError::MoleculeError(
+            MoleculeError::NodeError(NodeError::InvalidHydrogen(5)),
+            Span::new(0, 6)
+        ))
+    );
+    assert_eq!(
+        parse_with("[235U]", &options),
+        Err(ParserError::NodeError(
+            AtomError::InvalidIsotope(235).into(),
+            Span::new(0, 6)
+        ))
+    );
+    // Limits also apply inside branches.
+    assert!(parse_with("C([Fe-3])C", &options).is_err());
+}
@@ -0,0 +1,108 @@
+//! Tests for ring systems and ring assemblies.
+
+use opensmiles::{parse, RingSystem, RingSystemKind};
+
+fn systems(smiles: &str) -> Vec<RingSystem> {
+    parse(smiles).unwrap().ring_systems()
+}
+
+fn kinds(smiles: &str) -> Vec<RingSystemKind> {
+    systems(smiles).iter().map(|s| s.kind()).collect()
+}
+
+#[test]
+fn acyclic_molecule_has_no_systems() {
+    assert!(systems("CCCO").is_empty());
+    assert!(parse("CCCO").unwrap().ring_assemblies().is_empty());
+}
+
+#[test]
+fn isolated_rings() {
+    use RingSystemKind::Isolated;
+    assert_eq!(kinds("C1CCCCC1"), vec![Isolated]);
+    assert_eq!(kinds("c1ccccc1CCC1CC1"), vec![Isolated, Isolated]);
+    let benzene = &systems("c1ccccc1")[0];
+    assert_eq!(benzene.rings().len(), 1);
+    assert_eq!(benzene.atoms(), &[0, 1, 2, 3, 4, 5]);
+    assert!(benzene.spiro_atoms().is_empty());
+    assert!(benzene.bridgehead_atoms().is_empty());
+}
+
+#[test]
+fn fused_systems() {
+    use RingSystemKind::Fused;
+    assert_eq!(kinds("c1ccc2ccccc2c1"), vec![Fused]); // naphthalene
+    assert_eq!(kinds("c1cc2ccc3cccc4ccc(c1)c2c34"), vec![Fused]); // pyrene
+    assert_eq!(kinds("C1CCC2CCCCC2C1"), vec![Fused]); // decalin
+    let indole = &systems("c1ccc2[nH]ccc2c1")[0];
+    assert_eq!(indole.rings().len(), 2);
+    assert!(indole.bridgehead_atoms().is_empty());
+}
+
+#[test]
+fn spiro_systems() {
+    let spiro = &systems("C1CCC2(C1)CCCC2")[0]; // spiro[4.4]nonane
+    assert_eq!(spiro.kind(), RingSystemKind::Spiro);
+    assert_eq!(spiro.spiro_atoms(), &[3]);
+    assert_eq!(spiro.atoms().len(), 9);
+
+    // Dispiro: two spiro atoms in one system.
+    let dispiro = &systems("C1CC12CCC3(CC2)CC3")[0];
+    assert_eq!(dispiro.kind(), RingSystemKind::Spiro);
+    assert_eq!(dispiro.spiro_atoms(), &[2, 5]);
+}
+
+#[test]
+fn bridged_systems() {
+    let norbornane = &systems("C1CC2CCC1C2")[0];
+    assert_eq!(norbornane.kind(), RingSystemKind::Bridged);
+    assert_eq!(norbornane.bridgehead_atoms(), &[2, 5]);
+
+    let bicyclooctane = &systems("C1CC2CCC1CC2")[0]; // bicyclo[2.2.2]octane
+    assert_eq!(bicyclooctane.kind(), RingSystemKind::Bridged);
+    assert_eq!(bicyclooctane.bridgehead_atoms(), &[2, 5]);
+
+    let adamantane = &systems("C1C2CC3CC1CC(C2)C3")[0];
+    assert_eq!(adamantane.kind(), RingSystemKind::Bridged);
+    assert_eq!(adamantane.bridgehead_atoms(), &[1, 3, 5, 7]);
+
+    // No two faces of cubane share more than a bond, yet its six rings are
+    // more than twelve bonds over eight atoms can close in a fused system.
+    let cubane = &systems("C12C3C4C1C5C2C3C45")[0];
+    assert_eq!(cubane.kind(), RingSystemKind::Bridged);
+    assert_eq!(cubane.rings().len(), 6);
+    assert_eq!(cubane.bridgehead_atoms(), &[0, 1, 2, 3, 4, 5, 6, 7]);
+    // Prismane: two triangles and three squares.
+    assert_eq!(kinds("C12C3C1C4C2C34"), vec![RingSystemKind::Bridged]);
+}
+
+#[test]
+fn mixed_junctions_take_the_most_complex_kind() {
+    // Spiro-linked cyclopropane on naphthalene.
+    let mixed = &systems("C1CC2(C1)CCc1ccccc1C2")[0];
+    assert_eq!(mixed.kind(), RingSystemKind::Fused);
+    assert_eq!(mixed.spiro_atoms(), &[2]);
+}
+
+#[test]
+fn ring_assemblies() {
+    let biphenyl = parse("c1ccccc1-c1ccccc1").unwrap();
+    let assemblies = biphenyl.ring_assemblies();
+    assert_eq!(assemblies.len(), 1);
+    assert_eq!(assemblies[0].systems(), &[0, 1]);
+    assert_eq!(assemblies[0].atoms().len(), 12);
+
+    // p-Terphenyl: three systems in one assembly.
+    let terphenyl = parse("c1ccc(cc1)-c1ccc(cc1)-c1ccccc1").unwrap();
+    assert_eq!(terphenyl.ring_assemblies()[0].systems(), &[0, 1, 2]);
+
+    // A linker atom between the rings breaks the assembly.
+    assert!(parse("c1ccccc1Cc1ccccc1")
+        .unwrap()
+        .ring_assemblies()
+        .is_empty());
+    assert!(parse("c1ccccc1.c1ccccc1")
+        .unwrap()
+        .ring_assemblies()
+        .is_empty());
+}
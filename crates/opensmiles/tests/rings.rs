@@ -0,0 +1,198 @@
+//! Tests for ring perception (`Molecule::ring_info()`).
+
+use opensmiles::{parse, Ring, RingInfo};
+
+fn rings(smiles: &str) -> RingInfo {
+    parse(smiles).unwrap().ring_info()
+}
+
+fn sizes(rings: &[Ring]) -> Vec<usize> {
+    let mut sizes: Vec<usize> = rings.iter().map(|r| r.size()).collect();
+    sizes.sort_unstable();
+    sizes
+}
+
+#[test]
+fn acyclic_molecule_has_no_rings() {
+    let info = rings("CCC(C)O");
+    assert_eq!(info.ring_count(), 0);
+    assert!(info.sssr().is_empty());
+    assert!(info.relevant_cycles().unwrap().is_empty());
+    assert!(!info.is_in_ring(0));
+    assert!(!info.ring_bond(0));
+}
+
+#[test]
+fn single_rings() {
+    assert_eq!(sizes(rings("C1CC1").sssr()), vec![3]);
+    assert_eq!(sizes(rings("C1CCCCC1").sssr()), vec![6]);
+    assert_eq!(sizes(rings("c1ccccc1").sssr()), vec![6]);
+    assert_eq!(
+        sizes(rings("C1CCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCC1").sssr()),
+        vec![40]
+    );
+}
+
+#[test]
+fn ring_membership_of_substituents() {
+    // Toluene: the methyl and its bond are outside the ring.
+    let info = rings("Cc1ccccc1");
+    assert!(!info.is_in_ring(0));
+    assert!(!info.ring_bond(0));
+    assert!((1..7).all(|a| info.is_in_ring(a)));
+    assert!((1..7).all(|b| info.ring_bond(b)));
+    assert_eq!(info.smallest_ring_size(0), None);
+    assert_eq!(info.smallest_ring_size(3), Some(6));
+}
+
+#[test]
+fn fused_rings() {
+    let info = rings("c1ccc2ccccc2c1");
+    assert_eq!(info.ring_count(), 2);
+    assert_eq!(sizes(info.sssr()), vec![6, 6]);
+    // The 10-membered perimeter is the sum of the two rings: not relevant.
+    assert_eq!(sizes(info.relevant_cycles().unwrap()), vec![6, 6]);
+
+    let info = rings("c1ccc2c(c1)[nH]cc2");
+    assert_eq!(sizes(info.sssr()), vec![5, 6]);
+}
+
+#[test]
+fn spiro_rings() {
+    let info = rings("C1CCC2(C1)CCCC2");
+    assert_eq!(sizes(info.sssr()), vec![5, 5]);
+    assert_eq!(info.smallest_ring_size(3), Some(5));
+}
+
+#[test]
+fn bridged_rings() {
+    // Norbornane: two 5-rings; the 6-ring is their sum.
+    let info = rings("C1CC2CCC1C2");
+    assert_eq!(sizes(info.sssr()), vec![5, 5]);
+    assert_eq!(sizes(info.relevant_cycles().unwrap()), vec![5, 5]);
+
+    // Bicyclo[2.2.2]octane: any two of the three 6-rings form the SSSR,
+    // all three are relevant.
+    let info = rings("C1CC2CCC1CC2");
+    assert_eq!(sizes(info.sssr()), vec![6, 6]);
+    assert_eq!(sizes(info.relevant_cycles().unwrap()), vec![6, 6, 6]);
+
+    // Adamantane: three rings, four relevant chair cycles.
+    let info = rings("C1C2CC3CC1CC(C2)C3");
+    assert_eq!(sizes(info.sssr()), vec![6, 6, 6]);
+    assert_eq!(sizes(info.relevant_cycles().unwrap()), vec![6, 6, 6, 6]);
+
+    // Cubane: five rings, six faces.
+    let info = rings("C12C3C4C1C5C2C3C45");
+    assert_eq!(sizes(info.sssr()), vec![4; 5]);
+    assert_eq!(sizes(info.relevant_cycles().unwrap()), vec![4; 6]);
+}
+
+#[test]
+fn smallest_ring_sizes_of_bonds() {
+    // Bicyclo[4.1.0]heptane: the fusion bond is in the 3-ring and the 6-ring.
+    let mol = parse("C1CCC2CC2C1").unwrap();
+    let info = mol.ring_info();
+    for (index, bond) in mol.bonds().iter().enumerate() {
+        let expected = match (
+            bond.source().min(bond.target()),
+            bond.source().max(bond.target()),
+        ) {
+            (3, 4) | (4, 5) | (3, 5) => 3,
+            _ => 6,
+        };
+        assert_eq!(
+            info.smallest_bond_ring_size(index),
+            Some(expected),
+            "{index}"
+        );
+    }
+    assert_eq!(info.smallest_ring_size(3), Some(3));
+    assert_eq!(info.smallest_ring_size(0), Some(6));
+}
+
+#[test]
+fn disconnected_components() {
+    let info = rings("C1CC1.C1CCC1.CC");
+    assert_eq!(sizes(info.sssr()), vec![3, 4]);
+    assert!(!info.is_in_ring(7));
+}
+
+#[test]
+fn ring_count_is_cyclomatic_number() {
+    for smiles in [
+        "C1CC2CC3CC4CC5CC6CC7CC8CC9CC%10CC%11CCC%11C%10C9C8C7C6C5C4C3C2C1",
+        "CN1C=NC2=C1C(=O)N(C(=O)N2C)C",
+        "C1CC2(CC1)CCCC2",
+        "C12C3C4C1C5C2C3C45",
+    ] {
+        let mol = parse(smiles).unwrap();
+        let expected = mol.bonds().len() + 1 - mol.nodes().len();
+        assert_eq!(mol.ring_info().ring_count(), expected, "{smiles}");
+    }
+}
+
+/// Ladderane with `atoms / 2 - 1` fused four-membered rings, written as a
+/// zig-zag over the two rails so only two ring digits are open at a time.
+fn ladderane(atoms: usize) -> String {
+    let mut smiles = String::new();
+    for position in 0..atoms {
+        smiles.push('C');
+        if position % 2 == 1 && position >= 3 {
+            smiles.push_str(&(((position - 3) / 2) % 2 + 1).to_string());
+        }
+        if position % 2 == 0 && position + 3 < atoms {
+            smiles.push_str(&((position / 2) % 2 + 1).to_string());
+        }
+    }
+    smiles
+}
+
+#[test]
+fn thousands_of_fused_rings() {
+    let mol = parse(&ladderane(4002)).unwrap();
+    let info = mol.ring_info();
+    assert_eq!(info.ring_count(), 2000);
+    assert!(info.sssr().iter().all(|ring| ring.size() == 4));
+    assert_eq!(info.relevant_cycles().unwrap().len(), 2000);
+    assert!((0..mol.nodes().len() as u32).all(|a| info.smallest_ring_size(a) == Some(4)));
+}
+
+/// A ring of `count` diamonds: atom `3i` is bonded to `3i + 1` and `3i + 2`,
+/// which are both bonded to the next diamond. Every way around the ring is a
+/// relevant cycle, so there are `2^count` of them besides the diamonds.
+fn diamond_ring(count: usize) -> String {
+    let mut smiles = String::from("C1%10(C2)C");
+    for diamond in 1..count {
+        let (close, open) = if diamond % 2 == 1 { (2, 3) } else { (3, 2) };
+        if diamond + 1 == count {
+            smiles.push_str(&format!("C{close}(C%10)C1"));
+        } else {
+            smiles.push_str(&format!("C{close}(C{open})C"));
+        }
+    }
+    smiles
+}
+
+#[test]
+fn relevant_cycles_of_a_diamond_ring() {
+    let info = rings(&diamond_ring(6));
+    assert_eq!(info.ring_count(), 7);
+    let relevant = info.relevant_cycles().unwrap();
+    assert_eq!(sizes(relevant), [vec![4; 6], vec![12; 64]].concat());
+    assert!((0..18).all(|a| info.smallest_ring_size(a) == Some(4)));
+}
+
+#[test]
+fn too_many_relevant_cycles_are_not_listed() {
+    // 2^20 ways around the ring.
+    let mol = parse(&diamond_ring(20)).unwrap();
+    let info = mol.ring_info();
+    assert_eq!(info.ring_count(), 21);
+    assert_eq!(info.relevant_cycles(), None);
+    assert!((0..60).all(|a| info.smallest_ring_size(a) == Some(4)));
+    // Ring systems fall back to the SSSR.
+    let systems = mol.ring_systems();
+    assert_eq!(systems.len(), 1);
+    assert_eq!(systems[0].atoms().len(), 60);
+}
@@ -0,0 +1,169 @@
+//! Tests for the SMARTS parser and writer.
+
+use opensmiles::smarts::{
+    parse_smarts, AtomPrimitive, BondPrimitive, QueryAtom, QueryBond, QueryExpr, QueryMolecule,
+};
+use opensmiles::SmartsError;
+
+fn rewrite(smarts: &str) -> String {
+    parse_smarts(smarts).unwrap().to_string()
+}
+
+#[test]
+fn common_patterns_parse() {
+    for smarts in [
+        "[CX3](=O)[OX2H1]",
+        "[#7;R]",
+        "[NX3;H2,H1;!$(NC=O)]",
+        "c1ccccc1",
+        "[$([NX3](=O)=O),$([NX3+](=O)[O-])][!#8]",
+        "[#6]-,=[#6]",
+        "C!@C",
+        "[C@@H](F)(Cl)Br",
+        "F/C=C/F",
+        "[OH2+]",
+        "[2H]",
+        "[Na+].[Cl-]",
+        "*~*",
+        "[a;r6]:[a;r5]",
+        "[C;D3;v4;X3;R2;r6]",
+        "C%12CC%12",
+        "[CH3:1][OH:2]",
+    ] {
+        let query = parse_smarts(smarts).unwrap_or_else(|e| panic!("{smarts}: {e}"));
+        // Writing and reading again gives the same query.
+        let written = query.to_string();
+        assert_eq!(
+            parse_smarts(&written).unwrap(),
+            query,
+            "{smarts} -> {written}"
+        );
+    }
+}
+
+#[test]
+fn writer_keeps_the_written_layout() {
+    assert_eq!(rewrite("CC(C)(C)O"), "CC(C)(C)O");
+    assert_eq!(rewrite("c1ccccc1"), "c1ccccc1");
+    assert_eq!(rewrite("C1CC2CCC1C2"), "C1CC2CCC1C2");
+    assert_eq!(rewrite("[#7;R]"), "[#7;R]");
+    assert_eq!(rewrite("[C,N;!R]=,:*"), "[C,N;!R]=,:*");
+    assert_eq!(rewrite("[CH2+]"), "[C&H2&+1]");
+    assert_eq!(rewrite("[13C@H]"), "[13&C&@&H1]");
+    assert_eq!(rewrite("[H+]"), "[#1&+1]");
+    assert_eq!(rewrite("[H]"), "[#1]");
+    assert_eq!(rewrite("[se]"), "[se]");
+    assert_eq!(rewrite("[Fe]"), "[Fe]");
+    assert_eq!(rewrite("F/C=C/F"), "F/C=C/F");
+    assert_eq!(rewrite("C.C"), "C.C");
+    assert_eq!(rewrite("[$(C=O)]N"), "[$(C=O)]N");
+    assert_eq!(rewrite("C(=O)[OH,O-]"), "C(=O)[O&H1,O&-1]");
+}
+
+#[test]
+fn unwritable_expressions_use_normal_form() {
+    // (C or N) and (aromatic or ring), written from the API without `;`.
+    let expr = QueryExpr::And(vec![
+        QueryExpr::Or(vec![
+            QueryExpr::Primitive(AtomPrimitive::AtomicNumber(6)),
+            QueryExpr::Primitive(AtomPrimitive::AtomicNumber(7)),
+        ]),
+        QueryExpr::Not(Box::new(QueryExpr::Or(vec![
+            QueryExpr::Primitive(AtomPrimitive::Aromatic),
+            QueryExpr::Primitive(AtomPrimitive::RingMembership(None)),
+        ]))),
+    ]);
+    assert_eq!(expr.to_string(), "#6&!a&!R,#7&!a&!R");
+
+    let query = QueryMolecule::new(
+        vec![
+            QueryAtom::new(expr.clone(), None),
+            QueryAtom::new(QueryExpr::Primitive(AtomPrimitive::Any), None),
+        ],
+        vec![QueryBond::new(
+            1,
+            0,
+            Some(QueryExpr::Primitive(BondPrimitive::Up)),
+        )],
+    );
+    // The bond is written from atom 0, so `/` reads `\`.
+    assert_eq!(query.to_string(), "[#6&!a&!R,#7&!a&!R]\\*");
+}
+
+#[test]
+fn evaluate_expressions() {
+    let query = parse_smarts("[C,N;!R]").unwrap();
+    let expr = query.atoms()[0].expr();
+    fn element(symbol: &'static str) -> impl FnMut(&AtomPrimitive) -> bool {
+        move |p: &AtomPrimitive| match p {
+            AtomPrimitive::Element { element, .. } => element.to_string() == symbol,
+            AtomPrimitive::RingMembership(None) => false,
+            _ => unreachable!(),
+        }
+    }
+    assert!(expr.evaluate(&mut element("N")));
+    assert!(!expr.evaluate(&mut element("O")));
+}
+
+#[test]
+fn recursive_queries_nest() {
+    let query = parse_smarts("[$([CX3]=[OX1]),$([CX3+]-[OX1-])]").unwrap();
+    let QueryExpr::Or(operands) = query.atoms()[0].expr() else {
+        panic!("expected a disjunction");
+    };
+    for operand in operands {
+        let QueryExpr::Primitive(AtomPrimitive::Recursive(inner)) = operand else {
+            panic!("expected a recursive SMARTS");
+        };
+        assert_eq!(inner.atoms().len(), 2);
+        assert_eq!(inner.bonds().len(), 1);
+    }
+}
+
+#[test]
+fn bonds_and_rings() {
+    let query = parse_smarts("C1CC=1").unwrap();
+    assert_eq!(query.bonds().len(), 3);
+    let ring = &query.bonds()[2];
+    assert_eq!((ring.source(), ring.target()), (0, 2));
+    assert_eq!(
+        ring.expr(),
+        Some(&QueryExpr::Primitive(BondPrimitive::Double))
+    );
+    // Unwritten bonds carry no expression.
+    assert_eq!(query.bonds()[0].expr(), None);
+
+    let query = parse_smarts("*-!@*").unwrap();
+    assert_eq!(
+        query.bonds()[0].expr(),
+        Some(&QueryExpr::And(vec![
+            QueryExpr::Primitive(BondPrimitive::Single),
+            QueryExpr::Not(Box::new(QueryExpr::Primitive(BondPrimitive::Ring))),
+        ]))
+    );
+}
+
+#[test]
+fn whitespace_ends_the_pattern() {
+    let query: QueryMolecule = "C=O carbonyl".parse().unwrap();
+    assert_eq!(query.atoms().len(), 2);
+}
+
+#[test]
+fn invalid_patterns() {
+    assert_eq!(
+        parse_smarts("[C"),
+        Err(SmartsError::UnexpectedEndOfInput("]".to_string()))
+    );
+    assert_eq!(parse_smarts("[C&]"), Err(SmartsError::MissingPrimitive(4)));
+    assert_eq!(parse_smarts("[;C]"), Err(SmartsError::MissingPrimitive(2)));
+    assert_eq!(
+        parse_smarts("C?"),
+        Err(SmartsError::UnexpectedCharacter('?', 2))
+    );
+    assert_eq!(parse_smarts("C11"), Err(SmartsError::SelfBond(1)));
+    assert!(matches!(
+        parse_smarts("[C@TH3]"),
+        Err(SmartsError::ParserError(_))
+    ));
+}
@@ -0,0 +1,167 @@
+//! Tests for the SMILES file reader.
+
+use std::io::{self, BufReader, Cursor, Read};
+
+use opensmiles::smi::{Delimiter, SmilesReader, SmilesReaderOptions};
+use opensmiles::{parse, ParseOptions, ParserError, Span};
+
+#[test]
+fn titles_and_line_numbers() {
+    let file = "# header comment\r\nCCO ethanol\r\n\r\nc1ccccc1\tbenzene, the parent\r\nC\r\n";
+    let records: Vec<_> = SmilesReader::new(Cursor::new(file))
+        .map(Result::unwrap)
+        .collect();
+    let summary: Vec<_> = records
+        .iter()
+        .map(|r| (r.line, r.smiles.as_str(), r.title.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (2, "CCO", Some("ethanol")),
+            (4, "c1ccccc1", Some("benzene, the parent")),
+            (5, "C", None),
+        ]
+    );
+    assert_eq!(records[0].molecule, parse("CCO"));
+}
+
+#[test]
+fn bad_lines_do_not_stop_the_reader() {
+    let file = "C(C first\nCCO second\nC1CC third\nCN fourth\n";
+    let records: Vec<_> = SmilesReader::new(Cursor::new(file))
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(records.len(), 4);
+    assert!(records[0].molecule.is_err());
+    assert!(records[1].molecule.is_ok());
+    assert_eq!(
+        records[2].molecule,
+        Err(ParserError::UnclosedRing(vec![1], vec![Span::new(1, 2)]))
+    );
+    assert_eq!(records[3].title.as_deref(), Some("fourth"));
+}
+
+#[test]
+fn tab_separated_with_header() {
+    let file = "smiles\tname\tmw\n//comment\nCCO\tethanol\t46.07\nO\twater\t18.02\n";
+    let options = SmilesReaderOptions {
+        delimiter: Delimiter::Tab,
+        header: true,
+        comment: Some("//".to_string()),
+        ..Default::default()
+    };
+    let mut reader = SmilesReader::with_options(Cursor::new(file), options);
+    assert_eq!(reader.header(), None);
+    let first = reader.next().unwrap().unwrap();
+    assert_eq!(reader.header().unwrap(), ["smiles", "name", "mw"]);
+    assert_eq!(first.line, 3);
+    assert_eq!(first.columns, ["ethanol", "46.07"]);
+    assert_eq!(first.title.as_deref(), Some("ethanol"));
+    assert_eq!(reader.count(), 1);
+}
+
+#[test]
+fn comments_can_be_read_as_lines() {
+    let options = SmilesReaderOptions {
+        comment: None,
+        ..Default::default()
+    };
+    let records: Vec<_> = SmilesReader::with_options(Cursor::new("#1\nC\n"), options)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert!(records[0].molecule.is_err());
+}
+
+#[test]
+fn space_separated_columns() {
+    let options = SmilesReaderOptions {
+        delimiter: Delimiter::Space,
+        ..Default::default()
+    };
+    let record = SmilesReader::with_options(Cursor::new("CCO ethyl alcohol\n"), options)
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.columns, ["ethyl", "alcohol"]);
+    assert_eq!(record.title.as_deref(), Some("ethyl"));
+}
+
+#[test]
+fn parse_options_apply_to_every_line() {
+    let options = SmilesReaderOptions {
+        parse: ParseOptions {
+            max_hydrogens: 3,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records: Vec<_> = SmilesReader::with_options(Cursor::new("[CH3]C\n[NH4+]\n"), options)
+        .map(Result::unwrap)
+        .collect();
+    assert!(records[0].molecule.is_ok());
+    assert!(records[1].molecule.is_err());
+}
+
+/// Produces `count` lines of SMILES without ever holding them.
+struct Lines {
+    count: usize,
+    current: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for Lines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.current.len() {
+            if self.count == 0 {
+                return Ok(0);
+            }
+            self.count -= 1;
+            self.current = format!("C{}O mol{}\n", "C".repeat(self.count % 7), self.count).into();
+            self.offset = 0;
+        }
+        let n = buf.len().min(self.current.len() - self.offset);
+        buf[..n].copy_from_slice(&self.current[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn streams_large_inputs() {
+    let lines = Lines {
+        count: 100_000,
+        current: Vec::new(),
+        offset: 0,
+    };
+    let mut count = 0;
+    for record in SmilesReader::new(BufReader::new(lines)) {
+        let record = record.unwrap();
+        assert!(record.molecule.is_ok());
+        count += 1;
+        assert_eq!(record.line, count);
+    }
+    assert_eq!(count, 100_000);
+}
+
+/// Fails after its first line.
+struct Failing(bool);
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if std::mem::replace(&mut self.0, true) {
+            return Err(io::Error::new(io::ErrorKind::Other, "disk on fire"));
+        }
+        buf[..4].copy_from_slice(b"CC\nC");
+        Ok(4)
+    }
+}
+
+#[test]
+fn io_errors_end_the_iteration() {
+    let results: Vec<_> = SmilesReader::new(BufReader::new(Failing(false))).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert_eq!(results[1].as_ref().unwrap_err().to_string(), "disk on fire");
+}
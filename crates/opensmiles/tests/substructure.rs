@@ -0,0 +1,214 @@
+//! Tests for substructure matching.
+
+use opensmiles::parse;
+use opensmiles::smarts::{parse_smarts, QueryMolecule, SubstructureOptions};
+
+fn matches(smiles: &str, smarts: &str) -> Vec<Vec<u32>> {
+    parse(smiles)
+        .unwrap()
+        .substructure_matches(&parse_smarts(smarts).unwrap())
+}
+
+fn chiral_match(smiles: &str, query: &QueryMolecule) -> bool {
+    let options = SubstructureOptions {
+        chirality: true,
+        ..Default::default()
+    };
+    !parse(smiles)
+        .unwrap()
+        .substructure_matches_with(query, &options)
+        .is_empty()
+}
+
+fn mirror(smiles: &str) -> String {
+    smiles
+        .replace("@@", "\u{0}")
+        .replace('@', "@@")
+        .replace('\u{0}', "@")
+}
+
+#[test]
+fn functional_groups() {
+    let acid = "[CX3](=O)[OX2H1]";
+    assert_eq!(matches("CC(=O)O", acid), vec![vec![1, 2, 3]]);
+    assert!(matches("CC(=O)OC", acid).is_empty());
+    assert!(matches("CC(=O)[O-]", acid).is_empty());
+
+    let amine = "[NX3;H2,H1;!$(NC=O)]";
+    assert_eq!(matches("NCC(=O)N", amine), vec![vec![0]]);
+    assert_eq!(matches("CNC", amine).len(), 1);
+    assert!(matches("CN(C)C", amine).is_empty());
+
+    assert_eq!(matches("c1ccccc1O", "c[OH]"), vec![vec![5, 6]]);
+    assert_eq!(matches("OCCO", "[OH]CC[OH]").len(), 1);
+    assert_eq!(matches("C1CCCCC1", "[R]@[R]").len(), 6);
+    assert_eq!(matches("CC1CC1", "[R]!@*").len(), 1);
+    assert_eq!(matches("c1ccccc1C=O", "a-[#6]=O").len(), 1);
+}
+
+#[test]
+fn matches_map_query_atoms_in_order() {
+    let found = matches("OCC=O", "O=CC");
+    assert_eq!(found, vec![vec![3, 2, 1]]);
+    // Every query bond is a molecule bond.
+    let molecule = parse("c1ccc2ccccc2c1").unwrap();
+    let query = parse_smarts("c1ccccc1").unwrap();
+    for image in molecule.substructure_matches(&query) {
+        for bond in query.bonds() {
+            let (a, b) = (image[bond.source() as usize], image[bond.target() as usize]);
+            assert!(molecule
+                .bonds()
+                .iter()
+                .any(|m| (m.source(), m.target()) == (a, b) || (m.source(), m.target()) == (b, a)));
+        }
+    }
+}
+
+#[test]
+fn uniqueness_and_limits() {
+    let molecule = parse("OP(=O)(O)O").unwrap();
+    let query = parse_smarts("P(~O)(~O)~O").unwrap();
+    let all = SubstructureOptions {
+        unique: false,
+        ..Default::default()
+    };
+    // Four oxygens taken three at a time, in every order.
+    assert_eq!(molecule.substructure_matches_with(&query, &all).len(), 24);
+    assert_eq!(molecule.substructure_matches(&query).len(), 4);
+
+    let limited = SubstructureOptions {
+        max_matches: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        molecule.substructure_matches_with(&query, &limited).len(),
+        2
+    );
+    assert!(molecule.has_substructure(&query));
+    assert!(!molecule.has_substructure(&parse_smarts("PP").unwrap()));
+}
+
+#[test]
+fn disconnected_queries() {
+    assert_eq!(matches("[Na+].[Cl-]", "[Na+].[Cl-]"), vec![vec![0, 1]]);
+    assert_eq!(matches("CC", "C.C").len(), 1);
+    assert!(matches("C", "C.C").is_empty());
+    let empty = QueryMolecule::new(Vec::new(), Vec::new());
+    assert!(!parse("CC").unwrap().has_substructure(&empty));
+}
+
+#[test]
+fn molecules_as_queries() {
+    let benzene = QueryMolecule::from(&parse("c1ccccc1").unwrap());
+    assert!(parse("Cc1ccccc1").unwrap().has_substructure(&benzene));
+    assert!(!parse("C1=CCCCC1").unwrap().has_substructure(&benzene));
+
+    // Hydrogen counts are free, charges and isotopes are not.
+    let alcohol = QueryMolecule::from(&parse("CO").unwrap());
+    assert!(parse("CC(C)(C)O").unwrap().has_substructure(&alcohol));
+    assert!(parse("C[O-]").unwrap().has_substructure(&alcohol));
+    let alkoxide = QueryMolecule::from(&parse("C[O-]").unwrap());
+    assert!(!parse("CO").unwrap().has_substructure(&alkoxide));
+    let labelled = QueryMolecule::from(&parse("[13CH4]").unwrap());
+    assert!(!parse("C").unwrap().has_substructure(&labelled));
+    assert!(parse("[13CH3]O").unwrap().has_substructure(&labelled));
+
+    // A molecule always matches itself.
+    for smiles in ["CC(=O)Oc1ccccc1C(=O)O", "C1CC2CCC1C2", "[Na+].[Cl-]"] {
+        let molecule = parse(smiles).unwrap();
+        let query = QueryMolecule::from(&molecule);
+        let identity: Vec<u32> = (0..molecule.nodes().len() as u32).collect();
+        let options = SubstructureOptions {
+            unique: false,
+            ..Default::default()
+        };
+        assert!(molecule
+            .substructure_matches_with(&query, &options)
+            .contains(&identity));
+    }
+}
+
+#[test]
+fn kekule_molecules_are_searched_as_aromatic() {
+    assert_eq!(matches("C1=CC=CC=C1", "c1ccccc1").len(), 1);
+    assert_eq!(matches("C1=CC=CC=C1", "c").len(), 6);
+    assert_eq!(matches("C1=CC=CC=C1", "C=C").len(), 0);
+    assert_eq!(matches("CC1=CC=CC=C1O", "[OH]c1ccccc1").len(), 1);
+    assert_eq!(matches("C1=CC=C2C=CC=CC2=C1", "c:c").len(), 11);
+    assert_eq!(matches("C1=CNC=C1", "[nH]").len(), 1);
+    // Rings the model does not make aromatic stay as written.
+    assert_eq!(matches("C1=CCC=C1", "c").len(), 0);
+    assert_eq!(matches("C1=CCC=C1", "C=C").len(), 2);
+
+    // Molecules used as queries are perceived alike.
+    let kekule = QueryMolecule::from(&parse("C1=CC=CC=C1").unwrap());
+    assert!(parse("c1ccccc1O").unwrap().has_substructure(&kekule));
+    assert!(parse("OC1=CC=CC=C1").unwrap().has_substructure(&kekule));
+}
+
+#[test]
+fn chirality_is_optional() {
+    let query = parse_smarts("N[C@@H](C)C(=O)O").unwrap();
+    let l_alanine = "N[C@@H](C)C(=O)O";
+    let d_alanine = "N[C@H](C)C(=O)O";
+    assert!(parse(d_alanine).unwrap().has_substructure(&query));
+    assert!(chiral_match(l_alanine, &query));
+    assert!(!chiral_match(d_alanine, &query));
+    // Unspecified centres do not match a chiral query.
+    assert!(!chiral_match("NC(C)C(=O)O", &query));
+    // The same molecule written another way.
+    let rewritten = "C[C@H](N)C(=O)O";
+    assert_eq!(
+        parse(rewritten).unwrap().to_canonical_smiles(),
+        parse(l_alanine).unwrap().to_canonical_smiles()
+    );
+    assert!(chiral_match(rewritten, &query));
+}
+
+#[test]
+fn chirality_follows_the_written_order() {
+    for smiles in [
+        "N[C@@H](C)C(=O)O",
+        "F[C@](Cl)(Br)I",
+        "[C@@H]1(F)CCCCO1",
+        "C[C@@]1(F)CCCC1=O",
+        "O[C@H]1CCCC[C@@H]1C",
+    ] {
+        let molecule = parse(smiles).unwrap();
+        let from_smarts = parse_smarts(smiles).unwrap();
+        let from_molecule = QueryMolecule::from(&molecule);
+        let canonical = parse(&molecule.to_canonical_smiles()).unwrap();
+        for query in [&from_smarts, &from_molecule] {
+            assert!(chiral_match(smiles, query), "{smiles}");
+            assert!(
+                chiral_match(&canonical.to_string(), query),
+                "{smiles} as {canonical}"
+            );
+            assert!(!chiral_match(&mirror(smiles), query), "{smiles}");
+        }
+    }
+}
+
+#[test]
+fn a_query_lone_pair_stands_for_an_unmapped_neighbour() {
+    // The query leaves the fourth substituent of the centre unmapped.
+    let query = parse_smarts("F[C@](Cl)Br").unwrap();
+    assert!(chiral_match("F[C@](Cl)(Br)I", &query));
+    assert!(!chiral_match("F[C@@](Cl)(Br)I", &query));
+    assert!(chiral_match("F[C@H](Cl)Br", &query));
+    assert!(!chiral_match("F[C@@H](Cl)Br", &query));
+}
+
+#[test]
+fn explicit_hydrogens_take_part_in_chirality() {
+    let query = parse_smarts("[H][C@](F)(Cl)Br").unwrap();
+    let rewritten = "F[C@@]([H])(Cl)Br";
+    assert_eq!(
+        parse(rewritten).unwrap().to_canonical_smiles(),
+        parse("[H][C@](F)(Cl)Br").unwrap().to_canonical_smiles()
+    );
+    assert!(chiral_match(rewritten, &query));
+    assert!(!chiral_match("F[C@]([H])(Cl)Br", &query));
+    // An implicit hydrogen is no atom to map the query hydrogen to.
+    assert!(!chiral_match("F[C@@H](Cl)Br", &query));
+}